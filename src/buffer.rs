@@ -1,3 +1,6 @@
+use crate::util::floor_log2;
+
+#[derive(Clone)]
 pub struct Buffer<'a> {
     buf: &'a [u8],
     index: usize,
@@ -13,13 +16,38 @@ impl<'a> Buffer<'a> {
         }
     }
 
+    /// Returns the current position in the buffer, in bits.
+    pub fn get_position(&self) -> usize {
+        self.index * 8 + self.bit_pos
+    }
+
+    /// Returns the whole underlying data, regardless of the current position.
+    pub fn data(&self) -> &'a [u8] {
+        self.buf
+    }
+
+    /// Returns the number of bytes left in the buffer, including the current
+    /// partially read byte.
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.index
+    }
+
     pub fn seek_bits(&mut self, cut: usize) {
         for _ in 0..cut {
             self.advance();
         }
     }
 
-    pub fn get_bytes(&mut self, count: usize) -> &[u8] {
+    /// Skips the remaining bits of the current byte, if any.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#byte-alignment-syntax
+    pub fn byte_alignment(&mut self) {
+        if self.bit_pos != 0 {
+            self.seek_bits(8 - self.bit_pos);
+        }
+    }
+
+    pub fn get_bytes(&mut self, count: usize) -> &'a [u8] {
         assert_eq!(self.bit_pos, 0);
 
         self.index += count;
@@ -32,8 +60,11 @@ impl<'a> Buffer<'a> {
 
     /// Unsigned n-bit number appearing directly in the bitstream. The bits are
     /// read from high to low order.
+    ///
+    /// Note: A zero width read is allowed and always returns 0, this happens
+    /// for example with f(OrderHintBits) when order hints are disabled.
     pub fn get_bits(&mut self, count: usize) -> u32 {
-        assert!(count > 0 && count <= 32);
+        assert!(count <= 32);
 
         let mut aac = 0;
         for i in 0..count {
@@ -102,19 +133,19 @@ impl<'a> Buffer<'a> {
     /// It is a requirement of bitstream conformance that the most significant
     /// bit of leb128_byte is equal to 0 if i is equal to 7. (This
     /// ensures that this syntax descriptor never uses more than 8 bytes.)
-    pub fn get_leb128(&mut self) -> u16 {
+    pub fn get_leb128(&mut self) -> u32 {
         assert_eq!(self.bit_pos, 0);
 
-        let mut value = 0;
+        let mut value = 0u64;
         for i in 0..8 {
-            let byte = self.get_bits(8) as u16;
+            let byte = self.get_bits(8) as u64;
             value |= (byte & 0x7f) << (i * 7);
             if byte & 0x80 == 0 {
                 break;
             }
         }
 
-        value
+        value as u32
     }
 
     /// Signed integer converted from an n bits unsigned integer in the
@@ -124,12 +155,30 @@ impl<'a> Buffer<'a> {
         let mut value = self.get_bits(count) as i32;
         let sign_mask = 1 << (count - 1) as i32;
 
-        if value > 0 && sign_mask > 0 {
-            value = value - 2 * sign_mask;
+        if value & sign_mask != 0 {
+            value -= 2 * sign_mask;
         }
 
         value
     }
+
+    /// Unsigned encoded integer with maximum number of values n (i.e. output
+    /// in range 0..n-1).
+    ///
+    /// This encoding is non-symmetric because the values are not all coded
+    /// with the same number of bits.
+    pub fn get_ns(&mut self, n: u32) -> u32 {
+        let w = floor_log2(n) + 1;
+        let m = (1 << w) - n;
+        let v = self.get_bits(w as usize - 1);
+        if v < m {
+            return v;
+        }
+
+        // extra_bit	f(1)
+        let extra_bit = self.get_bit() as u32;
+        (v << 1) - m + extra_bit
+    }
 }
 
 impl<'a> Buffer<'a> {
@@ -0,0 +1,413 @@
+//! The cdfs used by the symbol decoder, their default values and the
+//! processes that save, load and reset them between frames.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#cdf-selection-process
+
+pub mod coeff;
+pub mod non_coeff;
+
+use crate::{constants::MV_CONTEXTS, obu::ObuContext};
+
+use self::{coeff::*, non_coeff::*};
+
+/// Resets the counter of every cdf contained in `self`.
+trait Counters {
+    fn clear_counters(&mut self);
+}
+
+impl<const N: usize> Counters for [u16; N] {
+    fn clear_counters(&mut self) {
+        self[N - 1] = 0;
+    }
+}
+
+impl<T: Counters, const N: usize> Counters for [T; N] {
+    fn clear_counters(&mut self) {
+        for cdf in self.iter_mut() {
+            cdf.clear_counters();
+        }
+    }
+}
+
+macro_rules! clear_counters {
+    ($cdfs:expr, $($field:ident),* $(,)?) => {
+        $( $cdfs.$field.clear_counters(); )*
+    };
+}
+
+/// Resets the counters of the palette color cdfs, which are not the last
+/// entry of their arrays: the cdf of a palette of n colors is padded after
+/// its counter at index n.
+fn clear_palette_color_counters(cdfs: &mut [[[u16; 9]; 5]; 7]) {
+    for (i, cdfs) in cdfs.iter_mut().enumerate() {
+        for cdf in cdfs {
+            cdf[i + 2] = 0;
+        }
+    }
+}
+
+/// The cdfs of the coefficients, the defaults depend on the quantizer of the
+/// frame.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CoeffCdfs {
+    pub txb_skip_cdf: [[[u16; 3]; 13]; 5],
+    pub eob_pt_16_cdf: [[[u16; 6]; 2]; 2],
+    pub eob_pt_32_cdf: [[[u16; 7]; 2]; 2],
+    pub eob_pt_64_cdf: [[[u16; 8]; 2]; 2],
+    pub eob_pt_128_cdf: [[[u16; 9]; 2]; 2],
+    pub eob_pt_256_cdf: [[[u16; 10]; 2]; 2],
+    pub eob_pt_512_cdf: [[u16; 11]; 2],
+    pub eob_pt_1024_cdf: [[u16; 12]; 2],
+    pub eob_extra_cdf: [[[[u16; 3]; 9]; 2]; 5],
+    pub dc_sign_cdf: [[[u16; 3]; 3]; 2],
+    pub coeff_base_eob_cdf: [[[[u16; 4]; 4]; 2]; 5],
+    pub coeff_base_cdf: [[[[u16; 5]; 42]; 2]; 5],
+    pub coeff_br_cdf: [[[[u16; 5]; 21]; 2]; 5],
+}
+
+impl CoeffCdfs {
+    /// The default coefficient cdfs for a frame using `base_q_idx`.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#initialization-process-for-coefficient-cdfs
+    pub fn new(base_q_idx: u8) -> Self {
+        let idx = match base_q_idx {
+            0..=20 => 0,
+            21..=60 => 1,
+            61..=120 => 2,
+            _ => 3,
+        };
+
+        Self {
+            txb_skip_cdf: DEFAULT_TXB_SKIP_CDF[idx],
+            eob_pt_16_cdf: DEFAULT_EOB_PT_16_CDF[idx],
+            eob_pt_32_cdf: DEFAULT_EOB_PT_32_CDF[idx],
+            eob_pt_64_cdf: DEFAULT_EOB_PT_64_CDF[idx],
+            eob_pt_128_cdf: DEFAULT_EOB_PT_128_CDF[idx],
+            eob_pt_256_cdf: DEFAULT_EOB_PT_256_CDF[idx],
+            eob_pt_512_cdf: DEFAULT_EOB_PT_512_CDF[idx],
+            eob_pt_1024_cdf: DEFAULT_EOB_PT_1024_CDF[idx],
+            eob_extra_cdf: DEFAULT_EOB_EXTRA_CDF[idx],
+            dc_sign_cdf: DEFAULT_DC_SIGN_CDF[idx],
+            coeff_base_eob_cdf: DEFAULT_COEFF_BASE_EOB_CDF[idx],
+            coeff_base_cdf: DEFAULT_COEFF_BASE_CDF[idx],
+            coeff_br_cdf: DEFAULT_COEFF_BR_CDF[idx],
+        }
+    }
+
+    fn clear_counters(&mut self) {
+        clear_counters!(
+            self,
+            txb_skip_cdf,
+            eob_pt_16_cdf,
+            eob_pt_32_cdf,
+            eob_pt_64_cdf,
+            eob_pt_128_cdf,
+            eob_pt_256_cdf,
+            eob_pt_512_cdf,
+            eob_pt_1024_cdf,
+            eob_extra_cdf,
+            dc_sign_cdf,
+            coeff_base_eob_cdf,
+            coeff_base_cdf,
+            coeff_br_cdf,
+        );
+    }
+}
+
+/// Every cdf that is adapted while decoding the tiles of a frame.
+///
+/// The palette color cdfs of all the palette sizes share the same arrays,
+/// padded to the largest size: the cdf of a palette of n colors is
+/// `palette_y_color_cdf[n - 2][ctx][..n + 1]`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CdfContext {
+    pub intra_frame_y_mode_cdf: [[[u16; 14]; 5]; 5],
+    pub y_mode_cdf: [[u16; 14]; 4],
+    pub uv_mode_cfl_not_allowed_cdf: [[u16; 14]; 13],
+    pub uv_mode_cfl_allowed_cdf: [[u16; 15]; 13],
+    pub angle_delta_cdf: [[u16; 8]; 8],
+    pub intrabc_cdf: [u16; 3],
+    pub partition_w8_cdf: [[u16; 5]; 4],
+    pub partition_w16_cdf: [[u16; 11]; 4],
+    pub partition_w32_cdf: [[u16; 11]; 4],
+    pub partition_w64_cdf: [[u16; 11]; 4],
+    pub partition_w128_cdf: [[u16; 9]; 4],
+    pub segment_id_cdf: [[u16; 9]; 3],
+    pub segment_id_predicted_cdf: [[u16; 3]; 3],
+    pub tx_8x8_cdf: [[u16; 3]; 3],
+    pub tx_16x16_cdf: [[u16; 4]; 3],
+    pub tx_32x32_cdf: [[u16; 4]; 3],
+    pub tx_64x64_cdf: [[u16; 4]; 3],
+    pub txfm_split_cdf: [[u16; 3]; 21],
+    pub filter_intra_mode_cdf: [u16; 6],
+    pub filter_intra_cdf: [[u16; 3]; 22],
+    pub interp_filter_cdf: [[u16; 4]; 16],
+    pub motion_mode_cdf: [[u16; 4]; 22],
+    pub new_mv_cdf: [[u16; 3]; 6],
+    pub zero_mv_cdf: [[u16; 3]; 2],
+    pub ref_mv_cdf: [[u16; 3]; 6],
+    pub compound_mode_cdf: [[u16; 9]; 8],
+    pub drl_mode_cdf: [[u16; 3]; 3],
+    pub is_inter_cdf: [[u16; 3]; 4],
+    pub comp_mode_cdf: [[u16; 3]; 5],
+    pub skip_mode_cdf: [[u16; 3]; 3],
+    pub skip_cdf: [[u16; 3]; 3],
+    pub comp_ref_cdf: [[[u16; 3]; 3]; 3],
+    pub comp_bwd_ref_cdf: [[[u16; 3]; 2]; 3],
+    pub single_ref_cdf: [[[u16; 3]; 6]; 3],
+    pub comp_ref_type_cdf: [[u16; 3]; 5],
+    pub uni_comp_ref_cdf: [[[u16; 3]; 3]; 3],
+    pub comp_group_idx_cdf: [[u16; 3]; 6],
+    pub compound_idx_cdf: [[u16; 3]; 6],
+    pub compound_type_cdf: [[u16; 3]; 22],
+    pub inter_intra_cdf: [[u16; 3]; 4],
+    pub inter_intra_mode_cdf: [[u16; 5]; 4],
+    pub wedge_index_cdf: [[u16; 17]; 22],
+    pub wedge_inter_intra_cdf: [[u16; 3]; 22],
+    pub use_obmc_cdf: [[u16; 3]; 22],
+    pub palette_y_mode_cdf: [[[u16; 3]; 3]; 7],
+    pub palette_uv_mode_cdf: [[u16; 3]; 2],
+    pub palette_y_size_cdf: [[u16; 8]; 7],
+    pub palette_uv_size_cdf: [[u16; 8]; 7],
+    pub palette_y_color_cdf: [[[u16; 9]; 5]; 7],
+    pub palette_uv_color_cdf: [[[u16; 9]; 5]; 7],
+    pub delta_q_cdf: [u16; 5],
+    pub delta_lf_cdf: [u16; 5],
+    pub delta_lf_multi_cdf: [[u16; 5]; 4],
+    pub intra_tx_type_set1_cdf: [[[u16; 8]; 13]; 2],
+    pub intra_tx_type_set2_cdf: [[[u16; 6]; 13]; 3],
+    pub inter_tx_type_set1_cdf: [[u16; 17]; 2],
+    pub inter_tx_type_set2_cdf: [u16; 13],
+    pub inter_tx_type_set3_cdf: [[u16; 3]; 4],
+    pub cfl_sign_cdf: [u16; 9],
+    pub cfl_alpha_cdf: [[u16; 17]; 6],
+    pub restoration_type_cdf: [u16; 4],
+    pub use_wiener_cdf: [u16; 3],
+    pub use_sgrproj_cdf: [u16; 3],
+    pub mv_joint_cdf: [[u16; 5]; MV_CONTEXTS as usize],
+    pub mv_class_cdf: [[[u16; 12]; 2]; MV_CONTEXTS as usize],
+    pub mv_class0_bit_cdf: [[[u16; 3]; 2]; MV_CONTEXTS as usize],
+    pub mv_class0_fr_cdf: [[[[u16; 5]; 2]; 2]; MV_CONTEXTS as usize],
+    pub mv_class0_hp_cdf: [[[u16; 3]; 2]; MV_CONTEXTS as usize],
+    pub mv_sign_cdf: [[[u16; 3]; 2]; MV_CONTEXTS as usize],
+    pub mv_bit_cdf: [[[[u16; 3]; 10]; 2]; MV_CONTEXTS as usize],
+    pub mv_fr_cdf: [[[u16; 5]; 2]; MV_CONTEXTS as usize],
+    pub mv_hp_cdf: [[[u16; 3]; 2]; MV_CONTEXTS as usize],
+    pub coeff: CoeffCdfs,
+}
+
+impl Default for CdfContext {
+    fn default() -> Self {
+        Self {
+            intra_frame_y_mode_cdf: DEFAULT_INTRA_FRAME_Y_MODE_CDF,
+            y_mode_cdf: DEFAULT_Y_MODE_CDF,
+            uv_mode_cfl_not_allowed_cdf: DEFAULT_UV_MODE_CFL_NOT_ALLOWED_CDF,
+            uv_mode_cfl_allowed_cdf: DEFAULT_UV_MODE_CFL_ALLOWED_CDF,
+            angle_delta_cdf: DEFAULT_ANGLE_DELTA_CDF,
+            intrabc_cdf: DEFAULT_INTRABC_CDF,
+            partition_w8_cdf: DEFAULT_PARTITION_W8_CDF,
+            partition_w16_cdf: DEFAULT_PARTITION_W16_CDF,
+            partition_w32_cdf: DEFAULT_PARTITION_W32_CDF,
+            partition_w64_cdf: DEFAULT_PARTITION_W64_CDF,
+            partition_w128_cdf: DEFAULT_PARTITION_W128_CDF,
+            segment_id_cdf: DEFAULT_SEGMENT_ID_CDF,
+            segment_id_predicted_cdf: DEFAULT_SEGMENT_ID_PREDICTED_CDF,
+            tx_8x8_cdf: DEFAULT_TX_8X8_CDF,
+            tx_16x16_cdf: DEFAULT_TX_16X16_CDF,
+            tx_32x32_cdf: DEFAULT_TX_32X32_CDF,
+            tx_64x64_cdf: DEFAULT_TX_64X64_CDF,
+            txfm_split_cdf: DEFAULT_TXFM_SPLIT_CDF,
+            filter_intra_mode_cdf: DEFAULT_FILTER_INTRA_MODE_CDF,
+            filter_intra_cdf: DEFAULT_FILTER_INTRA_CDF,
+            interp_filter_cdf: DEFAULT_INTERP_FILTER_CDF,
+            motion_mode_cdf: DEFAULT_MOTION_MODE_CDF,
+            new_mv_cdf: DEFAULT_NEW_MV_CDF,
+            zero_mv_cdf: DEFAULT_ZERO_MV_CDF,
+            ref_mv_cdf: DEFAULT_REF_MV_CDF,
+            compound_mode_cdf: DEFAULT_COMPOUND_MODE_CDF,
+            drl_mode_cdf: DEFAULT_DRL_MODE_CDF,
+            is_inter_cdf: DEFAULT_IS_INTER_CDF,
+            comp_mode_cdf: DEFAULT_COMP_MODE_CDF,
+            skip_mode_cdf: DEFAULT_SKIP_MODE_CDF,
+            skip_cdf: DEFAULT_SKIP_CDF,
+            comp_ref_cdf: DEFAULT_COMP_REF_CDF,
+            comp_bwd_ref_cdf: DEFAULT_COMP_BWD_REF_CDF,
+            single_ref_cdf: DEFAULT_SINGLE_REF_CDF,
+            comp_ref_type_cdf: DEFAULT_COMP_REF_TYPE_CDF,
+            uni_comp_ref_cdf: DEFAULT_UNI_COMP_REF_CDF,
+            comp_group_idx_cdf: DEFAULT_COMP_GROUP_IDX_CDF,
+            compound_idx_cdf: DEFAULT_COMPOUND_IDX_CDF,
+            compound_type_cdf: DEFAULT_COMPOUND_TYPE_CDF,
+            inter_intra_cdf: DEFAULT_INTER_INTRA_CDF,
+            inter_intra_mode_cdf: DEFAULT_INTER_INTRA_MODE_CDF,
+            wedge_index_cdf: DEFAULT_WEDGE_INDEX_CDF,
+            wedge_inter_intra_cdf: DEFAULT_WEDGE_INTER_INTRA_CDF,
+            use_obmc_cdf: DEFAULT_USE_OBMC_CDF,
+            palette_y_mode_cdf: DEFAULT_PALETTE_Y_MODE_CDF,
+            palette_uv_mode_cdf: DEFAULT_PALETTE_UV_MODE_CDF,
+            palette_y_size_cdf: DEFAULT_PALETTE_Y_SIZE_CDF,
+            palette_uv_size_cdf: DEFAULT_PALETTE_UV_SIZE_CDF,
+            palette_y_color_cdf: DEFAULT_PALETTE_Y_COLOR_CDF,
+            palette_uv_color_cdf: DEFAULT_PALETTE_UV_COLOR_CDF,
+            delta_q_cdf: DEFAULT_DELTA_Q_CDF,
+            delta_lf_cdf: DEFAULT_DELTA_LF_CDF,
+            delta_lf_multi_cdf: DEFAULT_DELTA_LF_MULTI_CDF,
+            intra_tx_type_set1_cdf: DEFAULT_INTRA_TX_TYPE_SET1_CDF,
+            intra_tx_type_set2_cdf: DEFAULT_INTRA_TX_TYPE_SET2_CDF,
+            inter_tx_type_set1_cdf: DEFAULT_INTER_TX_TYPE_SET1_CDF,
+            inter_tx_type_set2_cdf: DEFAULT_INTER_TX_TYPE_SET2_CDF,
+            inter_tx_type_set3_cdf: DEFAULT_INTER_TX_TYPE_SET3_CDF,
+            cfl_sign_cdf: DEFAULT_CFL_SIGN_CDF,
+            cfl_alpha_cdf: DEFAULT_CFL_ALPHA_CDF,
+            restoration_type_cdf: DEFAULT_RESTORATION_TYPE_CDF,
+            use_wiener_cdf: DEFAULT_USE_WIENER_CDF,
+            use_sgrproj_cdf: DEFAULT_USE_SGRPROJ_CDF,
+            mv_joint_cdf: [DEFAULT_MV_JOINT_CDF; MV_CONTEXTS as usize],
+            mv_class_cdf: [[DEFAULT_MV_CLASS_CDF; 2]; MV_CONTEXTS as usize],
+            mv_class0_bit_cdf: [[DEFAULT_MV_CLASS0_BIT_CDF; 2]; MV_CONTEXTS as usize],
+            mv_class0_fr_cdf: [[DEFAULT_MV_CLASS0_FR_CDF; 2]; MV_CONTEXTS as usize],
+            mv_class0_hp_cdf: [[DEFAULT_MV_CLASS0_HP_CDF; 2]; MV_CONTEXTS as usize],
+            mv_sign_cdf: [[DEFAULT_MV_SIGN_CDF; 2]; MV_CONTEXTS as usize],
+            mv_bit_cdf: [[DEFAULT_MV_BIT_CDF; 2]; MV_CONTEXTS as usize],
+            mv_fr_cdf: [[DEFAULT_MV_FR_CDF; 2]; MV_CONTEXTS as usize],
+            mv_hp_cdf: [[DEFAULT_MV_HP_CDF; 2]; MV_CONTEXTS as usize],
+            coeff: CoeffCdfs::new(0),
+        }
+    }
+}
+
+impl std::fmt::Debug for CdfContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CdfContext").finish_non_exhaustive()
+    }
+}
+
+impl CdfContext {
+    /// Sets every cdf that is not used by the coefficients to its default
+    /// value.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#initialization-process-for-non-coefficient-cdfs
+    pub fn init_non_coeff_cdfs(&mut self) {
+        let coeff = self.coeff.clone();
+        *self = Self {
+            coeff,
+            ..Self::default()
+        };
+    }
+
+    /// Sets the coefficient cdfs to the defaults selected by `base_q_idx`.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#initialization-process-for-coefficient-cdfs
+    pub fn init_coeff_cdfs(&mut self, base_q_idx: u8) {
+        self.coeff = CoeffCdfs::new(base_q_idx);
+    }
+
+    /// Sets the symbol counter, the last entry of each cdf, to zero.
+    pub fn clear_counters(&mut self) {
+        clear_counters!(
+            self,
+            intra_frame_y_mode_cdf,
+            y_mode_cdf,
+            uv_mode_cfl_not_allowed_cdf,
+            uv_mode_cfl_allowed_cdf,
+            angle_delta_cdf,
+            intrabc_cdf,
+            partition_w8_cdf,
+            partition_w16_cdf,
+            partition_w32_cdf,
+            partition_w64_cdf,
+            partition_w128_cdf,
+            segment_id_cdf,
+            segment_id_predicted_cdf,
+            tx_8x8_cdf,
+            tx_16x16_cdf,
+            tx_32x32_cdf,
+            tx_64x64_cdf,
+            txfm_split_cdf,
+            filter_intra_mode_cdf,
+            filter_intra_cdf,
+            interp_filter_cdf,
+            motion_mode_cdf,
+            new_mv_cdf,
+            zero_mv_cdf,
+            ref_mv_cdf,
+            compound_mode_cdf,
+            drl_mode_cdf,
+            is_inter_cdf,
+            comp_mode_cdf,
+            skip_mode_cdf,
+            skip_cdf,
+            comp_ref_cdf,
+            comp_bwd_ref_cdf,
+            single_ref_cdf,
+            comp_ref_type_cdf,
+            uni_comp_ref_cdf,
+            comp_group_idx_cdf,
+            compound_idx_cdf,
+            compound_type_cdf,
+            inter_intra_cdf,
+            inter_intra_mode_cdf,
+            wedge_index_cdf,
+            wedge_inter_intra_cdf,
+            use_obmc_cdf,
+            palette_y_mode_cdf,
+            palette_uv_mode_cdf,
+            palette_y_size_cdf,
+            palette_uv_size_cdf,
+            delta_q_cdf,
+            delta_lf_cdf,
+            delta_lf_multi_cdf,
+            intra_tx_type_set1_cdf,
+            intra_tx_type_set2_cdf,
+            inter_tx_type_set1_cdf,
+            inter_tx_type_set2_cdf,
+            inter_tx_type_set3_cdf,
+            cfl_sign_cdf,
+            cfl_alpha_cdf,
+            restoration_type_cdf,
+            use_wiener_cdf,
+            use_sgrproj_cdf,
+            mv_joint_cdf,
+            mv_class_cdf,
+            mv_class0_bit_cdf,
+            mv_class0_fr_cdf,
+            mv_class0_hp_cdf,
+            mv_sign_cdf,
+            mv_bit_cdf,
+            mv_fr_cdf,
+            mv_hp_cdf,
+        );
+
+        clear_palette_color_counters(&mut self.palette_y_color_cdf);
+        clear_palette_color_counters(&mut self.palette_uv_color_cdf);
+        self.coeff.clear_counters();
+    }
+}
+
+/// Loads the cdfs saved in the reference slot `ctx_idx` into the cdfs of the
+/// current frame, with cleared counters.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#reference-frame-loading-process
+pub fn load_cdfs(ctx: &mut ObuContext, ctx_idx: usize) {
+    ctx.cdf = ctx.saved_cdfs[ctx_idx].clone();
+    ctx.cdf.clear_counters();
+}
+
+/// Saves the cdfs of the current frame into the reference slot `ctx_idx`.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#reference-frame-update-process
+pub fn save_cdfs(ctx: &mut ObuContext, ctx_idx: usize) {
+    ctx.saved_cdfs[ctx_idx] = ctx.cdf.clone();
+}
+
+/// Replaces the cdfs of the current frame with the cdfs saved at the end of
+/// the tile context_update_tile_id, this is only invoked when
+/// disable_frame_end_update_cdf is equal to 0.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#frame-end-update-cdf-process
+pub fn frame_end_update_cdf(ctx: &mut ObuContext) {
+    if let Some(cdf) = ctx.context_update_cdf.take() {
+        ctx.cdf = cdf;
+        ctx.cdf.clear_counters();
+    }
+}
@@ -84,6 +84,7 @@ pub const PALETTE_NUM_NEIGHBORS: u8 = 3; // Number of neighbors considered withi
 pub const DELTA_Q_SMALL: u8 = 3; // Value indicating alternative encoding of quantizer index delta values
 pub const DELTA_LF_SMALL: u8 = 3; // Value indicating alternative encoding of loop filter delta values
 pub const QM_TOTAL_SIZE: u16 = 3344; // Number of values in the quantizer matrix
+pub const AOM_QM_BITS: u8 = 5; // Number of bits of precision in the quantizer matrix
 pub const MAX_ANGLE_DELTA: u8 = 3; // Maximum magnitude of AngleDeltaY and AngleDeltaUV
 pub const DIRECTIONAL_MODES: u8 = 8; // Number of directional intra modes
 pub const ANGLE_STEP: u8 = 3; // Number of degrees of step per unit increase in AngleDeltaY or AngleDeltaUV.
@@ -187,3 +188,121 @@ pub const INTRA_FILTER_MODES: u8 = 5; // Number of types of intra filtering
 pub const COEFF_CDF_Q_CTXS: u8 = 4; // Number of selectable context types for the coeff( ) syntax structure
 pub const PRIMARY_REF_NONE: u8 = 7; // Value of primary_ref_frame indicating that there is no primary reference frame
 pub const BUFFER_POOL_MAX_SIZE: u8 = 10; // Number of frames in buffer pool
+pub const NONE: i8 = -1; // Reference frame value indicating that no reference frame is used
+pub const INTRA_FRAME: i8 = 0; // Reference frame value used by intra blocks
+pub const LAST_FRAME: i8 = 1; // First of the forward reference frames
+pub const LAST2_FRAME: i8 = 2; // Second of the forward reference frames
+pub const LAST3_FRAME: i8 = 3; // Third of the forward reference frames
+pub const GOLDEN_FRAME: i8 = 4; // Golden forward reference frame
+pub const BWDREF_FRAME: i8 = 5; // First of the backward reference frames
+pub const ALTREF2_FRAME: i8 = 6; // Second of the backward reference frames
+pub const ALTREF_FRAME: i8 = 7; // Alternative backward reference frame
+pub const BLOCK_4X4: u8 = 0; // Block size of 4 by 4 luma samples
+pub const BLOCK_4X8: u8 = 1; // Block size of 4 by 8 luma samples
+pub const BLOCK_8X4: u8 = 2; // Block size of 8 by 4 luma samples
+pub const BLOCK_8X8: u8 = 3; // Block size of 8 by 8 luma samples
+pub const BLOCK_8X16: u8 = 4; // Block size of 8 by 16 luma samples
+pub const BLOCK_16X8: u8 = 5; // Block size of 16 by 8 luma samples
+pub const BLOCK_16X16: u8 = 6; // Block size of 16 by 16 luma samples
+pub const BLOCK_16X32: u8 = 7; // Block size of 16 by 32 luma samples
+pub const BLOCK_32X16: u8 = 8; // Block size of 32 by 16 luma samples
+pub const BLOCK_32X32: u8 = 9; // Block size of 32 by 32 luma samples
+pub const BLOCK_32X64: u8 = 10; // Block size of 32 by 64 luma samples
+pub const BLOCK_64X32: u8 = 11; // Block size of 64 by 32 luma samples
+pub const BLOCK_64X64: u8 = 12; // Block size of 64 by 64 luma samples
+pub const BLOCK_64X128: u8 = 13; // Block size of 64 by 128 luma samples
+pub const BLOCK_128X64: u8 = 14; // Block size of 128 by 64 luma samples
+pub const BLOCK_128X128: u8 = 15; // Block size of 128 by 128 luma samples
+pub const BLOCK_4X16: u8 = 16; // Block size of 4 by 16 luma samples
+pub const BLOCK_16X4: u8 = 17; // Block size of 16 by 4 luma samples
+pub const BLOCK_8X32: u8 = 18; // Block size of 8 by 32 luma samples
+pub const BLOCK_32X8: u8 = 19; // Block size of 32 by 8 luma samples
+pub const BLOCK_16X64: u8 = 20; // Block size of 16 by 64 luma samples
+pub const BLOCK_64X16: u8 = 21; // Block size of 64 by 16 luma samples
+pub const TX_4X4: u8 = 0; // Transform size of 4 by 4 samples
+pub const TX_8X8: u8 = 1; // Transform size of 8 by 8 samples
+pub const TX_16X16: u8 = 2; // Transform size of 16 by 16 samples
+pub const TX_32X32: u8 = 3; // Transform size of 32 by 32 samples
+pub const TX_64X64: u8 = 4; // Transform size of 64 by 64 samples
+pub const TX_4X8: u8 = 5; // Transform size of 4 by 8 samples
+pub const TX_8X4: u8 = 6; // Transform size of 8 by 4 samples
+pub const TX_8X16: u8 = 7; // Transform size of 8 by 16 samples
+pub const TX_16X8: u8 = 8; // Transform size of 16 by 8 samples
+pub const TX_16X32: u8 = 9; // Transform size of 16 by 32 samples
+pub const TX_32X16: u8 = 10; // Transform size of 32 by 16 samples
+pub const TX_32X64: u8 = 11; // Transform size of 32 by 64 samples
+pub const TX_64X32: u8 = 12; // Transform size of 64 by 32 samples
+pub const TX_4X16: u8 = 13; // Transform size of 4 by 16 samples
+pub const TX_16X4: u8 = 14; // Transform size of 16 by 4 samples
+pub const TX_8X32: u8 = 15; // Transform size of 8 by 32 samples
+pub const TX_32X8: u8 = 16; // Transform size of 32 by 8 samples
+pub const TX_16X64: u8 = 17; // Transform size of 16 by 64 samples
+pub const TX_64X16: u8 = 18; // Transform size of 64 by 16 samples
+pub const TX_SET_DCTONLY: u8 = 0; // Transform set using only DCT_DCT
+pub const TX_SET_INTRA_1: u8 = 1; // Intra transform set of 7 transform types
+pub const TX_SET_INTRA_2: u8 = 2; // Intra transform set of 5 transform types
+pub const TX_SET_INTER_1: u8 = 1; // Inter transform set of all 16 transform types
+pub const TX_SET_INTER_2: u8 = 2; // Inter transform set of 12 transform types
+pub const TX_SET_INTER_3: u8 = 3; // Inter transform set of 2 transform types
+pub const PARTITION_NONE: u8 = 0; // The block is not split
+pub const PARTITION_HORZ: u8 = 1; // The block is split into two horizontal halves
+pub const PARTITION_VERT: u8 = 2; // The block is split into two vertical halves
+pub const PARTITION_SPLIT: u8 = 3; // The block is split into four quarters
+pub const PARTITION_HORZ_A: u8 = 4; // The top half is split into two quarters, the bottom half is not split
+pub const PARTITION_HORZ_B: u8 = 5; // The top half is not split, the bottom half is split into two quarters
+pub const PARTITION_VERT_A: u8 = 6; // The left half is split into two quarters, the right half is not split
+pub const PARTITION_VERT_B: u8 = 7; // The left half is not split, the right half is split into two quarters
+pub const PARTITION_HORZ_4: u8 = 8; // The block is split into four horizontal strips
+pub const PARTITION_VERT_4: u8 = 9; // The block is split into four vertical strips
+pub const DC_PRED: u8 = 0; // Intra prediction from the average of the above and left samples
+pub const V_PRED: u8 = 1; // Directional intra prediction at 90 degrees
+pub const H_PRED: u8 = 2; // Directional intra prediction at 180 degrees
+pub const D45_PRED: u8 = 3; // Directional intra prediction at 45 degrees
+pub const D135_PRED: u8 = 4; // Directional intra prediction at 135 degrees
+pub const D113_PRED: u8 = 5; // Directional intra prediction at 113 degrees
+pub const D157_PRED: u8 = 6; // Directional intra prediction at 157 degrees
+pub const D203_PRED: u8 = 7; // Directional intra prediction at 203 degrees
+pub const D67_PRED: u8 = 8; // Directional intra prediction at 67 degrees
+pub const SMOOTH_PRED: u8 = 9; // Intra prediction with quadratic interpolation in both directions
+pub const SMOOTH_V_PRED: u8 = 10; // Intra prediction with quadratic interpolation in the vertical direction
+pub const SMOOTH_H_PRED: u8 = 11; // Intra prediction with quadratic interpolation in the horizontal direction
+pub const PAETH_PRED: u8 = 12; // Intra prediction from the neighbor closest to the Paeth estimate
+pub const UV_CFL_PRED: u8 = 13; // Chroma prediction from the reconstructed luma
+pub const FILTER_DC_PRED: u8 = 0; // Recursive filter intra mode based on DC prediction
+pub const FILTER_V_PRED: u8 = 1; // Recursive filter intra mode based on vertical prediction
+pub const FILTER_H_PRED: u8 = 2; // Recursive filter intra mode based on horizontal prediction
+pub const FILTER_D157_PRED: u8 = 3; // Recursive filter intra mode based on 157 degrees prediction
+pub const FILTER_PAETH_PRED: u8 = 4; // Recursive filter intra mode based on Paeth prediction
+pub const CFL_SIGN_ZERO: u8 = 0; // The chroma from luma alpha is zero
+pub const CFL_SIGN_NEG: u8 = 1; // The chroma from luma alpha is negative
+pub const CFL_SIGN_POS: u8 = 2; // The chroma from luma alpha is positive
+pub const NEARESTMV: u8 = 13; // Inter prediction with the nearest motion vector of the stack
+pub const NEARMV: u8 = 14; // Inter prediction with one of the near motion vectors of the stack
+pub const GLOBALMV: u8 = 15; // Inter prediction with the global motion vector
+pub const NEWMV: u8 = 16; // Inter prediction with a coded motion vector
+pub const NEAREST_NEARESTMV: u8 = 17; // Compound prediction using the nearest motion vectors for both references
+pub const NEAR_NEARMV: u8 = 18; // Compound prediction using near motion vectors for both references
+pub const NEAREST_NEWMV: u8 = 19; // Compound prediction using the nearest motion vector and a coded motion vector
+pub const NEW_NEARESTMV: u8 = 20; // Compound prediction using a coded motion vector and the nearest motion vector
+pub const NEAR_NEWMV: u8 = 21; // Compound prediction using a near motion vector and a coded motion vector
+pub const NEW_NEARMV: u8 = 22; // Compound prediction using a coded motion vector and a near motion vector
+pub const GLOBAL_GLOBALMV: u8 = 23; // Compound prediction using the global motion vectors for both references
+pub const NEW_NEWMV: u8 = 24; // Compound prediction using coded motion vectors for both references
+pub const SINGLE_REFERENCE: u8 = 0; // The block is predicted from a single reference frame
+pub const COMPOUND_REFERENCE: u8 = 1; // The block is predicted from two reference frames
+pub const UNIDIR_COMP_REFERENCE: u8 = 0; // Both reference frames are in the same direction
+pub const BIDIR_COMP_REFERENCE: u8 = 1; // The reference frames are in different directions
+pub const COMPOUND_WEDGE: u8 = 0; // Compound prediction blended with a wedge mask
+pub const COMPOUND_DIFFWTD: u8 = 1; // Compound prediction blended with a mask based on the difference of the predictions
+pub const COMPOUND_AVERAGE: u8 = 2; // Compound prediction using the average of the predictions
+pub const COMPOUND_INTRA: u8 = 3; // Inter intra prediction blended with a smooth mask
+pub const COMPOUND_DISTANCE: u8 = 4; // Compound prediction weighted by the distance to the reference frames
+pub const II_DC_PRED: u8 = 0; // Inter intra prediction using DC prediction
+pub const II_V_PRED: u8 = 1; // Inter intra prediction using vertical prediction
+pub const II_H_PRED: u8 = 2; // Inter intra prediction using horizontal prediction
+pub const II_SMOOTH_PRED: u8 = 3; // Inter intra prediction using smooth prediction
+pub const MV_JOINT_ZERO: u8 = 0; // Both motion vector components are zero
+pub const MV_JOINT_HNZVZ: u8 = 1; // The horizontal component is nonzero, the vertical component is zero
+pub const MV_JOINT_HZVNZ: u8 = 2; // The horizontal component is zero, the vertical component is nonzero
+pub const MV_JOINT_HNZVNZ: u8 = 3; // Both motion vector components are nonzero
+pub const MV_CLASS_0: u8 = 0; // The smallest motion vector class
@@ -0,0 +1,486 @@
+//! The inter prediction processes that predict a block from the samples of
+//! a reference frame.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#inter-prediction-process
+
+use crate::{
+    constants::{
+        MASK_MASTER_SIZE, MAX_FRAME_DISTANCE, REF_SCALE_SHIFT, SCALE_SUBPEL_BITS, SUBPEL_BITS,
+        SUBPEL_MASK,
+    },
+    obu::frame_header::InterpolationFilter,
+    picture::Plane,
+    tables::{NUM_4X4_BLOCKS_HIGH, NUM_4X4_BLOCKS_WIDE},
+    tile::mode_info::Mv,
+};
+
+/// The predicted samples of a block before the final rounding, row by row.
+pub type InterPrediction = [[i32; 128]; 128];
+
+/// The weights between 0 and 64 of the first prediction of a masked
+/// compound block, in luma samples.
+pub type Mask = [[u8; 128]; 128];
+
+const WEDGE_HORIZONTAL: usize = 0;
+const WEDGE_VERTICAL: usize = 1;
+const WEDGE_OBLIQUE27: usize = 2;
+const WEDGE_OBLIQUE63: usize = 3;
+const WEDGE_OBLIQUE117: usize = 4;
+const WEDGE_OBLIQUE153: usize = 5;
+const WEDGE_DIRECTIONS: usize = 6;
+
+const MASTER_SIZE: usize = MASK_MASTER_SIZE as usize;
+
+#[inline]
+fn round2(x: i32, n: u32) -> i32 {
+    if n == 0 {
+        x
+    } else {
+        (x + (1 << (n - 1))) >> n
+    }
+}
+
+#[inline]
+fn round2_signed(x: i64, n: u32) -> i64 {
+    if x >= 0 {
+        (x + (1 << (n - 1))) >> n
+    } else {
+        -((-x + (1 << (n - 1))) >> n)
+    }
+}
+
+/// The position of the block at `(x, y)` in a plane subsampled by
+/// `subsampling`, displaced by the motion vector `mv`, in a reference frame
+/// of `ref_size` (upscaled width and height) samples predicting a frame of
+/// `frame_size` samples, and the steps between its samples, in units of
+/// 1/1024 sample of the reference.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#motion-vector-scaling-process
+pub fn motion_vector_scaling(
+    frame_size: (u16, u16),
+    ref_size: (u16, u16),
+    subsampling: (u32, u32),
+    x: u32,
+    y: u32,
+    mv: Mv,
+) -> (i32, i32, i32, i32) {
+    let (frame_width, frame_height) = (frame_size.0 as i32, frame_size.1 as i32);
+    let (sub_x, sub_y) = subsampling;
+    let x_scale = (((ref_size.0 as i32) << REF_SCALE_SHIFT) + frame_width / 2) / frame_width;
+    let y_scale = (((ref_size.1 as i32) << REF_SCALE_SHIFT) + frame_height / 2) / frame_height;
+
+    let half_sample = 1 << (SUBPEL_BITS - 1);
+    let orig_x = ((x as i32) << SUBPEL_BITS) + ((2 * mv[1]) >> sub_x) + half_sample;
+    let orig_y = ((y as i32) << SUBPEL_BITS) + ((2 * mv[0]) >> sub_y) + half_sample;
+    let base_x = orig_x as i64 * x_scale as i64 - ((half_sample as i64) << REF_SCALE_SHIFT);
+    let base_y = orig_y as i64 * y_scale as i64 - ((half_sample as i64) << REF_SCALE_SHIFT);
+    let off = (1 << (SCALE_SUBPEL_BITS - SUBPEL_BITS)) / 2;
+    let shift = (REF_SCALE_SHIFT + SUBPEL_BITS - SCALE_SUBPEL_BITS) as u32;
+    let step_shift = (REF_SCALE_SHIFT - SCALE_SUBPEL_BITS) as u32;
+    (
+        round2_signed(base_x, shift) as i32 + off,
+        round2_signed(base_y, shift) as i32 + off,
+        round2_signed(x_scale as i64, step_shift) as i32,
+        round2_signed(y_scale as i64, step_shift) as i32,
+    )
+}
+
+/// The index in Subpel_Filters of the interpolation filter `filter` applied
+/// along a side of `size` samples, the small blocks use the 4 taps filters.
+fn filter_idx(filter: InterpolationFilter, size: usize) -> usize {
+    match filter {
+        InterpolationFilter::Eighttap | InterpolationFilter::EighttapSharp if size <= 4 => 4,
+        InterpolationFilter::EighttapSmooth if size <= 4 => 5,
+        filter => filter as usize,
+    }
+}
+
+/// Predicts a block of `w` by `h` samples from the plane `reference` with
+/// the separable interpolation filters `interp_filter`, horizontally then
+/// vertically. The position of the block and the steps between its samples
+/// are in units of 1/1024 sample, the samples of the reference past `last_x`
+/// and `last_y` repeat the samples at its edges.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#block-inter-prediction-process
+#[allow(clippy::too_many_arguments)]
+pub fn block_inter_prediction(
+    pred: &mut InterPrediction,
+    reference: &Plane,
+    last_x: i32,
+    last_y: i32,
+    x: i32,
+    y: i32,
+    x_step: i32,
+    y_step: i32,
+    w: usize,
+    h: usize,
+    interp_filter: [InterpolationFilter; 2],
+    inter_round: [u32; 2],
+) {
+    let intermediate_height = ((((h as i32 - 1) * y_step + (1 << SCALE_SUBPEL_BITS) - 1)
+        >> SCALE_SUBPEL_BITS)
+        + 8) as usize;
+
+    let filter_x = &SUBPEL_FILTERS[filter_idx(interp_filter[1], w)];
+    let filter_y = &SUBPEL_FILTERS[filter_idx(interp_filter[0], h)];
+    let mut intermediate = vec![[0; 128]; intermediate_height];
+    for (r, row) in intermediate.iter_mut().enumerate() {
+        let ref_row = reference.row((((y >> 10) + r as i32 - 3).clamp(0, last_y)) as usize);
+        for (c, v) in row.iter_mut().take(w).enumerate() {
+            let p = x + x_step * c as i32;
+            let filter = &filter_x[((p >> 6) & SUBPEL_MASK as i32) as usize];
+            let s = filter
+                .iter()
+                .enumerate()
+                .map(|(t, f)| {
+                    f * ref_row[((p >> 10) + t as i32 - 3).clamp(0, last_x) as usize] as i32
+                })
+                .sum();
+
+            *v = round2(s, inter_round[0]);
+        }
+    }
+
+    for (r, row) in pred.iter_mut().take(h).enumerate() {
+        let p = (y & 1023) + y_step * r as i32;
+        let filter = &filter_y[((p >> 6) & SUBPEL_MASK as i32) as usize];
+        for (c, v) in row.iter_mut().take(w).enumerate() {
+            let s = filter
+                .iter()
+                .enumerate()
+                .map(|(t, f)| f * intermediate[(p >> 10) as usize + t][c])
+                .sum();
+
+            *v = round2(s, inter_round[1]);
+        }
+    }
+}
+
+/// Fills `mask` with the wedge `index` of the block size `bsize`, `sign`
+/// selects which of the two predictions is on the side of the wedge with
+/// the largest weights.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#wedge-mask-process
+pub fn wedge_mask(mask: &mut Mask, bsize: u8, sign: u8, index: u8) {
+    let w4 = NUM_4X4_BLOCKS_WIDE[bsize as usize];
+    let h4 = NUM_4X4_BLOCKS_HIGH[bsize as usize];
+    let shape = if h4 > w4 {
+        0
+    } else if h4 < w4 {
+        1
+    } else {
+        2
+    };
+
+    let (w, h) = (4 * w4 as usize, 4 * h4 as usize);
+    let [dir, x_off, y_off] = WEDGE_CODEBOOK[shape][index as usize];
+    let master = &MASTER_MASK[dir];
+    let x_off = MASTER_SIZE / 2 - ((x_off * w) >> 3);
+    let y_off = MASTER_SIZE / 2 - ((y_off * h) >> 3);
+
+    // The wedges are oriented so that the first prediction has the largest
+    // weights on average along the top and left edges when sign is 0.
+    let sum = master[y_off][x_off..x_off + w]
+        .iter()
+        .chain(master[y_off + 1..y_off + h].iter().map(|row| &row[x_off]))
+        .map(|m| *m as usize)
+        .sum::<usize>();
+
+    let avg = (sum + (w + h - 1) / 2) / (w + h - 1);
+    let flip_sign = (avg < 32) as u8;
+    for (row, master) in mask.iter_mut().zip(&master[y_off..y_off + h]) {
+        for (m, master) in row.iter_mut().zip(&master[x_off..x_off + w]) {
+            *m = if sign == flip_sign {
+                *master
+            } else {
+                64 - master
+            };
+        }
+    }
+}
+
+/// Fills `mask` with weights growing with the difference between the two
+/// predictions `preds` of `w` by `h` samples, the weights go to the second
+/// prediction instead when `mask_type` is 1.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#difference-weight-mask-process
+pub fn difference_weight_mask(
+    mask: &mut Mask,
+    preds: &[InterPrediction; 2],
+    w: usize,
+    h: usize,
+    mask_type: u8,
+    bit_depth: u8,
+    inter_post_round: u32,
+) {
+    for ((row, pred0), pred1) in mask.iter_mut().zip(&preds[0]).zip(&preds[1]).take(h) {
+        for ((m, p0), p1) in row.iter_mut().zip(pred0).zip(pred1).take(w) {
+            let diff = round2((p0 - p1).abs(), (bit_depth - 8) as u32 + inter_post_round);
+            let v = (38 + diff / 16).clamp(0, 64) as u8;
+            *m = if mask_type == 1 { 64 - v } else { v };
+        }
+    }
+}
+
+/// The weights of the first and the second predictions of a compound block
+/// from the distances in display order `dist` of its two reference frames,
+/// the closest reference frame gets the largest weight.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#distance-weights-process
+pub fn distance_weights(dist: [i32; 2]) -> (i32, i32) {
+    let dist = dist.map(|d| d.abs().min(MAX_FRAME_DISTANCE as i32));
+    let (d0, d1) = (dist[1], dist[0]);
+    let order = (d0 <= d1) as usize;
+    let i = if d0 == 0 || d1 == 0 {
+        3
+    } else {
+        QUANT_DIST_WEIGHT
+            .iter()
+            .take(3)
+            .position(|c| {
+                let (c0, c1) = (c[order], c[1 - order]);
+                if order == 1 {
+                    d0 * c0 > d1 * c1
+                } else {
+                    d0 * c0 < d1 * c1
+                }
+            })
+            .unwrap_or(3)
+    };
+
+    (QUANT_DIST_LOOKUP[i][order], QUANT_DIST_LOOKUP[i][1 - order])
+}
+
+/// The MasterMask of each wedge direction, the masks of the wedges are
+/// windows of the size of the block in these masks.
+static MASTER_MASK: [[[u8; MASTER_SIZE]; MASTER_SIZE]; WEDGE_DIRECTIONS] = master_mask();
+
+const fn master_mask() -> [[[u8; MASTER_SIZE]; MASTER_SIZE]; WEDGE_DIRECTIONS] {
+    let mut master = [[[0; MASTER_SIZE]; MASTER_SIZE]; WEDGE_DIRECTIONS];
+    let (w, h) = (MASTER_SIZE, MASTER_SIZE);
+    let mut j = 0;
+    while j < w {
+        let mut shift = (MASTER_SIZE / 4) as i32;
+        let mut i = 0;
+        while i < h {
+            let x = clip_master(j as i32 - shift);
+            master[WEDGE_OBLIQUE63][i][j] = WEDGE_MASTER_OBLIQUE_EVEN[x];
+            shift -= 1;
+            let x = clip_master(j as i32 - shift);
+            master[WEDGE_OBLIQUE63][i + 1][j] = WEDGE_MASTER_OBLIQUE_ODD[x];
+            master[WEDGE_VERTICAL][i][j] = WEDGE_MASTER_VERTICAL[j];
+            master[WEDGE_VERTICAL][i + 1][j] = WEDGE_MASTER_VERTICAL[j];
+            i += 2;
+        }
+
+        j += 1;
+    }
+
+    let mut i = 0;
+    while i < h {
+        let mut j = 0;
+        while j < w {
+            let m = master[WEDGE_OBLIQUE63][i][j];
+            master[WEDGE_OBLIQUE27][j][i] = m;
+            master[WEDGE_OBLIQUE117][i][w - 1 - j] = 64 - m;
+            master[WEDGE_OBLIQUE153][w - 1 - j][i] = 64 - m;
+            master[WEDGE_HORIZONTAL][j][i] = master[WEDGE_VERTICAL][i][j];
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    master
+}
+
+const fn clip_master(x: i32) -> usize {
+    if x < 0 {
+        0
+    } else if x > MASTER_SIZE as i32 - 1 {
+        MASTER_SIZE - 1
+    } else {
+        x as usize
+    }
+}
+
+/// The Subpel_Filters of the inter prediction: the 8 taps of each 1/16
+/// sample position of the regular, smooth, sharp and bilinear filters, then
+/// of the 4 taps regular and smooth filters used by the small blocks.
+#[rustfmt::skip]
+const SUBPEL_FILTERS: [[[i32; 8]; 16]; 6] = [
+    [
+        [0, 0, 0, 128, 0, 0, 0, 0],
+        [0, 2, -6, 126, 8, -2, 0, 0],
+        [0, 2, -10, 122, 18, -4, 0, 0],
+        [0, 2, -12, 116, 28, -8, 2, 0],
+        [0, 2, -14, 110, 38, -10, 2, 0],
+        [0, 2, -14, 102, 48, -12, 2, 0],
+        [0, 2, -16, 94, 58, -12, 2, 0],
+        [0, 2, -14, 84, 66, -12, 2, 0],
+        [0, 2, -14, 76, 76, -14, 2, 0],
+        [0, 2, -12, 66, 84, -14, 2, 0],
+        [0, 2, -12, 58, 94, -16, 2, 0],
+        [0, 2, -12, 48, 102, -14, 2, 0],
+        [0, 2, -10, 38, 110, -14, 2, 0],
+        [0, 2, -8, 28, 116, -12, 2, 0],
+        [0, 0, -4, 18, 122, -10, 2, 0],
+        [0, 0, -2, 8, 126, -6, 2, 0],
+    ],
+    [
+        [0, 0, 0, 128, 0, 0, 0, 0],
+        [0, 2, 28, 62, 34, 2, 0, 0],
+        [0, 0, 26, 62, 36, 4, 0, 0],
+        [0, 0, 22, 62, 40, 4, 0, 0],
+        [0, 0, 20, 60, 42, 6, 0, 0],
+        [0, 0, 18, 58, 44, 8, 0, 0],
+        [0, 0, 16, 56, 46, 10, 0, 0],
+        [0, -2, 16, 54, 48, 12, 0, 0],
+        [0, -2, 14, 52, 52, 14, -2, 0],
+        [0, 0, 12, 48, 54, 16, -2, 0],
+        [0, 0, 10, 46, 56, 16, 0, 0],
+        [0, 0, 8, 44, 58, 18, 0, 0],
+        [0, 0, 6, 42, 60, 20, 0, 0],
+        [0, 0, 4, 40, 62, 22, 0, 0],
+        [0, 0, 4, 36, 62, 26, 0, 0],
+        [0, 0, 2, 34, 62, 28, 2, 0],
+    ],
+    [
+        [0, 0, 0, 128, 0, 0, 0, 0],
+        [-2, 2, -6, 126, 8, -2, 2, 0],
+        [-2, 6, -12, 124, 16, -6, 4, -2],
+        [-2, 8, -18, 120, 26, -10, 6, -2],
+        [-4, 10, -22, 116, 38, -14, 6, -2],
+        [-4, 10, -22, 108, 48, -18, 8, -2],
+        [-4, 10, -24, 100, 60, -20, 8, -2],
+        [-4, 10, -24, 90, 70, -22, 10, -2],
+        [-4, 12, -24, 80, 80, -24, 12, -4],
+        [-2, 10, -22, 70, 90, -24, 10, -4],
+        [-2, 8, -20, 60, 100, -24, 10, -4],
+        [-2, 8, -18, 48, 108, -22, 10, -4],
+        [-2, 6, -14, 38, 116, -22, 10, -4],
+        [-2, 6, -10, 26, 120, -18, 8, -2],
+        [-2, 4, -6, 16, 124, -12, 6, -2],
+        [0, 2, -2, 8, 126, -6, 2, -2],
+    ],
+    [
+        [0, 0, 0, 128, 0, 0, 0, 0],
+        [0, 0, 0, 120, 8, 0, 0, 0],
+        [0, 0, 0, 112, 16, 0, 0, 0],
+        [0, 0, 0, 104, 24, 0, 0, 0],
+        [0, 0, 0, 96, 32, 0, 0, 0],
+        [0, 0, 0, 88, 40, 0, 0, 0],
+        [0, 0, 0, 80, 48, 0, 0, 0],
+        [0, 0, 0, 72, 56, 0, 0, 0],
+        [0, 0, 0, 64, 64, 0, 0, 0],
+        [0, 0, 0, 56, 72, 0, 0, 0],
+        [0, 0, 0, 48, 80, 0, 0, 0],
+        [0, 0, 0, 40, 88, 0, 0, 0],
+        [0, 0, 0, 32, 96, 0, 0, 0],
+        [0, 0, 0, 24, 104, 0, 0, 0],
+        [0, 0, 0, 16, 112, 0, 0, 0],
+        [0, 0, 0, 8, 120, 0, 0, 0],
+    ],
+    [
+        [0, 0, 0, 128, 0, 0, 0, 0],
+        [0, 0, -4, 126, 8, -2, 0, 0],
+        [0, 0, -8, 122, 18, -4, 0, 0],
+        [0, 0, -10, 116, 28, -6, 0, 0],
+        [0, 0, -12, 110, 38, -8, 0, 0],
+        [0, 0, -12, 102, 48, -10, 0, 0],
+        [0, 0, -14, 94, 58, -10, 0, 0],
+        [0, 0, -12, 84, 66, -10, 0, 0],
+        [0, 0, -12, 76, 76, -12, 0, 0],
+        [0, 0, -10, 66, 84, -12, 0, 0],
+        [0, 0, -10, 58, 94, -14, 0, 0],
+        [0, 0, -10, 48, 102, -12, 0, 0],
+        [0, 0, -8, 38, 110, -12, 0, 0],
+        [0, 0, -6, 28, 116, -10, 0, 0],
+        [0, 0, -4, 18, 122, -8, 0, 0],
+        [0, 0, -2, 8, 126, -4, 0, 0],
+    ],
+    [
+        [0, 0, 0, 128, 0, 0, 0, 0],
+        [0, 0, 30, 62, 34, 2, 0, 0],
+        [0, 0, 26, 62, 36, 4, 0, 0],
+        [0, 0, 22, 62, 40, 4, 0, 0],
+        [0, 0, 20, 60, 42, 6, 0, 0],
+        [0, 0, 18, 58, 44, 8, 0, 0],
+        [0, 0, 16, 56, 46, 10, 0, 0],
+        [0, 0, 14, 54, 48, 12, 0, 0],
+        [0, 0, 12, 52, 52, 12, 0, 0],
+        [0, 0, 12, 48, 54, 14, 0, 0],
+        [0, 0, 10, 46, 56, 16, 0, 0],
+        [0, 0, 8, 44, 58, 18, 0, 0],
+        [0, 0, 6, 42, 60, 20, 0, 0],
+        [0, 0, 4, 40, 62, 22, 0, 0],
+        [0, 0, 4, 36, 62, 26, 0, 0],
+        [0, 0, 2, 34, 62, 30, 0, 0],
+    ],
+];
+
+#[rustfmt::skip]
+const WEDGE_MASTER_OBLIQUE_ODD: [u8; MASTER_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 6, 18,
+    37, 53, 60, 63, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64,
+    64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64,
+];
+
+#[rustfmt::skip]
+const WEDGE_MASTER_OBLIQUE_EVEN: [u8; MASTER_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 4, 11, 27,
+    46, 58, 62, 63, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64,
+    64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64,
+];
+
+#[rustfmt::skip]
+const WEDGE_MASTER_VERTICAL: [u8; MASTER_SIZE] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 7, 21,
+    43, 57, 62, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64,
+    64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64, 64,
+];
+
+/// The direction and the offsets in 1/8 of the block size of the center of
+/// each wedge, for the blocks higher than wide, wider than high and square.
+#[rustfmt::skip]
+const WEDGE_CODEBOOK: [[[usize; 3]; 16]; 3] = [
+    [
+        [WEDGE_OBLIQUE27, 4, 4], [WEDGE_OBLIQUE63, 4, 4],
+        [WEDGE_OBLIQUE117, 4, 4], [WEDGE_OBLIQUE153, 4, 4],
+        [WEDGE_HORIZONTAL, 4, 2], [WEDGE_HORIZONTAL, 4, 4],
+        [WEDGE_HORIZONTAL, 4, 6], [WEDGE_VERTICAL, 4, 4],
+        [WEDGE_OBLIQUE27, 4, 2], [WEDGE_OBLIQUE27, 4, 6],
+        [WEDGE_OBLIQUE153, 4, 2], [WEDGE_OBLIQUE153, 4, 6],
+        [WEDGE_OBLIQUE63, 2, 4], [WEDGE_OBLIQUE63, 6, 4],
+        [WEDGE_OBLIQUE117, 2, 4], [WEDGE_OBLIQUE117, 6, 4],
+    ],
+    [
+        [WEDGE_OBLIQUE27, 4, 4], [WEDGE_OBLIQUE63, 4, 4],
+        [WEDGE_OBLIQUE117, 4, 4], [WEDGE_OBLIQUE153, 4, 4],
+        [WEDGE_VERTICAL, 2, 4], [WEDGE_VERTICAL, 4, 4],
+        [WEDGE_VERTICAL, 6, 4], [WEDGE_HORIZONTAL, 4, 4],
+        [WEDGE_OBLIQUE27, 4, 2], [WEDGE_OBLIQUE27, 4, 6],
+        [WEDGE_OBLIQUE153, 4, 2], [WEDGE_OBLIQUE153, 4, 6],
+        [WEDGE_OBLIQUE63, 2, 4], [WEDGE_OBLIQUE63, 6, 4],
+        [WEDGE_OBLIQUE117, 2, 4], [WEDGE_OBLIQUE117, 6, 4],
+    ],
+    [
+        [WEDGE_OBLIQUE27, 4, 4], [WEDGE_OBLIQUE63, 4, 4],
+        [WEDGE_OBLIQUE117, 4, 4], [WEDGE_OBLIQUE153, 4, 4],
+        [WEDGE_HORIZONTAL, 4, 2], [WEDGE_HORIZONTAL, 4, 6],
+        [WEDGE_VERTICAL, 2, 4], [WEDGE_VERTICAL, 6, 4],
+        [WEDGE_OBLIQUE27, 4, 2], [WEDGE_OBLIQUE27, 4, 6],
+        [WEDGE_OBLIQUE153, 4, 2], [WEDGE_OBLIQUE153, 4, 6],
+        [WEDGE_OBLIQUE63, 2, 4], [WEDGE_OBLIQUE63, 6, 4],
+        [WEDGE_OBLIQUE117, 2, 4], [WEDGE_OBLIQUE117, 6, 4],
+    ],
+];
+
+/// The ratios of the distances to the reference frames that select the
+/// distance weights, then the weights of the closest and the farthest
+/// reference frames.
+const QUANT_DIST_WEIGHT: [[i32; 2]; 4] = [[2, 3], [2, 5], [2, 7], [1, MAX_FRAME_DISTANCE as i32]];
+const QUANT_DIST_LOOKUP: [[i32; 2]; 4] = [[9, 7], [11, 5], [12, 4], [13, 3]];
@@ -0,0 +1,676 @@
+//! The intra prediction processes that predict a block from the samples
+//! above and to the left of it.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#intra-prediction-process
+
+use std::ops::{Index, IndexMut};
+
+use crate::{
+    constants::{INTRA_EDGE_KERNELS, INTRA_EDGE_TAPS, INTRA_FILTER_MODES, INTRA_FILTER_SCALE_BITS},
+    picture::Plane,
+};
+
+/// The number of entries of an edge before its first sample, the upsampling
+/// of an edge writes down to the entry -2.
+const EDGE_OFFSET: usize = 16;
+
+/// The number of entries of an edge, the largest blocks read up to `w + h`
+/// samples and the upsampled edges up to twice as many for small blocks.
+const EDGE_SIZE: usize = EDGE_OFFSET + 2 * 128 + 16;
+
+/// The samples above (AboveRow) or to the left (LeftCol) of a block, indexed
+/// from -1 for the sample at the top left corner of the block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge([u16; EDGE_SIZE]);
+
+impl Default for Edge {
+    fn default() -> Self {
+        Self([0; EDGE_SIZE])
+    }
+}
+
+impl Index<i32> for Edge {
+    type Output = u16;
+
+    #[inline]
+    fn index(&self, i: i32) -> &u16 {
+        &self.0[(i + EDGE_OFFSET as i32) as usize]
+    }
+}
+
+impl IndexMut<i32> for Edge {
+    #[inline]
+    fn index_mut(&mut self, i: i32) -> &mut u16 {
+        &mut self.0[(i + EDGE_OFFSET as i32) as usize]
+    }
+}
+
+/// The predicted samples of a block, row by row.
+pub type Prediction = [[u16; 64]; 64];
+
+#[inline]
+fn round2(x: u32, n: u32) -> u32 {
+    if n == 0 {
+        x
+    } else {
+        (x + (1 << (n - 1))) >> n
+    }
+}
+
+#[inline]
+fn round2_signed(x: i32, n: u32) -> i32 {
+    if x >= 0 {
+        (x + (1 << (n - 1))) >> n
+    } else {
+        -((-x + (1 << (n - 1))) >> n)
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#basic-intra-prediction-process
+pub fn predict_paeth(pred: &mut Prediction, above: &Edge, left: &Edge, log2_w: u32, log2_h: u32) {
+    let top_left = above[-1] as i32;
+    for (i, row) in pred.iter_mut().take(1 << log2_h).enumerate() {
+        for (j, v) in row.iter_mut().take(1 << log2_w).enumerate() {
+            let (a, l) = (above[j as i32] as i32, left[i as i32] as i32);
+            let base = a + l - top_left;
+            let p_left = (base - l).abs();
+            let p_top = (base - a).abs();
+            let p_top_left = (base - top_left).abs();
+            *v = if p_left <= p_top && p_left <= p_top_left {
+                l
+            } else if p_top <= p_top_left {
+                a
+            } else {
+                top_left
+            } as u16;
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#dc-intra-prediction-process
+#[allow(clippy::too_many_arguments)]
+pub fn predict_dc(
+    pred: &mut Prediction,
+    above: &Edge,
+    left: &Edge,
+    have_above: bool,
+    have_left: bool,
+    log2_w: u32,
+    log2_h: u32,
+    bit_depth: u8,
+) {
+    let (w, h) = (1 << log2_w, 1 << log2_h);
+    let sum_above = || (0..w).map(|k| above[k] as u32).sum::<u32>();
+    let sum_left = || (0..h).map(|k| left[k] as u32).sum::<u32>();
+    let avg = if have_above && have_left {
+        let sum = sum_above() + sum_left();
+        (sum + ((w + h) as u32 >> 1)) / (w + h) as u32
+    } else if have_left {
+        round2(sum_left(), log2_h)
+    } else if have_above {
+        round2(sum_above(), log2_w)
+    } else {
+        1 << (bit_depth - 1)
+    };
+
+    for row in pred.iter_mut().take(h as usize) {
+        row[..w as usize].fill(avg as u16);
+    }
+}
+
+/// Predicts the block in units of 4x2 samples with the filter intra mode
+/// `mode`, each unit is predicted from the 7 samples above and to the left
+/// of it, which are predicted samples for the units inside the block.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#recursive-intra-prediction-process
+#[allow(clippy::too_many_arguments)]
+pub fn predict_filter_intra(
+    pred: &mut Prediction,
+    above: &Edge,
+    left: &Edge,
+    log2_w: u32,
+    log2_h: u32,
+    mode: u8,
+    bit_depth: u8,
+) {
+    let max = (1 << bit_depth) - 1;
+    let taps = &INTRA_FILTER_TAPS[mode as usize];
+    for i2 in 0..1 << (log2_h - 1) {
+        for j4 in 0..1 << (log2_w - 2) {
+            let mut p = [0; 7];
+            for (i, p) in p.iter_mut().enumerate() {
+                *p = if i < 5 {
+                    if i2 == 0 {
+                        above[(j4 << 2) + i as i32 - 1]
+                    } else if j4 == 0 && i == 0 {
+                        left[(i2 << 1) - 1]
+                    } else {
+                        pred[((i2 << 1) - 1) as usize][((j4 << 2) + i as i32 - 1) as usize]
+                    }
+                } else if j4 == 0 {
+                    left[(i2 << 1) + i as i32 - 5]
+                } else {
+                    pred[((i2 << 1) + i as i32 - 5) as usize][((j4 << 2) - 1) as usize]
+                } as i32;
+            }
+
+            for (i, taps) in taps.iter().enumerate() {
+                let pr = taps.iter().zip(p).map(|(t, p)| t * p).sum();
+                pred[(i2 << 1) as usize + (i >> 2)][(j4 << 2) as usize + (i & 3)] =
+                    round2_signed(pr, INTRA_FILTER_SCALE_BITS as u32).clamp(0, max) as u16;
+            }
+        }
+    }
+}
+
+/// The weights of the smooth predictions for a side of `1 << log2` samples.
+#[inline]
+fn sm_weights(log2: u32) -> &'static [u8] {
+    &SM_WEIGHTS[1 << log2..2 << log2]
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#smooth-intra-prediction-process
+pub fn predict_smooth(pred: &mut Prediction, above: &Edge, left: &Edge, log2_w: u32, log2_h: u32) {
+    let (w, h) = (1 << log2_w, 1 << log2_h);
+    let (weights_x, weights_y) = (sm_weights(log2_w), sm_weights(log2_h));
+    let (bottom_left, top_right) = (left[h - 1] as u32, above[w - 1] as u32);
+    for (i, row) in pred.iter_mut().take(h as usize).enumerate() {
+        let weight_y = weights_y[i] as u32;
+        for (j, v) in row.iter_mut().take(w as usize).enumerate() {
+            let weight_x = weights_x[j] as u32;
+            let smooth_pred = weight_y * above[j as i32] as u32
+                + (256 - weight_y) * bottom_left
+                + weight_x * left[i as i32] as u32
+                + (256 - weight_x) * top_right;
+            *v = round2(smooth_pred, 9) as u16;
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#smooth-intra-prediction-process
+pub fn predict_smooth_v(
+    pred: &mut Prediction,
+    above: &Edge,
+    left: &Edge,
+    log2_w: u32,
+    log2_h: u32,
+) {
+    let weights_y = sm_weights(log2_h);
+    let bottom_left = left[(1 << log2_h) - 1] as u32;
+    for (i, row) in pred.iter_mut().take(1 << log2_h).enumerate() {
+        let weight_y = weights_y[i] as u32;
+        for (j, v) in row.iter_mut().take(1 << log2_w).enumerate() {
+            let smooth_pred = weight_y * above[j as i32] as u32 + (256 - weight_y) * bottom_left;
+            *v = round2(smooth_pred, 8) as u16;
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#smooth-intra-prediction-process
+pub fn predict_smooth_h(
+    pred: &mut Prediction,
+    above: &Edge,
+    left: &Edge,
+    log2_w: u32,
+    log2_h: u32,
+) {
+    let weights_x = sm_weights(log2_w);
+    let top_right = above[(1 << log2_w) - 1] as u32;
+    for (i, row) in pred.iter_mut().take(1 << log2_h).enumerate() {
+        for (j, v) in row.iter_mut().take(1 << log2_w).enumerate() {
+            let weight_x = weights_x[j] as u32;
+            let smooth_pred = weight_x * left[i as i32] as u32 + (256 - weight_x) * top_right;
+            *v = round2(smooth_pred, 8) as u16;
+        }
+    }
+}
+
+/// The smoothed sample at the top left corner of a block, used by the
+/// directional predictions of the larger blocks that read both edges.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#intra-edge-filter-corner-process
+pub fn filter_corner(above: &Edge, left: &Edge) -> u16 {
+    let s = left[0] as u32 * 5 + above[-1] as u32 * 6 + above[0] as u32 * 5;
+    round2(s, 4) as u16
+}
+
+/// The strength of the edge filter of a `w` by `h` block predicted at an
+/// angle of `delta` degrees from the edge, `filter_type` is set when a
+/// neighboring block uses a smooth prediction.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#intra-edge-filter-strength-selection-process
+pub fn intra_edge_filter_strength(w: u32, h: u32, filter_type: bool, delta: i32) -> u8 {
+    let d = delta.abs();
+    let blk_wh = w + h;
+    if !filter_type {
+        match blk_wh {
+            0..=8 if d >= 56 => 1,
+            9..=16 if d >= 40 => 1,
+            17..=24 if d >= 32 => 3,
+            17..=24 if d >= 16 => 2,
+            17..=24 if d >= 8 => 1,
+            25..=32 if d >= 32 => 3,
+            25..=32 if d >= 4 => 2,
+            25..=32 if d >= 1 => 1,
+            33.. if d >= 1 => 3,
+            _ => 0,
+        }
+    } else {
+        match blk_wh {
+            0..=8 if d >= 64 => 2,
+            0..=8 if d >= 40 => 1,
+            9..=16 if d >= 48 => 2,
+            9..=16 if d >= 20 => 1,
+            17..=24 if d >= 4 => 3,
+            25.. if d >= 1 => 3,
+            _ => 0,
+        }
+    }
+}
+
+/// Whether the edge of a `w` by `h` block predicted at an angle of `delta`
+/// degrees from it is upsampled.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#intra-edge-upsample-selection-process
+pub fn intra_edge_upsample(w: u32, h: u32, filter_type: bool, delta: i32) -> bool {
+    let d = delta.abs();
+    let blk_wh = w + h;
+    if d <= 0 || d >= 40 {
+        false
+    } else if filter_type {
+        blk_wh <= 8
+    } else {
+        blk_wh <= 16
+    }
+}
+
+/// Filters the `size` samples of an edge starting at the top left corner
+/// with the kernel of `strength`, the first sample is left unchanged.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#intra-edge-filter-process
+pub fn filter_edge(edge: &mut Edge, size: i32, strength: u8) {
+    if strength == 0 {
+        return;
+    }
+
+    let mut samples = [0; 2 * 64 + 1];
+    for (i, v) in samples.iter_mut().take(size as usize).enumerate() {
+        *v = edge[i as i32 - 1] as u32;
+    }
+
+    let kernel = &INTRA_EDGE_KERNEL[strength as usize - 1];
+    for i in 1..size {
+        let s: u32 = kernel
+            .iter()
+            .enumerate()
+            .map(|(j, k)| k * samples[(i - 2 + j as i32).clamp(0, size - 1) as usize])
+            .sum();
+
+        edge[i - 1] = ((s + 8) >> 4) as u16;
+    }
+}
+
+/// Doubles the resolution of the `num_px` samples of an edge after its top
+/// left corner, the upsampled edge starts at the entry -2.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#intra-edge-upsample-process
+pub fn upsample_edge(edge: &mut Edge, num_px: i32, bit_depth: u8) {
+    let mut dup = [0; 16 + 3];
+    dup[0] = edge[-1] as i32;
+    for i in -1..num_px {
+        dup[(i + 2) as usize] = edge[i] as i32;
+    }
+
+    dup[(num_px + 2) as usize] = edge[num_px - 1] as i32;
+
+    let max = (1 << bit_depth) - 1;
+    edge[-2] = dup[0] as u16;
+    for i in 0..num_px {
+        let k = i as usize;
+        let s = -dup[k] + 9 * dup[k + 1] + 9 * dup[k + 2] - dup[k + 3];
+        edge[2 * i - 1] = ((s + 8) >> 4).clamp(0, max) as u16;
+        edge[2 * i] = dup[k + 2] as u16;
+    }
+}
+
+/// The interpolation between the samples `base` and `base + 1` of an edge.
+#[inline]
+fn interpolate(edge: &Edge, base: i32, shift: i32) -> u16 {
+    let s = edge[base] as i32 * (32 - shift) + edge[base + 1] as i32 * shift;
+    round2(s as u32, 5) as u16
+}
+
+/// Predicts a block along the angle `p_angle` in degrees, the edges have
+/// been upsampled by 2 when `upsample_above` or `upsample_left` is set.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#directional-intra-prediction-process
+#[allow(clippy::too_many_arguments)]
+pub fn predict_directional(
+    pred: &mut Prediction,
+    above: &Edge,
+    left: &Edge,
+    log2_w: u32,
+    log2_h: u32,
+    p_angle: i32,
+    upsample_above: bool,
+    upsample_left: bool,
+) {
+    let (w, h) = (1 << log2_w, 1 << log2_h);
+    let (upsample_above, upsample_left) = (upsample_above as i32, upsample_left as i32);
+    let dx = if p_angle < 90 {
+        dr_intra_derivative(p_angle)
+    } else if p_angle > 90 && p_angle < 180 {
+        dr_intra_derivative(180 - p_angle)
+    } else {
+        0
+    };
+
+    let dy = if p_angle > 90 && p_angle < 180 {
+        dr_intra_derivative(p_angle - 90)
+    } else if p_angle > 180 {
+        dr_intra_derivative(270 - p_angle)
+    } else {
+        0
+    };
+
+    for (i, row) in pred.iter_mut().take(h as usize).enumerate() {
+        let i = i as i32;
+        for (j, v) in row.iter_mut().take(w as usize).enumerate() {
+            let j = j as i32;
+            *v = if p_angle < 90 {
+                let idx = (i + 1) * dx;
+                let base = (idx >> (6 - upsample_above)) + (j << upsample_above);
+                let shift = ((idx << upsample_above) >> 1) & 0x1f;
+                let max_base_x = (w + h - 1) << upsample_above;
+                if base < max_base_x {
+                    interpolate(above, base, shift)
+                } else {
+                    above[max_base_x]
+                }
+            } else if p_angle > 90 && p_angle < 180 {
+                let idx = (j << 6) - (i + 1) * dx;
+                let base = idx >> (6 - upsample_above);
+                if base >= -(1 << upsample_above) {
+                    let shift = ((idx << upsample_above) >> 1) & 0x1f;
+                    interpolate(above, base, shift)
+                } else {
+                    let idx = (i << 6) - (j + 1) * dy;
+                    let base = idx >> (6 - upsample_left);
+                    let shift = ((idx << upsample_left) >> 1) & 0x1f;
+                    interpolate(left, base, shift)
+                }
+            } else if p_angle > 180 {
+                let idx = (j + 1) * dy;
+                let base = (idx >> (6 - upsample_left)) + (i << upsample_left);
+                let shift = ((idx << upsample_left) >> 1) & 0x1f;
+                interpolate(left, base, shift)
+            } else if p_angle == 90 {
+                above[j]
+            } else {
+                left[i]
+            };
+        }
+    }
+}
+
+/// The AC contribution of the luma to the chroma transform block of `1 <<
+/// log2_w` by `1 << log2_h` samples at `(x, y)`: the luma samples are
+/// subsampled to the chroma resolution with 3 fractional bits, less their
+/// average. The chroma samples past `(max_x, max_y)` reuse the last decoded
+/// luma samples.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#predict-chroma-from-luma-process
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn cfl_luma_ac(
+    luma: &Plane,
+    x: usize,
+    y: usize,
+    log2_w: u32,
+    log2_h: u32,
+    sub_x: u32,
+    sub_y: u32,
+    max_x: usize,
+    max_y: usize,
+) -> [[i32; 32]; 32] {
+    let (w, h) = (1 << log2_w, 1 << log2_h);
+    let mut l = [[0; 32]; 32];
+    let mut luma_avg = 0;
+    for (i, row) in l.iter_mut().take(h).enumerate() {
+        let luma_y = (y + i).min(max_y) << sub_y;
+        for (j, v) in row.iter_mut().take(w).enumerate() {
+            let luma_x = (x + j).min(max_x) << sub_x;
+            let mut t = 0;
+            for dy in 0..=sub_y as usize {
+                for dx in 0..=sub_x as usize {
+                    t += luma.get(luma_x + dx, luma_y + dy) as i32;
+                }
+            }
+
+            *v = t << (3 - sub_x - sub_y);
+            luma_avg += *v;
+        }
+    }
+
+    let luma_avg = round2_signed(luma_avg, log2_w + log2_h);
+    for row in l.iter_mut().take(h) {
+        for v in row.iter_mut().take(w) {
+            *v -= luma_avg;
+        }
+    }
+
+    l
+}
+
+/// Adds the luma AC contribution `ac` scaled by `alpha` (in 1/8 steps) to
+/// the DC prediction of the `w` by `h` chroma block at `(x, y)` in `plane`.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#predict-chroma-from-luma-process
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn predict_cfl(
+    plane: &mut Plane,
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+    ac: &[[i32; 32]; 32],
+    alpha: i32,
+    bit_depth: u8,
+) {
+    let max = (1 << bit_depth) - 1;
+    for (i, ac) in ac.iter().take(h).enumerate() {
+        for (v, ac) in plane.row_mut(y + i)[x..x + w].iter_mut().zip(ac) {
+            let scaled_luma = round2_signed(alpha * ac, 6);
+            *v = (*v as i32 + scaled_luma).clamp(0, max) as u16;
+        }
+    }
+}
+
+/// The Dr_Intra_Derivative table, the step in 1/64 samples along an edge
+/// per sample away from it for the prediction angle `angle`.
+fn dr_intra_derivative(angle: i32) -> i32 {
+    match angle {
+        3 => 1023,
+        6 => 547,
+        9 => 372,
+        14 => 273,
+        17 => 215,
+        20 => 178,
+        23 => 151,
+        26 => 132,
+        29 => 116,
+        32 => 102,
+        36 => 90,
+        39 => 80,
+        42 => 71,
+        45 => 64,
+        48 => 57,
+        51 => 51,
+        54 => 45,
+        58 => 40,
+        61 => 35,
+        64 => 31,
+        67 => 27,
+        70 => 23,
+        73 => 19,
+        76 => 15,
+        81 => 11,
+        84 => 7,
+        87 => 3,
+        _ => 0,
+    }
+}
+
+/// The kernels of the intra edge filter for each strength.
+const INTRA_EDGE_KERNEL: [[u32; INTRA_EDGE_TAPS as usize]; INTRA_EDGE_KERNELS as usize] =
+    [[0, 4, 8, 4, 0], [0, 5, 6, 5, 0], [2, 4, 4, 4, 2]];
+
+/// The taps of the recursive intra prediction for each filter intra mode,
+/// the 8 samples of a 4x2 unit are weighted sums of the sample above left of
+/// the unit, the 4 samples above it and the 2 samples to the left of it.
+#[rustfmt::skip]
+const INTRA_FILTER_TAPS: [[[i32; 7]; 8]; INTRA_FILTER_MODES as usize] = [
+    [
+        [-6, 10, 0, 0, 0, 12, 0],
+        [-5, 2, 10, 0, 0, 9, 0],
+        [-3, 1, 1, 10, 0, 7, 0],
+        [-3, 1, 1, 2, 10, 5, 0],
+        [-4, 6, 0, 0, 0, 2, 12],
+        [-3, 2, 6, 0, 0, 2, 9],
+        [-3, 2, 2, 6, 0, 2, 7],
+        [-3, 1, 2, 2, 6, 3, 5],
+    ],
+    [
+        [-10, 16, 0, 0, 0, 10, 0],
+        [-6, 0, 16, 0, 0, 6, 0],
+        [-4, 0, 0, 16, 0, 4, 0],
+        [-2, 0, 0, 0, 16, 2, 0],
+        [-10, 16, 0, 0, 0, 0, 10],
+        [-6, 0, 16, 0, 0, 0, 6],
+        [-4, 0, 0, 16, 0, 0, 4],
+        [-2, 0, 0, 0, 16, 0, 2],
+    ],
+    [
+        [-8, 8, 0, 0, 0, 16, 0],
+        [-8, 0, 8, 0, 0, 16, 0],
+        [-8, 0, 0, 8, 0, 16, 0],
+        [-8, 0, 0, 0, 8, 16, 0],
+        [-4, 4, 0, 0, 0, 0, 16],
+        [-4, 0, 4, 0, 0, 0, 16],
+        [-4, 0, 0, 4, 0, 0, 16],
+        [-4, 0, 0, 0, 4, 0, 16],
+    ],
+    [
+        [-2, 8, 0, 0, 0, 10, 0],
+        [-1, 3, 8, 0, 0, 6, 0],
+        [-1, 2, 3, 8, 0, 4, 0],
+        [0, 1, 2, 3, 8, 2, 0],
+        [-1, 4, 0, 0, 0, 3, 10],
+        [-1, 3, 4, 0, 0, 4, 6],
+        [-1, 2, 3, 4, 0, 4, 4],
+        [-1, 2, 2, 3, 4, 3, 3],
+    ],
+    [
+        [-12, 14, 0, 0, 0, 14, 0],
+        [-10, 0, 14, 0, 0, 12, 0],
+        [-9, 0, 0, 14, 0, 11, 0],
+        [-8, 0, 0, 0, 14, 10, 0],
+        [-10, 12, 0, 0, 0, 0, 14],
+        [-9, 1, 12, 0, 0, 0, 12],
+        [-8, 0, 0, 12, 0, 1, 11],
+        [-7, 0, 0, 1, 12, 1, 9],
+    ],
+];
+
+/// The Sm_Weights_Tx_* tables of the smooth predictions, the weights of a
+/// side of n samples start at the index n.
+#[rustfmt::skip]
+const SM_WEIGHTS: [u8; 128] = [
+    0, 0, 0, 0,
+    // 4 samples
+    255, 149, 85, 64,
+    // 8 samples
+    255, 197, 146, 105, 73, 50, 37, 32,
+    // 16 samples
+    255, 225, 196, 170, 145, 123, 102, 84, 68, 54, 43, 33, 26, 20, 17, 16,
+    // 32 samples
+    255, 240, 225, 210, 196, 182, 169, 157, 145, 133, 122, 111, 101, 92, 83, 74,
+    66, 59, 52, 45, 39, 34, 29, 25, 21, 17, 14, 12, 10, 9, 8, 8,
+    // 64 samples
+    255, 248, 240, 233, 225, 218, 210, 203, 196, 189, 182, 176, 169, 163, 156, 150,
+    144, 138, 133, 127, 121, 116, 111, 106, 101, 96, 91, 86, 82, 77, 73, 69,
+    65, 61, 57, 54, 50, 47, 44, 41, 38, 35, 32, 29, 27, 25, 22, 20,
+    18, 16, 15, 13, 12, 10, 9, 8, 7, 6, 6, 5, 5, 4, 4, 4,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A plane of `w` by `h` samples with the value `f(x, y)` at `(x, y)`.
+    fn plane(w: usize, h: usize, f: impl Fn(usize, usize) -> u16) -> Plane {
+        let mut plane = Plane::new(w, h);
+        for y in 0..h {
+            for x in 0..w {
+                plane.set(x, y, f(x, y));
+            }
+        }
+
+        plane
+    }
+
+    #[test]
+    fn cfl_luma_is_subsampled_less_its_average() {
+        // Each 2x2 luma block is summed, with 3 fractional bits: 16 * j + 1600 *
+        // i + 404, averaging 2828.
+        let luma = plane(8, 8, |x, y| (x + 100 * y) as u16);
+        let ac = cfl_luma_ac(&luma, 0, 0, 2, 2, 1, 1, 3, 3);
+        for (i, row) in ac.iter().take(4).enumerate() {
+            for (j, v) in row.iter().take(4).enumerate() {
+                assert_eq!(*v, 16 * j as i32 + 1600 * i as i32 - 2424, "at {i}, {j}");
+            }
+        }
+
+        // Without subsampling, the chroma samples past max_x reuse the luma of
+        // the column max_x.
+        let luma = plane(4, 4, |x, y| (x + 10 * y) as u16);
+        let ac = cfl_luma_ac(&luma, 0, 0, 2, 2, 0, 0, 1, 3);
+        assert_eq!(ac[0][..4], [-126, -118, -118, -118]);
+        assert_eq!(ac[3][..4], [114, 122, 122, 122]);
+
+        // Only the block at (x, y) is read.
+        let luma = plane(16, 8, |x, _| if x < 8 { 1000 } else { (x % 2) as u16 * 4 });
+        let ac = cfl_luma_ac(&luma, 8, 0, 3, 3, 0, 0, 15, 7);
+        assert!(ac[..8]
+            .iter()
+            .all(|row| row[..8] == [-16, 16, -16, 16, -16, 16, -16, 16]));
+    }
+
+    #[test]
+    fn cfl_scales_the_luma_by_alpha() {
+        let mut ac = [[0; 32]; 32];
+        ac[0][..4].copy_from_slice(&[-64, 0, 64, 96]);
+        ac[1][..4].copy_from_slice(&[-32, 32, -33, 33]);
+
+        let mut dc = plane(4, 2, |_, _| 512);
+        predict_cfl(&mut dc, 0, 0, 4, 2, &ac, 8, 10);
+        assert_eq!(dc.row(0)[..4], [504, 512, 520, 524]);
+        assert_eq!(dc.row(1)[..4], [508, 516, 508, 516]);
+
+        // A negative alpha subtracts the luma, with the same rounding.
+        let mut dc = plane(4, 2, |_, _| 512);
+        predict_cfl(&mut dc, 0, 0, 4, 2, &ac, -8, 10);
+        assert_eq!(dc.row(0)[..4], [520, 512, 504, 500]);
+        assert_eq!(dc.row(1)[..4], [516, 508, 516, 508]);
+
+        // The halves are rounded away from zero.
+        let mut dc = plane(4, 2, |_, _| 512);
+        predict_cfl(&mut dc, 0, 0, 4, 2, &ac, 1, 10);
+        assert_eq!(dc.row(1)[..4], [511, 513, 511, 513]);
+
+        // The prediction is clipped to the bit depth.
+        let mut dc = plane(4, 2, |x, _| if x < 2 { 4 } else { 1020 });
+        predict_cfl(&mut dc, 0, 0, 4, 1, &ac, 16, 10);
+        assert_eq!(dc.row(0)[..4], [0, 4, 1023, 1023]);
+        assert_eq!(dc.row(1)[..4], [4, 4, 1020, 1020]);
+    }
+}
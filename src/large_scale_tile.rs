@@ -0,0 +1,244 @@
+//! Large scale tile decoding.
+//!
+//! In this mode the application first decodes a set of anchor frames and hands
+//! them to the decoder, then every tile list OBU is decoded against them: each
+//! tile list entry is a single tile of the camera frame, predicted from one of
+//! the anchor frames, and the decoded tiles are placed in raster order into an
+//! output frame of `output_frame_width_in_tiles` x
+//! `output_frame_height_in_tiles` tiles.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#tile-list-obu-semantics
+
+use crate::{
+    constants::{MI_SIZE, REFS_PER_FRAME},
+    obu::{
+        tile_group::Tile,
+        tile_list::{TileList, TileListEntry, MAX_ANCHOR_FRAMES},
+        ObuContext, ObuError,
+    },
+    picture::Picture,
+    tile::{mode_info::FrameState, TileDecoder},
+};
+
+#[derive(Debug, Default)]
+pub struct LargeScaleTileDecoder {
+    anchor_frames: Vec<Option<Picture>>,
+}
+
+impl LargeScaleTileDecoder {
+    /// Stores `picture` as the anchor frame `idx`, replacing any previous
+    /// anchor frame with the same index.
+    pub fn set_anchor_frame(&mut self, idx: u8, picture: Picture) -> Result<(), ObuError> {
+        if idx >= MAX_ANCHOR_FRAMES {
+            return Err(ObuError::InvalidAnchorFrameIdx);
+        }
+
+        if self.anchor_frames.len() <= idx as usize {
+            self.anchor_frames.resize(idx as usize + 1, None);
+        }
+
+        self.anchor_frames[idx as usize] = Some(picture);
+        Ok(())
+    }
+
+    pub fn anchor_frame(&self, idx: u8) -> Option<&Picture> {
+        self.anchor_frames.get(idx as usize)?.as_ref()
+    }
+
+    pub fn clear_anchor_frames(&mut self) {
+        self.anchor_frames.clear();
+    }
+
+    /// Decodes all the tiles of `tile_list` with the frame header of the
+    /// camera frame (the last frame header seen by `ctx`), and returns the
+    /// assembled output frame.
+    pub fn decode(&self, ctx: &mut ObuContext, tile_list: &TileList) -> Result<Picture, ObuError> {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .ok_or(ObuError::NotFoundSequenceHeader)?;
+
+        let frame_header = ctx
+            .frame_header
+            .as_ref()
+            .ok_or(ObuError::NotFoundFrameHeader)?;
+
+        // The output frame is a grid of cells as large as the largest tile of
+        // the camera frame, the tiles on its right and bottom edges are
+        // smaller.
+        let tile_info = &frame_header.tile_info;
+        let tile_width = tile_info
+            .mi_col_starts
+            .windows(2)
+            .map(|starts| (starts[1] - starts[0]) as usize * MI_SIZE as usize)
+            .max()
+            .unwrap_or(0);
+        let tile_height = tile_info
+            .mi_row_starts
+            .windows(2)
+            .map(|starts| (starts[1] - starts[0]) as usize * MI_SIZE as usize)
+            .max()
+            .unwrap_or(0);
+
+        let color_config = &sequence_header.color_config;
+        let (subsampling_x, subsampling_y) =
+            (color_config.subsampling_x, color_config.subsampling_y);
+        let mut output = Picture::new(
+            tile_list.output_frame_width_in_tiles as usize * tile_width,
+            tile_list.output_frame_height_in_tiles as usize * tile_height,
+            ctx.bit_depth,
+            subsampling_x,
+            subsampling_y,
+            ctx.num_planes,
+        );
+
+        let capacity = tile_list.output_frame_width_in_tiles as usize
+            * tile_list.output_frame_height_in_tiles as usize;
+        if tile_list.tile_list_entrys.len() > capacity {
+            return Err(ObuError::InvalidTileList);
+        }
+
+        for (i, entry) in tile_list.tile_list_entrys.iter().enumerate() {
+            let anchor_frame = self
+                .anchor_frame(entry.anchor_frame_idx)
+                .ok_or(ObuError::InvalidAnchorFrameIdx)?;
+
+            let tile = Self::decode_entry(ctx, entry, anchor_frame)?;
+            let x = (i % tile_list.output_frame_width_in_tiles as usize) * tile_width;
+            let y = (i / tile_list.output_frame_width_in_tiles as usize) * tile_height;
+            for (plane, (dst, src)) in output.planes.iter_mut().zip(&tile.planes).enumerate() {
+                let (ss_x, ss_y) = if plane == 0 {
+                    (0, 0)
+                } else {
+                    (subsampling_x as usize, subsampling_y as usize)
+                };
+
+                dst.copy_from(src, x >> ss_x, y >> ss_y);
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// Decodes a single tile list entry, all seven references of the camera
+    /// frame point to the anchor frame of the entry.
+    fn decode_entry(
+        ctx: &mut ObuContext,
+        entry: &TileListEntry,
+        anchor_frame: &Picture,
+    ) -> Result<Picture, ObuError> {
+        if anchor_frame.width() < ctx.upscaled_width as usize
+            || anchor_frame.height() < ctx.frame_height as usize
+            || anchor_frame.planes.len() != ctx.num_planes as usize
+        {
+            return Err(ObuError::InvalidTileList);
+        }
+
+        // The anchor frame takes the place of the reference of LAST_FRAME,
+        // which all the references are redirected to while the tile is
+        // decoded.
+        let header = ctx
+            .frame_header
+            .as_mut()
+            .ok_or(ObuError::NotFoundFrameHeader)?;
+        let slot = header.ref_frame_idx[0];
+        let ref_frame_idx =
+            std::mem::replace(&mut header.ref_frame_idx, [slot; REFS_PER_FRAME as usize]);
+
+        let slot = slot as usize;
+        let reference = std::mem::replace(&mut ctx.frame_store[slot], anchor_frame.clone());
+        let ref_size = (ctx.ref_upscaled_width[slot], ctx.ref_frame_height[slot]);
+        ctx.ref_upscaled_width[slot] = ctx.upscaled_width;
+        ctx.ref_frame_height[slot] = ctx.frame_height;
+
+        let result = Self::decode_tile(ctx, entry);
+
+        ctx.frame_store[slot] = reference;
+        (ctx.ref_upscaled_width[slot], ctx.ref_frame_height[slot]) = ref_size;
+        if let Some(header) = ctx.frame_header.as_mut() {
+            header.ref_frame_idx = ref_frame_idx;
+        }
+
+        result
+    }
+
+    /// Decodes the tile of the camera frame selected by `entry` from its
+    /// coded_tile_data, and returns its samples.
+    fn decode_tile(ctx: &ObuContext, entry: &TileListEntry) -> Result<Picture, ObuError> {
+        let tile_info = &ctx
+            .frame_header
+            .as_ref()
+            .ok_or(ObuError::NotFoundFrameHeader)?
+            .tile_info;
+
+        let tile_row = entry.anchor_tile_row as u32;
+        let tile_col = entry.anchor_tile_col as u32;
+        if tile_row >= tile_info.tile_rows || tile_col >= tile_info.tile_cols {
+            return Err(ObuError::InvalidTileList);
+        }
+
+        let tile = Tile {
+            tile_num: tile_row * tile_info.tile_cols + tile_col,
+            tile_row,
+            tile_col,
+            data: entry.coded_tile_data,
+        };
+
+        // Every tile is decoded into a frame of its own, with the state
+        // derived from the camera frame header.
+        let camera = &ctx.frame_state;
+        let cur_frame = &camera.cur_frame;
+        let mut frame = FrameState {
+            lr_units: camera.lr_units.clone(),
+            prev_segment_ids: camera.prev_segment_ids.clone(),
+            motion_field_mvs: camera.motion_field_mvs.clone(),
+            cur_frame: Picture::new(
+                cur_frame.width(),
+                cur_frame.height(),
+                cur_frame.bit_depth,
+                cur_frame.subsampling_x,
+                cur_frame.subsampling_y,
+                cur_frame.planes.len() as u8,
+            ),
+            ..FrameState::new(camera.mi_rows, camera.mi_cols)
+        };
+        TileDecoder::new(ctx, &mut frame, &tile)?.decode()?;
+
+        let x0 = (tile_info.mi_col_starts[tile_col as usize] * MI_SIZE as u32) as usize;
+        let x1 = (tile_info.mi_col_starts[tile_col as usize + 1] * MI_SIZE as u32) as usize;
+        let y0 = (tile_info.mi_row_starts[tile_row as usize] * MI_SIZE as u32) as usize;
+        let y1 = (tile_info.mi_row_starts[tile_row as usize + 1] * MI_SIZE as u32) as usize;
+        let mut picture = Picture::new(
+            x1.min(ctx.upscaled_width as usize) - x0,
+            y1.min(ctx.frame_height as usize) - y0,
+            cur_frame.bit_depth,
+            cur_frame.subsampling_x,
+            cur_frame.subsampling_y,
+            cur_frame.planes.len() as u8,
+        );
+
+        for (plane, (dst, src)) in picture
+            .planes
+            .iter_mut()
+            .zip(&frame.cur_frame.planes)
+            .enumerate()
+        {
+            let (ss_x, ss_y) = if plane == 0 {
+                (0, 0)
+            } else {
+                (
+                    cur_frame.subsampling_x as usize,
+                    cur_frame.subsampling_y as usize,
+                )
+            };
+
+            let width = dst.width;
+            for y in 0..dst.height {
+                dst.row_mut(y)
+                    .copy_from_slice(&src.row((y0 >> ss_y) + y)[x0 >> ss_x..][..width]);
+            }
+        }
+
+        Ok(picture)
+    }
+}
@@ -1,4 +1,17 @@
 pub mod buffer;
+pub mod cdf;
 pub mod constants;
+pub mod inter_prediction;
+pub mod intra;
+pub mod large_scale_tile;
 pub mod obu;
+pub mod picture;
+pub mod quantizer;
+pub mod quantizer_matrix;
+pub mod scan;
+pub mod symbol;
+pub mod tables;
+pub mod tile;
+pub mod transform;
 pub mod util;
+pub mod warp;
@@ -1,12 +1,37 @@
 use crate::buffer::Buffer;
 
-use super::{ObuContext, ObuError};
+use super::{frame_header::FrameHeader, tile_group::TileGroup, ObuContext, ObuError};
 
+/// see: https://aomediacodec.github.io/av1-spec/#frame-obu-syntax
 #[derive(Debug, Clone)]
-pub struct Frame {}
+pub struct Frame<'a> {
+    pub frame_header: FrameHeader,
+    pub tile_group: TileGroup<'a>,
+}
+
+impl<'a> Frame<'a> {
+    /// `size` is the number of bytes of the frame obu payload, starting at the
+    /// current position of the buffer.
+    pub fn decode(
+        ctx: &mut ObuContext,
+        buf: &mut Buffer<'a>,
+        size: usize,
+    ) -> Result<Self, ObuError> {
+        let start_bit_pos = buf.get_position();
+        let frame_header = FrameHeader::decode(ctx, buf)?;
+        buf.byte_alignment();
+
+        let header_bytes = (buf.get_position() - start_bit_pos) / 8;
+        let tile_group = TileGroup::decode(
+            ctx,
+            buf,
+            size.checked_sub(header_bytes)
+                .ok_or(ObuError::InvalidTileGroup)?,
+        )?;
 
-impl Frame {
-    pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer) -> Result<Self, ObuError> {
-        todo!()
+        Ok(Self {
+            frame_header,
+            tile_group,
+        })
     }
 }
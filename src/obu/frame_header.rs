@@ -1,14 +1,32 @@
 use super::{
-    sequence_header::FrameIdNumbersPresent, Buffer, ObuContext, ObuError, ObuUnknownError,
+    order_hint::{get_relative_dist, set_frame_refs, setup_order_hints},
+    Buffer, ObuContext, ObuError, ObuUnknownError,
 };
 
-use crate::constants::{
-    NUM_REF_FRAMES, PRIMARY_REF_NONE, REFS_PER_FRAME, SELECT_INTEGER_MV,
-    SELECT_SCREEN_CONTENT_TOOLS, SUPERRES_DENOM_BITS, SUPERRES_DENOM_MIN, SUPERRES_NUM,
+use crate::{
+    cdf::{load_cdfs, save_cdfs},
+    constants::{
+        ALTREF2_FRAME, ALTREF_FRAME, GM_ABS_ALPHA_BITS, GM_ABS_TRANS_BITS, GM_ABS_TRANS_ONLY_BITS,
+        GM_ALPHA_PREC_BITS, GM_TRANS_ONLY_PREC_BITS, GM_TRANS_PREC_BITS, GOLDEN_FRAME, INTRA_FRAME,
+        LAST_FRAME, MAX_LOOP_FILTER, MAX_SEGMENTS, MAX_TILE_AREA, MAX_TILE_COLS, MAX_TILE_ROWS,
+        MAX_TILE_WIDTH, MI_SIZE, NUM_REF_FRAMES, PRIMARY_REF_NONE, REFS_PER_FRAME,
+        RESTORATION_TILESIZE_MAX, SEG_LVL_ALT_Q, SEG_LVL_MAX, SEG_LVL_REF_FRAME, SELECT_INTEGER_MV,
+        SELECT_SCREEN_CONTENT_TOOLS, SUPERRES_DENOM_BITS, SUPERRES_DENOM_MIN, SUPERRES_NUM,
+        TOTAL_REFS_PER_FRAME, WARPEDMODEL_PREC_BITS,
+    },
+    picture::Picture,
+    quantizer::{ac_q, dc_q},
+    tile::{
+        init_lr_units,
+        mode_info::FrameState,
+        motion_field::{motion_field_estimation, motion_field_motion_vector_storage},
+    },
+    warp::{setup_shear, Shear},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum FrameType {
+    #[default]
     KeyFrame,
     InterFrame,
     InterOnlyFrame,
@@ -124,20 +142,24 @@ pub fn render_size(ctx: &mut ObuContext, buf: &mut Buffer) {
 }
 
 #[inline]
-pub fn frame_size_with_refs(ctx: &mut ObuContext, frame_size_override: bool, buf: &mut Buffer) {
+pub fn frame_size_with_refs(
+    ctx: &mut ObuContext,
+    frame_size_override: bool,
+    ref_frame_idx: &[u8; REFS_PER_FRAME as usize],
+    buf: &mut Buffer,
+) {
     let mut found_ref = false;
-    for _ in 0..REFS_PER_FRAME {
+    for idx in ref_frame_idx.iter().map(|v| *v as usize) {
         // found_ref	f(1)
         found_ref = buf.get_bit();
-
-        // if ( found_ref == 1 ) {
-        //     UpscaledWidth = RefUpscaledWidth[ ref_frame_idx[ i ] ]
-        //     FrameWidth = UpscaledWidth
-        //     FrameHeight = RefFrameHeight[ ref_frame_idx[ i ] ]
-        //     RenderWidth = RefRenderWidth[ ref_frame_idx[ i ] ]
-        //     RenderHeight = RefRenderHeight[ ref_frame_idx[ i ] ]
-        //     break
-        // }
+        if found_ref {
+            ctx.upscaled_width = ctx.ref_upscaled_width[idx];
+            ctx.frame_width = ctx.upscaled_width;
+            ctx.frame_height = ctx.ref_frame_height[idx];
+            ctx.render_width = ctx.ref_render_width[idx];
+            ctx.render_height = ctx.ref_render_height[idx];
+            break;
+        }
     }
 
     if !found_ref {
@@ -149,8 +171,9 @@ pub fn frame_size_with_refs(ctx: &mut ObuContext, frame_size_override: bool, buf
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum InterpolationFilter {
+    #[default]
     Eighttap,
     EighttapSmooth,
     EighttapSharp,
@@ -185,8 +208,1298 @@ pub fn read_interpolation_filter(buf: &mut Buffer) -> Result<InterpolationFilter
     })
 }
 
-#[derive(Debug, Clone)]
-pub struct UncompressedHeader;
+#[inline]
+fn tile_log2(blk_size: u32, target: u32) -> u32 {
+    let mut k = 0;
+    while (blk_size << k) < target {
+        k += 1;
+    }
+
+    k
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#tile-info-syntax
+#[derive(Debug, Clone, Default)]
+pub struct TileInfo {
+    pub tile_cols_log2: u32,
+    pub tile_rows_log2: u32,
+    pub tile_cols: u32,
+    pub tile_rows: u32,
+    /// The start column (in units of 4x4 luma samples) of each tile, the last
+    /// entry is equal to MiCols.
+    pub mi_col_starts: Vec<u32>,
+    /// The start row (in units of 4x4 luma samples) of each tile, the last
+    /// entry is equal to MiRows.
+    pub mi_row_starts: Vec<u32>,
+    pub context_update_tile_id: u32,
+    pub tile_size_bytes: u8,
+}
+
+impl TileInfo {
+    pub fn decode(ctx: &ObuContext, buf: &mut Buffer) -> Self {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .expect("sequence header cannot be found, this is a undefined behavior!");
+
+        let (sb_cols, sb_rows, sb_shift) = if sequence_header.use_128x128_superblock {
+            ((ctx.mi_cols + 31) >> 5, (ctx.mi_rows + 31) >> 5, 5)
+        } else {
+            ((ctx.mi_cols + 15) >> 4, (ctx.mi_rows + 15) >> 4, 4)
+        };
+
+        let sb_size = sb_shift + 2;
+        let max_tile_width_sb = MAX_TILE_WIDTH as u32 >> sb_size;
+        let mut max_tile_area_sb = MAX_TILE_AREA >> (2 * sb_size);
+        let min_log2_tile_cols = tile_log2(max_tile_width_sb, sb_cols);
+        let max_log2_tile_cols = tile_log2(1, sb_cols.min(MAX_TILE_COLS as u32));
+        let max_log2_tile_rows = tile_log2(1, sb_rows.min(MAX_TILE_ROWS as u32));
+        let min_log2_tiles = min_log2_tile_cols.max(tile_log2(max_tile_area_sb, sb_rows * sb_cols));
+
+        let mut mi_col_starts = Vec::with_capacity(MAX_TILE_COLS as usize + 1);
+        let mut mi_row_starts = Vec::with_capacity(MAX_TILE_ROWS as usize + 1);
+
+        let tile_cols_log2;
+        let tile_rows_log2;
+
+        // uniform_tile_spacing_flag	f(1)
+        let uniform_tile_spacing_flag = buf.get_bit();
+        if uniform_tile_spacing_flag {
+            let mut cols_log2 = min_log2_tile_cols;
+            while cols_log2 < max_log2_tile_cols {
+                // increment_tile_cols_log2	f(1)
+                if buf.get_bit() {
+                    cols_log2 += 1;
+                } else {
+                    break;
+                }
+            }
+
+            let tile_width_sb = (sb_cols + (1 << cols_log2) - 1) >> cols_log2;
+            for start_sb in (0..sb_cols).step_by(tile_width_sb as usize) {
+                mi_col_starts.push(start_sb << sb_shift);
+            }
+
+            let mut rows_log2 = min_log2_tiles.saturating_sub(cols_log2);
+            while rows_log2 < max_log2_tile_rows {
+                // increment_tile_rows_log2	f(1)
+                if buf.get_bit() {
+                    rows_log2 += 1;
+                } else {
+                    break;
+                }
+            }
+
+            let tile_height_sb = (sb_rows + (1 << rows_log2) - 1) >> rows_log2;
+            for start_sb in (0..sb_rows).step_by(tile_height_sb as usize) {
+                mi_row_starts.push(start_sb << sb_shift);
+            }
+
+            tile_cols_log2 = cols_log2;
+            tile_rows_log2 = rows_log2;
+        } else {
+            let mut widest_tile_sb = 0;
+            let mut start_sb = 0;
+            while start_sb < sb_cols {
+                mi_col_starts.push(start_sb << sb_shift);
+
+                let max_width = (sb_cols - start_sb).min(max_tile_width_sb);
+
+                // width_in_sbs_minus_1	ns(maxWidth)
+                let size_sb = buf.get_ns(max_width) + 1;
+                widest_tile_sb = widest_tile_sb.max(size_sb);
+                start_sb += size_sb;
+            }
+
+            max_tile_area_sb = if min_log2_tiles > 0 {
+                (sb_rows * sb_cols) >> (min_log2_tiles + 1)
+            } else {
+                sb_rows * sb_cols
+            };
+
+            let max_tile_height_sb = (max_tile_area_sb / widest_tile_sb).max(1);
+
+            start_sb = 0;
+            while start_sb < sb_rows {
+                mi_row_starts.push(start_sb << sb_shift);
+
+                let max_height = (sb_rows - start_sb).min(max_tile_height_sb);
+
+                // height_in_sbs_minus_1	ns(maxHeight)
+                start_sb += buf.get_ns(max_height) + 1;
+            }
+
+            tile_cols_log2 = tile_log2(1, mi_col_starts.len() as u32);
+            tile_rows_log2 = tile_log2(1, mi_row_starts.len() as u32);
+        }
+
+        let tile_cols = mi_col_starts.len() as u32;
+        let tile_rows = mi_row_starts.len() as u32;
+        mi_col_starts.push(ctx.mi_cols);
+        mi_row_starts.push(ctx.mi_rows);
+
+        let (context_update_tile_id, tile_size_bytes) = if tile_cols_log2 > 0 || tile_rows_log2 > 0
+        {
+            (
+                // context_update_tile_id	f(TileRowsLog2 + TileColsLog2)
+                buf.get_bits((tile_rows_log2 + tile_cols_log2) as usize),
+                // tile_size_bytes_minus_1	f(2)
+                buf.get_bits(2) as u8 + 1,
+            )
+        } else {
+            (0, 0)
+        };
+
+        Self {
+            tile_cols_log2,
+            tile_rows_log2,
+            tile_cols,
+            tile_rows,
+            mi_col_starts,
+            mi_row_starts,
+            context_update_tile_id,
+            tile_size_bytes,
+        }
+    }
+}
+
+#[inline]
+fn read_delta_q(buf: &mut Buffer) -> i8 {
+    // delta_coded	f(1)
+    if buf.get_bit() {
+        // delta_q	su(1+6)
+        buf.get_su(7) as i8
+    } else {
+        0
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#quantization-params-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuantizationParams {
+    pub base_q_idx: u8,
+    pub delta_q_y_dc: i8,
+    pub diff_uv_delta: bool,
+    pub delta_q_u_dc: i8,
+    pub delta_q_u_ac: i8,
+    pub delta_q_v_dc: i8,
+    pub delta_q_v_ac: i8,
+    pub using_qmatrix: bool,
+    pub qm_y: u8,
+    pub qm_u: u8,
+    pub qm_v: u8,
+}
+
+impl QuantizationParams {
+    pub fn decode(ctx: &ObuContext, buf: &mut Buffer) -> Self {
+        let separate_uv_delta_q = ctx
+            .sequence_header
+            .as_ref()
+            .map(|v| v.color_config.separate_uv_delta_q)
+            .unwrap_or(false);
+
+        // base_q_idx	f(8)
+        let base_q_idx = buf.get_bits(8) as u8;
+        let delta_q_y_dc = read_delta_q(buf);
+
+        let mut diff_uv_delta = false;
+        let mut delta_q_u_dc = 0;
+        let mut delta_q_u_ac = 0;
+        let mut delta_q_v_dc = 0;
+        let mut delta_q_v_ac = 0;
+
+        if ctx.num_planes > 1 {
+            if separate_uv_delta_q {
+                // diff_uv_delta	f(1)
+                diff_uv_delta = buf.get_bit();
+            }
+
+            delta_q_u_dc = read_delta_q(buf);
+            delta_q_u_ac = read_delta_q(buf);
+
+            if diff_uv_delta {
+                delta_q_v_dc = read_delta_q(buf);
+                delta_q_v_ac = read_delta_q(buf);
+            } else {
+                delta_q_v_dc = delta_q_u_dc;
+                delta_q_v_ac = delta_q_u_ac;
+            }
+        }
+
+        let mut qm_y = 0;
+        let mut qm_u = 0;
+        let mut qm_v = 0;
+
+        // using_qmatrix	f(1)
+        let using_qmatrix = buf.get_bit();
+        if using_qmatrix {
+            // qm_y	f(4)
+            qm_y = buf.get_bits(4) as u8;
+
+            // qm_u	f(4)
+            qm_u = buf.get_bits(4) as u8;
+
+            qm_v = if separate_uv_delta_q {
+                // qm_v	f(4)
+                buf.get_bits(4) as u8
+            } else {
+                qm_u
+            };
+        }
+
+        Self {
+            base_q_idx,
+            delta_q_y_dc,
+            diff_uv_delta,
+            delta_q_u_dc,
+            delta_q_u_ac,
+            delta_q_v_dc,
+            delta_q_v_ac,
+            using_qmatrix,
+            qm_y,
+            qm_u,
+            qm_v,
+        }
+    }
+
+    /// Returns the quantizer step size of the DC coefficient for the given
+    /// plane, `qindex` is the quantizer index of the block (which is equal to
+    /// base_q_idx when segmentation and delta q are not in use).
+    pub fn dc_quant(&self, bit_depth: u8, plane: usize, qindex: u8) -> i32 {
+        let delta = match plane {
+            0 => self.delta_q_y_dc,
+            1 => self.delta_q_u_dc,
+            _ => self.delta_q_v_dc,
+        };
+
+        dc_q(bit_depth, qindex as i32 + delta as i32)
+    }
+
+    /// Returns the quantizer step size of the AC coefficients for the given
+    /// plane, `qindex` is the quantizer index of the block (which is equal to
+    /// base_q_idx when segmentation and delta q are not in use).
+    pub fn ac_quant(&self, bit_depth: u8, plane: usize, qindex: u8) -> i32 {
+        let delta = match plane {
+            0 => 0,
+            1 => self.delta_q_u_ac,
+            _ => self.delta_q_v_ac,
+        };
+
+        ac_q(bit_depth, qindex as i32 + delta as i32)
+    }
+}
+
+const SEGMENTATION_FEATURE_BITS: [usize; SEG_LVL_MAX as usize] = [8, 6, 6, 6, 6, 3, 0, 0];
+const SEGMENTATION_FEATURE_SIGNED: [bool; SEG_LVL_MAX as usize] =
+    [true, true, true, true, true, false, false, false];
+const SEGMENTATION_FEATURE_MAX: [i16; SEG_LVL_MAX as usize] = [
+    255,
+    MAX_LOOP_FILTER as i16,
+    MAX_LOOP_FILTER as i16,
+    MAX_LOOP_FILTER as i16,
+    MAX_LOOP_FILTER as i16,
+    7,
+    0,
+    0,
+];
+
+/// see: https://aomediacodec.github.io/av1-spec/#segmentation-params-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SegmentationParams {
+    pub segmentation_enabled: bool,
+    pub segmentation_update_map: bool,
+    pub segmentation_temporal_update: bool,
+    pub segmentation_update_data: bool,
+    pub feature_enabled: [[bool; SEG_LVL_MAX as usize]; MAX_SEGMENTS as usize],
+    pub feature_data: [[i16; SEG_LVL_MAX as usize]; MAX_SEGMENTS as usize],
+    /// Equal to 1 indicates that the segment id will be read before the skip
+    /// syntax element.
+    pub seg_id_pre_skip: bool,
+    /// The highest numbered segment id that has some enabled feature.
+    pub last_active_seg_id: u8,
+}
+
+impl SegmentationParams {
+    /// `prev` holds the segmentation parameters loaded from the primary
+    /// reference frame, and is used when the feature data is not updated by
+    /// this frame.
+    pub fn decode(buf: &mut Buffer, primary_ref_frame: u8, prev: Option<&Self>) -> Self {
+        let mut segmentation_update_map = false;
+        let mut segmentation_temporal_update = false;
+        let mut segmentation_update_data = false;
+        let mut feature_enabled = [[false; SEG_LVL_MAX as usize]; MAX_SEGMENTS as usize];
+        let mut feature_data = [[0i16; SEG_LVL_MAX as usize]; MAX_SEGMENTS as usize];
+
+        // segmentation_enabled	f(1)
+        let segmentation_enabled = buf.get_bit();
+        if segmentation_enabled {
+            if primary_ref_frame == PRIMARY_REF_NONE {
+                segmentation_update_map = true;
+                segmentation_temporal_update = false;
+                segmentation_update_data = true;
+            } else {
+                // segmentation_update_map	f(1)
+                segmentation_update_map = buf.get_bit();
+                if segmentation_update_map {
+                    // segmentation_temporal_update	f(1)
+                    segmentation_temporal_update = buf.get_bit();
+                }
+
+                // segmentation_update_data	f(1)
+                segmentation_update_data = buf.get_bit();
+            }
+
+            if segmentation_update_data {
+                for i in 0..MAX_SEGMENTS as usize {
+                    for j in 0..SEG_LVL_MAX as usize {
+                        // feature_enabled	f(1)
+                        feature_enabled[i][j] = buf.get_bit();
+                        if feature_enabled[i][j] {
+                            let bits_to_read = SEGMENTATION_FEATURE_BITS[j];
+                            let limit = SEGMENTATION_FEATURE_MAX[j];
+                            feature_data[i][j] = if SEGMENTATION_FEATURE_SIGNED[j] {
+                                // feature_value	su(1+bitsToRead)
+                                (buf.get_su(1 + bits_to_read) as i16).clamp(-limit, limit)
+                            } else {
+                                // feature_value	f(bitsToRead)
+                                (buf.get_bits(bits_to_read) as i16).clamp(0, limit)
+                            };
+                        }
+                    }
+                }
+            } else if let Some(prev) = prev {
+                feature_enabled = prev.feature_enabled;
+                feature_data = prev.feature_data;
+            }
+        }
+
+        let mut seg_id_pre_skip = false;
+        let mut last_active_seg_id = 0;
+        for (i, features) in feature_enabled.iter().enumerate() {
+            for (j, enabled) in features.iter().enumerate() {
+                if *enabled {
+                    last_active_seg_id = i as u8;
+                    if j >= SEG_LVL_REF_FRAME as usize {
+                        seg_id_pre_skip = true;
+                    }
+                }
+            }
+        }
+
+        Self {
+            segmentation_enabled,
+            segmentation_update_map,
+            segmentation_temporal_update,
+            segmentation_update_data,
+            feature_enabled,
+            feature_data,
+            seg_id_pre_skip,
+            last_active_seg_id,
+        }
+    }
+
+    /// Returns the quantizer index for the given segment, without taking the
+    /// block level delta q into account.
+    pub fn qidx(&self, segment_id: u8, base_q_idx: u8) -> u8 {
+        if self.feature_active(segment_id, SEG_LVL_ALT_Q) {
+            let data = self.feature_data[segment_id as usize][SEG_LVL_ALT_Q as usize];
+            (base_q_idx as i16 + data).clamp(0, 255) as u8
+        } else {
+            base_q_idx
+        }
+    }
+
+    /// Returns whether the given feature is active for the given segment.
+    #[inline]
+    pub fn feature_active(&self, segment_id: u8, feature: u8) -> bool {
+        self.segmentation_enabled && self.feature_enabled[segment_id as usize][feature as usize]
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#quantizer-index-delta-parameters-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeltaQParams {
+    pub delta_q_present: bool,
+    pub delta_q_res: u8,
+}
+
+impl DeltaQParams {
+    pub fn decode(buf: &mut Buffer, base_q_idx: u8) -> Self {
+        let mut delta_q_present = false;
+        let mut delta_q_res = 0;
+
+        if base_q_idx > 0 {
+            // delta_q_present	f(1)
+            delta_q_present = buf.get_bit();
+        }
+
+        if delta_q_present {
+            // delta_q_res	f(2)
+            delta_q_res = buf.get_bits(2) as u8;
+        }
+
+        Self {
+            delta_q_present,
+            delta_q_res,
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#loop-filter-delta-parameters-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeltaLfParams {
+    pub delta_lf_present: bool,
+    pub delta_lf_res: u8,
+    pub delta_lf_multi: bool,
+}
+
+impl DeltaLfParams {
+    pub fn decode(buf: &mut Buffer, delta_q_present: bool, allow_intrabc: bool) -> Self {
+        let mut delta_lf_present = false;
+        let mut delta_lf_res = 0;
+        let mut delta_lf_multi = false;
+
+        if delta_q_present {
+            if !allow_intrabc {
+                // delta_lf_present	f(1)
+                delta_lf_present = buf.get_bit();
+            }
+
+            if delta_lf_present {
+                // delta_lf_res	f(2)
+                delta_lf_res = buf.get_bits(2) as u8;
+
+                // delta_lf_multi	f(1)
+                delta_lf_multi = buf.get_bit();
+            }
+        }
+
+        Self {
+            delta_lf_present,
+            delta_lf_res,
+            delta_lf_multi,
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#loop-filter-params-syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopFilterParams {
+    pub loop_filter_level: [u8; 4],
+    pub loop_filter_sharpness: u8,
+    pub loop_filter_delta_enabled: bool,
+    pub loop_filter_delta_update: bool,
+    pub loop_filter_ref_deltas: [i8; TOTAL_REFS_PER_FRAME as usize],
+    pub loop_filter_mode_deltas: [i8; 2],
+}
+
+impl Default for LoopFilterParams {
+    fn default() -> Self {
+        let mut loop_filter_ref_deltas = [0; TOTAL_REFS_PER_FRAME as usize];
+        loop_filter_ref_deltas[INTRA_FRAME as usize] = 1;
+        loop_filter_ref_deltas[GOLDEN_FRAME as usize] = -1;
+        loop_filter_ref_deltas[ALTREF_FRAME as usize] = -1;
+        loop_filter_ref_deltas[ALTREF2_FRAME as usize] = -1;
+
+        Self {
+            loop_filter_level: [0; 4],
+            loop_filter_sharpness: 0,
+            loop_filter_delta_enabled: true,
+            loop_filter_delta_update: false,
+            loop_filter_ref_deltas,
+            loop_filter_mode_deltas: [0; 2],
+        }
+    }
+}
+
+impl LoopFilterParams {
+    /// `prev` holds the loop filter parameters loaded from the primary
+    /// reference frame, the deltas start from it when present and from the
+    /// default values otherwise.
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        coded_lossless: bool,
+        allow_intrabc: bool,
+        prev: Option<&Self>,
+    ) -> Self {
+        let mut params = Self::default();
+        if coded_lossless || allow_intrabc {
+            return params;
+        }
+
+        if let Some(prev) = prev {
+            params.loop_filter_ref_deltas = prev.loop_filter_ref_deltas;
+            params.loop_filter_mode_deltas = prev.loop_filter_mode_deltas;
+        }
+
+        // loop_filter_level[ 0 ]	f(6)
+        params.loop_filter_level[0] = buf.get_bits(6) as u8;
+
+        // loop_filter_level[ 1 ]	f(6)
+        params.loop_filter_level[1] = buf.get_bits(6) as u8;
+
+        if ctx.num_planes > 1
+            && (params.loop_filter_level[0] != 0 || params.loop_filter_level[1] != 0)
+        {
+            // loop_filter_level[ 2 ]	f(6)
+            params.loop_filter_level[2] = buf.get_bits(6) as u8;
+
+            // loop_filter_level[ 3 ]	f(6)
+            params.loop_filter_level[3] = buf.get_bits(6) as u8;
+        }
+
+        // loop_filter_sharpness	f(3)
+        params.loop_filter_sharpness = buf.get_bits(3) as u8;
+
+        // loop_filter_delta_enabled	f(1)
+        params.loop_filter_delta_enabled = buf.get_bit();
+        if params.loop_filter_delta_enabled {
+            // loop_filter_delta_update	f(1)
+            params.loop_filter_delta_update = buf.get_bit();
+            if params.loop_filter_delta_update {
+                for delta in params.loop_filter_ref_deltas.iter_mut() {
+                    // update_ref_delta	f(1)
+                    if buf.get_bit() {
+                        // loop_filter_ref_deltas[ i ]	su(1+6)
+                        *delta = buf.get_su(7) as i8;
+                    }
+                }
+
+                for delta in params.loop_filter_mode_deltas.iter_mut() {
+                    // update_mode_delta	f(1)
+                    if buf.get_bit() {
+                        // loop_filter_mode_deltas[ i ]	su(1+6)
+                        *delta = buf.get_su(7) as i8;
+                    }
+                }
+            }
+        }
+
+        params
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#cdef-params-syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CdefParams {
+    pub cdef_damping: u8,
+    pub cdef_bits: u8,
+    pub cdef_y_pri_strength: [u8; 8],
+    pub cdef_y_sec_strength: [u8; 8],
+    pub cdef_uv_pri_strength: [u8; 8],
+    pub cdef_uv_sec_strength: [u8; 8],
+}
+
+impl Default for CdefParams {
+    fn default() -> Self {
+        Self {
+            cdef_damping: 3,
+            cdef_bits: 0,
+            cdef_y_pri_strength: [0; 8],
+            cdef_y_sec_strength: [0; 8],
+            cdef_uv_pri_strength: [0; 8],
+            cdef_uv_sec_strength: [0; 8],
+        }
+    }
+}
+
+impl CdefParams {
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        coded_lossless: bool,
+        allow_intrabc: bool,
+    ) -> Self {
+        let enable_cdef = ctx
+            .sequence_header
+            .as_ref()
+            .map(|v| v.enable_cdef)
+            .unwrap_or(false);
+
+        let mut params = Self::default();
+        if coded_lossless || allow_intrabc || !enable_cdef {
+            return params;
+        }
+
+        // cdef_damping_minus_3	f(2)
+        params.cdef_damping = buf.get_bits(2) as u8 + 3;
+
+        // cdef_bits	f(2)
+        params.cdef_bits = buf.get_bits(2) as u8;
+
+        for i in 0..(1 << params.cdef_bits) {
+            // cdef_y_pri_strength[ i ]	f(4)
+            params.cdef_y_pri_strength[i] = buf.get_bits(4) as u8;
+
+            // cdef_y_sec_strength[ i ]	f(2)
+            params.cdef_y_sec_strength[i] = buf.get_bits(2) as u8;
+            if params.cdef_y_sec_strength[i] == 3 {
+                params.cdef_y_sec_strength[i] += 1;
+            }
+
+            if ctx.num_planes > 1 {
+                // cdef_uv_pri_strength[ i ]	f(4)
+                params.cdef_uv_pri_strength[i] = buf.get_bits(4) as u8;
+
+                // cdef_uv_sec_strength[ i ]	f(2)
+                params.cdef_uv_sec_strength[i] = buf.get_bits(2) as u8;
+                if params.cdef_uv_sec_strength[i] == 3 {
+                    params.cdef_uv_sec_strength[i] += 1;
+                }
+            }
+        }
+
+        params
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RestorationType {
+    #[default]
+    None,
+    Wiener,
+    Sgrproj,
+    Switchable,
+}
+
+impl RestorationType {
+    /// Maps the coded lr_type to the restoration type.
+    pub fn from_lr_type(lr_type: u8) -> Self {
+        match lr_type {
+            0 => Self::None,
+            1 => Self::Switchable,
+            2 => Self::Wiener,
+            _ => Self::Sgrproj,
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#loop-restoration-params-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LrParams {
+    pub frame_restoration_type: [RestorationType; 3],
+    pub uses_lr: bool,
+    pub uses_chroma_lr: bool,
+    pub lr_unit_shift: u8,
+    pub lr_uv_shift: u8,
+    /// The size of the loop restoration units of each plane, in units of
+    /// samples of that plane.
+    pub loop_restoration_size: [u16; 3],
+}
+
+impl LrParams {
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        all_lossless: bool,
+        allow_intrabc: bool,
+    ) -> Self {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .expect("sequence header cannot be found, this is a undefined behavior!");
+
+        let mut params = Self::default();
+        if all_lossless || allow_intrabc || !sequence_header.enable_restoration {
+            return params;
+        }
+
+        for i in 0..ctx.num_planes as usize {
+            // lr_type	f(2)
+            params.frame_restoration_type[i] = RestorationType::from_lr_type(buf.get_bits(2) as u8);
+            if params.frame_restoration_type[i] != RestorationType::None {
+                params.uses_lr = true;
+                if i > 0 {
+                    params.uses_chroma_lr = true;
+                }
+            }
+        }
+
+        if params.uses_lr {
+            if sequence_header.use_128x128_superblock {
+                // lr_unit_shift	f(1)
+                params.lr_unit_shift = buf.get_bit() as u8 + 1;
+            } else {
+                // lr_unit_shift	f(1)
+                params.lr_unit_shift = buf.get_bit() as u8;
+                if params.lr_unit_shift != 0 {
+                    // lr_unit_extra_shift	f(1)
+                    params.lr_unit_shift += buf.get_bit() as u8;
+                }
+            }
+
+            let color_config = &sequence_header.color_config;
+            if color_config.subsampling_x && color_config.subsampling_y && params.uses_chroma_lr {
+                // lr_uv_shift	f(1)
+                params.lr_uv_shift = buf.get_bit() as u8;
+            }
+
+            let size = RESTORATION_TILESIZE_MAX >> (2 - params.lr_unit_shift);
+            params.loop_restoration_size =
+                [size, size >> params.lr_uv_shift, size >> params.lr_uv_shift];
+        }
+
+        params
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TxMode {
+    /// The inverse transform will use only 4x4 transforms.
+    #[default]
+    Only4x4,
+    /// The inverse transform will use the largest transform size that fits
+    /// inside the block.
+    Largest,
+    /// The choice of transform size is specified explicitly for each block.
+    Select,
+}
+
+impl TxMode {
+    /// see: https://aomediacodec.github.io/av1-spec/#tx-mode-syntax
+    pub fn decode(buf: &mut Buffer, coded_lossless: bool) -> Self {
+        if coded_lossless {
+            Self::Only4x4
+        } else {
+            // tx_mode_select	f(1)
+            if buf.get_bit() {
+                Self::Select
+            } else {
+                Self::Largest
+            }
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#skip-mode-params-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SkipModeParams {
+    pub skip_mode_present: bool,
+    /// The two reference frames used by skip mode, only meaningful when
+    /// skip_mode_present is set.
+    pub skip_mode_frame: [i8; 2],
+}
+
+impl SkipModeParams {
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        reference_select: bool,
+        ref_frame_idx: &[u8; REFS_PER_FRAME as usize],
+    ) -> Self {
+        let enable_order_hint = ctx
+            .sequence_header
+            .as_ref()
+            .map(|v| v.enable_order_hint)
+            .unwrap_or(false);
+
+        let mut skip_mode_frame = None;
+        if !ctx.frame_is_intra && reference_select && enable_order_hint {
+            let mut forward: Option<(usize, u32)> = None;
+            let mut backward: Option<(usize, u32)> = None;
+            for (i, idx) in ref_frame_idx.iter().enumerate() {
+                let ref_hint = ctx.ref_order_hint[*idx as usize];
+                if get_relative_dist(ctx, ref_hint, ctx.order_hint) < 0 {
+                    if forward
+                        .map(|(_, hint)| get_relative_dist(ctx, ref_hint, hint) > 0)
+                        .unwrap_or(true)
+                    {
+                        forward = Some((i, ref_hint));
+                    }
+                } else if get_relative_dist(ctx, ref_hint, ctx.order_hint) > 0
+                    && backward
+                        .map(|(_, hint)| get_relative_dist(ctx, ref_hint, hint) < 0)
+                        .unwrap_or(true)
+                {
+                    backward = Some((i, ref_hint));
+                }
+            }
+
+            if let Some((forward_idx, forward_hint)) = forward {
+                if let Some((backward_idx, _)) = backward {
+                    skip_mode_frame = Some((forward_idx, backward_idx));
+                } else {
+                    let mut second_forward: Option<(usize, u32)> = None;
+                    for (i, idx) in ref_frame_idx.iter().enumerate() {
+                        let ref_hint = ctx.ref_order_hint[*idx as usize];
+                        if get_relative_dist(ctx, ref_hint, forward_hint) < 0
+                            && second_forward
+                                .map(|(_, hint)| get_relative_dist(ctx, ref_hint, hint) > 0)
+                                .unwrap_or(true)
+                        {
+                            second_forward = Some((i, ref_hint));
+                        }
+                    }
+
+                    if let Some((second_forward_idx, _)) = second_forward {
+                        skip_mode_frame = Some((forward_idx, second_forward_idx));
+                    }
+                }
+            }
+        }
+
+        if let Some((a, b)) = skip_mode_frame {
+            Self {
+                // skip_mode_present	f(1)
+                skip_mode_present: buf.get_bit(),
+                skip_mode_frame: [LAST_FRAME + a.min(b) as i8, LAST_FRAME + a.max(b) as i8],
+            }
+        } else {
+            Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum GmType {
+    /// Warp model is just an identity transform.
+    #[default]
+    Identity,
+    /// Warp model is a pure translation.
+    Translation,
+    /// Warp model is a rotation + symmetric zoom + translation.
+    RotZoom,
+    /// Warp model is a general affine transform.
+    Affine,
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#decode-subexp-syntax
+fn decode_subexp(buf: &mut Buffer, num_syms: u32) -> u32 {
+    let mut i = 0;
+    let mut mk = 0;
+    let k = 3;
+    loop {
+        let b2 = if i > 0 { k + i - 1 } else { k };
+        let a = 1 << b2;
+        if num_syms <= mk + 3 * a {
+            // subexp_final_bits	ns(numSyms - mk)
+            return buf.get_ns(num_syms - mk) + mk;
+        }
+
+        // subexp_more_bits	f(1)
+        if buf.get_bit() {
+            i += 1;
+            mk += a;
+        } else {
+            // subexp_bits	f(b2)
+            return buf.get_bits(b2 as usize) + mk;
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-recenter-function
+#[inline]
+pub(crate) fn inverse_recenter(r: u32, v: u32) -> u32 {
+    if v > 2 * r {
+        v
+    } else if v & 1 == 1 {
+        r - ((v + 1) >> 1)
+    } else {
+        r + (v >> 1)
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#decode-unsigned-subexp-with-reference-syntax
+fn decode_unsigned_subexp_with_ref(buf: &mut Buffer, mx: u32, r: u32) -> u32 {
+    let v = decode_subexp(buf, mx);
+    if (r << 1) <= mx {
+        inverse_recenter(r, v)
+    } else {
+        mx - 1 - inverse_recenter(mx - 1 - r, v)
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#decode-signed-subexp-with-reference-syntax
+fn decode_signed_subexp_with_ref(buf: &mut Buffer, low: i32, high: i32, r: i32) -> i32 {
+    decode_unsigned_subexp_with_ref(buf, (high - low) as u32, (r - low) as u32) as i32 + low
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#global-motion-params-syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlobalMotionParams {
+    /// The warp model type of each reference frame, indexed by the reference
+    /// frame type (LAST_FRAME..=ALTREF_FRAME).
+    pub gm_type: [GmType; TOTAL_REFS_PER_FRAME as usize],
+    /// The warp matrix of each reference frame, indexed by the reference frame
+    /// type (LAST_FRAME..=ALTREF_FRAME).
+    pub gm_params: [[i32; 6]; TOTAL_REFS_PER_FRAME as usize],
+}
+
+impl Default for GlobalMotionParams {
+    fn default() -> Self {
+        let mut gm_params = [[0; 6]; TOTAL_REFS_PER_FRAME as usize];
+        for params in gm_params.iter_mut() {
+            params[2] = 1 << WARPEDMODEL_PREC_BITS;
+            params[5] = 1 << WARPEDMODEL_PREC_BITS;
+        }
+
+        Self {
+            gm_type: [GmType::Identity; TOTAL_REFS_PER_FRAME as usize],
+            gm_params,
+        }
+    }
+}
+
+impl GlobalMotionParams {
+    /// `prev` holds the global motion parameters loaded from the primary
+    /// reference frame (PrevGmParams), the parameters are coded relative to
+    /// them when present and relative to the identity model otherwise.
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        allow_high_precision_mv: bool,
+        prev: Option<&Self>,
+    ) -> Self {
+        let mut params = Self::default();
+        if ctx.frame_is_intra {
+            return params;
+        }
+
+        let prev_gm_params = prev.map(|v| v.gm_params).unwrap_or(params.gm_params);
+        for (ref_frame, prev_params) in prev_gm_params
+            .iter()
+            .enumerate()
+            .take(ALTREF_FRAME as usize + 1)
+            .skip(LAST_FRAME as usize)
+        {
+            // is_global	f(1)
+            let gm_type = if buf.get_bit() {
+                // is_rot_zoom	f(1)
+                if buf.get_bit() {
+                    GmType::RotZoom
+                } else {
+                    // is_translation	f(1)
+                    if buf.get_bit() {
+                        GmType::Translation
+                    } else {
+                        GmType::Affine
+                    }
+                }
+            } else {
+                GmType::Identity
+            };
+
+            params.gm_type[ref_frame] = gm_type;
+
+            let mut read_global_param = |idx: usize| -> i32 {
+                let (abs_bits, prec_bits) = if idx < 2 {
+                    if gm_type == GmType::Translation {
+                        let hp = !allow_high_precision_mv as u8;
+                        (GM_ABS_TRANS_ONLY_BITS - hp, GM_TRANS_ONLY_PREC_BITS - hp)
+                    } else {
+                        (GM_ABS_TRANS_BITS, GM_TRANS_PREC_BITS)
+                    }
+                } else {
+                    (GM_ABS_ALPHA_BITS, GM_ALPHA_PREC_BITS)
+                };
+
+                let prec_diff = WARPEDMODEL_PREC_BITS - prec_bits;
+                let (round, sub) = if idx % 3 == 2 {
+                    (1 << WARPEDMODEL_PREC_BITS, 1 << prec_bits)
+                } else {
+                    (0, 0)
+                };
+
+                let mx = 1 << abs_bits;
+                let r = (prev_params[idx] >> prec_diff) - sub;
+                (decode_signed_subexp_with_ref(buf, -mx, mx + 1, r) << prec_diff) + round
+            };
+
+            let warp = &mut params.gm_params[ref_frame];
+            if gm_type >= GmType::RotZoom {
+                warp[2] = read_global_param(2);
+                warp[3] = read_global_param(3);
+                if gm_type == GmType::Affine {
+                    warp[4] = read_global_param(4);
+                    warp[5] = read_global_param(5);
+                } else {
+                    warp[4] = -warp[3];
+                    warp[5] = warp[2];
+                }
+            }
+
+            if gm_type >= GmType::Translation {
+                warp[0] = read_global_param(0);
+                warp[1] = read_global_param(1);
+            }
+        }
+
+        params
+    }
+
+    /// Returns the shear parameters of the warp model of the given reference
+    /// frame, the model can only be used for global motion prediction when
+    /// `warp_valid` is set.
+    pub fn shear(&self, ref_frame: i8) -> Shear {
+        setup_shear(&self.gm_params[ref_frame as usize])
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#film-grain-params-syntax
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilmGrainParams {
+    pub apply_grain: bool,
+    pub grain_seed: u16,
+    pub update_grain: bool,
+    /// The reference slot the parameters were loaded from, only present when
+    /// update_grain is not set.
+    pub film_grain_params_ref_idx: Option<u8>,
+    pub num_y_points: u8,
+    pub point_y_value: [u8; 14],
+    pub point_y_scaling: [u8; 14],
+    pub chroma_scaling_from_luma: bool,
+    pub num_cb_points: u8,
+    pub point_cb_value: [u8; 10],
+    pub point_cb_scaling: [u8; 10],
+    pub num_cr_points: u8,
+    pub point_cr_value: [u8; 10],
+    pub point_cr_scaling: [u8; 10],
+    /// The shift applied to the scaling function, in the range 8..=11.
+    pub grain_scaling: u8,
+    pub ar_coeff_lag: u8,
+    pub ar_coeffs_y: [i8; 24],
+    pub ar_coeffs_cb: [i8; 25],
+    pub ar_coeffs_cr: [i8; 25],
+    /// The shift applied to the auto-regressive coefficients, in the range
+    /// 6..=9.
+    pub ar_coeff_shift: u8,
+    pub grain_scale_shift: u8,
+    pub cb_mult: u8,
+    pub cb_luma_mult: u8,
+    pub cb_offset: u16,
+    pub cr_mult: u8,
+    pub cr_luma_mult: u8,
+    pub cr_offset: u16,
+    pub overlap_flag: bool,
+    pub clip_to_restricted_range: bool,
+}
+
+impl FilmGrainParams {
+    pub fn decode(
+        ctx: &ObuContext,
+        buf: &mut Buffer,
+        frame_type: FrameType,
+        show_frame: bool,
+        showable_frame: bool,
+    ) -> Result<Self, ObuError> {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .ok_or(ObuError::NotFoundSequenceHeader)?;
+
+        let mut params = Self::default();
+        if !sequence_header.film_grain_params_present || (!show_frame && !showable_frame) {
+            return Ok(params);
+        }
+
+        // apply_grain	f(1)
+        params.apply_grain = buf.get_bit();
+        if !params.apply_grain {
+            return Ok(Self::default());
+        }
+
+        // grain_seed	f(16)
+        params.grain_seed = buf.get_bits(16) as u16;
+        params.update_grain = if frame_type == FrameType::InterFrame {
+            // update_grain	f(1)
+            buf.get_bit()
+        } else {
+            true
+        };
+
+        if !params.update_grain {
+            // film_grain_params_ref_idx	f(3)
+            let film_grain_params_ref_idx = buf.get_bits(3) as u8;
+
+            // load_grain_params( film_grain_params_ref_idx ), but keep the
+            // grain seed of this frame.
+            return Ok(Self {
+                grain_seed: params.grain_seed,
+                update_grain: false,
+                film_grain_params_ref_idx: Some(film_grain_params_ref_idx),
+                ..ctx.saved_film_grain_params[film_grain_params_ref_idx as usize]
+            });
+        }
+
+        // num_y_points	f(4)
+        params.num_y_points = buf.get_bits(4) as u8;
+        if params.num_y_points as usize > params.point_y_value.len() {
+            return Err(ObuError::InvalidFilmGrainParams);
+        }
+
+        for i in 0..params.num_y_points as usize {
+            // point_y_value[ i ]	f(8)
+            params.point_y_value[i] = buf.get_bits(8) as u8;
+
+            // point_y_scaling[ i ]	f(8)
+            params.point_y_scaling[i] = buf.get_bits(8) as u8;
+        }
+
+        let color_config = &sequence_header.color_config;
+        params.chroma_scaling_from_luma = if color_config.mono_chrome {
+            false
+        } else {
+            // chroma_scaling_from_luma	f(1)
+            buf.get_bit()
+        };
+
+        if !(color_config.mono_chrome
+            || params.chroma_scaling_from_luma
+            || (color_config.subsampling_x
+                && color_config.subsampling_y
+                && params.num_y_points == 0))
+        {
+            // num_cb_points	f(4)
+            params.num_cb_points = buf.get_bits(4) as u8;
+            if params.num_cb_points as usize > params.point_cb_value.len() {
+                return Err(ObuError::InvalidFilmGrainParams);
+            }
+
+            for i in 0..params.num_cb_points as usize {
+                // point_cb_value[ i ]	f(8)
+                params.point_cb_value[i] = buf.get_bits(8) as u8;
+
+                // point_cb_scaling[ i ]	f(8)
+                params.point_cb_scaling[i] = buf.get_bits(8) as u8;
+            }
+
+            // num_cr_points	f(4)
+            params.num_cr_points = buf.get_bits(4) as u8;
+            if params.num_cr_points as usize > params.point_cr_value.len() {
+                return Err(ObuError::InvalidFilmGrainParams);
+            }
+
+            for i in 0..params.num_cr_points as usize {
+                // point_cr_value[ i ]	f(8)
+                params.point_cr_value[i] = buf.get_bits(8) as u8;
+
+                // point_cr_scaling[ i ]	f(8)
+                params.point_cr_scaling[i] = buf.get_bits(8) as u8;
+            }
+        }
+
+        // grain_scaling_minus_8	f(2)
+        params.grain_scaling = buf.get_bits(2) as u8 + 8;
+
+        // ar_coeff_lag	f(2)
+        params.ar_coeff_lag = buf.get_bits(2) as u8;
+
+        let num_pos_luma = 2 * params.ar_coeff_lag as usize * (params.ar_coeff_lag as usize + 1);
+        let num_pos_chroma = if params.num_y_points > 0 {
+            for coeff in params.ar_coeffs_y.iter_mut().take(num_pos_luma) {
+                // ar_coeffs_y_plus_128[ i ]	f(8)
+                *coeff = (buf.get_bits(8) as i32 - 128) as i8;
+            }
+
+            num_pos_luma + 1
+        } else {
+            num_pos_luma
+        };
+
+        if params.chroma_scaling_from_luma || params.num_cb_points > 0 {
+            for coeff in params.ar_coeffs_cb.iter_mut().take(num_pos_chroma) {
+                // ar_coeffs_cb_plus_128[ i ]	f(8)
+                *coeff = (buf.get_bits(8) as i32 - 128) as i8;
+            }
+        }
+
+        if params.chroma_scaling_from_luma || params.num_cr_points > 0 {
+            for coeff in params.ar_coeffs_cr.iter_mut().take(num_pos_chroma) {
+                // ar_coeffs_cr_plus_128[ i ]	f(8)
+                *coeff = (buf.get_bits(8) as i32 - 128) as i8;
+            }
+        }
+
+        // ar_coeff_shift_minus_6	f(2)
+        params.ar_coeff_shift = buf.get_bits(2) as u8 + 6;
+
+        // grain_scale_shift	f(2)
+        params.grain_scale_shift = buf.get_bits(2) as u8;
+
+        if params.num_cb_points > 0 {
+            // cb_mult	f(8)
+            params.cb_mult = buf.get_bits(8) as u8;
+
+            // cb_luma_mult	f(8)
+            params.cb_luma_mult = buf.get_bits(8) as u8;
+
+            // cb_offset	f(9)
+            params.cb_offset = buf.get_bits(9) as u16;
+        }
+
+        if params.num_cr_points > 0 {
+            // cr_mult	f(8)
+            params.cr_mult = buf.get_bits(8) as u8;
+
+            // cr_luma_mult	f(8)
+            params.cr_luma_mult = buf.get_bits(8) as u8;
+
+            // cr_offset	f(9)
+            params.cr_offset = buf.get_bits(9) as u16;
+        }
+
+        // overlap_flag	f(1)
+        params.overlap_flag = buf.get_bit();
+
+        // clip_to_restricted_range	f(1)
+        params.clip_to_restricted_range = buf.get_bit();
+
+        Ok(params)
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#uncompressed-header-syntax
+#[derive(Debug, Clone, Default)]
+pub struct UncompressedHeader {
+    pub show_existing_frame: bool,
+    pub frame_to_show_map_idx: u8,
+    pub temporal_point_info: Option<TemporalPointInfo>,
+    pub display_frame_id: Option<u32>,
+    pub frame_type: FrameType,
+    pub show_frame: bool,
+    pub showable_frame: bool,
+    pub error_resilient_mode: bool,
+    pub disable_cdf_update: bool,
+    pub allow_screen_content_tools: bool,
+    pub force_integer_mv: bool,
+    pub current_frame_id: u32,
+    pub frame_size_override_flag: bool,
+    pub order_hint: u32,
+    pub primary_ref_frame: u8,
+    pub buffer_removal_times: Vec<u32>,
+    pub refresh_frame_flags: u8,
+    pub ref_order_hint: Option<[u32; NUM_REF_FRAMES as usize]>,
+    pub allow_intrabc: bool,
+    pub frame_refs_short_signaling: bool,
+    pub ref_frame_idx: [u8; REFS_PER_FRAME as usize],
+    pub allow_high_precision_mv: bool,
+    pub interpolation_filter: InterpolationFilter,
+    pub is_motion_mode_switchable: bool,
+    pub use_ref_frame_mvs: bool,
+    pub disable_frame_end_update_cdf: bool,
+    pub tile_info: TileInfo,
+    pub quantization_params: QuantizationParams,
+    pub segmentation_params: SegmentationParams,
+    pub delta_q_params: DeltaQParams,
+    pub delta_lf_params: DeltaLfParams,
+    /// Whether each segment is coded losslessly.
+    pub lossless_array: [bool; MAX_SEGMENTS as usize],
+    /// The quantizer matrix level of each plane and segment.
+    pub seg_qm_level: [[u8; MAX_SEGMENTS as usize]; 3],
+    pub coded_lossless: bool,
+    pub all_lossless: bool,
+    pub loop_filter_params: LoopFilterParams,
+    pub cdef_params: CdefParams,
+    pub lr_params: LrParams,
+    pub tx_mode: TxMode,
+    pub reference_select: bool,
+    pub skip_mode_params: SkipModeParams,
+    pub allow_warped_motion: bool,
+    pub reduced_tx_set: bool,
+    pub global_motion_params: GlobalMotionParams,
+    pub film_grain_params: FilmGrainParams,
+}
 
 impl UncompressedHeader {
     pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer) -> Result<Self, ObuError> {
@@ -194,70 +1507,82 @@ impl UncompressedHeader {
             .sequence_header
             .as_ref()
             .cloned()
-            .ok_or_else(|| ObuError::NotFoundSequenceHeader)?;
+            .ok_or(ObuError::NotFoundSequenceHeader)?;
 
         let mut id_len = 0;
         if let Some(ref value) = sequence_header.frame_id_numbers_present {
-            id_len = value.additional_frame_id_length as usize
-                + value.delta_frame_id_length as usize
-                + 3;
+            id_len =
+                value.additional_frame_id_length as usize + value.delta_frame_id_length as usize;
         }
 
-        let all_frames = (1 << NUM_REF_FRAMES) - 1;
+        let all_frames = ((1u16 << NUM_REF_FRAMES) - 1) as u8;
+        let equal_picture_interval = sequence_header
+            .timing_info
+            .map(|v| v.equal_picture_interval.is_some())
+            .unwrap_or(false);
 
-        let mut show_existing_frame = false;
         let mut frame_type = FrameType::KeyFrame;
         let mut show_frame = true;
         let mut showable_frame = false;
-
-        let mut refresh_frame_flags = 0;
         let mut error_resilient_mode = false;
         let mut temporal_point_info = None;
-        let mut display_frame_id = None;
 
         if sequence_header.reduced_still_picture_header {
             ctx.frame_is_intra = true;
         } else {
             // show_existing_frame	f(1)
-            show_existing_frame = buf.get_bit();
-
+            let show_existing_frame = buf.get_bit();
             if show_existing_frame {
                 // frame_to_show_map_idx	f(3)
                 let frame_to_show_map_idx = buf.get_bits(3) as u8;
                 if let Some(decoder_model_info) = &sequence_header.decoder_model_info {
-                    if !sequence_header
-                        .timing_info
-                        .map(|v| v.equal_picture_interval.is_some())
-                        .unwrap_or(false)
-                    {
+                    if !equal_picture_interval {
                         temporal_point_info = Some(TemporalPointInfo::decode(
-                            buf.as_mut(),
+                            buf,
                             decoder_model_info.frame_presentation_time_length as usize,
                         ));
                     }
                 }
 
-                if sequence_header.frame_id_numbers_present.is_some() {
+                let display_frame_id = if sequence_header.frame_id_numbers_present.is_some() {
                     // display_frame_id	f(idLen)
-                    display_frame_id = Some(buf.get_bits(id_len));
-                }
+                    Some(buf.get_bits(id_len))
+                } else {
+                    None
+                };
 
                 frame_type = *ctx
                     .ref_frame_type
                     .get(frame_to_show_map_idx as usize)
-                    .ok_or_else(|| ObuError::Unknown(ObuUnknownError::FrameTypeRefIndex))?;
-
-                if frame_type == FrameType::KeyFrame {
-                    refresh_frame_flags = all_frames;
-                }
+                    .ok_or(ObuError::Unknown(ObuUnknownError::FrameTypeRefIndex))?;
 
-                if sequence_header.film_grain_params_present {
-                    // TODO
+                let film_grain_params = if sequence_header.film_grain_params_present {
                     // load_grain_params( frame_to_show_map_idx )
-                }
-
-                // TODO
-                // return
+                    ctx.saved_film_grain_params[frame_to_show_map_idx as usize]
+                } else {
+                    FilmGrainParams::default()
+                };
+
+                return Ok(Self {
+                    show_existing_frame,
+                    frame_to_show_map_idx,
+                    temporal_point_info,
+                    display_frame_id,
+                    frame_type,
+                    show_frame,
+                    refresh_frame_flags: if frame_type == FrameType::KeyFrame {
+                        all_frames
+                    } else {
+                        0
+                    },
+                    segmentation_params: ctx.saved_segmentation_params
+                        [frame_to_show_map_idx as usize],
+                    loop_filter_params: ctx.saved_loop_filter_params
+                        [frame_to_show_map_idx as usize],
+                    global_motion_params: ctx.saved_gm_params[frame_to_show_map_idx as usize],
+                    film_grain_params,
+                    ..Default::default()
+                });
             }
 
             // frame_type	f(2)
@@ -270,13 +1595,9 @@ impl UncompressedHeader {
 
             if show_frame {
                 if let Some(decoder_model_info) = &sequence_header.decoder_model_info {
-                    if !sequence_header
-                        .timing_info
-                        .map(|v| v.equal_picture_interval.is_some())
-                        .unwrap_or(false)
-                    {
+                    if !equal_picture_interval {
                         temporal_point_info = Some(TemporalPointInfo::decode(
-                            buf.as_mut(),
+                            buf,
                             decoder_model_info.frame_presentation_time_length as usize,
                         ));
                     }
@@ -298,17 +1619,14 @@ impl UncompressedHeader {
             };
         }
 
-        if frame_type == FrameType::SwitchFrame && show_frame {
+        if frame_type == FrameType::KeyFrame && show_frame {
             for i in 0..NUM_REF_FRAMES as usize {
                 ctx.ref_frame_marking[i] = false;
-                ctx.ref_order_hint[i] = i as u32;
+                ctx.ref_order_hint[i] = 0;
             }
 
-            for i in 0..REFS_PER_FRAME as u8 {
-                // TODO
-                // OrderHints[ LAST_FRAME + i ] = 0
-                //
-                // ctx.ref_order_hints[i] = 0;
+            for i in 0..REFS_PER_FRAME as usize {
+                ctx.order_hints[LAST_FRAME as usize + i] = 0;
             }
         }
 
@@ -337,7 +1655,7 @@ impl UncompressedHeader {
             force_integer_mv = true;
         }
 
-        let current_frame_id = if let Some(f) = &sequence_header.frame_id_numbers_present {
+        let current_frame_id = if sequence_header.frame_id_numbers_present.is_some() {
             // current_frame_id	f(idLen)
             buf.get_bits(id_len)
 
@@ -347,7 +1665,7 @@ impl UncompressedHeader {
             0
         };
 
-        let frame_size_override = if frame_type == FrameType::SwitchFrame {
+        let frame_size_override_flag = if frame_type == FrameType::SwitchFrame {
             true
         } else if sequence_header.reduced_still_picture_header {
             false
@@ -374,18 +1692,21 @@ impl UncompressedHeader {
             if buffer_removal_time_present_flag {
                 for operating_point in &sequence_header.operating_points {
                     if operating_point.operating_parameters_info.is_some() {
-                        if let Some(extension) = ctx.obu_header_extension {
-                            let op_pt_dic = operating_point.idc;
-                            let in_temporal_layer = ((op_pt_dic >> extension.temporal_id) & 1) != 0;
-                            let in_spatial_layer =
-                                ((op_pt_dic >> (extension.spatial_id + 8)) & 1) != 0;
-
-                            if op_pt_dic == 0 || (in_temporal_layer && in_spatial_layer) {
-                                // buffer_removal_time[ opNum ]	f(n)
-                                buffer_removal_times.push(buf.get_bits(
+                        let op_pt_idc = operating_point.idc;
+                        let (temporal_id, spatial_id) = ctx
+                            .obu_header_extension
+                            .map(|v| (v.temporal_id, v.spatial_id))
+                            .unwrap_or((0, 0));
+
+                        let in_temporal_layer = ((op_pt_idc >> temporal_id) & 1) != 0;
+                        let in_spatial_layer = ((op_pt_idc >> (spatial_id + 8)) & 1) != 0;
+                        if op_pt_idc == 0 || (in_temporal_layer && in_spatial_layer) {
+                            // buffer_removal_time[ opNum ]	f(n)
+                            buffer_removal_times.push(
+                                buf.get_bits(
                                     decoder_model_info.buffer_removal_time_length as usize,
-                                ));
-                            }
+                                ),
+                            );
                         }
                     }
                 }
@@ -395,14 +1716,19 @@ impl UncompressedHeader {
         let mut allow_high_precision_mv = false;
         let mut use_ref_frame_mvs = false;
         let mut allow_intrabc = false;
-        let mut ref_order_hints = None;
+        let mut ref_order_hint = None;
+        let mut frame_refs_short_signaling = false;
+        let mut ref_frame_idx = [0u8; REFS_PER_FRAME as usize];
+        let mut interpolation_filter = InterpolationFilter::default();
+        let mut is_motion_mode_switchable = false;
 
-        refresh_frame_flags = if frame_type == FrameType::SwitchFrame
+        let refresh_frame_flags = if frame_type == FrameType::SwitchFrame
             || frame_type == FrameType::KeyFrame && show_frame
         {
             all_frames
         } else {
-            buf.get_bits(8)
+            // refresh_frame_flags	f(8)
+            buf.get_bits(8) as u8
         };
 
         if (!ctx.frame_is_intra || refresh_frame_flags != all_frames)
@@ -410,47 +1736,44 @@ impl UncompressedHeader {
             && sequence_header.enable_order_hint
         {
             let mut hints = [0u32; NUM_REF_FRAMES as usize];
-            for i in 0..NUM_REF_FRAMES as usize {
+            for (i, hint) in hints.iter_mut().enumerate() {
                 // ref_order_hint[ i ]	f(OrderHintBits)
-                hints[i] = buf.get_bits(ctx.order_hint_bits);
+                *hint = buf.get_bits(ctx.order_hint_bits);
 
-                if ctx.ref_order_hint[i] != hints[i] {
+                if ctx.ref_order_hint[i] != *hint {
                     ctx.ref_frame_marking[i] = false;
                 }
             }
 
-            ref_order_hints = Some(hints);
+            ref_order_hint = Some(hints);
         }
 
-        let mut allow_intrabc = false;
         if ctx.frame_is_intra {
-            frame_size(ctx, frame_size_override, buf);
+            frame_size(ctx, frame_size_override_flag, buf);
             render_size(ctx, buf);
             if allow_screen_content_tools && ctx.upscaled_width == ctx.frame_width {
                 // allow_intrabc	f(1)
                 allow_intrabc = buf.get_bit();
             }
         } else {
-            let mut frame_refs_short_signaling = false;
-            if !sequence_header.enable_order_hint {
+            if sequence_header.enable_order_hint {
                 // frame_refs_short_signaling	f(1)
                 frame_refs_short_signaling = buf.get_bit();
                 if frame_refs_short_signaling {
                     // last_frame_idx	f(3)
-                    let last_frame_idx = buf.get_bits(3);
+                    let last_frame_idx = buf.get_bits(3) as u8;
 
                     // gold_frame_idx	f(3)
-                    let gold_frame_idx = buf.get_bits(3);
+                    let gold_frame_idx = buf.get_bits(3) as u8;
 
-                    // TODO
-                    // set_frame_refs()
+                    ref_frame_idx = set_frame_refs(ctx, last_frame_idx, gold_frame_idx);
                 }
             }
 
-            for _ in 0..REFS_PER_FRAME {
-                if frame_refs_short_signaling {
+            for idx in ref_frame_idx.iter_mut() {
+                if !frame_refs_short_signaling {
                     // ref_frame_idx[ i ]	f(3)
-                    let ref_frame_idx = buf.get_bits(3);
+                    *idx = buf.get_bits(3) as u8;
                 }
 
                 if let Some(frame_id_numbers_present) = &sequence_header.frame_id_numbers_present {
@@ -464,24 +1787,24 @@ impl UncompressedHeader {
                 }
             }
 
-            if frame_size_override && !error_resilient_mode {
-                frame_size_with_refs(ctx, frame_size_override, buf);
+            if frame_size_override_flag && !error_resilient_mode {
+                frame_size_with_refs(ctx, frame_size_override_flag, &ref_frame_idx, buf);
             } else {
-                frame_size(ctx, frame_size_override, buf);
+                frame_size(ctx, frame_size_override_flag, buf);
                 render_size(ctx, buf);
             }
 
-            let allow_high_precision_mv = if force_integer_mv {
+            allow_high_precision_mv = if force_integer_mv {
                 false
             } else {
                 // allow_high_precision_mv	f(1)
                 buf.get_bit()
             };
 
-            let interpolation_filter = read_interpolation_filter(buf)?;
+            interpolation_filter = read_interpolation_filter(buf)?;
 
             // is_motion_mode_switchable	f(1)
-            let is_motion_mode_switchable = buf.get_bit();
+            is_motion_mode_switchable = buf.get_bit();
             use_ref_frame_mvs = if error_resilient_mode || !sequence_header.enable_ref_frame_mvs {
                 false
             } else {
@@ -489,16 +1812,7 @@ impl UncompressedHeader {
                 buf.get_bit()
             };
 
-            // for ( i = 0; i < REFS_PER_FRAME; i++ ) {
-            //     refFrame = LAST_FRAME + i
-            //     hint = RefOrderHint[ ref_frame_idx[ i ] ]
-            //     OrderHints[ refFrame ] = hint
-            //     if ( !enable_order_hint ) {
-            //         RefFrameSignBias[ refFrame ] = 0
-            //     } else {
-            //         RefFrameSignBias[ refFrame ] = get_relative_dist( hint, OrderHint) > 0
-            //     }
-            // }
+            setup_order_hints(ctx, &ref_frame_idx);
         }
 
         let disable_frame_end_update_cdf =
@@ -509,34 +1823,377 @@ impl UncompressedHeader {
                 buf.get_bit()
             };
 
+        // The rest of setup_past_independence( ) and load_previous( ) is done
+        // by the decoding of the segmentation, loop filter and global motion
+        // params, which start from the params of primary_ref_frame.
         if primary_ref_frame == PRIMARY_REF_NONE {
-            // TODO:
-            // 
-            // init_non_coeff_cdfs()
-            // setup_past_independence()
+            ctx.cdf.init_non_coeff_cdfs();
         } else {
-            // TODO:
-            //
-            // load_cdfs( ref_frame_idx[ primary_ref_frame ] )
-            // load_previous( )
+            load_cdfs(ctx, ref_frame_idx[primary_ref_frame as usize] as usize);
+        }
+
+        let tile_info = TileInfo::decode(ctx, buf);
+        let quantization_params = QuantizationParams::decode(ctx, buf);
+        let segmentation_params = SegmentationParams::decode(
+            buf,
+            primary_ref_frame,
+            ref_frame_idx
+                .get(primary_ref_frame as usize)
+                .map(|idx| &ctx.saved_segmentation_params[*idx as usize]),
+        );
+
+        let delta_q_params = DeltaQParams::decode(buf, quantization_params.base_q_idx);
+        let delta_lf_params =
+            DeltaLfParams::decode(buf, delta_q_params.delta_q_present, allow_intrabc);
+
+        let color_config = &sequence_header.color_config;
+        ctx.frame_state = FrameState::new(ctx.mi_rows, ctx.mi_cols);
+        ctx.frame_state.cur_frame = Picture::new(
+            (ctx.mi_cols * MI_SIZE as u32).next_multiple_of(64) as usize,
+            (ctx.mi_rows * MI_SIZE as u32).next_multiple_of(64) as usize,
+            ctx.bit_depth,
+            color_config.subsampling_x,
+            color_config.subsampling_y,
+            ctx.num_planes,
+        );
+
+        if primary_ref_frame == PRIMARY_REF_NONE {
+            ctx.cdf.init_coeff_cdfs(quantization_params.base_q_idx);
+        } else {
+            load_previous_segment_ids(
+                ctx,
+                ref_frame_idx[primary_ref_frame as usize] as usize,
+                segmentation_params.segmentation_enabled,
+            );
+        }
+
+        if use_ref_frame_mvs {
+            motion_field_estimation(ctx, &ref_frame_idx);
+        }
+
+        let mut coded_lossless = true;
+        let mut lossless_array = [false; MAX_SEGMENTS as usize];
+        let mut seg_qm_level = [[0u8; MAX_SEGMENTS as usize]; 3];
+        for segment_id in 0..MAX_SEGMENTS {
+            let qindex = segmentation_params.qidx(segment_id, quantization_params.base_q_idx);
+            let lossless = qindex == 0
+                && quantization_params.delta_q_y_dc == 0
+                && quantization_params.delta_q_u_ac == 0
+                && quantization_params.delta_q_u_dc == 0
+                && quantization_params.delta_q_v_ac == 0
+                && quantization_params.delta_q_v_dc == 0;
+
+            lossless_array[segment_id as usize] = lossless;
+            if !lossless {
+                coded_lossless = false;
+            }
+
+            if quantization_params.using_qmatrix {
+                let levels = if lossless {
+                    [15, 15, 15]
+                } else {
+                    [
+                        quantization_params.qm_y,
+                        quantization_params.qm_u,
+                        quantization_params.qm_v,
+                    ]
+                };
+
+                for (plane, level) in levels.into_iter().enumerate() {
+                    seg_qm_level[plane][segment_id as usize] = level;
+                }
+            }
+        }
+
+        let all_lossless = coded_lossless && ctx.frame_width == ctx.upscaled_width;
+        let loop_filter_params = LoopFilterParams::decode(
+            ctx,
+            buf,
+            coded_lossless,
+            allow_intrabc,
+            ref_frame_idx
+                .get(primary_ref_frame as usize)
+                .map(|idx| &ctx.saved_loop_filter_params[*idx as usize]),
+        );
+
+        let cdef_params = CdefParams::decode(ctx, buf, coded_lossless, allow_intrabc);
+        let lr_params = LrParams::decode(ctx, buf, all_lossless, allow_intrabc);
+        init_lr_units(ctx, &lr_params)?;
+        let tx_mode = TxMode::decode(buf, coded_lossless);
+        let reference_select = if ctx.frame_is_intra {
+            false
+        } else {
+            // reference_select	f(1)
+            buf.get_bit()
+        };
+
+        let skip_mode_params = SkipModeParams::decode(ctx, buf, reference_select, &ref_frame_idx);
+        let allow_warped_motion = if ctx.frame_is_intra
+            || error_resilient_mode
+            || !sequence_header.enable_warped_motion
+        {
+            false
+        } else {
+            // allow_warped_motion	f(1)
+            buf.get_bit()
+        };
+
+        // reduced_tx_set	f(1)
+        let reduced_tx_set = buf.get_bit();
+        let global_motion_params = GlobalMotionParams::decode(
+            ctx,
+            buf,
+            allow_high_precision_mv,
+            ref_frame_idx
+                .get(primary_ref_frame as usize)
+                .map(|idx| &ctx.saved_gm_params[*idx as usize]),
+        );
+
+        let film_grain_params =
+            FilmGrainParams::decode(ctx, buf, frame_type, show_frame, showable_frame)?;
+
+        Ok(Self {
+            show_existing_frame: false,
+            frame_to_show_map_idx: 0,
+            temporal_point_info,
+            display_frame_id: None,
+            frame_type,
+            show_frame,
+            showable_frame,
+            error_resilient_mode,
+            disable_cdf_update,
+            allow_screen_content_tools,
+            force_integer_mv,
+            current_frame_id,
+            frame_size_override_flag,
+            order_hint,
+            primary_ref_frame,
+            buffer_removal_times,
+            refresh_frame_flags,
+            ref_order_hint,
+            allow_intrabc,
+            frame_refs_short_signaling,
+            ref_frame_idx,
+            allow_high_precision_mv,
+            interpolation_filter,
+            is_motion_mode_switchable,
+            use_ref_frame_mvs,
+            disable_frame_end_update_cdf,
+            tile_info,
+            quantization_params,
+            segmentation_params,
+            delta_q_params,
+            delta_lf_params,
+            lossless_array,
+            seg_qm_level,
+            coded_lossless,
+            all_lossless,
+            loop_filter_params,
+            cdef_params,
+            lr_params,
+            tx_mode,
+            reference_select,
+            skip_mode_params,
+            allow_warped_motion,
+            reduced_tx_set,
+            global_motion_params,
+            film_grain_params,
+        })
+    }
+}
+
+impl UncompressedHeader {
+    /// Returns the quantizer index for the given segment, without taking the
+    /// block level delta q into account.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#dequantization-functions
+    pub fn get_qidx(&self, segment_id: u8) -> u8 {
+        self.segmentation_params
+            .qidx(segment_id, self.quantization_params.base_q_idx)
+    }
+}
+
+/// The reference frame update process, this saves the state of the current
+/// frame into every reference slot selected by refresh_frame_flags.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#reference-frame-update-process
+pub fn reference_frame_update(ctx: &mut ObuContext, header: &UncompressedHeader) {
+    let (mf_mvs, mf_ref_frames) = if header.show_existing_frame {
+        // MfMvs and MfRefFrames are loaded from the shown frame by the
+        // reference frame loading process.
+        let idx = header.frame_to_show_map_idx as usize;
+        (
+            ctx.saved_mvs[idx].clone(),
+            ctx.saved_ref_frames[idx].clone(),
+        )
+    } else {
+        motion_field_motion_vector_storage(ctx)
+    };
+
+    for i in 0..NUM_REF_FRAMES as usize {
+        if (header.refresh_frame_flags >> i) & 1 == 1 {
+            ctx.ref_frame_marking[i] = true;
+            ctx.ref_frame_type[i] = header.frame_type;
+            ctx.ref_upscaled_width[i] = ctx.upscaled_width;
+            ctx.ref_frame_width[i] = ctx.frame_width;
+            ctx.ref_frame_height[i] = ctx.frame_height;
+            ctx.ref_render_width[i] = ctx.render_width;
+            ctx.ref_render_height[i] = ctx.render_height;
+            ctx.ref_order_hint[i] = ctx.order_hint;
+            ctx.saved_segmentation_params[i] = header.segmentation_params;
+            ctx.saved_loop_filter_params[i] = header.loop_filter_params;
+            ctx.saved_gm_params[i] = header.global_motion_params;
+            ctx.saved_film_grain_params[i] = header.film_grain_params;
+            save_cdfs(ctx, i);
+            ctx.ref_mi_rows[i] = ctx.frame_state.mi_rows;
+            ctx.ref_mi_cols[i] = ctx.frame_state.mi_cols;
+            ctx.saved_segment_ids[i] = ctx.frame_state.segment_ids();
+            ctx.saved_order_hints[i] = ctx.order_hints;
+            ctx.saved_mvs[i].clone_from(&mf_mvs);
+            ctx.saved_ref_frames[i].clone_from(&mf_ref_frames);
+            ctx.frame_store[i].clone_from(&ctx.frame_state.cur_frame);
         }
+    }
+}
+
+/// The decode frame wrapup process, run once the last tile of a frame has
+/// been decoded or when an existing frame is shown. The in-loop filters are
+/// not implemented, the reconstructed frame is saved as it is.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#decode-frame-wrapup-process
+pub fn decode_frame_wrapup(ctx: &mut ObuContext, header: &UncompressedHeader) {
+    if header.show_existing_frame {
+        if header.frame_type == FrameType::KeyFrame {
+            let idx = header.frame_to_show_map_idx as usize;
+            load_cdfs(ctx, idx);
+            reference_frame_loading(ctx, idx);
+            reference_frame_update(ctx, header);
+        }
+
+        return;
+    }
+
+    let segmentation_params = &header.segmentation_params;
+    if segmentation_params.segmentation_enabled && !segmentation_params.segmentation_update_map {
+        let frame_state = &mut ctx.frame_state;
+        for (mi, segment_id) in frame_state
+            .mode_info
+            .iter_mut()
+            .zip(&frame_state.prev_segment_ids)
+        {
+            mi.segment_id = *segment_id;
+        }
+    }
+
+    reference_frame_update(ctx, header);
+}
+
+/// Loads the segment ids of the reference frame `prev_frame` used for the
+/// prediction of the segment ids of the current frame, they are only used
+/// when the frame has the same size as the reference frame.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#load-previous-segment-ids-process
+fn load_previous_segment_ids(ctx: &mut ObuContext, prev_frame: usize, segmentation_enabled: bool) {
+    if segmentation_enabled
+        && ctx.ref_mi_cols[prev_frame] == ctx.mi_cols
+        && ctx.ref_mi_rows[prev_frame] == ctx.mi_rows
+    {
+        ctx.frame_state
+            .prev_segment_ids
+            .clone_from(&ctx.saved_segment_ids[prev_frame]);
+    }
+}
 
-        Ok(Self {})
+/// The reference frame loading process, this restores the state saved with
+/// the reference frame `idx` as the state of the current frame before a
+/// shown key frame refreshes every slot with it.
+///
+/// The parameters saved in the uncompressed header (segmentation, loop
+/// filter, global motion and film grain) are loaded by the header itself.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#reference-frame-loading-process
+pub fn reference_frame_loading(ctx: &mut ObuContext, idx: usize) {
+    ctx.upscaled_width = ctx.ref_upscaled_width[idx];
+    ctx.frame_width = ctx.ref_frame_width[idx];
+    ctx.frame_height = ctx.ref_frame_height[idx];
+    ctx.render_width = ctx.ref_render_width[idx];
+    ctx.render_height = ctx.ref_render_height[idx];
+    ctx.mi_cols = ctx.ref_mi_cols[idx];
+    ctx.mi_rows = ctx.ref_mi_rows[idx];
+    ctx.order_hint = ctx.ref_order_hint[idx];
+    ctx.order_hints = ctx.saved_order_hints[idx];
+
+    ctx.frame_state = FrameState::new(ctx.ref_mi_rows[idx], ctx.ref_mi_cols[idx]);
+    ctx.frame_state.cur_frame.clone_from(&ctx.frame_store[idx]);
+    for (mi, segment_id) in ctx
+        .frame_state
+        .mode_info
+        .iter_mut()
+        .zip(&ctx.saved_segment_ids[idx])
+    {
+        mi.segment_id = *segment_id;
     }
 }
 
+/// Reads `count` bits into bytes, the last byte is padded with zero bits
+/// when `count` is not a multiple of 8.
+fn read_raw_bits(buf: &mut Buffer, count: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(count.div_ceil(8));
+    let mut remaining = count;
+    while remaining > 0 {
+        let n = remaining.min(8);
+        bytes.push((buf.get_bits(n) << (8 - n)) as u8);
+        remaining -= n;
+    }
+
+    bytes
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#frame-header-obu-syntax
 #[derive(Debug, Clone)]
-pub struct FrameHeader {}
+pub struct FrameHeader {
+    pub uncompressed_header: UncompressedHeader,
+}
 
 impl FrameHeader {
     pub fn decode(ctx: &mut ObuContext, buf: &mut Buffer) -> Result<Self, ObuError> {
         if ctx.seen_frame_header {
-            // TODO:
-            // frame_header_copy
-        } else {
-            ctx.seen_frame_header = true;
+            // frame_header_copy()
+            //
+            // It is a requirement of bitstream conformance that a copy of the
+            // frame header is bit-identical to the first one of the frame.
+            let uncompressed_header = ctx
+                .frame_header
+                .clone()
+                .ok_or(ObuError::NotFoundFrameHeader)?;
+
+            if read_raw_bits(buf, ctx.frame_header_size) != ctx.frame_header_data {
+                return Err(ObuError::FrameHeaderMismatch);
+            }
+
+            return Ok(Self {
+                uncompressed_header,
+            });
         }
 
-        Ok(Self {})
+        let mut raw = buf.clone();
+        let start = buf.get_position();
+        let uncompressed_header = UncompressedHeader::decode(ctx, buf)?;
+        ctx.frame_header_size = buf.get_position() - start;
+        ctx.frame_header_data = read_raw_bits(&mut raw, ctx.frame_header_size);
+
+        // Only a header that was decoded is copied by the next frame header
+        // obus of the frame.
+        ctx.seen_frame_header = true;
+
+        if uncompressed_header.show_existing_frame {
+            decode_frame_wrapup(ctx, &uncompressed_header);
+            ctx.seen_frame_header = false;
+        }
+
+        ctx.frame_header = Some(uncompressed_header.clone());
+        Ok(Self {
+            uncompressed_header,
+        })
     }
 }
@@ -1,15 +1,27 @@
 pub mod frame;
 pub mod frame_header;
 pub mod metadata;
+pub mod order_hint;
 pub mod sequence_header;
 pub mod tile_group;
 pub mod tile_list;
 
 use frame::Frame;
-use frame_header::{FrameHeader, FrameType};
+use frame_header::{
+    FilmGrainParams, FrameHeader, FrameType, GlobalMotionParams, LoopFilterParams,
+    SegmentationParams, UncompressedHeader,
+};
 use sequence_header::SequenceHeader;
+use tile_group::TileGroup;
+use tile_list::TileList;
 
-use crate::buffer::Buffer;
+use crate::{
+    buffer::Buffer,
+    cdf::CdfContext,
+    constants::{NUM_REF_FRAMES, TOTAL_REFS_PER_FRAME},
+    picture::Picture,
+    tile::mode_info::{FrameState, Mv},
+};
 
 /// see: https://aomediacodec.github.io/av1-spec/#obu-header-semantics
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -108,7 +120,7 @@ impl ObuHeader {
         buf.seek_bits(1);
 
         let extension = if obu_extension_flag {
-            Some(ObuHeaderExtension::decode(buf.as_mut())?)
+            Some(ObuHeaderExtension::decode(buf)?)
         } else {
             None
         };
@@ -122,12 +134,16 @@ impl ObuHeader {
 }
 
 #[derive(Debug)]
-pub enum Obu {
+pub enum Obu<'a> {
     SequenceHeader(SequenceHeader),
-    Frame(Frame),
-    FrameHeader(FrameHeader),
+    Frame(Box<Frame<'a>>),
+    FrameHeader(Box<FrameHeader>),
+    TileGroup(TileGroup<'a>),
+    TileList(TileList<'a>),
     TemporalDelimiter,
     Drop,
+    /// Metadata, padding and reserved obus, which are ignored by the decoder.
+    Skipped(ObuType),
 }
 
 #[derive(Default)]
@@ -139,8 +155,10 @@ pub struct ObuParser {
 }
 
 impl ObuParser {
-    pub fn parse(&mut self, buf: &mut Buffer) -> Result<Obu, ObuError> {
-        let header = ObuHeader::decode(buf.as_mut())?;
+    pub fn parse<'a>(&mut self, buf: &mut Buffer<'a>) -> Result<Obu<'a>, ObuError> {
+        let header = ObuHeader::decode(buf)?;
+        self.ctx.obu_header_extension = header.extension;
+
         let size = if header.has_size {
             // obu_size leb128()
             Some(buf.get_leb128() as usize)
@@ -148,28 +166,69 @@ impl ObuParser {
             None
         };
 
+        let start = buf.get_position();
         if header.r#type != ObuType::SequenceHeader
             && header.r#type != ObuType::TemporalDelimiter
             && self.ctx.operating_point_idc != 0
         {
             if let Some(ext) = header.extension {
-                let in_temporal_layer = (1 >> ext.temporal_id) & 1;
-                let in_spatial_layer = (1 >> (ext.spatial_id + 8)) & 1;
+                let in_temporal_layer = (self.ctx.operating_point_idc >> ext.temporal_id) & 1;
+                let in_spatial_layer = (self.ctx.operating_point_idc >> (ext.spatial_id + 8)) & 1;
                 if in_temporal_layer == 0 || in_spatial_layer == 0 {
+                    if let Some(size) = size {
+                        buf.seek_bits(size * 8);
+                    }
+
                     return Ok(Obu::Drop);
                 }
             }
         }
 
-        Ok(match header.r#type {
+        let obu = match header.r#type {
             ObuType::SequenceHeader => {
-                Obu::SequenceHeader(SequenceHeader::decode(&mut self.ctx, buf)?)
+                let sequence_header = SequenceHeader::decode(&mut self.ctx, buf)?;
+                self.ctx.sequence_header = Some(sequence_header.clone());
+                Obu::SequenceHeader(sequence_header)
             }
-            ObuType::FrameHeader => Obu::FrameHeader(FrameHeader::decode(&mut self.ctx, buf)?),
-            ObuType::Frame => Obu::Frame(Frame::decode(&mut self.ctx, buf)?),
-            ObuType::TemporalDelimiter => Obu::TemporalDelimiter,
-            _ => todo!(),
-        })
+            ObuType::FrameHeader | ObuType::RedundantFrameHeader => {
+                Obu::FrameHeader(Box::new(FrameHeader::decode(&mut self.ctx, buf)?))
+            }
+            ObuType::TileGroup => Obu::TileGroup(TileGroup::decode(
+                &mut self.ctx,
+                buf,
+                size.unwrap_or_else(|| buf.remaining()),
+            )?),
+            ObuType::Frame => Obu::Frame(Box::new(Frame::decode(
+                &mut self.ctx,
+                buf,
+                size.unwrap_or_else(|| buf.remaining()),
+            )?)),
+            ObuType::TileList => Obu::TileList(TileList::decode(buf)?),
+            ObuType::TemporalDelimiter => {
+                self.ctx.seen_frame_header = false;
+                Obu::TemporalDelimiter
+            }
+            ObuType::Metadata | ObuType::Padding | ObuType::Reserved(_) => {
+                if size.is_none() {
+                    // The obu extends to the end of the data.
+                    buf.seek_bits(buf.remaining() * 8);
+                }
+
+                Obu::Skipped(header.r#type)
+            }
+        };
+
+        // The payload may be followed by trailing bits (or be partially parsed),
+        // so move on to the start of the next obu when the size is known.
+        if let Some(size) = size {
+            let end = start + size * 8;
+            let position = buf.get_position();
+            if end > position {
+                buf.seek_bits(end - position);
+            }
+        }
+
+        Ok(obu)
     }
 }
 
@@ -192,13 +251,22 @@ pub enum ObuUnknownError {
 pub enum ObuError {
     Unknown(ObuUnknownError),
     NotFoundSequenceHeader,
+    NotFoundFrameHeader,
+    InvalidFilmGrainParams,
+    FrameHeaderMismatch,
+    InvalidTileGroup,
+    InvalidTileList,
+    InvalidAnchorFrameIdx,
+    Unsupported,
+    InvalidSymbolData,
+    InvalidMotionVector,
 }
 
 impl std::error::Error for ObuError {}
 
 impl std::fmt::Display for ObuError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self)
+        write!(f, "{:?}", self)
     }
 }
 
@@ -223,8 +291,44 @@ pub struct ObuContext {
     pub order_hint_bits: usize,
     pub operating_point: usize,
     pub operating_point_idc: u16,
-    pub ref_frame_type: Vec<FrameType>,
-    pub ref_frame_marking: Vec<bool>,
-    pub ref_order_hint: Vec<u32>,
-    pub ref_order_hints: Vec<bool>, 
+    pub frame_header: Option<UncompressedHeader>,
+    /// The raw bits of the first frame header of the current frame, used to
+    /// check that its copies are identical.
+    pub frame_header_data: Vec<u8>,
+    /// The size of the first frame header of the current frame, in bits.
+    pub frame_header_size: usize,
+    pub ref_frame_type: [FrameType; NUM_REF_FRAMES as usize],
+    pub ref_frame_marking: [bool; NUM_REF_FRAMES as usize],
+    pub ref_order_hint: [u32; NUM_REF_FRAMES as usize],
+    pub order_hints: [u32; TOTAL_REFS_PER_FRAME as usize],
+    pub ref_frame_sign_bias: [bool; TOTAL_REFS_PER_FRAME as usize],
+    pub ref_upscaled_width: [u16; NUM_REF_FRAMES as usize],
+    pub ref_frame_width: [u16; NUM_REF_FRAMES as usize],
+    pub ref_frame_height: [u16; NUM_REF_FRAMES as usize],
+    pub ref_render_width: [u16; NUM_REF_FRAMES as usize],
+    pub ref_render_height: [u16; NUM_REF_FRAMES as usize],
+    pub saved_segmentation_params: [SegmentationParams; NUM_REF_FRAMES as usize],
+    pub saved_loop_filter_params: [LoopFilterParams; NUM_REF_FRAMES as usize],
+    pub saved_gm_params: [GlobalMotionParams; NUM_REF_FRAMES as usize],
+    pub saved_film_grain_params: [FilmGrainParams; NUM_REF_FRAMES as usize],
+    /// The cdfs of the current frame.
+    pub cdf: CdfContext,
+    pub saved_cdfs: [CdfContext; NUM_REF_FRAMES as usize],
+    /// The cdfs at the end of the tile context_update_tile_id, used by the
+    /// frame end update of the cdfs.
+    pub context_update_cdf: Option<CdfContext>,
+    pub ref_mi_cols: [u32; NUM_REF_FRAMES as usize],
+    pub ref_mi_rows: [u32; NUM_REF_FRAMES as usize],
+    pub saved_segment_ids: [Vec<u8>; NUM_REF_FRAMES as usize],
+    /// The OrderHints of each reference frame when it was decoded.
+    pub saved_order_hints: [[u32; TOTAL_REFS_PER_FRAME as usize]; NUM_REF_FRAMES as usize],
+    /// The motion vectors of each reference frame that can be projected by
+    /// the motion field estimation, with their reference frames.
+    pub saved_mvs: [Vec<Mv>; NUM_REF_FRAMES as usize],
+    pub saved_ref_frames: [Vec<i8>; NUM_REF_FRAMES as usize],
+    /// The samples of each reference frame (FrameStore) used by the inter
+    /// prediction.
+    pub frame_store: [Picture; NUM_REF_FRAMES as usize],
+    /// The mode info and the other state of the frame being decoded.
+    pub frame_state: FrameState,
 }
@@ -0,0 +1,150 @@
+//! Order hint helpers, used to find the relative position in display order
+//! of the current frame and its reference frames.
+
+use super::ObuContext;
+
+use crate::constants::{
+    ALTREF2_FRAME, ALTREF_FRAME, BWDREF_FRAME, GOLDEN_FRAME, LAST2_FRAME, LAST3_FRAME, LAST_FRAME,
+    NUM_REF_FRAMES, REFS_PER_FRAME,
+};
+
+/// Returns the signed distance between two order hints, a positive value
+/// means that `a` comes after `b` in display order.
+///
+/// OrderHintBits is zero when enable_order_hint is not set, so only the
+/// number of bits is checked here.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#order-hint-semantics
+pub fn get_relative_dist(ctx: &ObuContext, a: u32, b: u32) -> i32 {
+    if ctx.order_hint_bits == 0 {
+        return 0;
+    }
+
+    let diff = a as i32 - b as i32;
+    let m = 1 << (ctx.order_hint_bits - 1);
+    (diff & (m - 1)) - (diff & m)
+}
+
+/// Fills `OrderHints` and `RefFrameSignBias` for each reference frame used by
+/// the current frame.
+pub fn setup_order_hints(ctx: &mut ObuContext, ref_frame_idx: &[u8; REFS_PER_FRAME as usize]) {
+    for (i, idx) in ref_frame_idx.iter().enumerate() {
+        let ref_frame = LAST_FRAME as usize + i;
+        let hint = ctx.ref_order_hint[*idx as usize];
+
+        ctx.order_hints[ref_frame] = hint;
+        ctx.ref_frame_sign_bias[ref_frame] = get_relative_dist(ctx, hint, ctx.order_hint) > 0;
+    }
+}
+
+/// The set frame refs process, computes the reference frames used by the
+/// current frame when only the LAST_FRAME and GOLDEN_FRAME references are
+/// signaled.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#set-frame-refs-process
+pub fn set_frame_refs(
+    ctx: &ObuContext,
+    last_frame_idx: u8,
+    gold_frame_idx: u8,
+) -> [u8; REFS_PER_FRAME as usize] {
+    let mut ref_frame_idx = [-1i8; REFS_PER_FRAME as usize];
+    ref_frame_idx[0] = last_frame_idx as i8;
+    ref_frame_idx[(GOLDEN_FRAME - LAST_FRAME) as usize] = gold_frame_idx as i8;
+
+    let mut used_frame = [false; NUM_REF_FRAMES as usize];
+    used_frame[last_frame_idx as usize] = true;
+    used_frame[gold_frame_idx as usize] = true;
+
+    let cur_frame_hint = 1i32 << (ctx.order_hint_bits.max(1) - 1);
+    let mut shifted_order_hints = [0i32; NUM_REF_FRAMES as usize];
+    for (i, hint) in shifted_order_hints.iter_mut().enumerate() {
+        *hint = cur_frame_hint + get_relative_dist(ctx, ctx.ref_order_hint[i], ctx.order_hint);
+    }
+
+    // The ALTREF_FRAME is the backward reference with the highest order hint,
+    // then the BWDREF_FRAME and ALTREF2_FRAME are the closest backward ones.
+    for (ref_frame, latest) in [
+        (ALTREF_FRAME, true),
+        (BWDREF_FRAME, false),
+        (ALTREF2_FRAME, false),
+    ] {
+        if let Some(i) = find_ref(
+            &shifted_order_hints,
+            &used_frame,
+            cur_frame_hint,
+            true,
+            latest,
+        ) {
+            ref_frame_idx[(ref_frame - LAST_FRAME) as usize] = i as i8;
+            used_frame[i] = true;
+        }
+    }
+
+    // The remaining references are the forward ones, in decreasing order hint.
+    for ref_frame in [
+        LAST2_FRAME,
+        LAST3_FRAME,
+        BWDREF_FRAME,
+        ALTREF2_FRAME,
+        ALTREF_FRAME,
+    ] {
+        if ref_frame_idx[(ref_frame - LAST_FRAME) as usize] < 0 {
+            if let Some(i) = find_ref(
+                &shifted_order_hints,
+                &used_frame,
+                cur_frame_hint,
+                false,
+                true,
+            ) {
+                ref_frame_idx[(ref_frame - LAST_FRAME) as usize] = i as i8;
+                used_frame[i] = true;
+            }
+        }
+    }
+
+    // Anything left uses the reference with the earliest order hint.
+    let mut earliest_ref = 0;
+    for (i, hint) in shifted_order_hints.iter().enumerate() {
+        if *hint < shifted_order_hints[earliest_ref] {
+            earliest_ref = i;
+        }
+    }
+
+    ref_frame_idx.map(|idx| {
+        if idx < 0 {
+            earliest_ref as u8
+        } else {
+            idx as u8
+        }
+    })
+}
+
+/// Finds the unused backward (or forward) reference with the latest (or
+/// earliest) shifted order hint, this covers find_latest_backward,
+/// find_earliest_backward and find_latest_forward of the spec.
+fn find_ref(
+    shifted_order_hints: &[i32; NUM_REF_FRAMES as usize],
+    used_frame: &[bool; NUM_REF_FRAMES as usize],
+    cur_frame_hint: i32,
+    backward: bool,
+    latest: bool,
+) -> Option<usize> {
+    let mut found: Option<(usize, i32)> = None;
+    for (i, hint) in shifted_order_hints.iter().copied().enumerate() {
+        if used_frame[i] || (hint >= cur_frame_hint) != backward {
+            continue;
+        }
+
+        let better = match found {
+            None => true,
+            Some((_, best)) if latest => hint >= best,
+            Some((_, best)) => hint < best,
+        };
+
+        if better {
+            found = Some((i, hint));
+        }
+    }
+
+    found.map(|(i, _)| i)
+}
@@ -1,2 +1,127 @@
+use super::{frame_header::decode_frame_wrapup, Buffer, ObuContext, ObuError};
+use crate::{cdf::frame_end_update_cdf, tile::decode_tile};
+
+/// The coded data of a single tile.
+#[derive(Debug, Clone, Copy)]
+pub struct Tile<'a> {
+    pub tile_num: u32,
+    pub tile_row: u32,
+    pub tile_col: u32,
+    pub data: &'a [u8],
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#tile-group-obu-syntax
 #[derive(Debug, Clone)]
-pub struct TileGroup {}
+pub struct TileGroup<'a> {
+    pub tile_start_and_end_present_flag: bool,
+    pub tg_start: u32,
+    pub tg_end: u32,
+    pub tiles: Vec<Tile<'a>>,
+}
+
+impl<'a> TileGroup<'a> {
+    /// `size` is the number of bytes of the tile group, starting at the
+    /// current position of the buffer.
+    pub fn decode(
+        ctx: &mut ObuContext,
+        buf: &mut Buffer<'a>,
+        size: usize,
+    ) -> Result<Self, ObuError> {
+        let frame_header = ctx
+            .frame_header
+            .as_ref()
+            .ok_or(ObuError::NotFoundFrameHeader)?;
+
+        let tile_info = &frame_header.tile_info;
+        let num_tiles = tile_info.tile_cols * tile_info.tile_rows;
+        let start_bit_pos = buf.get_position();
+
+        let mut tile_start_and_end_present_flag = false;
+        if num_tiles > 1 {
+            // tile_start_and_end_present_flag	f(1)
+            tile_start_and_end_present_flag = buf.get_bit();
+        }
+
+        let (tg_start, tg_end) = if num_tiles == 1 || !tile_start_and_end_present_flag {
+            (0, num_tiles - 1)
+        } else {
+            let tile_bits = (tile_info.tile_cols_log2 + tile_info.tile_rows_log2) as usize;
+
+            // tg_start	f(tileBits)
+            let tg_start = buf.get_bits(tile_bits);
+
+            // tg_end	f(tileBits)
+            let tg_end = buf.get_bits(tile_bits);
+
+            (tg_start, tg_end)
+        };
+
+        if tg_start > tg_end || tg_end >= num_tiles {
+            return Err(ObuError::InvalidTileGroup);
+        }
+
+        buf.byte_alignment();
+
+        let header_bytes = (buf.get_position() - start_bit_pos) / 8;
+        let mut size = size
+            .checked_sub(header_bytes)
+            .ok_or(ObuError::InvalidTileGroup)?;
+
+        let mut tiles = Vec::with_capacity((tg_end - tg_start + 1) as usize);
+        for tile_num in tg_start..=tg_end {
+            let tile_size = if tile_num == tg_end {
+                size
+            } else {
+                let tile_size_bytes = tile_info.tile_size_bytes as usize;
+                if size < tile_size_bytes {
+                    return Err(ObuError::InvalidTileGroup);
+                }
+
+                // tile_size_minus_1	le(TileSizeBytes)
+                let tile_size = buf.get_le(tile_size_bytes) as usize + 1;
+                size = size
+                    .checked_sub(tile_size + tile_size_bytes)
+                    .ok_or(ObuError::InvalidTileGroup)?;
+
+                tile_size
+            };
+
+            if tile_size > buf.remaining() {
+                return Err(ObuError::InvalidTileGroup);
+            }
+
+            tiles.push(Tile {
+                tile_num,
+                tile_row: tile_num / tile_info.tile_cols,
+                tile_col: tile_num % tile_info.tile_cols,
+                data: buf.get_bytes(tile_size),
+            });
+        }
+
+        for tile in &tiles {
+            decode_tile(ctx, tile)?;
+        }
+
+        if tg_end == num_tiles - 1 {
+            let frame_header = ctx
+                .frame_header
+                .take()
+                .ok_or(ObuError::NotFoundFrameHeader)?;
+
+            if !frame_header.disable_frame_end_update_cdf {
+                frame_end_update_cdf(ctx);
+            }
+
+            decode_frame_wrapup(ctx, &frame_header);
+            ctx.frame_header = Some(frame_header);
+            ctx.seen_frame_header = false;
+        }
+
+        Ok(Self {
+            tile_start_and_end_present_flag,
+            tg_start,
+            tg_end,
+            tiles,
+        })
+    }
+}
@@ -1,19 +1,29 @@
-use super::Buffer;
+use super::{Buffer, ObuError};
 
+/// The largest number of anchor frames a tile list entry can refer to.
+pub const MAX_ANCHOR_FRAMES: u8 = 128;
+
+/// see: https://aomediacodec.github.io/av1-spec/#tile-list-entry-syntax
 #[derive(Debug, Clone, Copy)]
-pub struct TileListEntry {
+pub struct TileListEntry<'a> {
     pub anchor_frame_idx: u8,
     pub anchor_tile_row: u8,
     pub anchor_tile_col: u8,
-    pub tile_data_size: u16,
-    pub coded_tile_data: u32,
+    pub tile_data_size: u32,
+    pub coded_tile_data: &'a [u8],
 }
 
-impl TileListEntry {
-    pub fn decode(buf: &mut Buffer) -> Self {
+impl<'a> TileListEntry<'a> {
+    pub fn decode(buf: &mut Buffer<'a>) -> Result<Self, ObuError> {
         // anchor_frame_idx	f(8)
         let anchor_frame_idx = buf.get_bits(8) as u8;
 
+        // It is a requirement of bitstream conformance that anchor_frame_idx
+        // is less than or equal to 127.
+        if anchor_frame_idx >= MAX_ANCHOR_FRAMES {
+            return Err(ObuError::InvalidAnchorFrameIdx);
+        }
+
         // anchor_tile_row	f(8)
         let anchor_tile_row = buf.get_bits(8) as u8;
 
@@ -21,46 +31,50 @@ impl TileListEntry {
         let anchor_tile_col = buf.get_bits(8) as u8;
 
         // tile_data_size_minus_1	f(16)
-        let tile_data_size = buf.get_bits(16) as u16 + 1;
+        let tile_data_size = buf.get_bits(16) + 1;
+        if tile_data_size as usize > buf.remaining() {
+            return Err(ObuError::InvalidTileList);
+        }
 
         // coded_tile_data	f(N)
-        let coded_tile_data = buf.get_bits(8 * tile_data_size as usize);
-        Self {
+        let coded_tile_data = buf.get_bytes(tile_data_size as usize);
+        Ok(Self {
             anchor_frame_idx,
             anchor_tile_col,
             anchor_tile_row,
             tile_data_size,
             coded_tile_data,
-        }
+        })
     }
 }
 
+/// see: https://aomediacodec.github.io/av1-spec/#tile-list-obu-syntax
 #[derive(Debug, Clone)]
-pub struct TileList {
-    pub output_frame_width_in_tiles: u8,
-    pub output_frame_height_in_tiles: u8,
-    pub tile_list_entrys: Vec<TileListEntry>,
+pub struct TileList<'a> {
+    pub output_frame_width_in_tiles: u16,
+    pub output_frame_height_in_tiles: u16,
+    pub tile_list_entrys: Vec<TileListEntry<'a>>,
 }
 
-impl TileList {
-    pub fn decode(buf: &mut Buffer) -> Self {
+impl<'a> TileList<'a> {
+    pub fn decode(buf: &mut Buffer<'a>) -> Result<Self, ObuError> {
         // output_frame_width_in_tiles_minus_1	f(8)
-        let output_frame_width_in_tiles = buf.get_bits(8) as u8;
+        let output_frame_width_in_tiles = buf.get_bits(8) as u16 + 1;
 
         // output_frame_height_in_tiles_minus_1	f(8)
-        let output_frame_height_in_tiles = buf.get_bits(8) as u8;
+        let output_frame_height_in_tiles = buf.get_bits(8) as u16 + 1;
 
         // tile_count_minus_1	f(16)
-        let tile_count = buf.get_bits(16) as usize;
+        let tile_count = buf.get_bits(16) as usize + 1;
         let mut tile_list_entrys = Vec::with_capacity(tile_count);
         for _ in 0..tile_count {
-            tile_list_entrys.push(TileListEntry::decode(buf));
+            tile_list_entrys.push(TileListEntry::decode(buf)?);
         }
 
-        Self {
+        Ok(Self {
             output_frame_height_in_tiles,
             output_frame_width_in_tiles,
             tile_list_entrys,
-        }
+        })
     }
 }
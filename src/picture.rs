@@ -0,0 +1,100 @@
+//! Decoded sample storage.
+
+/// A plane of samples, samples are stored as u16 regardless of the bit depth.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Plane {
+    pub width: usize,
+    pub height: usize,
+    pub stride: usize,
+    pub data: Vec<u16>,
+}
+
+impl Plane {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            stride: width,
+            data: vec![0; width * height],
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> u16 {
+        self.data[y * self.stride + x]
+    }
+
+    #[inline]
+    pub fn set(&mut self, x: usize, y: usize, value: u16) {
+        self.data[y * self.stride + x] = value;
+    }
+
+    #[inline]
+    pub fn row(&self, y: usize) -> &[u16] {
+        &self.data[y * self.stride..y * self.stride + self.width]
+    }
+
+    #[inline]
+    pub fn row_mut(&mut self, y: usize) -> &mut [u16] {
+        &mut self.data[y * self.stride..y * self.stride + self.width]
+    }
+
+    /// Copies all the samples of `src` to `(x, y)` in this plane, clipped to
+    /// the size of this plane.
+    pub fn copy_from(&mut self, src: &Plane, x: usize, y: usize) {
+        let width = src.width.min(self.width.saturating_sub(x));
+        let height = src.height.min(self.height.saturating_sub(y));
+        for row in 0..height {
+            self.row_mut(y + row)[x..x + width].copy_from_slice(&src.row(row)[..width]);
+        }
+    }
+}
+
+/// A decoded frame, made of one plane for monochrome content and three planes
+/// otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Picture {
+    pub bit_depth: u8,
+    pub subsampling_x: bool,
+    pub subsampling_y: bool,
+    pub planes: Vec<Plane>,
+}
+
+impl Picture {
+    pub fn new(
+        width: usize,
+        height: usize,
+        bit_depth: u8,
+        subsampling_x: bool,
+        subsampling_y: bool,
+        num_planes: u8,
+    ) -> Self {
+        let planes = (0..num_planes)
+            .map(|plane| {
+                if plane == 0 {
+                    Plane::new(width, height)
+                } else {
+                    Plane::new(
+                        (width + subsampling_x as usize) >> subsampling_x as usize,
+                        (height + subsampling_y as usize) >> subsampling_y as usize,
+                    )
+                }
+            })
+            .collect();
+
+        Self {
+            bit_depth,
+            subsampling_x,
+            subsampling_y,
+            planes,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.planes[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.planes[0].height
+    }
+}
@@ -0,0 +1,200 @@
+//! Symbol decoder, the arithmetic decoder used for the tile data.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#symbol-decoding-process
+
+use crate::{
+    buffer::Buffer,
+    constants::{EC_MIN_PROB, EC_PROB_SHIFT},
+    obu::ObuError,
+    util::floor_log2,
+};
+
+/// Updates `cdf` after `symbol` has been decoded with it, `cdf` holds the N
+/// cumulative probabilities of an N symbols alphabet followed by the counter
+/// used to compute the adaptation rate.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#symbol-decoding-process
+pub fn update_cdf(cdf: &mut [u16], symbol: usize) {
+    let n = cdf.len() - 1;
+    let rate = 3 + (cdf[n] > 15) as u32 + (cdf[n] > 31) as u32 + floor_log2(n as u32).min(2);
+
+    let mut tmp = 0;
+    for (i, value) in cdf.iter_mut().enumerate().take(n - 1) {
+        if i == symbol {
+            tmp = 1 << 15;
+        }
+
+        if tmp < *value {
+            *value -= (*value - tmp) >> rate;
+        } else {
+            *value += (tmp - *value) >> rate;
+        }
+    }
+
+    if cdf[n] < 32 {
+        cdf[n] += 1;
+    }
+}
+
+/// Decodes the symbols of a single tile.
+pub struct SymbolDecoder<'a> {
+    buf: Buffer<'a>,
+    symbol_value: u32,
+    symbol_range: u32,
+    symbol_max_bits: i32,
+    disable_cdf_update: bool,
+}
+
+impl<'a> SymbolDecoder<'a> {
+    /// Initializes the symbol decoder with the coded data of a tile, the
+    /// whole slice is consumed by the decoder.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#initialization-process-for-symbol-decoder
+    pub fn new(data: &'a [u8], disable_cdf_update: bool) -> Result<Self, ObuError> {
+        if data.is_empty() {
+            return Err(ObuError::InvalidSymbolData);
+        }
+
+        let mut buf = Buffer::new(data);
+        let sz = data.len();
+        let num_bits = (sz * 8).min(15);
+        let padded_buf = buf.get_bits(num_bits) << (15 - num_bits);
+
+        Ok(Self {
+            buf,
+            symbol_value: ((1 << 15) - 1) ^ padded_buf,
+            symbol_range: 1 << 15,
+            symbol_max_bits: 8 * sz as i32 - 15,
+            disable_cdf_update,
+        })
+    }
+
+    /// Decodes a symbol using `cdf` and adapts `cdf` to it, unless the cdf
+    /// update is disabled for the frame.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#decoding-process-for-symbol
+    pub fn read_symbol(&mut self, cdf: &mut [u16]) -> usize {
+        let symbol = self.decode_symbol(cdf);
+        if !self.disable_cdf_update {
+            update_cdf(cdf, symbol);
+        }
+
+        symbol
+    }
+
+    /// Decodes a single bit with an equal probability.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#boolean-decoding-process
+    pub fn read_bool(&mut self) -> bool {
+        self.decode_symbol(&[1 << 14, 1 << 15, 0]) == 1
+    }
+
+    /// Decodes an unsigned `n` bits number, most significant bit first.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#literal-decoding-process
+    pub fn read_literal(&mut self, n: usize) -> u32 {
+        let mut x = 0;
+        for _ in 0..n {
+            x = 2 * x + self.read_bool() as u32;
+        }
+
+        x
+    }
+
+    /// Decodes an Exp-Golomb code of at most 20 bits, used for the
+    /// remainder of the coefficients above the range of coeff_br.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#coefficients-syntax
+    pub fn read_golomb(&mut self) -> Result<u32, ObuError> {
+        let mut length = 0;
+        loop {
+            length += 1;
+            // It is a requirement of bitstream conformance that the length
+            // does not exceed 20.
+            if length > 20 {
+                return Err(ObuError::InvalidSymbolData);
+            }
+
+            // golomb_length_bit	L(1)
+            if self.read_literal(1) == 1 {
+                break;
+            }
+        }
+
+        let mut x = 1;
+        for _ in 1..length {
+            // golomb_data_bit	L(1)
+            x = (x << 1) | self.read_literal(1);
+        }
+
+        Ok(x - 1)
+    }
+
+    /// Finishes the decoding of the tile and checks the trailing bit and the
+    /// padding that follow the symbols.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#exit-process-for-symbol-decoder
+    pub fn exit(mut self) -> Result<(), ObuError> {
+        // It is a requirement of bitstream conformance that SymbolMaxBits is
+        // greater than or equal to -14 when this process is invoked.
+        if self.symbol_max_bits < -14 {
+            return Err(ObuError::InvalidSymbolData);
+        }
+
+        let trailing_bit_position =
+            self.buf.get_position() - (15.min(self.symbol_max_bits + 15) as usize);
+
+        self.buf.seek_bits(self.symbol_max_bits.max(0) as usize);
+        let padding_end_position = self.buf.get_position();
+
+        // The trailing bit is equal to 1 and all the bits after it up to the
+        // end of the tile are equal to 0.
+        let mut buf = Buffer::new(self.buf.data());
+        buf.seek_bits(trailing_bit_position);
+        if !buf.get_bit() {
+            return Err(ObuError::InvalidSymbolData);
+        }
+
+        for _ in trailing_bit_position + 1..padding_end_position {
+            if buf.get_bit() {
+                return Err(ObuError::InvalidSymbolData);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_symbol(&mut self, cdf: &[u16]) -> usize {
+        let n = cdf.len() as u32 - 1;
+        let mut cur = self.symbol_range;
+        let mut prev;
+        let mut symbol = 0;
+        loop {
+            prev = cur;
+            let f = (1 << 15) - cdf[symbol] as u32;
+            cur = ((self.symbol_range >> 8) * (f >> EC_PROB_SHIFT)) >> (7 - EC_PROB_SHIFT);
+            cur += EC_MIN_PROB as u32 * (n - symbol as u32 - 1);
+            if self.symbol_value >= cur {
+                break;
+            }
+
+            symbol += 1;
+        }
+
+        self.renormalize(prev - cur, cur);
+        symbol
+    }
+
+    fn renormalize(&mut self, rng: u32, cur: u32) {
+        let value = self.symbol_value - cur;
+        let bits = 15 - floor_log2(rng);
+        self.symbol_range = rng << bits;
+
+        let num_bits = bits.min(self.symbol_max_bits.max(0) as u32);
+        let new_data = self.buf.get_bits(num_bits as usize);
+        let padded_data = new_data << (bits - num_bits);
+
+        self.symbol_value = padded_data ^ (((value + 1) << bits) - 1);
+        self.symbol_max_bits -= bits as i32;
+    }
+}
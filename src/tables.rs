@@ -0,0 +1,654 @@
+//! Lookup tables of the decoding process.
+
+use crate::constants::*;
+
+/// Width of each block size in units of 4x4 luma samples.
+pub const NUM_4X4_BLOCKS_WIDE: [u8; BLOCK_SIZES as usize] = [
+    1, 1, 2, 2, 2, 4, 4, 4, 8, 8, 8, 16, 16, 16, 32, 32, 1, 4, 2, 8, 4, 16,
+];
+
+/// Height of each block size in units of 4x4 luma samples.
+pub const NUM_4X4_BLOCKS_HIGH: [u8; BLOCK_SIZES as usize] = [
+    1, 2, 1, 2, 4, 2, 4, 8, 4, 8, 16, 8, 16, 32, 16, 32, 4, 1, 8, 2, 16, 4,
+];
+
+/// Base 2 logarithm of the width of each block size in units of 4x4 luma
+/// samples.
+pub const MI_WIDTH_LOG2: [u8; BLOCK_SIZES as usize] = [
+    0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4, 5, 5, 0, 2, 1, 3, 2, 4,
+];
+
+/// Base 2 logarithm of the height of each block size in units of 4x4 luma
+/// samples.
+pub const MI_HEIGHT_LOG2: [u8; BLOCK_SIZES as usize] = [
+    0, 1, 0, 1, 2, 1, 2, 3, 2, 3, 4, 3, 4, 5, 4, 5, 2, 0, 3, 1, 4, 2,
+];
+
+/// The context used for y_mode of each block size.
+pub const SIZE_GROUP: [u8; BLOCK_SIZES as usize] = [
+    0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 0, 0, 1, 1, 2, 2,
+];
+
+/// The block size of the sub blocks of each partition type.
+pub const PARTITION_SUBSIZE: [[u8; BLOCK_SIZES as usize]; 10] = [
+    [
+        BLOCK_4X4,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_128X128,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X4,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_128X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_4X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X128,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_4X4,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X4,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_128X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X4,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_128X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_4X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X128,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_4X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X128,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X4,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_32X8,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_64X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+    [
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_4X16,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_8X32,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_16X64,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+        BLOCK_INVALID,
+    ],
+];
+
+/// The block size of the chroma samples of each block size, indexed by
+/// subsampling_x and subsampling_y.
+pub const SUBSAMPLED_SIZE: [[[u8; 2]; 2]; BLOCK_SIZES as usize] = [
+    [[BLOCK_4X4, BLOCK_4X4], [BLOCK_4X4, BLOCK_4X4]],
+    [[BLOCK_4X8, BLOCK_4X4], [BLOCK_INVALID, BLOCK_4X4]],
+    [[BLOCK_8X4, BLOCK_INVALID], [BLOCK_4X4, BLOCK_4X4]],
+    [[BLOCK_8X8, BLOCK_8X4], [BLOCK_4X8, BLOCK_4X4]],
+    [[BLOCK_8X16, BLOCK_8X8], [BLOCK_INVALID, BLOCK_4X8]],
+    [[BLOCK_16X8, BLOCK_INVALID], [BLOCK_8X8, BLOCK_8X4]],
+    [[BLOCK_16X16, BLOCK_16X8], [BLOCK_8X16, BLOCK_8X8]],
+    [[BLOCK_16X32, BLOCK_16X16], [BLOCK_INVALID, BLOCK_8X16]],
+    [[BLOCK_32X16, BLOCK_INVALID], [BLOCK_16X16, BLOCK_16X8]],
+    [[BLOCK_32X32, BLOCK_32X16], [BLOCK_16X32, BLOCK_16X16]],
+    [[BLOCK_32X64, BLOCK_32X32], [BLOCK_INVALID, BLOCK_16X32]],
+    [[BLOCK_64X32, BLOCK_INVALID], [BLOCK_32X32, BLOCK_32X16]],
+    [[BLOCK_64X64, BLOCK_64X32], [BLOCK_32X64, BLOCK_32X32]],
+    [[BLOCK_64X128, BLOCK_64X64], [BLOCK_INVALID, BLOCK_32X64]],
+    [[BLOCK_128X64, BLOCK_INVALID], [BLOCK_64X64, BLOCK_64X32]],
+    [[BLOCK_128X128, BLOCK_128X64], [BLOCK_64X128, BLOCK_64X64]],
+    [[BLOCK_4X16, BLOCK_4X8], [BLOCK_INVALID, BLOCK_4X8]],
+    [[BLOCK_16X4, BLOCK_INVALID], [BLOCK_8X4, BLOCK_8X4]],
+    [[BLOCK_8X32, BLOCK_8X16], [BLOCK_INVALID, BLOCK_4X16]],
+    [[BLOCK_32X8, BLOCK_INVALID], [BLOCK_16X8, BLOCK_16X4]],
+    [[BLOCK_16X64, BLOCK_16X32], [BLOCK_INVALID, BLOCK_8X32]],
+    [[BLOCK_64X16, BLOCK_INVALID], [BLOCK_32X16, BLOCK_32X8]],
+];
+
+/// The context of intra_frame_y_mode derived from the mode of a neighbor.
+pub const INTRA_MODE_CONTEXT: [u8; INTRA_MODES as usize] = [0, 1, 2, 3, 4, 4, 4, 4, 3, 0, 1, 2, 0];
+
+/// The weights of the three highest scores of the neighboring colors in the
+/// palette color context hash.
+pub const PALETTE_COLOR_HASH_MULTIPLIERS: [u8; PALETTE_NUM_NEIGHBORS as usize] = [1, 2, 2];
+
+/// The context of palette_color_idx_y and palette_color_idx_uv indexed by
+/// the palette color context hash, -1 for the hashes that cannot occur.
+pub const PALETTE_COLOR_CONTEXT: [i8; PALETTE_MAX_COLOR_CONTEXT_HASH as usize + 1] =
+    [-1, -1, 0, -1, -1, 4, 3, 2, 1];
+
+pub const WIENER_TAPS_MIN: [i32; 3] = [-5, -23, -17];
+pub const WIENER_TAPS_MID: [i32; 3] = [3, -7, 15];
+pub const WIENER_TAPS_MAX: [i32; 3] = [10, 8, 46];
+pub const WIENER_TAPS_K: [u32; 3] = [1, 2, 3];
+pub const SGRPROJ_XQD_MIN: [i32; 2] = [-96, -32];
+pub const SGRPROJ_XQD_MID: [i32; 2] = [-32, 31];
+pub const SGRPROJ_XQD_MAX: [i32; 2] = [31, 95];
+
+/// The radius and the noise parameter of the two self guided filters of
+/// each lr_sgr_set.
+pub const SGR_PARAMS: [[i32; 4]; 1 << SGRPROJ_PARAMS_BITS] = [
+    [2, 140, 1, 3236],
+    [2, 112, 1, 2158],
+    [2, 93, 1, 1618],
+    [2, 80, 1, 1438],
+    [2, 70, 1, 1295],
+    [2, 58, 1, 1177],
+    [2, 47, 1, 1079],
+    [2, 37, 1, 996],
+    [2, 30, 1, 925],
+    [2, 25, 1, 863],
+    [0, -1, 2, 2589],
+    [0, -1, 2, 1618],
+    [0, -1, 2, 1177],
+    [0, -1, 2, 925],
+    [2, 56, 0, -1],
+    [2, 22, 0, -1],
+];
+
+/// Returns the width of the block size `bsize` in luma samples.
+#[inline]
+pub fn block_width(bsize: u8) -> u32 {
+    4 * NUM_4X4_BLOCKS_WIDE[bsize as usize] as u32
+}
+
+/// Returns the height of the block size `bsize` in luma samples.
+#[inline]
+pub fn block_height(bsize: u8) -> u32 {
+    4 * NUM_4X4_BLOCKS_HIGH[bsize as usize] as u32
+}
+
+/// The context of compound_mode, indexed by RefMvContext >> 1 and
+/// Min(NewMvContext, COMP_NEWMV_CTXS - 1).
+pub const COMPOUND_MODE_CTX_MAP: [[u8; COMP_NEWMV_CTXS as usize]; 3] =
+    [[0, 1, 1, 1, 1], [1, 2, 3, 4, 4], [4, 4, 5, 6, 7]];
+
+/// The number of bits of the wedge index of each block size, zero when wedge
+/// prediction is not allowed.
+pub const WEDGE_BITS: [u8; BLOCK_SIZES as usize] = [
+    0, 0, 0, 4, 4, 4, 4, 4, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0, 4, 4, 0, 0,
+];
+
+/// The reciprocals of the frame distances, in units of 1/16384, used by the
+/// projection of motion vectors.
+pub const DIV_MULT: [u32; MAX_FRAME_DISTANCE as usize + 1] = [
+    0, 16384, 8192, 5461, 4096, 3276, 2730, 2340, 2048, 1820, 1638, 1489, 1365, 1260, 1170, 1092,
+    1024, 963, 910, 862, 819, 780, 744, 712, 682, 655, 630, 606, 585, 564, 546, 528,
+];
+
+/// Width of each transform size in samples.
+pub const TX_WIDTH: [u8; TX_SIZES_ALL as usize] = [
+    4, 8, 16, 32, 64, 4, 8, 8, 16, 16, 32, 32, 64, 4, 16, 8, 32, 16, 64,
+];
+
+/// Height of each transform size in samples.
+pub const TX_HEIGHT: [u8; TX_SIZES_ALL as usize] = [
+    4, 8, 16, 32, 64, 8, 4, 16, 8, 32, 16, 64, 32, 16, 4, 32, 8, 64, 16,
+];
+
+/// Base 2 logarithm of the width of each transform size in samples.
+pub const TX_WIDTH_LOG2: [u8; TX_SIZES_ALL as usize] =
+    [2, 3, 4, 5, 6, 2, 3, 3, 4, 4, 5, 5, 6, 2, 4, 3, 5, 4, 6];
+
+/// Base 2 logarithm of the height of each transform size in samples.
+pub const TX_HEIGHT_LOG2: [u8; TX_SIZES_ALL as usize] =
+    [2, 3, 4, 5, 6, 3, 2, 4, 3, 5, 4, 6, 5, 4, 2, 5, 3, 6, 4];
+
+/// The largest square transform size that fits inside each transform size.
+pub const TX_SIZE_SQR: [u8; TX_SIZES_ALL as usize] = [
+    TX_4X4, TX_8X8, TX_16X16, TX_32X32, TX_64X64, TX_4X4, TX_4X4, TX_8X8, TX_8X8, TX_16X16,
+    TX_16X16, TX_32X32, TX_32X32, TX_4X4, TX_4X4, TX_8X8, TX_8X8, TX_16X16, TX_16X16,
+];
+
+/// The smallest square transform size that contains each transform size.
+pub const TX_SIZE_SQR_UP: [u8; TX_SIZES_ALL as usize] = [
+    TX_4X4, TX_8X8, TX_16X16, TX_32X32, TX_64X64, TX_8X8, TX_8X8, TX_16X16, TX_16X16, TX_32X32,
+    TX_32X32, TX_64X64, TX_64X64, TX_16X16, TX_16X16, TX_32X32, TX_32X32, TX_64X64, TX_64X64,
+];
+
+/// The transform size used by each transform size split once.
+pub const SPLIT_TX_SIZE: [u8; TX_SIZES_ALL as usize] = [
+    TX_4X4, TX_4X4, TX_8X8, TX_16X16, TX_32X32, TX_4X4, TX_4X4, TX_8X8, TX_8X8, TX_16X16, TX_16X16,
+    TX_32X32, TX_32X32, TX_4X8, TX_8X4, TX_8X16, TX_16X8, TX_16X32, TX_32X16,
+];
+
+/// The size used for the coefficient contexts and the scan of each transform
+/// size, the 64 sample wide or high sizes only code their top left 32x32
+/// coefficients.
+pub const ADJUSTED_TX_SIZE: [u8; TX_SIZES_ALL as usize] = [
+    TX_4X4, TX_8X8, TX_16X16, TX_32X32, TX_32X32, TX_4X8, TX_8X4, TX_8X16, TX_16X8, TX_16X32,
+    TX_32X16, TX_32X32, TX_32X32, TX_4X16, TX_16X4, TX_8X32, TX_32X8, TX_16X32, TX_32X16,
+];
+
+/// The largest transform size that fits inside each block size.
+pub const MAX_TX_SIZE_RECT: [u8; BLOCK_SIZES as usize] = [
+    TX_4X4, TX_4X8, TX_8X4, TX_8X8, TX_8X16, TX_16X8, TX_16X16, TX_16X32, TX_32X16, TX_32X32,
+    TX_32X64, TX_64X32, TX_64X64, TX_64X64, TX_64X64, TX_64X64, TX_4X16, TX_16X4, TX_8X32, TX_32X8,
+    TX_16X64, TX_64X16,
+];
+
+/// The number of times the largest transform of each block size can be
+/// split before reaching a 4x4 transform, the Max_Tx_Depth table.
+pub const MAX_TX_DEPTHS: [u8; BLOCK_SIZES as usize] = [
+    0, 1, 1, 1, 2, 2, 2, 3, 3, 3, 4, 4, 4, 4, 4, 4, 2, 2, 3, 3, 4, 4,
+];
+
+/// The transform type implied by each chroma intra prediction mode.
+pub const MODE_TO_TXFM: [u8; UV_INTRA_MODES_CFL_ALLOWED as usize] = [
+    DCT_DCT, ADST_DCT, DCT_ADST, DCT_DCT, ADST_ADST, ADST_DCT, DCT_ADST, DCT_ADST, ADST_DCT,
+    ADST_ADST, ADST_DCT, DCT_ADST, ADST_ADST, DCT_DCT,
+];
+
+/// The prediction angle in degrees of each intra prediction mode, 0 for the
+/// modes that are not directional.
+pub const MODE_TO_ANGLE: [i32; INTRA_MODES as usize] =
+    [0, 90, 180, 45, 135, 113, 157, 203, 67, 0, 0, 0, 0];
+
+/// The intra prediction mode used for the transform type cdfs of each
+/// filter intra mode.
+pub const FILTER_INTRA_MODE_TO_INTRA_DIR: [u8; INTRA_FILTER_MODES as usize] =
+    [DC_PRED, V_PRED, H_PRED, D157_PRED, DC_PRED];
+
+/// The transform types of the TX_SET_INTRA_1 set, indexed by intra_tx_type.
+pub const TX_TYPE_INTRA_INV_SET1: [u8; 7] =
+    [IDTX, DCT_DCT, V_DCT, H_DCT, ADST_ADST, ADST_DCT, DCT_ADST];
+
+/// The transform types of the TX_SET_INTRA_2 set, indexed by intra_tx_type.
+pub const TX_TYPE_INTRA_INV_SET2: [u8; 5] = [IDTX, DCT_DCT, ADST_ADST, ADST_DCT, DCT_ADST];
+
+/// The transform types of the TX_SET_INTER_1 set, indexed by inter_tx_type.
+pub const TX_TYPE_INTER_INV_SET1: [u8; 16] = [
+    IDTX,
+    V_DCT,
+    H_DCT,
+    V_ADST,
+    H_ADST,
+    V_FLIPADST,
+    H_FLIPADST,
+    DCT_DCT,
+    ADST_DCT,
+    DCT_ADST,
+    FLIPADST_DCT,
+    DCT_FLIPADST,
+    ADST_ADST,
+    FLIPADST_FLIPADST,
+    ADST_FLIPADST,
+    FLIPADST_ADST,
+];
+
+/// The transform types of the TX_SET_INTER_2 set, indexed by inter_tx_type.
+pub const TX_TYPE_INTER_INV_SET2: [u8; 12] = [
+    IDTX,
+    V_DCT,
+    H_DCT,
+    DCT_DCT,
+    ADST_DCT,
+    DCT_ADST,
+    FLIPADST_DCT,
+    DCT_FLIPADST,
+    ADST_ADST,
+    FLIPADST_FLIPADST,
+    ADST_FLIPADST,
+    FLIPADST_ADST,
+];
+
+/// The transform types of the TX_SET_INTER_3 set, indexed by inter_tx_type.
+pub const TX_TYPE_INTER_INV_SET3: [u8; 2] = [IDTX, DCT_DCT];
+
+/// The row and column offsets of the coefficients used by the context of
+/// coeff_base and coeff_base_eob, indexed by the transform class.
+pub const SIG_REF_DIFF_OFFSET: [[[u8; 2]; SIG_REF_DIFF_OFFSET_NUM as usize]; 3] = [
+    [[0, 1], [1, 0], [1, 1], [0, 2], [2, 0]],
+    [[0, 1], [1, 0], [0, 2], [0, 3], [0, 4]],
+    [[0, 1], [1, 0], [2, 0], [3, 0], [4, 0]],
+];
+
+/// The row and column offsets of the coefficients used by the context of
+/// coeff_br, indexed by the transform class.
+pub const MAG_REF_OFFSET_WITH_TX_CLASS: [[[u8; 2]; 3]; 3] = [
+    [[0, 1], [1, 0], [1, 1]],
+    [[0, 1], [1, 0], [0, 2]],
+    [[0, 1], [1, 0], [2, 0]],
+];
+
+/// The context offset of coeff_base for the 2D transform classes, indexed
+/// by the transform size and then by the row and the column of the
+/// coefficient clamped to 4.
+pub const COEFF_BASE_CTX_OFFSET: [[[u8; 5]; 5]; TX_SIZES_ALL as usize] = [
+    [
+        [0, 1, 6, 6, 0],
+        [1, 6, 6, 21, 0],
+        [6, 6, 21, 21, 0],
+        [6, 21, 21, 21, 0],
+        [0, 0, 0, 0, 0],
+    ],
+    [
+        [0, 1, 6, 6, 21],
+        [1, 6, 6, 21, 21],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 1, 6, 6, 21],
+        [1, 6, 6, 21, 21],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 1, 6, 6, 21],
+        [1, 6, 6, 21, 21],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 1, 6, 6, 21],
+        [1, 6, 6, 21, 21],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 11, 11, 11, 0],
+        [11, 11, 11, 11, 0],
+        [6, 6, 21, 21, 0],
+        [6, 21, 21, 21, 0],
+        [21, 21, 21, 21, 0],
+    ],
+    [
+        [0, 16, 6, 6, 21],
+        [16, 16, 6, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+        [0, 0, 0, 0, 0],
+    ],
+    [
+        [0, 11, 11, 11, 11],
+        [11, 11, 11, 11, 11],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 16, 6, 6, 21],
+        [16, 16, 6, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+    ],
+    [
+        [0, 11, 11, 11, 11],
+        [11, 11, 11, 11, 11],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 16, 6, 6, 21],
+        [16, 16, 6, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+    ],
+    [
+        [0, 11, 11, 11, 11],
+        [11, 11, 11, 11, 11],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 16, 6, 6, 21],
+        [16, 16, 6, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+    ],
+    [
+        [0, 11, 11, 11, 0],
+        [11, 11, 11, 11, 0],
+        [6, 6, 21, 21, 0],
+        [6, 21, 21, 21, 0],
+        [21, 21, 21, 21, 0],
+    ],
+    [
+        [0, 16, 6, 6, 21],
+        [16, 16, 6, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+        [0, 0, 0, 0, 0],
+    ],
+    [
+        [0, 11, 11, 11, 11],
+        [11, 11, 11, 11, 11],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 16, 6, 6, 21],
+        [16, 16, 6, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+    ],
+    [
+        [0, 11, 11, 11, 11],
+        [11, 11, 11, 11, 11],
+        [6, 6, 21, 21, 21],
+        [6, 21, 21, 21, 21],
+        [21, 21, 21, 21, 21],
+    ],
+    [
+        [0, 16, 6, 6, 21],
+        [16, 16, 6, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+        [16, 16, 21, 21, 21],
+    ],
+];
+
+/// The context offset of coeff_base for the 1D transform classes, indexed by
+/// the position of the coefficient along the transform clamped to 2.
+pub const COEFF_BASE_POS_CTX_OFFSET: [u8; 3] = [
+    SIG_COEF_CONTEXTS_2D,
+    SIG_COEF_CONTEXTS_2D + 5,
+    SIG_COEF_CONTEXTS_2D + 10,
+];
@@ -0,0 +1,838 @@
+use super::{mode_info::Mv, plane_subsampling, TileDecoder};
+
+use crate::{
+    constants::*,
+    obu::{frame_header::InterpolationFilter, ObuError},
+    tables::*,
+};
+
+/// The state of the block being decoded.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#decode-block-syntax
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Block {
+    pub mi_row: u32,
+    pub mi_col: u32,
+    pub mi_size: u8,
+    pub has_chroma: bool,
+    pub avail_u: bool,
+    pub avail_l: bool,
+    pub avail_u_chroma: bool,
+    pub avail_l_chroma: bool,
+    pub skip: bool,
+    pub skip_mode: bool,
+    pub segment_id: u8,
+    pub lossless: bool,
+    pub is_inter: bool,
+    pub use_intrabc: bool,
+    pub ref_frame: [i8; 2],
+    pub mv: [Mv; 2],
+    pub y_mode: u8,
+    pub uv_mode: u8,
+    pub angle_delta_y: i8,
+    pub angle_delta_uv: i8,
+    pub cfl_alpha_u: i8,
+    pub cfl_alpha_v: i8,
+    pub use_filter_intra: bool,
+    pub filter_intra_mode: u8,
+    pub palette_size_y: u8,
+    pub palette_size_uv: u8,
+    pub palette_colors_y: [u16; PALETTE_COLORS as usize],
+    pub palette_colors_u: [u16; PALETTE_COLORS as usize],
+    pub palette_colors_v: [u16; PALETTE_COLORS as usize],
+    pub left_ref_frame: [i8; 2],
+    pub above_ref_frame: [i8; 2],
+    pub left_intra: bool,
+    pub above_intra: bool,
+    pub left_single: bool,
+    pub above_single: bool,
+    pub ref_mv_idx: usize,
+    pub pred_mv: [Mv; 2],
+    pub interintra: bool,
+    pub interintra_mode: u8,
+    pub wedge_interintra: bool,
+    pub wedge_index: u8,
+    pub wedge_sign: u8,
+    pub mask_type: u8,
+    pub motion_mode: u8,
+    pub compound_type: u8,
+    pub comp_group_idx: u8,
+    pub compound_idx: u8,
+    pub interp_filter: [InterpolationFilter; 2],
+    pub tx_size: u8,
+}
+
+/// Returns whether `mode` is one of the directional intra prediction modes.
+#[inline]
+pub fn is_directional_mode(mode: u8) -> bool {
+    (V_PRED..=D67_PRED).contains(&mode)
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#segmentation-feature-process
+fn neg_deinterleave(diff: i32, r: i32, max: i32) -> i32 {
+    if r == 0 {
+        return diff;
+    }
+
+    if r >= max - 1 {
+        return max - diff - 1;
+    }
+
+    if 2 * r < max {
+        if diff <= 2 * r {
+            if diff & 1 == 1 {
+                return r + ((diff + 1) >> 1);
+            } else {
+                return r - (diff >> 1);
+            }
+        }
+
+        diff
+    } else {
+        if diff <= 2 * (max - r - 1) {
+            if diff & 1 == 1 {
+                return r + ((diff + 1) >> 1);
+            } else {
+                return r - (diff >> 1);
+            }
+        }
+
+        max - (diff + 1)
+    }
+}
+
+impl TileDecoder<'_, '_> {
+    /// see: https://aomediacodec.github.io/av1-spec/#decode-block-syntax
+    pub(crate) fn decode_block(&mut self, r: u32, c: u32, sub_size: u8) -> Result<(), ObuError> {
+        let bw4 = NUM_4X4_BLOCKS_WIDE[sub_size as usize] as u32;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[sub_size as usize] as u32;
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, 1);
+
+        self.b = Block {
+            mi_row: r,
+            mi_col: c,
+            mi_size: sub_size,
+            ..Default::default()
+        };
+
+        let no_chroma =
+            (bh4 == 1 && sub_y == 1 && (r & 1) == 0) || (bw4 == 1 && sub_x == 1 && (c & 1) == 0);
+        self.b.has_chroma = !no_chroma && self.ctx.num_planes > 1;
+
+        self.b.avail_u = self.is_inside(r as i64 - 1, c as i64);
+        self.b.avail_l = self.is_inside(r as i64, c as i64 - 1);
+        if self.b.has_chroma {
+            self.b.avail_u_chroma = if sub_y == 1 && bh4 == 1 {
+                self.is_inside(r as i64 - 2, c as i64)
+            } else {
+                self.b.avail_u
+            };
+
+            self.b.avail_l_chroma = if sub_x == 1 && bw4 == 1 {
+                self.is_inside(r as i64, c as i64 - 2)
+            } else {
+                self.b.avail_l
+            };
+        }
+
+        self.mode_info()?;
+
+        self.palette_tokens();
+
+        self.read_block_tx_size();
+        if self.b.skip {
+            self.reset_block_context(bw4, bh4);
+        }
+
+        self.store_mode_info(bw4, bh4);
+
+        self.compute_prediction()?;
+
+        self.residual()
+    }
+
+    /// Copies the mode info of the current block to every 4x4 block of the
+    /// frame that it covers, the transform sizes of the inter blocks are
+    /// stored by read_block_tx_size.
+    fn store_mode_info(&mut self, bw4: u32, bh4: u32) {
+        let b = self.b;
+        let row_end = (b.mi_row + bh4).min(self.frame.mi_rows);
+        let col_end = (b.mi_col + bw4).min(self.frame.mi_cols);
+        for row in b.mi_row..row_end {
+            for col in b.mi_col..col_end {
+                let mi = self.frame.mi_mut(row, col);
+                mi.y_mode = b.y_mode;
+                if b.ref_frame[0] == INTRA_FRAME && b.has_chroma {
+                    mi.uv_mode = b.uv_mode;
+                }
+
+                mi.ref_frame = b.ref_frame;
+                if b.is_inter {
+                    mi.mv = b.mv;
+                }
+
+                mi.segment_id = b.segment_id;
+                mi.is_inter = b.is_inter;
+                mi.skip_mode = b.skip_mode;
+                mi.skip = b.skip;
+                mi.mi_size = b.mi_size;
+                mi.tx_size = b.tx_size;
+                mi.palette_size = [b.palette_size_y, b.palette_size_uv];
+                mi.palette_colors = [b.palette_colors_y, b.palette_colors_u];
+                mi.delta_lf = self.delta_lf;
+                mi.interp_filter = b.interp_filter;
+                mi.comp_group_idx = b.comp_group_idx;
+                mi.compound_idx = b.compound_idx;
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#mode-info-syntax
+    fn mode_info(&mut self) -> Result<(), ObuError> {
+        if self.ctx.frame_is_intra {
+            self.intra_frame_mode_info()
+        } else {
+            self.inter_frame_mode_info()
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#intra-frame-mode-info-syntax
+    fn intra_frame_mode_info(&mut self) -> Result<(), ObuError> {
+        let seg_id_pre_skip = self.header.segmentation_params.seg_id_pre_skip;
+
+        self.b.skip = false;
+        if seg_id_pre_skip {
+            self.intra_segment_id();
+        }
+
+        self.b.skip_mode = false;
+        self.read_skip();
+        if !seg_id_pre_skip {
+            self.intra_segment_id();
+        }
+
+        self.read_cdef();
+        self.read_delta_qindex();
+        self.read_delta_lf();
+        self.read_deltas = false;
+        self.b.ref_frame = [INTRA_FRAME, NONE];
+
+        if self.header.allow_intrabc {
+            // use_intrabc	S()
+            self.b.use_intrabc = self.sd.read_symbol(&mut self.cdf.intrabc_cdf) == 1;
+        }
+
+        if self.b.use_intrabc {
+            self.b.is_inter = true;
+            self.b.motion_mode = SIMPLE;
+            self.b.compound_type = COMPOUND_AVERAGE;
+            self.b.palette_size_y = 0;
+            self.b.palette_size_uv = 0;
+            self.b.interp_filter = [InterpolationFilter::Bilinear; 2];
+            self.find_mv_stack(false);
+            self.assign_mv(false);
+            if !self.is_dv_valid() {
+                return Err(ObuError::InvalidMotionVector);
+            }
+
+            return Ok(());
+        }
+
+        self.b.is_inter = false;
+
+        // intra_frame_y_mode	S()
+        let above_mode = if self.b.avail_u {
+            self.frame.mi(self.b.mi_row - 1, self.b.mi_col).y_mode
+        } else {
+            DC_PRED
+        };
+
+        let left_mode = if self.b.avail_l {
+            self.frame.mi(self.b.mi_row, self.b.mi_col - 1).y_mode
+        } else {
+            DC_PRED
+        };
+
+        let above_ctx = INTRA_MODE_CONTEXT[above_mode as usize] as usize;
+        let left_ctx = INTRA_MODE_CONTEXT[left_mode as usize] as usize;
+        self.b.y_mode = self
+            .sd
+            .read_symbol(&mut self.cdf.intra_frame_y_mode_cdf[above_ctx][left_ctx])
+            as u8;
+
+        self.intra_angle_info_y();
+        if self.b.has_chroma {
+            self.read_uv_mode();
+            if self.b.uv_mode == UV_CFL_PRED {
+                self.read_cfl_alphas();
+            }
+
+            self.intra_angle_info_uv();
+        }
+
+        self.b.palette_size_y = 0;
+        self.b.palette_size_uv = 0;
+        let mi_size = self.b.mi_size;
+        if mi_size >= BLOCK_8X8
+            && block_width(mi_size) <= 64
+            && block_height(mi_size) <= 64
+            && self.header.allow_screen_content_tools
+        {
+            self.palette_mode_info();
+        }
+
+        self.filter_intra_mode_info();
+        Ok(())
+    }
+
+    /// Whether the displacement vector of the intra block copy points to a
+    /// block of the current tile that has been decoded and is far enough
+    /// from the current block to not be filtered by the in-loop filters of
+    /// a hardware decoder processing the superblocks in wavefront order.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#assign-mv-semantics
+    fn is_dv_valid(&self) -> bool {
+        let mv = self.b.mv[0];
+        if mv[0].abs() >= 1 << 14 || mv[1].abs() >= 1 << 14 {
+            return false;
+        }
+
+        if mv[0] & 7 != 0 || mv[1] & 7 != 0 {
+            return false;
+        }
+
+        let mi_size = self.b.mi_size as usize;
+        let bw4 = NUM_4X4_BLOCKS_WIDE[mi_size] as i32;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[mi_size] as i32;
+        let mi_size_px = MI_SIZE as i32;
+        let delta_row = mv[0] >> 3;
+        let delta_col = mv[1] >> 3;
+        let mut src_top_edge = self.b.mi_row as i32 * mi_size_px + delta_row;
+        let mut src_left_edge = self.b.mi_col as i32 * mi_size_px + delta_col;
+        let src_bottom_edge = src_top_edge + bh4 * mi_size_px;
+        let src_right_edge = src_left_edge + bw4 * mi_size_px;
+        if self.b.has_chroma {
+            let (sub_x, sub_y) = plane_subsampling(self.sequence_header, 1);
+            if bw4 < 2 && sub_x == 1 {
+                src_left_edge -= 4;
+            }
+
+            if bh4 < 2 && sub_y == 1 {
+                src_top_edge -= 4;
+            }
+        }
+
+        if src_top_edge < self.mi_row_start as i32 * mi_size_px
+            || src_left_edge < self.mi_col_start as i32 * mi_size_px
+            || src_bottom_edge > self.mi_row_end as i32 * mi_size_px
+            || src_right_edge > self.mi_col_end as i32 * mi_size_px
+        {
+            return false;
+        }
+
+        let sb_h = block_height(self.sb_size()) as i32;
+        let active_sb_row = (self.b.mi_row as i32 * mi_size_px) / sb_h;
+        let active_sb64_col = (self.b.mi_col as i32 * mi_size_px) >> 6;
+        let src_sb_row = (src_bottom_edge - 1) / sb_h;
+        let src_sb64_col = (src_right_edge - 1) >> 6;
+        let total_sb64_per_row = ((self.mi_col_end - self.mi_col_start - 1) as i32 >> 4) + 1;
+        let active_sb64 = active_sb_row * total_sb64_per_row + active_sb64_col;
+        let src_sb64 = src_sb_row * total_sb64_per_row + src_sb64_col;
+        if src_sb64 >= active_sb64 - INTRABC_DELAY_SB64 as i32 {
+            return false;
+        }
+
+        let gradient = 1 + INTRABC_DELAY_SB64 as i32 + (sb_h > 64) as i32;
+        let wf_offset = gradient * (active_sb_row - src_sb_row);
+        src_sb_row <= active_sb_row
+            && src_sb64_col < active_sb64_col - INTRABC_DELAY_SB64 as i32 + wf_offset
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#intra-segment-id-syntax
+    pub(crate) fn intra_segment_id(&mut self) {
+        if self.header.segmentation_params.segmentation_enabled {
+            self.read_segment_id();
+        } else {
+            self.b.segment_id = 0;
+        }
+
+        self.b.lossless = self.header.lossless_array[self.b.segment_id as usize];
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#read-segment-id-syntax
+    pub(crate) fn read_segment_id(&mut self) {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        let prev_ul = if self.b.avail_u && self.b.avail_l {
+            self.frame.mi(r - 1, c - 1).segment_id as i32
+        } else {
+            -1
+        };
+
+        let prev_u = if self.b.avail_u {
+            self.frame.mi(r - 1, c).segment_id as i32
+        } else {
+            -1
+        };
+
+        let prev_l = if self.b.avail_l {
+            self.frame.mi(r, c - 1).segment_id as i32
+        } else {
+            -1
+        };
+
+        let pred = if prev_u == -1 {
+            prev_l.max(0)
+        } else if prev_l == -1 || prev_ul == prev_u {
+            prev_u
+        } else {
+            prev_l
+        };
+
+        if self.b.skip {
+            self.b.segment_id = pred as u8;
+            return;
+        }
+
+        let ctx = if prev_ul < 0 {
+            0
+        } else if prev_ul == prev_u && prev_ul == prev_l {
+            2
+        } else if prev_ul == prev_u || prev_ul == prev_l || prev_u == prev_l {
+            1
+        } else {
+            0
+        };
+
+        // segment_id	S()
+        let last_active_seg_id = self.header.segmentation_params.last_active_seg_id as i32;
+        let segment_id = self.sd.read_symbol(&mut self.cdf.segment_id_cdf[ctx]) as i32;
+        self.b.segment_id = neg_deinterleave(segment_id, pred, last_active_seg_id + 1)
+            .clamp(0, last_active_seg_id) as u8;
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#skip-syntax
+    pub(crate) fn read_skip(&mut self) {
+        let segmentation_params = &self.header.segmentation_params;
+        if segmentation_params.seg_id_pre_skip
+            && segmentation_params.feature_active(self.b.segment_id, SEG_LVL_SKIP)
+        {
+            self.b.skip = true;
+            return;
+        }
+
+        let mut ctx = 0;
+        if self.b.avail_u && self.frame.mi(self.b.mi_row - 1, self.b.mi_col).skip {
+            ctx += 1;
+        }
+
+        if self.b.avail_l && self.frame.mi(self.b.mi_row, self.b.mi_col - 1).skip {
+            ctx += 1;
+        }
+
+        // skip	S()
+        self.b.skip = self.sd.read_symbol(&mut self.cdf.skip_cdf[ctx]) == 1;
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#cdef-params-syntax
+    pub(crate) fn read_cdef(&mut self) {
+        if self.b.skip
+            || self.header.coded_lossless
+            || !self.sequence_header.enable_cdef
+            || self.header.allow_intrabc
+        {
+            return;
+        }
+
+        let cdef_size4 = NUM_4X4_BLOCKS_WIDE[BLOCK_64X64 as usize] as u32;
+        let cdef_mask4 = !(cdef_size4 - 1);
+        let r = self.b.mi_row & cdef_mask4;
+        let c = self.b.mi_col & cdef_mask4;
+        if *self.frame.cdef_idx_mut(r, c) != -1 {
+            return;
+        }
+
+        // cdef_idx	L(cdef_bits)
+        let cdef_idx = self
+            .sd
+            .read_literal(self.header.cdef_params.cdef_bits as usize) as i8;
+
+        let w4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32;
+        let h4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as u32;
+        for y in (r..r + h4).step_by(cdef_size4 as usize) {
+            for x in (c..c + w4).step_by(cdef_size4 as usize) {
+                if y < self.frame.mi_rows && x < self.frame.mi_cols {
+                    *self.frame.cdef_idx_mut(y, x) = cdef_idx;
+                }
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#quantizer-index-delta-syntax
+    pub(crate) fn read_delta_qindex(&mut self) {
+        if self.b.mi_size == self.sb_size() && self.b.skip {
+            return;
+        }
+
+        if self.read_deltas {
+            // delta_q_abs	S()
+            let mut delta_q_abs = self.sd.read_symbol(&mut self.cdf.delta_q_cdf) as i32;
+            if delta_q_abs == DELTA_Q_SMALL as i32 {
+                // delta_q_rem_bits	L(3)
+                let delta_q_rem_bits = self.sd.read_literal(3) as usize + 1;
+
+                // delta_q_abs_bits	L(delta_q_rem_bits)
+                let delta_q_abs_bits = self.sd.read_literal(delta_q_rem_bits) as i32;
+                delta_q_abs = delta_q_abs_bits + (1 << delta_q_rem_bits) + 1;
+            }
+
+            if delta_q_abs != 0 {
+                // delta_q_sign_bit	L(1)
+                let reduced_delta_q_index = if self.sd.read_literal(1) == 1 {
+                    -delta_q_abs
+                } else {
+                    delta_q_abs
+                };
+
+                let delta_q_res = self.header.delta_q_params.delta_q_res;
+                self.current_q_index = (self.current_q_index as i32
+                    + (reduced_delta_q_index << delta_q_res))
+                    .clamp(1, 255) as u8;
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#loop-filter-delta-syntax
+    pub(crate) fn read_delta_lf(&mut self) {
+        if self.b.mi_size == self.sb_size() && self.b.skip {
+            return;
+        }
+
+        let delta_lf_params = self.header.delta_lf_params;
+        if self.read_deltas && delta_lf_params.delta_lf_present {
+            let frame_lf_count = if delta_lf_params.delta_lf_multi {
+                if self.ctx.num_planes > 1 {
+                    FRAME_LF_COUNT
+                } else {
+                    FRAME_LF_COUNT - 2
+                }
+            } else {
+                1
+            };
+
+            for i in 0..frame_lf_count as usize {
+                // delta_lf_abs	S()
+                let cdf = if delta_lf_params.delta_lf_multi {
+                    &mut self.cdf.delta_lf_multi_cdf[i]
+                } else {
+                    &mut self.cdf.delta_lf_cdf
+                };
+
+                let delta_lf_abs = self.sd.read_symbol(cdf) as i32;
+                let delta_lf_abs = if delta_lf_abs == DELTA_LF_SMALL as i32 {
+                    // delta_lf_rem_bits	L(2)
+                    let n = self.sd.read_literal(2) as usize + 1;
+
+                    // delta_lf_abs_bits	L(n)
+                    let delta_lf_abs_bits = self.sd.read_literal(n) as i32;
+                    delta_lf_abs_bits + (1 << n) + 1
+                } else {
+                    delta_lf_abs
+                };
+
+                if delta_lf_abs != 0 {
+                    // delta_lf_sign_bit	L(1)
+                    let reduced_delta_lf_level = if self.sd.read_literal(1) == 1 {
+                        -delta_lf_abs
+                    } else {
+                        delta_lf_abs
+                    };
+
+                    self.delta_lf[i] = (self.delta_lf[i] as i32
+                        + (reduced_delta_lf_level << delta_lf_params.delta_lf_res))
+                        .clamp(-(MAX_LOOP_FILTER as i32), MAX_LOOP_FILTER as i32)
+                        as i8;
+                }
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#intra-angle-info-luma-syntax
+    pub(crate) fn intra_angle_info_y(&mut self) {
+        self.b.angle_delta_y = 0;
+        if self.b.mi_size >= BLOCK_8X8 && is_directional_mode(self.b.y_mode) {
+            // angle_delta_y	S()
+            let cdf = &mut self.cdf.angle_delta_cdf[(self.b.y_mode - V_PRED) as usize];
+            self.b.angle_delta_y = self.sd.read_symbol(cdf) as i8 - MAX_ANGLE_DELTA as i8;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#intra-angle-info-chroma-syntax
+    pub(crate) fn intra_angle_info_uv(&mut self) {
+        self.b.angle_delta_uv = 0;
+        if self.b.mi_size >= BLOCK_8X8 && is_directional_mode(self.b.uv_mode) {
+            // angle_delta_uv	S()
+            let cdf = &mut self.cdf.angle_delta_cdf[(self.b.uv_mode - V_PRED) as usize];
+            self.b.angle_delta_uv = self.sd.read_symbol(cdf) as i8 - MAX_ANGLE_DELTA as i8;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#intra-frame-mode-info-semantics
+    pub(crate) fn read_uv_mode(&mut self) {
+        let mi_size = self.b.mi_size;
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, 1);
+        let cfl_allowed = if self.b.lossless {
+            SUBSAMPLED_SIZE[mi_size as usize][sub_x as usize][sub_y as usize] == BLOCK_4X4
+        } else {
+            block_width(mi_size).max(block_height(mi_size)) <= 32
+        };
+
+        // uv_mode	S()
+        let y_mode = self.b.y_mode as usize;
+        self.b.uv_mode = if cfl_allowed {
+            self.sd
+                .read_symbol(&mut self.cdf.uv_mode_cfl_allowed_cdf[y_mode])
+        } else {
+            self.sd
+                .read_symbol(&mut self.cdf.uv_mode_cfl_not_allowed_cdf[y_mode])
+        } as u8;
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#cfl-alphas-syntax
+    pub(crate) fn read_cfl_alphas(&mut self) {
+        // cfl_alpha_signs	S()
+        let cfl_alpha_signs = self.sd.read_symbol(&mut self.cdf.cfl_sign_cdf) as u8;
+        let sign_u = (cfl_alpha_signs + 1) / 3;
+        let sign_v = (cfl_alpha_signs + 1) % 3;
+
+        self.b.cfl_alpha_u = if sign_u != CFL_SIGN_ZERO {
+            // cfl_alpha_u	S()
+            let ctx = ((sign_u - 1) * 3 + sign_v) as usize;
+            let alpha = 1 + self.sd.read_symbol(&mut self.cdf.cfl_alpha_cdf[ctx]) as i8;
+            if sign_u == CFL_SIGN_NEG {
+                -alpha
+            } else {
+                alpha
+            }
+        } else {
+            0
+        };
+
+        self.b.cfl_alpha_v = if sign_v != CFL_SIGN_ZERO {
+            // cfl_alpha_v	S()
+            let ctx = ((sign_v - 1) * 3 + sign_u) as usize;
+            let alpha = 1 + self.sd.read_symbol(&mut self.cdf.cfl_alpha_cdf[ctx]) as i8;
+            if sign_v == CFL_SIGN_NEG {
+                -alpha
+            } else {
+                alpha
+            }
+        } else {
+            0
+        };
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#filter-intra-mode-info-syntax
+    pub(crate) fn filter_intra_mode_info(&mut self) {
+        let mi_size = self.b.mi_size;
+        self.b.use_filter_intra = false;
+        if self.sequence_header.enable_filter_intra
+            && self.b.y_mode == DC_PRED
+            && self.b.palette_size_y == 0
+            && block_width(mi_size).max(block_height(mi_size)) <= 32
+        {
+            // use_filter_intra	S()
+            self.b.use_filter_intra = self
+                .sd
+                .read_symbol(&mut self.cdf.filter_intra_cdf[mi_size as usize])
+                == 1;
+
+            if self.b.use_filter_intra {
+                // filter_intra_mode	S()
+                self.b.filter_intra_mode =
+                    self.sd.read_symbol(&mut self.cdf.filter_intra_mode_cdf) as u8;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use super::*;
+
+    use crate::{
+        obu::frame_header::UncompressedHeader,
+        tile::{mode_info::FrameState, testing},
+    };
+
+    /// A block using the intra block copy, by default a luma only 8x8 block
+    /// of a 512x256 tile with 64x64 superblocks.
+    struct IntraBcBlock {
+        mi_row: u32,
+        mi_col: u32,
+        mi_size: u8,
+        use_128x128_superblock: bool,
+        /// The subsampling of the chroma planes when the block has chroma.
+        chroma_subsampling: Option<(bool, bool)>,
+        tile_mi_rows: Range<u32>,
+        tile_mi_cols: Range<u32>,
+    }
+
+    impl Default for IntraBcBlock {
+        fn default() -> Self {
+            Self {
+                mi_row: 0,
+                mi_col: 0,
+                mi_size: BLOCK_8X8,
+                use_128x128_superblock: false,
+                chroma_subsampling: None,
+                tile_mi_rows: 0..64,
+                tile_mi_cols: 0..128,
+            }
+        }
+    }
+
+    impl IntraBcBlock {
+        /// Whether the vector `mv`, in 1/8 samples, is valid for the block.
+        fn is_mv_valid(&self, mv: Mv) -> bool {
+            let mut ctx = testing::context(UncompressedHeader::default(), 16, 16);
+            let sequence_header = ctx.sequence_header.as_mut().unwrap();
+            sequence_header.use_128x128_superblock = self.use_128x128_superblock;
+            if let Some((sub_x, sub_y)) = self.chroma_subsampling {
+                sequence_header.color_config.subsampling_x = sub_x;
+                sequence_header.color_config.subsampling_y = sub_y;
+            }
+
+            let mut frame = FrameState::new(16, 16);
+            let mut decoder = testing::decoder(&ctx, &mut frame);
+            decoder.mi_row_start = self.tile_mi_rows.start;
+            decoder.mi_row_end = self.tile_mi_rows.end;
+            decoder.mi_col_start = self.tile_mi_cols.start;
+            decoder.mi_col_end = self.tile_mi_cols.end;
+            decoder.b = Block {
+                mi_row: self.mi_row,
+                mi_col: self.mi_col,
+                mi_size: self.mi_size,
+                has_chroma: self.chroma_subsampling.is_some(),
+                mv: [mv, [0; 2]],
+                ..Default::default()
+            };
+
+            decoder.is_dv_valid()
+        }
+
+        /// Whether `dv`, in whole luma samples, is valid for the block.
+        fn is_valid(&self, dv: [i32; 2]) -> bool {
+            self.is_mv_valid([dv[0] * 8, dv[1] * 8])
+        }
+    }
+
+    fn block_at(mi_row: u32, mi_col: u32) -> IntraBcBlock {
+        IntraBcBlock {
+            mi_row,
+            mi_col,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn dv_must_be_whole_samples_in_range() {
+        let block = block_at(0, 80);
+        assert!(block.is_mv_valid([0, -2112]));
+        assert!(!block.is_mv_valid([0, -2116]));
+        assert!(!block.is_mv_valid([4, -2112]));
+
+        // 2048 samples up is out of range.
+        let block = IntraBcBlock {
+            tile_mi_rows: 0..4096,
+            ..block_at(2048, 80)
+        };
+        assert!(block.is_mv_valid([-(1 << 14) + 8, -2112]));
+        assert!(!block.is_mv_valid([-(1 << 14), -2112]));
+    }
+
+    #[test]
+    fn dv_in_the_same_superblock_row_is_delayed_by_4_sb64() {
+        // The block is in the sixth 64x64 block of the first row, only the
+        // first 64x64 block is 4 blocks behind it.
+        let block = block_at(0, 80);
+        assert!(block.is_valid([0, -264]));
+        assert!(block.is_valid([0, -320]));
+        assert!(!block.is_valid([0, -256]));
+        assert!(!block.is_valid([0, -8]));
+
+        // Nothing is far enough from the first 64x64 blocks of the tile.
+        assert!(!block_at(0, 16).is_valid([0, -64]));
+    }
+
+    #[test]
+    fn dv_to_the_rows_above_follows_the_wavefront() {
+        // The block is in the second 64x64 block of the second row: the row
+        // above is decoded up to the second 64x64 block by a wavefront
+        // decoder (an offset of 5 blocks per row less the delay of 4 blocks).
+        let block = block_at(16, 16);
+        assert!(block.is_valid([-64, 0]));
+        assert!(block.is_valid([-64, 56]));
+        assert!(!block.is_valid([-64, 57]));
+        assert!(!block.is_valid([-64, 64]));
+
+        // The bottom of the source block must be in the row above.
+        assert!(block.is_valid([-8, -64]));
+        assert!(!block.is_valid([-7, -64]));
+
+        // Two rows above, the wavefront is 5 blocks further right.
+        let block = block_at(32, 16);
+        assert!(block.is_valid([-128, 376]));
+        assert!(!block.is_valid([-128, 384]));
+
+        // With 128x128 superblocks the offset is 6 blocks per row.
+        let block = IntraBcBlock {
+            use_128x128_superblock: true,
+            ..block_at(32, 16)
+        };
+        assert!(block.is_valid([-128, 120]));
+        assert!(!block.is_valid([-128, 128]));
+    }
+
+    #[test]
+    fn dv_must_point_inside_the_tile() {
+        // The tile starts at (128, 64) and is 384 samples wide.
+        let block = IntraBcBlock {
+            tile_mi_rows: 16..64,
+            tile_mi_cols: 32..128,
+            ..block_at(48, 32)
+        };
+        assert!(block.is_valid([-128, 0]));
+        assert!(!block.is_valid([-136, 0]));
+        assert!(!block.is_valid([-128, -8]));
+        assert!(block.is_valid([-128, 376]));
+        assert!(!block.is_valid([-128, 377]));
+    }
+
+    #[test]
+    fn dv_of_sub8x8_chroma_blocks_covers_the_previous_luma_block() {
+        // The chroma of a 4x4 block also covers the 4x4 luma blocks above and
+        // to the left.
+        let block = |chroma_subsampling| IntraBcBlock {
+            mi_size: BLOCK_4X4,
+            chroma_subsampling,
+            ..block_at(16, 1)
+        };
+        assert!(block(None).is_valid([-60, -4]));
+        assert!(block(Some((false, false))).is_valid([-60, -4]));
+        assert!(!block(Some((true, true))).is_valid([-60, -4]));
+        assert!(block(Some((true, true))).is_valid([-60, 0]));
+        assert!(!block(Some((true, true))).is_valid([-64, 0]));
+
+        // At the top of the tile, only the vertical subsampling moves the
+        // top edge.
+        let block = |chroma_subsampling| IntraBcBlock {
+            mi_size: BLOCK_4X4,
+            chroma_subsampling,
+            ..block_at(1, 81)
+        };
+        assert!(block(Some((true, false))).is_valid([-4, -320]));
+        assert!(!block(Some((true, true))).is_valid([-4, -320]));
+    }
+}
@@ -0,0 +1,1180 @@
+//! The mode info of the blocks of inter frames.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#inter-frame-mode-info-syntax
+
+use super::{mode_info::Mv, TileDecoder};
+
+use crate::{
+    constants::*,
+    obu::{
+        frame_header::{GmType, InterpolationFilter},
+        order_hint::get_relative_dist,
+        ObuError,
+    },
+    tables::*,
+};
+
+#[inline]
+fn check_backward(ref_frame: i8) -> bool {
+    (BWDREF_FRAME..=ALTREF_FRAME).contains(&ref_frame)
+}
+
+#[inline]
+fn is_samedir_ref_pair(ref0: i8, ref1: i8) -> bool {
+    (ref0 >= BWDREF_FRAME) == (ref1 >= BWDREF_FRAME)
+}
+
+#[inline]
+fn ref_count_ctx(counts0: u32, counts1: u32) -> usize {
+    match counts0.cmp(&counts1) {
+        std::cmp::Ordering::Less => 0,
+        std::cmp::Ordering::Equal => 1,
+        std::cmp::Ordering::Greater => 2,
+    }
+}
+
+impl TileDecoder<'_, '_> {
+    /// see: https://aomediacodec.github.io/av1-spec/#inter-frame-mode-info-syntax
+    pub(crate) fn inter_frame_mode_info(&mut self) -> Result<(), ObuError> {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        self.b.use_intrabc = false;
+        self.b.left_ref_frame = if self.b.avail_l {
+            self.frame.mi(r, c - 1).ref_frame
+        } else {
+            [INTRA_FRAME, NONE]
+        };
+
+        self.b.above_ref_frame = if self.b.avail_u {
+            self.frame.mi(r - 1, c).ref_frame
+        } else {
+            [INTRA_FRAME, NONE]
+        };
+
+        self.b.left_intra = self.b.left_ref_frame[0] <= INTRA_FRAME;
+        self.b.above_intra = self.b.above_ref_frame[0] <= INTRA_FRAME;
+        self.b.left_single = self.b.left_ref_frame[1] <= INTRA_FRAME;
+        self.b.above_single = self.b.above_ref_frame[1] <= INTRA_FRAME;
+
+        self.b.skip = false;
+        self.inter_segment_id(true);
+        self.read_skip_mode();
+        if self.b.skip_mode {
+            self.b.skip = true;
+        } else {
+            self.read_skip();
+        }
+
+        if !self.header.segmentation_params.seg_id_pre_skip {
+            self.inter_segment_id(false);
+        }
+
+        self.b.lossless = self.header.lossless_array[self.b.segment_id as usize];
+        self.read_cdef();
+        self.read_delta_qindex();
+        self.read_delta_lf();
+        self.read_deltas = false;
+        self.read_is_inter();
+        if self.b.is_inter {
+            self.inter_block_mode_info();
+            Ok(())
+        } else {
+            self.intra_block_mode_info()
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#inter-segment-id-syntax
+    fn inter_segment_id(&mut self, pre_skip: bool) {
+        let segmentation_params = &self.header.segmentation_params;
+        if !segmentation_params.segmentation_enabled {
+            self.b.segment_id = 0;
+            return;
+        }
+
+        let predicted_segment_id = self.get_segment_id();
+        if !segmentation_params.segmentation_update_map {
+            self.b.segment_id = predicted_segment_id;
+            return;
+        }
+
+        if pre_skip && !segmentation_params.seg_id_pre_skip {
+            self.b.segment_id = 0;
+            return;
+        }
+
+        if !pre_skip && self.b.skip {
+            self.set_seg_pred_context(false);
+            self.read_segment_id();
+            return;
+        }
+
+        if segmentation_params.segmentation_temporal_update {
+            // seg_id_predicted	S()
+            let ctx = (self.left_seg_pred_context[self.b.mi_row as usize]
+                + self.above_seg_pred_context[self.b.mi_col as usize])
+                as usize;
+            let seg_id_predicted = self
+                .sd
+                .read_symbol(&mut self.cdf.segment_id_predicted_cdf[ctx])
+                == 1;
+
+            if seg_id_predicted {
+                self.b.segment_id = predicted_segment_id;
+            } else {
+                self.read_segment_id();
+            }
+
+            self.set_seg_pred_context(seg_id_predicted);
+        } else {
+            self.read_segment_id();
+        }
+    }
+
+    fn set_seg_pred_context(&mut self, seg_id_predicted: bool) {
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as usize;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as usize;
+        let col = self.b.mi_col as usize;
+        let row = self.b.mi_row as usize;
+        let cols = bw4.min(self.above_seg_pred_context.len() - col);
+        let rows = bh4.min(self.left_seg_pred_context.len() - row);
+        self.above_seg_pred_context[col..col + cols].fill(seg_id_predicted as u8);
+        self.left_seg_pred_context[row..row + rows].fill(seg_id_predicted as u8);
+    }
+
+    /// The smallest segment id of the previous frame over the current
+    /// block.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#get-segment-id-function
+    fn get_segment_id(&self) -> u8 {
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as u32;
+        let x_mis = (self.frame.mi_cols - self.b.mi_col).min(bw4);
+        let y_mis = (self.frame.mi_rows - self.b.mi_row).min(bh4);
+        let mut seg = 7;
+        for y in 0..y_mis {
+            for x in 0..x_mis {
+                let idx = (self.b.mi_row + y) * self.frame.mi_cols + self.b.mi_col + x;
+                seg = seg.min(self.frame.prev_segment_ids[idx as usize]);
+            }
+        }
+
+        seg
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#skip-mode-syntax
+    fn read_skip_mode(&mut self) {
+        let segment_id = self.b.segment_id;
+        let segmentation_params = &self.header.segmentation_params;
+        let mi_size = self.b.mi_size;
+        if segmentation_params.feature_active(segment_id, SEG_LVL_SKIP)
+            || segmentation_params.feature_active(segment_id, SEG_LVL_REF_FRAME)
+            || segmentation_params.feature_active(segment_id, SEG_LVL_GLOBALMV)
+            || !self.header.skip_mode_params.skip_mode_present
+            || block_width(mi_size) < 8
+            || block_height(mi_size) < 8
+        {
+            self.b.skip_mode = false;
+            return;
+        }
+
+        let mut ctx = 0;
+        if self.b.avail_u && self.frame.mi(self.b.mi_row - 1, self.b.mi_col).skip_mode {
+            ctx += 1;
+        }
+
+        if self.b.avail_l && self.frame.mi(self.b.mi_row, self.b.mi_col - 1).skip_mode {
+            ctx += 1;
+        }
+
+        // skip_mode	S()
+        self.b.skip_mode = self.sd.read_symbol(&mut self.cdf.skip_mode_cdf[ctx]) == 1;
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#is-inter-syntax
+    fn read_is_inter(&mut self) {
+        let segment_id = self.b.segment_id;
+        let segmentation_params = &self.header.segmentation_params;
+        self.b.is_inter = if self.b.skip_mode {
+            true
+        } else if segmentation_params.feature_active(segment_id, SEG_LVL_REF_FRAME) {
+            segmentation_params.feature_data[segment_id as usize][SEG_LVL_REF_FRAME as usize]
+                != INTRA_FRAME as i16
+        } else if segmentation_params.feature_active(segment_id, SEG_LVL_GLOBALMV) {
+            true
+        } else {
+            let b = &self.b;
+            let ctx = if b.avail_u && b.avail_l {
+                if b.left_intra && b.above_intra {
+                    3
+                } else {
+                    (b.left_intra || b.above_intra) as usize
+                }
+            } else if b.avail_u {
+                2 * b.above_intra as usize
+            } else if b.avail_l {
+                2 * b.left_intra as usize
+            } else {
+                0
+            };
+
+            // is_inter	S()
+            self.sd.read_symbol(&mut self.cdf.is_inter_cdf[ctx]) == 1
+        };
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#intra-block-mode-info-syntax
+    fn intra_block_mode_info(&mut self) -> Result<(), ObuError> {
+        self.b.ref_frame = [INTRA_FRAME, NONE];
+
+        // y_mode	S()
+        let ctx = SIZE_GROUP[self.b.mi_size as usize] as usize;
+        self.b.y_mode = self.sd.read_symbol(&mut self.cdf.y_mode_cdf[ctx]) as u8;
+
+        self.intra_angle_info_y();
+        if self.b.has_chroma {
+            self.read_uv_mode();
+            if self.b.uv_mode == UV_CFL_PRED {
+                self.read_cfl_alphas();
+            }
+
+            self.intra_angle_info_uv();
+        }
+
+        self.b.palette_size_y = 0;
+        self.b.palette_size_uv = 0;
+        let mi_size = self.b.mi_size;
+        if mi_size >= BLOCK_8X8
+            && block_width(mi_size) <= 64
+            && block_height(mi_size) <= 64
+            && self.header.allow_screen_content_tools
+        {
+            self.palette_mode_info();
+        }
+
+        self.filter_intra_mode_info();
+        Ok(())
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#inter-block-mode-info-syntax
+    fn inter_block_mode_info(&mut self) {
+        self.b.palette_size_y = 0;
+        self.b.palette_size_uv = 0;
+        self.read_ref_frames();
+
+        let is_compound = self.b.ref_frame[1] > INTRA_FRAME;
+        self.find_mv_stack(is_compound);
+
+        let segment_id = self.b.segment_id;
+        let segmentation_params = &self.header.segmentation_params;
+        if self.b.skip_mode {
+            self.b.y_mode = NEAREST_NEARESTMV;
+        } else if segmentation_params.feature_active(segment_id, SEG_LVL_SKIP)
+            || segmentation_params.feature_active(segment_id, SEG_LVL_GLOBALMV)
+        {
+            self.b.y_mode = GLOBALMV;
+        } else if is_compound {
+            // compound_mode	S()
+            let ctx = COMPOUND_MODE_CTX_MAP[(self.mv_stack.ref_mv_context >> 1) as usize]
+                [self.mv_stack.new_mv_context.min(COMP_NEWMV_CTXS - 1) as usize]
+                as usize;
+            let compound_mode = self.sd.read_symbol(&mut self.cdf.compound_mode_cdf[ctx]);
+            self.b.y_mode = NEAREST_NEARESTMV + compound_mode as u8;
+        } else {
+            // new_mv	S()
+            let ctx = self.mv_stack.new_mv_context as usize;
+            if self.sd.read_symbol(&mut self.cdf.new_mv_cdf[ctx]) == 0 {
+                self.b.y_mode = NEWMV;
+            } else {
+                // zero_mv	S()
+                let ctx = self.mv_stack.zero_mv_context as usize;
+                if self.sd.read_symbol(&mut self.cdf.zero_mv_cdf[ctx]) == 0 {
+                    self.b.y_mode = GLOBALMV;
+                } else {
+                    // ref_mv	S()
+                    let ctx = self.mv_stack.ref_mv_context as usize;
+                    self.b.y_mode = if self.sd.read_symbol(&mut self.cdf.ref_mv_cdf[ctx]) == 0 {
+                        NEARESTMV
+                    } else {
+                        NEARMV
+                    };
+                }
+            }
+        }
+
+        self.b.ref_mv_idx = 0;
+        let y_mode = self.b.y_mode;
+        if y_mode == NEWMV || y_mode == NEW_NEWMV {
+            self.read_drl_mode(0);
+        } else if matches!(y_mode, NEARMV | NEAR_NEARMV | NEAR_NEWMV | NEW_NEARMV) {
+            self.b.ref_mv_idx = 1;
+            self.read_drl_mode(1);
+        }
+
+        self.assign_mv(is_compound);
+        self.read_interintra_mode(is_compound);
+        self.read_motion_mode(is_compound);
+        self.read_compound_type(is_compound);
+
+        if self.header.interpolation_filter == InterpolationFilter::Switchable {
+            let enable_dual_filter = self.sequence_header.enable_dual_filter;
+            for dir in 0..if enable_dual_filter { 2 } else { 1 } {
+                self.b.interp_filter[dir] = if self.needs_interp_filter() {
+                    // interp_filter[ dir ]	S()
+                    let ctx = self.interp_filter_ctx(dir);
+                    match self.sd.read_symbol(&mut self.cdf.interp_filter_cdf[ctx]) {
+                        0 => InterpolationFilter::Eighttap,
+                        1 => InterpolationFilter::EighttapSmooth,
+                        _ => InterpolationFilter::EighttapSharp,
+                    }
+                } else {
+                    InterpolationFilter::Eighttap
+                };
+            }
+
+            if !enable_dual_filter {
+                self.b.interp_filter[1] = self.b.interp_filter[0];
+            }
+        } else {
+            self.b.interp_filter = [self.header.interpolation_filter; 2];
+        }
+    }
+
+    /// Reads the drl_mode syntax elements that select the RefMvIdx-th
+    /// motion vector of the stack, starting from the index `start`.
+    fn read_drl_mode(&mut self, start: usize) {
+        for idx in start..start + 2 {
+            if self.mv_stack.num_mv_found > idx + 1 {
+                // drl_mode	S()
+                let ctx = self.mv_stack.drl_ctx_stack[idx] as usize;
+                if self.sd.read_symbol(&mut self.cdf.drl_mode_cdf[ctx]) == 0 {
+                    self.b.ref_mv_idx = idx;
+                    break;
+                }
+
+                self.b.ref_mv_idx = idx + 1;
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#ref-frames-syntax
+    fn read_ref_frames(&mut self) {
+        let segment_id = self.b.segment_id;
+        let segmentation_params = &self.header.segmentation_params;
+        if self.b.skip_mode {
+            self.b.ref_frame = self.header.skip_mode_params.skip_mode_frame;
+            return;
+        }
+
+        if segmentation_params.feature_active(segment_id, SEG_LVL_REF_FRAME) {
+            self.b.ref_frame = [
+                segmentation_params.feature_data[segment_id as usize][SEG_LVL_REF_FRAME as usize]
+                    as i8,
+                NONE,
+            ];
+
+            return;
+        }
+
+        if segmentation_params.feature_active(segment_id, SEG_LVL_SKIP)
+            || segmentation_params.feature_active(segment_id, SEG_LVL_GLOBALMV)
+        {
+            self.b.ref_frame = [LAST_FRAME, NONE];
+            return;
+        }
+
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize];
+        let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize];
+        let comp_mode = if self.header.reference_select && bw4.min(bh4) >= 2 {
+            // comp_mode	S()
+            let ctx = self.comp_mode_ctx();
+            self.sd.read_symbol(&mut self.cdf.comp_mode_cdf[ctx]) as u8
+        } else {
+            SINGLE_REFERENCE
+        };
+
+        if comp_mode == COMPOUND_REFERENCE {
+            // comp_ref_type	S()
+            let ctx = self.comp_ref_type_ctx();
+            let comp_ref_type = self.sd.read_symbol(&mut self.cdf.comp_ref_type_cdf[ctx]) as u8;
+            if comp_ref_type == UNIDIR_COMP_REFERENCE {
+                // uni_comp_ref	S()
+                let ctx = self.single_ref_p1_ctx();
+                self.b.ref_frame =
+                    if self.sd.read_symbol(&mut self.cdf.uni_comp_ref_cdf[ctx][0]) == 1 {
+                        [BWDREF_FRAME, ALTREF_FRAME]
+                    } else {
+                        // uni_comp_ref_p1	S()
+                        let last2_count = self.count_refs(LAST2_FRAME);
+                        let last3_gold_count =
+                            self.count_refs(LAST3_FRAME) + self.count_refs(GOLDEN_FRAME);
+                        let ctx = ref_count_ctx(last2_count, last3_gold_count);
+                        if self.sd.read_symbol(&mut self.cdf.uni_comp_ref_cdf[ctx][1]) == 1 {
+                            // uni_comp_ref_p2	S()
+                            let ctx = ref_count_ctx(
+                                self.count_refs(LAST3_FRAME),
+                                self.count_refs(GOLDEN_FRAME),
+                            );
+
+                            if self.sd.read_symbol(&mut self.cdf.uni_comp_ref_cdf[ctx][2]) == 1 {
+                                [LAST_FRAME, GOLDEN_FRAME]
+                            } else {
+                                [LAST_FRAME, LAST3_FRAME]
+                            }
+                        } else {
+                            [LAST_FRAME, LAST2_FRAME]
+                        }
+                    };
+            } else {
+                // comp_ref	S()
+                let ctx = self.single_ref_p3_ctx();
+                self.b.ref_frame[0] =
+                    if self.sd.read_symbol(&mut self.cdf.comp_ref_cdf[ctx][0]) == 0 {
+                        // comp_ref_p1	S()
+                        let ctx = self.single_ref_p4_ctx();
+                        if self.sd.read_symbol(&mut self.cdf.comp_ref_cdf[ctx][1]) == 1 {
+                            LAST2_FRAME
+                        } else {
+                            LAST_FRAME
+                        }
+                    } else {
+                        // comp_ref_p2	S()
+                        let ctx = self.single_ref_p5_ctx();
+                        if self.sd.read_symbol(&mut self.cdf.comp_ref_cdf[ctx][2]) == 1 {
+                            GOLDEN_FRAME
+                        } else {
+                            LAST3_FRAME
+                        }
+                    };
+
+                // comp_bwdref	S()
+                let ctx = self.single_ref_p2_ctx();
+                self.b.ref_frame[1] =
+                    if self.sd.read_symbol(&mut self.cdf.comp_bwd_ref_cdf[ctx][0]) == 0 {
+                        // comp_bwdref_p1	S()
+                        let ctx = self.single_ref_p6_ctx();
+                        if self.sd.read_symbol(&mut self.cdf.comp_bwd_ref_cdf[ctx][1]) == 1 {
+                            ALTREF2_FRAME
+                        } else {
+                            BWDREF_FRAME
+                        }
+                    } else {
+                        ALTREF_FRAME
+                    };
+            }
+        } else {
+            // single_ref_p1	S()
+            let ctx = self.single_ref_p1_ctx();
+            self.b.ref_frame[0] = if self.sd.read_symbol(&mut self.cdf.single_ref_cdf[ctx][0]) == 1
+            {
+                // single_ref_p2	S()
+                let ctx = self.single_ref_p2_ctx();
+                if self.sd.read_symbol(&mut self.cdf.single_ref_cdf[ctx][1]) == 0 {
+                    // single_ref_p6	S()
+                    let ctx = self.single_ref_p6_ctx();
+                    if self.sd.read_symbol(&mut self.cdf.single_ref_cdf[ctx][5]) == 1 {
+                        ALTREF2_FRAME
+                    } else {
+                        BWDREF_FRAME
+                    }
+                } else {
+                    ALTREF_FRAME
+                }
+            } else {
+                // single_ref_p3	S()
+                let ctx = self.single_ref_p3_ctx();
+                if self.sd.read_symbol(&mut self.cdf.single_ref_cdf[ctx][2]) == 1 {
+                    // single_ref_p5	S()
+                    let ctx = self.single_ref_p5_ctx();
+                    if self.sd.read_symbol(&mut self.cdf.single_ref_cdf[ctx][4]) == 1 {
+                        GOLDEN_FRAME
+                    } else {
+                        LAST3_FRAME
+                    }
+                } else {
+                    // single_ref_p4	S()
+                    let ctx = self.single_ref_p4_ctx();
+                    if self.sd.read_symbol(&mut self.cdf.single_ref_cdf[ctx][3]) == 1 {
+                        LAST2_FRAME
+                    } else {
+                        LAST_FRAME
+                    }
+                }
+            };
+
+            self.b.ref_frame[1] = NONE;
+        }
+    }
+
+    /// The number of times `frame_type` is used as a reference frame by the
+    /// above and left blocks.
+    fn count_refs(&self, frame_type: i8) -> u32 {
+        let mut c = 0;
+        if self.b.avail_u {
+            c += self
+                .b
+                .above_ref_frame
+                .iter()
+                .filter(|v| **v == frame_type)
+                .count();
+        }
+
+        if self.b.avail_l {
+            c += self
+                .b
+                .left_ref_frame
+                .iter()
+                .filter(|v| **v == frame_type)
+                .count();
+        }
+
+        c as u32
+    }
+
+    fn single_ref_p1_ctx(&self) -> usize {
+        let fwd_count = self.count_refs(LAST_FRAME)
+            + self.count_refs(LAST2_FRAME)
+            + self.count_refs(LAST3_FRAME)
+            + self.count_refs(GOLDEN_FRAME);
+        let bwd_count = self.count_refs(BWDREF_FRAME)
+            + self.count_refs(ALTREF2_FRAME)
+            + self.count_refs(ALTREF_FRAME);
+        ref_count_ctx(fwd_count, bwd_count)
+    }
+
+    fn single_ref_p2_ctx(&self) -> usize {
+        let brfarf2_count = self.count_refs(BWDREF_FRAME) + self.count_refs(ALTREF2_FRAME);
+        ref_count_ctx(brfarf2_count, self.count_refs(ALTREF_FRAME))
+    }
+
+    fn single_ref_p3_ctx(&self) -> usize {
+        let last12_count = self.count_refs(LAST_FRAME) + self.count_refs(LAST2_FRAME);
+        let last3_gold_count = self.count_refs(LAST3_FRAME) + self.count_refs(GOLDEN_FRAME);
+        ref_count_ctx(last12_count, last3_gold_count)
+    }
+
+    fn single_ref_p4_ctx(&self) -> usize {
+        ref_count_ctx(self.count_refs(LAST_FRAME), self.count_refs(LAST2_FRAME))
+    }
+
+    fn single_ref_p5_ctx(&self) -> usize {
+        ref_count_ctx(self.count_refs(LAST3_FRAME), self.count_refs(GOLDEN_FRAME))
+    }
+
+    fn single_ref_p6_ctx(&self) -> usize {
+        ref_count_ctx(
+            self.count_refs(BWDREF_FRAME),
+            self.count_refs(ALTREF2_FRAME),
+        )
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#semantics-for-ref-frames
+    fn comp_mode_ctx(&self) -> usize {
+        let b = &self.b;
+        let above0 = b.above_ref_frame[0];
+        let left0 = b.left_ref_frame[0];
+        if b.avail_u && b.avail_l {
+            if b.above_single && b.left_single {
+                (check_backward(above0) ^ check_backward(left0)) as usize
+            } else if b.above_single {
+                2 + (check_backward(above0) || b.above_intra) as usize
+            } else if b.left_single {
+                2 + (check_backward(left0) || b.left_intra) as usize
+            } else {
+                4
+            }
+        } else if b.avail_u {
+            if b.above_single {
+                check_backward(above0) as usize
+            } else {
+                3
+            }
+        } else if b.avail_l {
+            if b.left_single {
+                check_backward(left0) as usize
+            } else {
+                3
+            }
+        } else {
+            1
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#semantics-for-ref-frames
+    fn comp_ref_type_ctx(&self) -> usize {
+        let b = &self.b;
+        let [above0, above1] = b.above_ref_frame;
+        let [left0, left1] = b.left_ref_frame;
+        let above_comp_inter = b.avail_u && !b.above_intra && !b.above_single;
+        let left_comp_inter = b.avail_l && !b.left_intra && !b.left_single;
+        let above_uni_comp = above_comp_inter && is_samedir_ref_pair(above0, above1);
+        let left_uni_comp = left_comp_inter && is_samedir_ref_pair(left0, left1);
+
+        if b.avail_u && !b.above_intra && b.avail_l && !b.left_intra {
+            let samedir = is_samedir_ref_pair(above0, left0) as usize;
+            if !above_comp_inter && !left_comp_inter {
+                1 + 2 * samedir
+            } else if !above_comp_inter {
+                if !left_uni_comp {
+                    1
+                } else {
+                    3 + samedir
+                }
+            } else if !left_comp_inter {
+                if !above_uni_comp {
+                    1
+                } else {
+                    3 + samedir
+                }
+            } else if !above_uni_comp && !left_uni_comp {
+                0
+            } else if !above_uni_comp || !left_uni_comp {
+                2
+            } else {
+                3 + ((above0 == BWDREF_FRAME) == (left0 == BWDREF_FRAME)) as usize
+            }
+        } else if b.avail_u && b.avail_l {
+            if above_comp_inter {
+                1 + 2 * above_uni_comp as usize
+            } else if left_comp_inter {
+                1 + 2 * left_uni_comp as usize
+            } else {
+                2
+            }
+        } else if above_comp_inter {
+            4 * above_uni_comp as usize
+        } else if left_comp_inter {
+            4 * left_uni_comp as usize
+        } else {
+            2
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#assign-mv-syntax
+    pub(crate) fn assign_mv(&mut self, is_compound: bool) {
+        for i in 0..1 + is_compound as usize {
+            let comp_mode = if self.b.use_intrabc {
+                NEWMV
+            } else {
+                self.get_mode(i)
+            };
+
+            self.b.pred_mv[i] = if self.b.use_intrabc {
+                self.ref_dv()
+            } else if comp_mode == GLOBALMV {
+                self.mv_stack.global_mvs[i]
+            } else {
+                let mut pos = if comp_mode == NEARESTMV {
+                    0
+                } else {
+                    self.b.ref_mv_idx
+                };
+
+                if comp_mode == NEWMV && self.mv_stack.num_mv_found <= 1 {
+                    pos = 0;
+                }
+
+                self.mv_stack.ref_stack_mv[pos][i]
+            };
+
+            if comp_mode == NEWMV {
+                self.read_mv(i);
+            } else {
+                self.b.mv[i] = self.b.pred_mv[i];
+            }
+        }
+    }
+
+    /// The prediction of the displacement vector of an intra block copy: the
+    /// first non-zero vector of the stack, or by default the vector pointing
+    /// to the superblock row above, or to the left of the current superblock
+    /// on the first superblock row of the tile.
+    fn ref_dv(&self) -> Mv {
+        let ref_stack_mv = &self.mv_stack.ref_stack_mv;
+        if ref_stack_mv[0][0] != [0, 0] {
+            return ref_stack_mv[0][0];
+        }
+
+        if ref_stack_mv[1][0] != [0, 0] {
+            return ref_stack_mv[1][0];
+        }
+
+        let sb_size4 = NUM_4X4_BLOCKS_HIGH[self.sb_size() as usize] as i32;
+        if (self.b.mi_row as i32) - sb_size4 < self.mi_row_start as i32 {
+            [
+                0,
+                -(sb_size4 * MI_SIZE as i32 + INTRABC_DELAY_PIXELS as i32) * 8,
+            ]
+        } else {
+            [-(sb_size4 * MI_SIZE as i32 * 8), 0]
+        }
+    }
+
+    /// The single reference mode used by the reference list `ref_list` of
+    /// the current block.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#get-mode-function
+    fn get_mode(&self, ref_list: usize) -> u8 {
+        let y_mode = self.b.y_mode;
+        if ref_list == 0 {
+            match y_mode {
+                _ if y_mode < NEAREST_NEARESTMV => y_mode,
+                NEW_NEWMV | NEW_NEARESTMV | NEW_NEARMV => NEWMV,
+                NEAREST_NEARESTMV | NEAREST_NEWMV => NEARESTMV,
+                NEAR_NEARMV | NEAR_NEWMV => NEARMV,
+                _ => GLOBALMV,
+            }
+        } else {
+            match y_mode {
+                NEW_NEWMV | NEAREST_NEWMV | NEAR_NEWMV => NEWMV,
+                NEAREST_NEARESTMV | NEW_NEARESTMV => NEARESTMV,
+                NEAR_NEARMV | NEW_NEARMV => NEARMV,
+                _ => GLOBALMV,
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#mv-syntax
+    pub(crate) fn read_mv(&mut self, r: usize) {
+        let mv_ctx = if self.b.use_intrabc {
+            MV_INTRABC_CONTEXT as usize
+        } else {
+            0
+        };
+
+        let mut diff_mv: Mv = [0, 0];
+
+        // mv_joint	S()
+        let mv_joint = self.sd.read_symbol(&mut self.cdf.mv_joint_cdf[mv_ctx]) as u8;
+        if mv_joint == MV_JOINT_HZVNZ || mv_joint == MV_JOINT_HNZVNZ {
+            diff_mv[0] = self.read_mv_component(mv_ctx, 0);
+        }
+
+        if mv_joint == MV_JOINT_HNZVZ || mv_joint == MV_JOINT_HNZVNZ {
+            diff_mv[1] = self.read_mv_component(mv_ctx, 1);
+        }
+
+        self.b.mv[r] = [
+            self.b.pred_mv[r][0] + diff_mv[0],
+            self.b.pred_mv[r][1] + diff_mv[1],
+        ];
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#mv-component-syntax
+    fn read_mv_component(&mut self, mv_ctx: usize, comp: usize) -> i32 {
+        let force_integer_mv = self.header.force_integer_mv;
+        let allow_high_precision_mv = self.header.allow_high_precision_mv;
+
+        // mv_sign	S()
+        let mv_sign = self.sd.read_symbol(&mut self.cdf.mv_sign_cdf[mv_ctx][comp]) == 1;
+
+        // mv_class	S()
+        let mv_class = self
+            .sd
+            .read_symbol(&mut self.cdf.mv_class_cdf[mv_ctx][comp]) as u8;
+
+        let mag = if mv_class == MV_CLASS_0 {
+            // mv_class0_bit	S()
+            let mv_class0_bit = self
+                .sd
+                .read_symbol(&mut self.cdf.mv_class0_bit_cdf[mv_ctx][comp])
+                as i32;
+
+            let mv_class0_fr = if force_integer_mv {
+                3
+            } else {
+                // mv_class0_fr	S()
+                self.sd.read_symbol(
+                    &mut self.cdf.mv_class0_fr_cdf[mv_ctx][comp][mv_class0_bit as usize],
+                ) as i32
+            };
+
+            let mv_class0_hp = if allow_high_precision_mv {
+                // mv_class0_hp	S()
+                self.sd
+                    .read_symbol(&mut self.cdf.mv_class0_hp_cdf[mv_ctx][comp])
+                    as i32
+            } else {
+                1
+            };
+
+            ((mv_class0_bit << 3) | (mv_class0_fr << 1) | mv_class0_hp) + 1
+        } else {
+            let mut d = 0;
+            for i in 0..mv_class as usize {
+                // mv_bit	S()
+                let mv_bit = self
+                    .sd
+                    .read_symbol(&mut self.cdf.mv_bit_cdf[mv_ctx][comp][i]);
+                d |= (mv_bit as i32) << i;
+            }
+
+            let mag = (CLASS0_SIZE as i32) << (mv_class + 2);
+            let mv_fr = if force_integer_mv {
+                3
+            } else {
+                // mv_fr	S()
+                self.sd.read_symbol(&mut self.cdf.mv_fr_cdf[mv_ctx][comp]) as i32
+            };
+
+            let mv_hp = if allow_high_precision_mv {
+                // mv_hp	S()
+                self.sd.read_symbol(&mut self.cdf.mv_hp_cdf[mv_ctx][comp]) as i32
+            } else {
+                1
+            };
+
+            mag + ((d << 3) | (mv_fr << 1) | mv_hp) + 1
+        };
+
+        if mv_sign {
+            -mag
+        } else {
+            mag
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#interintra-mode-syntax
+    fn read_interintra_mode(&mut self, is_compound: bool) {
+        let mi_size = self.b.mi_size;
+        self.b.interintra = false;
+        if !self.b.skip_mode
+            && self.sequence_header.enable_interintra_compound
+            && !is_compound
+            && (BLOCK_8X8..=BLOCK_32X32).contains(&mi_size)
+        {
+            // interintra	S()
+            let ctx = SIZE_GROUP[mi_size as usize] as usize;
+            self.b.interintra = self.sd.read_symbol(&mut self.cdf.inter_intra_cdf[ctx]) == 1;
+            if self.b.interintra {
+                // interintra_mode	S()
+                self.b.interintra_mode =
+                    self.sd.read_symbol(&mut self.cdf.inter_intra_mode_cdf[ctx]) as u8;
+
+                self.b.ref_frame[1] = INTRA_FRAME;
+                self.b.angle_delta_y = 0;
+                self.b.angle_delta_uv = 0;
+                self.b.use_filter_intra = false;
+
+                // wedge_interintra	S()
+                self.b.wedge_interintra = self
+                    .sd
+                    .read_symbol(&mut self.cdf.wedge_inter_intra_cdf[mi_size as usize])
+                    == 1;
+
+                if self.b.wedge_interintra {
+                    // wedge_index	S()
+                    self.b.wedge_index = self
+                        .sd
+                        .read_symbol(&mut self.cdf.wedge_index_cdf[mi_size as usize])
+                        as u8;
+                    self.b.wedge_sign = 0;
+                }
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#motion-mode-syntax
+    fn read_motion_mode(&mut self, is_compound: bool) {
+        let mi_size = self.b.mi_size;
+        self.b.motion_mode = SIMPLE;
+        if self.b.skip_mode || !self.header.is_motion_mode_switchable {
+            return;
+        }
+
+        if block_width(mi_size).min(block_height(mi_size)) < 8 {
+            return;
+        }
+
+        let gm_type = self.header.global_motion_params.gm_type;
+        if !self.header.force_integer_mv
+            && (self.b.y_mode == GLOBALMV || self.b.y_mode == GLOBAL_GLOBALMV)
+            && gm_type[self.b.ref_frame[0] as usize] > GmType::Translation
+        {
+            return;
+        }
+
+        if is_compound || self.b.ref_frame[1] == INTRA_FRAME || !self.has_overlappable_candidates()
+        {
+            return;
+        }
+
+        self.find_warp_samples();
+        if self.header.force_integer_mv
+            || self.warp_samples.num_samples == 0
+            || !self.header.allow_warped_motion
+            || self.is_scaled(self.b.ref_frame[0])
+        {
+            // use_obmc	S()
+            if self
+                .sd
+                .read_symbol(&mut self.cdf.use_obmc_cdf[mi_size as usize])
+                == 1
+            {
+                self.b.motion_mode = OBMC;
+            }
+        } else {
+            // motion_mode	S()
+            self.b.motion_mode = self
+                .sd
+                .read_symbol(&mut self.cdf.motion_mode_cdf[mi_size as usize])
+                as u8;
+        }
+    }
+
+    /// Returns whether the reference frame `ref_frame` has a different size
+    /// than the current frame.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#motion-mode-semantics
+    pub(crate) fn is_scaled(&self, ref_frame: i8) -> bool {
+        let ref_idx = self.header.ref_frame_idx[(ref_frame - LAST_FRAME) as usize] as usize;
+        let frame_width = self.ctx.frame_width as u32;
+        let frame_height = self.ctx.frame_height as u32;
+        let x_scale = (((self.ctx.ref_upscaled_width[ref_idx] as u32) << REF_SCALE_SHIFT)
+            + frame_width / 2)
+            / frame_width;
+        let y_scale = (((self.ctx.ref_frame_height[ref_idx] as u32) << REF_SCALE_SHIFT)
+            + frame_height / 2)
+            / frame_height;
+        let no_scale = 1 << REF_SCALE_SHIFT;
+        x_scale != no_scale || y_scale != no_scale
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#compound-type-syntax
+    fn read_compound_type(&mut self, is_compound: bool) {
+        let mi_size = self.b.mi_size;
+        self.b.comp_group_idx = 0;
+        self.b.compound_idx = 1;
+        if self.b.skip_mode {
+            self.b.compound_type = COMPOUND_AVERAGE;
+            return;
+        }
+
+        if is_compound {
+            let n = WEDGE_BITS[mi_size as usize];
+            if self.sequence_header.enable_masked_compound {
+                // comp_group_idx	S()
+                let ctx = self.comp_group_idx_ctx();
+                self.b.comp_group_idx =
+                    self.sd.read_symbol(&mut self.cdf.comp_group_idx_cdf[ctx]) as u8;
+            }
+
+            if self.b.comp_group_idx == 0 {
+                self.b.compound_type = if self.sequence_header.enable_jnt_comp {
+                    // compound_idx	S()
+                    let ctx = self.compound_idx_ctx();
+                    self.b.compound_idx =
+                        self.sd.read_symbol(&mut self.cdf.compound_idx_cdf[ctx]) as u8;
+                    if self.b.compound_idx == 1 {
+                        COMPOUND_AVERAGE
+                    } else {
+                        COMPOUND_DISTANCE
+                    }
+                } else {
+                    COMPOUND_AVERAGE
+                };
+            } else if n == 0 {
+                self.b.compound_type = COMPOUND_DIFFWTD;
+            } else {
+                // compound_type	S()
+                self.b.compound_type = self
+                    .sd
+                    .read_symbol(&mut self.cdf.compound_type_cdf[mi_size as usize])
+                    as u8;
+            }
+
+            if self.b.compound_type == COMPOUND_WEDGE {
+                // wedge_index	S()
+                self.b.wedge_index = self
+                    .sd
+                    .read_symbol(&mut self.cdf.wedge_index_cdf[mi_size as usize])
+                    as u8;
+
+                // wedge_sign	L(1)
+                self.b.wedge_sign = self.sd.read_literal(1) as u8;
+            } else if self.b.compound_type == COMPOUND_DIFFWTD {
+                // mask_type	L(1)
+                self.b.mask_type = self.sd.read_literal(1) as u8;
+            }
+        } else if self.b.interintra {
+            self.b.compound_type = if self.b.wedge_interintra {
+                COMPOUND_WEDGE
+            } else {
+                COMPOUND_INTRA
+            };
+        } else {
+            self.b.compound_type = COMPOUND_AVERAGE;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#semantics-for-compound-type
+    fn comp_group_idx_ctx(&self) -> usize {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        let mut ctx = 0;
+        if self.b.avail_u {
+            if !self.b.above_single {
+                ctx += self.frame.mi(r - 1, c).comp_group_idx as usize;
+            } else if self.b.above_ref_frame[0] == ALTREF_FRAME {
+                ctx += 3;
+            }
+        }
+
+        if self.b.avail_l {
+            if !self.b.left_single {
+                ctx += self.frame.mi(r, c - 1).comp_group_idx as usize;
+            } else if self.b.left_ref_frame[0] == ALTREF_FRAME {
+                ctx += 3;
+            }
+        }
+
+        ctx.min(5)
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#semantics-for-compound-type
+    fn compound_idx_ctx(&self) -> usize {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        let order_hints = &self.ctx.order_hints;
+        let fwd = get_relative_dist(
+            self.ctx,
+            order_hints[self.b.ref_frame[0] as usize],
+            self.ctx.order_hint,
+        )
+        .abs();
+        let bck = get_relative_dist(
+            self.ctx,
+            order_hints[self.b.ref_frame[1] as usize],
+            self.ctx.order_hint,
+        )
+        .abs();
+
+        let mut ctx = if fwd == bck { 3 } else { 0 };
+        if self.b.avail_u {
+            if !self.b.above_single {
+                ctx += self.frame.mi(r - 1, c).compound_idx as usize;
+            } else if self.b.above_ref_frame[0] == ALTREF_FRAME {
+                ctx += 1;
+            }
+        }
+
+        if self.b.avail_l {
+            if !self.b.left_single {
+                ctx += self.frame.mi(r, c - 1).compound_idx as usize;
+            } else if self.b.left_ref_frame[0] == ALTREF_FRAME {
+                ctx += 1;
+            }
+        }
+
+        ctx
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#inter-block-mode-info-semantics
+    fn needs_interp_filter(&self) -> bool {
+        let large = block_width(self.b.mi_size).min(block_height(self.b.mi_size)) >= 8;
+        let gm_type = &self.header.global_motion_params.gm_type;
+        if self.b.skip_mode || self.b.motion_mode == LOCALWARP {
+            false
+        } else if large && self.b.y_mode == GLOBALMV {
+            gm_type[self.b.ref_frame[0] as usize] == GmType::Translation
+        } else if large && self.b.y_mode == GLOBAL_GLOBALMV {
+            gm_type[self.b.ref_frame[0] as usize] == GmType::Translation
+                || gm_type[self.b.ref_frame[1] as usize] == GmType::Translation
+        } else {
+            true
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#semantics-for-interpolation-filter
+    fn interp_filter_ctx(&self, dir: usize) -> usize {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        let ref_frame = self.b.ref_frame;
+        let mut ctx = ((dir & 1) * 2 + (ref_frame[1] > INTRA_FRAME) as usize) * 4;
+        let mut left_type = 3;
+        let mut above_type = 3;
+        if self.b.avail_l {
+            let left = self.frame.mi(r, c - 1);
+            if left.ref_frame[0] == ref_frame[0] || left.ref_frame[1] == ref_frame[0] {
+                left_type = left.interp_filter[dir] as usize;
+            }
+        }
+
+        if self.b.avail_u {
+            let above = self.frame.mi(r - 1, c);
+            if above.ref_frame[0] == ref_frame[0] || above.ref_frame[1] == ref_frame[0] {
+                above_type = above.interp_filter[dir] as usize;
+            }
+        }
+
+        ctx += if left_type == above_type || above_type == 3 {
+            left_type
+        } else if left_type == 3 {
+            above_type
+        } else {
+            3
+        };
+
+        ctx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        obu::frame_header::UncompressedHeader,
+        tile::{mode_info::FrameState, testing},
+    };
+
+    /// The predicted displacement vector of the block at the row `mi_row` of
+    /// the tile starting at the row `mi_row_start`, with the mv stack
+    /// `ref_stack_mv`.
+    fn ref_dv(
+        ref_stack_mv: &[Mv],
+        mi_row: u32,
+        mi_row_start: u32,
+        use_128x128_superblock: bool,
+    ) -> Mv {
+        let mut ctx = testing::context(UncompressedHeader::default(), 16, 16);
+        ctx.sequence_header.as_mut().unwrap().use_128x128_superblock = use_128x128_superblock;
+
+        let mut frame = FrameState::new(16, 16);
+        let mut decoder = testing::decoder(&ctx, &mut frame);
+        decoder.mi_row_start = mi_row_start;
+        decoder.b.mi_row = mi_row;
+        for (mvs, mv) in decoder.mv_stack.ref_stack_mv.iter_mut().zip(ref_stack_mv) {
+            mvs[0] = *mv;
+        }
+
+        decoder.ref_dv()
+    }
+
+    #[test]
+    fn ref_dv_defaults_to_the_superblock_above_or_the_delay_to_the_left() {
+        // On the first superblock row of the tile, 256 samples to the left of
+        // the superblock.
+        assert_eq!(ref_dv(&[], 0, 0, false), [0, -(64 + 256) * 8]);
+        assert_eq!(ref_dv(&[], 20, 16, false), [0, -(64 + 256) * 8]);
+        assert_eq!(ref_dv(&[], 16, 0, true), [0, -(128 + 256) * 8]);
+
+        // Below, one superblock up.
+        assert_eq!(ref_dv(&[], 16, 0, false), [-64 * 8, 0]);
+        assert_eq!(ref_dv(&[], 40, 16, false), [-64 * 8, 0]);
+        assert_eq!(ref_dv(&[], 32, 0, true), [-128 * 8, 0]);
+    }
+
+    #[test]
+    fn ref_dv_takes_the_first_non_zero_vector_of_the_stack() {
+        assert_eq!(
+            ref_dv(&[[0, 0], [-16, 8], [-32, 0]], 16, 0, false),
+            [-16, 8]
+        );
+        assert_eq!(
+            ref_dv(&[[0, -24], [-16, 8], [-32, 0]], 16, 0, false),
+            [0, -24]
+        );
+
+        // Only the first two entries are considered.
+        assert_eq!(
+            ref_dv(&[[0, 0], [0, 0], [-32, 0]], 16, 0, false),
+            [-64 * 8, 0]
+        );
+    }
+}
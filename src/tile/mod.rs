@@ -0,0 +1,744 @@
+//! Decoding of the tile data: the partition of the superblocks, the mode info
+//! of the blocks and the residual.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#decode-tile-syntax
+
+pub mod block;
+pub mod inter;
+pub mod mode_info;
+pub mod motion_field;
+pub mod mvpred;
+pub mod palette;
+pub mod prediction;
+pub mod residual;
+
+use self::{
+    block::Block,
+    mode_info::{FrameState, RestorationUnits},
+    mvpred::{MvStack, WarpSamples},
+};
+
+use crate::{
+    cdf::CdfContext,
+    constants::*,
+    inter_prediction::{InterPrediction, Mask},
+    obu::{
+        frame_header::{inverse_recenter, LrParams, RestorationType, UncompressedHeader},
+        sequence_header::SequenceHeader,
+        tile_group::Tile,
+        ObuContext, ObuError,
+    },
+    symbol::SymbolDecoder,
+    tables::*,
+    util::floor_log2,
+};
+
+/// Decodes a tile of the current frame into `ctx.frame_state`, the cdfs of
+/// the tile context_update_tile_id are kept for the frame end update.
+pub fn decode_tile(ctx: &mut ObuContext, tile: &Tile) -> Result<(), ObuError> {
+    let mut frame = std::mem::take(&mut ctx.frame_state);
+    let result = TileDecoder::new(ctx, &mut frame, tile).and_then(|decoder| decoder.decode());
+    ctx.frame_state = frame;
+
+    let cdf = result?;
+    let context_update_tile_id = ctx
+        .frame_header
+        .as_ref()
+        .ok_or(ObuError::NotFoundFrameHeader)?
+        .tile_info
+        .context_update_tile_id;
+
+    if tile.tile_num == context_update_tile_id {
+        ctx.context_update_cdf = Some(cdf);
+    }
+
+    Ok(())
+}
+
+/// Allocates the loop restoration units of every plane that uses loop
+/// restoration in the current frame.
+pub fn init_lr_units(ctx: &mut ObuContext, lr_params: &LrParams) -> Result<(), ObuError> {
+    let sequence_header = ctx
+        .sequence_header
+        .as_ref()
+        .ok_or(ObuError::NotFoundSequenceHeader)?;
+
+    for plane in 0..ctx.num_planes as usize {
+        if lr_params.frame_restoration_type[plane] == RestorationType::None {
+            continue;
+        }
+
+        let (sub_x, sub_y) = plane_subsampling(sequence_header, plane);
+        let unit_size = lr_params.loop_restoration_size[plane] as u32;
+        let unit_rows = count_units_in_frame(unit_size, round2(ctx.frame_height as u32, sub_y));
+        let unit_cols = count_units_in_frame(unit_size, round2(ctx.upscaled_width as u32, sub_x));
+        ctx.frame_state.lr_units[plane] = RestorationUnits::new(unit_rows, unit_cols);
+    }
+
+    Ok(())
+}
+
+/// The horizontal and vertical subsampling shifts of a plane.
+#[inline]
+pub fn plane_subsampling(sequence_header: &SequenceHeader, plane: usize) -> (u32, u32) {
+    if plane == 0 {
+        (0, 0)
+    } else {
+        let color_config = &sequence_header.color_config;
+        (
+            color_config.subsampling_x as u32,
+            color_config.subsampling_y as u32,
+        )
+    }
+}
+
+#[inline]
+fn round2(x: u32, n: u32) -> u32 {
+    if n == 0 {
+        x
+    } else {
+        (x + (1 << (n - 1))) >> n
+    }
+}
+
+fn count_units_in_frame(unit_size: u32, frame_size: u32) -> u32 {
+    ((frame_size + (unit_size >> 1)) / unit_size).max(1)
+}
+
+/// The number of entries of the coefficient contexts past the edge of the
+/// frame, the blocks and transform blocks at the edge of the frame can
+/// extend past it by up to 128 luma samples.
+const CONTEXT_PADDING: usize = 32;
+
+/// The number of 4x4 blocks tracked by the decoded flags of a plane in each
+/// direction, a superblock of 128x128 samples and the blocks above and to
+/// the left of it.
+const BLOCK_DECODED_SIZE: usize = 32 + 2;
+
+/// The state of the decoding of a single tile.
+pub struct TileDecoder<'a, 'b> {
+    pub ctx: &'b ObuContext,
+    pub sequence_header: &'b SequenceHeader,
+    pub header: &'b UncompressedHeader,
+    pub frame: &'b mut FrameState,
+    pub sd: SymbolDecoder<'a>,
+    /// The cdfs of the tile, initialized from the cdfs of the frame.
+    pub cdf: CdfContext,
+    pub mi_row_start: u32,
+    pub mi_row_end: u32,
+    pub mi_col_start: u32,
+    pub mi_col_end: u32,
+    pub current_q_index: u8,
+    pub delta_lf: [i8; FRAME_LF_COUNT as usize],
+    pub read_deltas: bool,
+    pub ref_sgr_xqd: [[i32; 2]; 3],
+    pub ref_lr_wiener: [[[i32; 3]; 2]; 3],
+    /// The block being decoded.
+    pub b: Block,
+    pub mv_stack: MvStack,
+    pub warp_samples: WarpSamples,
+    pub above_seg_pred_context: Vec<u8>,
+    pub left_seg_pred_context: Vec<u8>,
+    /// The culLevel of the coefficients of the transform blocks above and to
+    /// the left, per plane in units of 4x4 samples of the plane.
+    pub above_level_context: [Vec<u8>; 3],
+    pub left_level_context: [Vec<u8>; 3],
+    /// The dcCategory of the transform blocks above and to the left.
+    pub above_dc_context: [Vec<u8>; 3],
+    pub left_dc_context: [Vec<u8>; 3],
+    /// The transform type of each 4x4 luma block of the frame.
+    pub tx_types: Vec<u8>,
+    /// The transform type of the transform block being decoded.
+    pub plane_tx_type: u8,
+    /// The quantized coefficients of the transform block being decoded,
+    /// stored row by row with a stride of at most 32.
+    pub quant: Vec<i32>,
+    /// The dequantized coefficients of the transform block being decoded.
+    pub dequant: Box<[[i32; 64]; 64]>,
+    /// The residual of the transform block being decoded.
+    pub residual: Box<[[i32; 64]; 64]>,
+    /// The palette color index of each sample of the block being decoded,
+    /// for the Y plane and for the U and V planes.
+    pub color_map_y: Box<[[u8; 64]; 64]>,
+    pub color_map_uv: Box<[[u8; 64]; 64]>,
+    /// The inter predictions of the block being predicted from each of its
+    /// references.
+    pub preds: Box<[InterPrediction; 2]>,
+    /// The blending weights of the masked compound block being predicted,
+    /// computed with the luma and reused by the chroma.
+    pub mask: Box<Mask>,
+    /// Whether each 4x4 block of the superblock being decoded has been
+    /// decoded, per plane, offset by one to hold the blocks above and to
+    /// the left of the superblock.
+    pub block_decoded: [[[bool; BLOCK_DECODED_SIZE]; BLOCK_DECODED_SIZE]; 3],
+    /// The right and bottom edges of the last luma transform block predicted
+    /// in the current block, in luma samples.
+    pub max_luma_w: u32,
+    pub max_luma_h: u32,
+}
+
+impl<'a, 'b> TileDecoder<'a, 'b> {
+    /// see: https://aomediacodec.github.io/av1-spec/#initialization-process-for-symbol-decoder
+    pub fn new(
+        ctx: &'b ObuContext,
+        frame: &'b mut FrameState,
+        tile: &Tile<'a>,
+    ) -> Result<Self, ObuError> {
+        let sequence_header = ctx
+            .sequence_header
+            .as_ref()
+            .ok_or(ObuError::NotFoundSequenceHeader)?;
+
+        let header = ctx
+            .frame_header
+            .as_ref()
+            .ok_or(ObuError::NotFoundFrameHeader)?;
+
+        let tile_info = &header.tile_info;
+        if tile.tile_row >= tile_info.tile_rows || tile.tile_col >= tile_info.tile_cols {
+            return Err(ObuError::InvalidTileGroup);
+        }
+
+        let (mi_rows, mi_cols) = (frame.mi_rows as usize, frame.mi_cols as usize);
+        Ok(Self {
+            ctx,
+            sequence_header,
+            header,
+            frame,
+            sd: SymbolDecoder::new(tile.data, header.disable_cdf_update)?,
+            cdf: ctx.cdf.clone(),
+            mi_row_start: tile_info.mi_row_starts[tile.tile_row as usize],
+            mi_row_end: tile_info.mi_row_starts[tile.tile_row as usize + 1],
+            mi_col_start: tile_info.mi_col_starts[tile.tile_col as usize],
+            mi_col_end: tile_info.mi_col_starts[tile.tile_col as usize + 1],
+            current_q_index: header.quantization_params.base_q_idx,
+            delta_lf: [0; FRAME_LF_COUNT as usize],
+            read_deltas: false,
+            ref_sgr_xqd: [SGRPROJ_XQD_MID; 3],
+            ref_lr_wiener: [[WIENER_TAPS_MID; 2]; 3],
+            b: Block::default(),
+            mv_stack: MvStack::default(),
+            warp_samples: WarpSamples::default(),
+            above_seg_pred_context: vec![0; mi_cols],
+            left_seg_pred_context: vec![0; mi_rows],
+            above_level_context: std::array::from_fn(|_| vec![0; mi_cols + CONTEXT_PADDING]),
+            left_level_context: std::array::from_fn(|_| vec![0; mi_rows + CONTEXT_PADDING]),
+            above_dc_context: std::array::from_fn(|_| vec![0; mi_cols + CONTEXT_PADDING]),
+            left_dc_context: std::array::from_fn(|_| vec![0; mi_rows + CONTEXT_PADDING]),
+            tx_types: vec![DCT_DCT; mi_rows * mi_cols],
+            plane_tx_type: DCT_DCT,
+            quant: vec![0; 1024],
+            dequant: Box::new([[0; 64]; 64]),
+            residual: Box::new([[0; 64]; 64]),
+            color_map_y: Box::new([[0; 64]; 64]),
+            color_map_uv: Box::new([[0; 64]; 64]),
+            preds: Box::new([[[0; 128]; 128]; 2]),
+            mask: Box::new([[0; 128]; 128]),
+            block_decoded: [[[false; BLOCK_DECODED_SIZE]; BLOCK_DECODED_SIZE]; 3],
+            max_luma_w: 0,
+            max_luma_h: 0,
+        })
+    }
+
+    /// Decodes all the superblocks of the tile and returns the cdfs at the
+    /// end of the tile.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#decode-tile-syntax
+    pub fn decode(mut self) -> Result<CdfContext, ObuError> {
+        self.clear_above_context();
+
+        let sb_size = self.sb_size();
+        let sb_size4 = NUM_4X4_BLOCKS_WIDE[sb_size as usize] as u32;
+        for mi_row in (self.mi_row_start..self.mi_row_end).step_by(sb_size4 as usize) {
+            self.clear_left_context();
+            for mi_col in (self.mi_col_start..self.mi_col_end).step_by(sb_size4 as usize) {
+                self.clear_block_decoded_flags(mi_row, mi_col, sb_size4);
+                self.read_deltas = self.header.delta_q_params.delta_q_present;
+                self.clear_cdef(mi_row, mi_col);
+                self.read_lr(mi_row, mi_col, sb_size);
+                self.decode_partition(mi_row, mi_col, sb_size)?;
+            }
+        }
+
+        self.sd.exit()?;
+        Ok(self.cdf)
+    }
+
+    #[inline]
+    pub fn sb_size(&self) -> u8 {
+        if self.sequence_header.use_128x128_superblock {
+            BLOCK_128X128
+        } else {
+            BLOCK_64X64
+        }
+    }
+
+    /// Returns whether the 4x4 block at `(cand_r, cand_c)` is inside the
+    /// tile.
+    #[inline]
+    pub fn is_inside(&self, cand_r: i64, cand_c: i64) -> bool {
+        cand_c >= self.mi_col_start as i64
+            && cand_c < self.mi_col_end as i64
+            && cand_r >= self.mi_row_start as i64
+            && cand_r < self.mi_row_end as i64
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#clear-above-context-process
+    fn clear_above_context(&mut self) {
+        for plane in 0..3 {
+            self.above_level_context[plane].fill(0);
+            self.above_dc_context[plane].fill(0);
+        }
+
+        self.above_seg_pred_context.fill(0);
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#clear-left-context-process
+    fn clear_left_context(&mut self) {
+        for plane in 0..3 {
+            self.left_level_context[plane].fill(0);
+            self.left_dc_context[plane].fill(0);
+        }
+
+        self.left_seg_pred_context.fill(0);
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#clear-block-decoded-flags-function
+    fn clear_block_decoded_flags(&mut self, r: u32, c: u32, sb_size4: u32) {
+        for plane in 0..self.ctx.num_planes as usize {
+            let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+            let sb_width4 = ((self.mi_col_end - c) >> sub_x) as i32;
+            let sb_height4 = ((self.mi_row_end - r) >> sub_y) as i32;
+            for y in -1..=(sb_size4 >> sub_y) as i32 {
+                for x in -1..=(sb_size4 >> sub_x) as i32 {
+                    *self.block_decoded_mut(plane, y, x) =
+                        (y < 0 && x < sb_width4) || (x < 0 && y < sb_height4);
+                }
+            }
+
+            *self.block_decoded_mut(plane, (sb_size4 >> sub_y) as i32, -1) = false;
+        }
+    }
+
+    /// Whether the 4x4 block at `(row, col)` of the plane `plane` relative to
+    /// the superblock being decoded has been decoded.
+    #[inline]
+    pub fn block_decoded(&self, plane: usize, row: i32, col: i32) -> bool {
+        self.block_decoded[plane][(row + 1) as usize][(col + 1) as usize]
+    }
+
+    #[inline]
+    pub fn block_decoded_mut(&mut self, plane: usize, row: i32, col: i32) -> &mut bool {
+        &mut self.block_decoded[plane][(row + 1) as usize][(col + 1) as usize]
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#clear-cdef-process
+    fn clear_cdef(&mut self, r: u32, c: u32) {
+        *self.frame.cdef_idx_mut(r, c) = -1;
+        if self.sequence_header.use_128x128_superblock {
+            let cdef_size4 = NUM_4X4_BLOCKS_WIDE[BLOCK_64X64 as usize] as u32;
+            for (y, x) in [(0, cdef_size4), (cdef_size4, 0), (cdef_size4, cdef_size4)] {
+                if r + y < self.frame.mi_rows && c + x < self.frame.mi_cols {
+                    *self.frame.cdef_idx_mut(r + y, c + x) = -1;
+                }
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#read-loop-restoration-syntax
+    fn read_lr(&mut self, r: u32, c: u32, bsize: u8) {
+        if self.header.allow_intrabc {
+            return;
+        }
+
+        let w = NUM_4X4_BLOCKS_WIDE[bsize as usize] as u32;
+        let h = NUM_4X4_BLOCKS_HIGH[bsize as usize] as u32;
+        for plane in 0..self.ctx.num_planes as usize {
+            if self.header.lr_params.frame_restoration_type[plane] == RestorationType::None {
+                continue;
+            }
+
+            let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+            let unit_size = self.header.lr_params.loop_restoration_size[plane] as u32;
+            let unit_rows = self.frame.lr_units[plane].unit_rows;
+            let unit_cols = self.frame.lr_units[plane].unit_cols;
+            let unit_row_start = (r * (MI_SIZE as u32 >> sub_y)).div_ceil(unit_size);
+            let unit_row_end =
+                unit_rows.min(((r + h) * (MI_SIZE as u32 >> sub_y)).div_ceil(unit_size));
+
+            let (numerator, denominator) = if self.ctx.superres_denom != SUPERRES_NUM {
+                (
+                    (MI_SIZE as u32 >> sub_x) * self.ctx.superres_denom as u32,
+                    unit_size * SUPERRES_NUM as u32,
+                )
+            } else {
+                (MI_SIZE as u32 >> sub_x, unit_size)
+            };
+
+            let unit_col_start = (c * numerator).div_ceil(denominator);
+            let unit_col_end = unit_cols.min(((c + w) * numerator).div_ceil(denominator));
+            for unit_row in unit_row_start..unit_row_end {
+                for unit_col in unit_col_start..unit_col_end {
+                    self.read_lr_unit(plane, unit_row, unit_col);
+                }
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#read-loop-restoration-unit-syntax
+    fn read_lr_unit(&mut self, plane: usize, unit_row: u32, unit_col: u32) {
+        let restoration_type = match self.header.lr_params.frame_restoration_type[plane] {
+            RestorationType::Wiener => {
+                // use_wiener	S()
+                if self.sd.read_symbol(&mut self.cdf.use_wiener_cdf) == 1 {
+                    RestorationType::Wiener
+                } else {
+                    RestorationType::None
+                }
+            }
+            RestorationType::Sgrproj => {
+                // use_sgrproj	S()
+                if self.sd.read_symbol(&mut self.cdf.use_sgrproj_cdf) == 1 {
+                    RestorationType::Sgrproj
+                } else {
+                    RestorationType::None
+                }
+            }
+            _ => {
+                // restoration_type	S()
+                match self.sd.read_symbol(&mut self.cdf.restoration_type_cdf) {
+                    0 => RestorationType::None,
+                    1 => RestorationType::Wiener,
+                    _ => RestorationType::Sgrproj,
+                }
+            }
+        };
+
+        let unit = self.frame.lr_units[plane].get_mut(unit_row, unit_col);
+        unit.lr_type = restoration_type;
+        if restoration_type == RestorationType::Wiener {
+            for pass in 0..2 {
+                let first_coeff = if plane > 0 {
+                    unit.lr_wiener[pass][0] = 0;
+                    1
+                } else {
+                    0
+                };
+
+                for j in first_coeff..3 {
+                    let v = decode_signed_subexp_with_ref_bool(
+                        &mut self.sd,
+                        WIENER_TAPS_MIN[j],
+                        WIENER_TAPS_MAX[j] + 1,
+                        WIENER_TAPS_K[j],
+                        self.ref_lr_wiener[plane][pass][j],
+                    );
+
+                    unit.lr_wiener[pass][j] = v;
+                    self.ref_lr_wiener[plane][pass][j] = v;
+                }
+            }
+        } else if restoration_type == RestorationType::Sgrproj {
+            // lr_sgr_set	L(SGRPROJ_PARAMS_BITS)
+            let lr_sgr_set = self.sd.read_literal(SGRPROJ_PARAMS_BITS as usize) as usize;
+            unit.lr_sgr_set = lr_sgr_set as u8;
+            for i in 0..2 {
+                let radius = SGR_PARAMS[lr_sgr_set][i * 2];
+                let min = SGRPROJ_XQD_MIN[i];
+                let max = SGRPROJ_XQD_MAX[i];
+                let v = if radius != 0 {
+                    decode_signed_subexp_with_ref_bool(
+                        &mut self.sd,
+                        min,
+                        max + 1,
+                        SGRPROJ_PRJ_SUBEXP_K as u32,
+                        self.ref_sgr_xqd[plane][i],
+                    )
+                } else if i == 1 {
+                    ((1 << SGRPROJ_PRJ_BITS) - self.ref_sgr_xqd[plane][0]).clamp(min, max)
+                } else {
+                    0
+                };
+
+                unit.lr_sgr_xqd[i] = v;
+                self.ref_sgr_xqd[plane][i] = v;
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#decode-partition-syntax
+    fn decode_partition(&mut self, r: u32, c: u32, bsize: u8) -> Result<(), ObuError> {
+        if r >= self.frame.mi_rows || c >= self.frame.mi_cols {
+            return Ok(());
+        }
+
+        let avail_u = self.is_inside(r as i64 - 1, c as i64);
+        let avail_l = self.is_inside(r as i64, c as i64 - 1);
+        let num4x4 = NUM_4X4_BLOCKS_WIDE[bsize as usize] as u32;
+        let half_block4x4 = num4x4 >> 1;
+        let quarter_block4x4 = half_block4x4 >> 1;
+        let has_rows = (r + half_block4x4) < self.frame.mi_rows;
+        let has_cols = (c + half_block4x4) < self.frame.mi_cols;
+
+        let partition = if bsize < BLOCK_8X8 {
+            PARTITION_NONE
+        } else if has_rows && has_cols {
+            // partition	S()
+            let ctx = self.partition_ctx(r, c, bsize, avail_u, avail_l);
+            self.sd
+                .read_symbol(partition_cdf(&mut self.cdf, bsize, ctx)) as u8
+        } else if has_cols {
+            // split_or_horz	S()
+            let ctx = self.partition_ctx(r, c, bsize, avail_u, avail_l);
+            let mut psum = 0;
+            {
+                let cdf = partition_cdf(&mut self.cdf, bsize, ctx);
+                for partition in [
+                    PARTITION_VERT,
+                    PARTITION_SPLIT,
+                    PARTITION_HORZ_A,
+                    PARTITION_VERT_A,
+                    PARTITION_VERT_B,
+                    PARTITION_VERT_4,
+                ] {
+                    if partition != PARTITION_VERT_4 || bsize != BLOCK_128X128 {
+                        psum += cdf_element_prob(cdf, partition);
+                    }
+                }
+            }
+
+            if self.read_bool_with_prob(psum) {
+                PARTITION_SPLIT
+            } else {
+                PARTITION_HORZ
+            }
+        } else if has_rows {
+            // split_or_vert	S()
+            let ctx = self.partition_ctx(r, c, bsize, avail_u, avail_l);
+            let mut psum = 0;
+            {
+                let cdf = partition_cdf(&mut self.cdf, bsize, ctx);
+                for partition in [
+                    PARTITION_HORZ,
+                    PARTITION_SPLIT,
+                    PARTITION_HORZ_A,
+                    PARTITION_HORZ_B,
+                    PARTITION_VERT_A,
+                    PARTITION_HORZ_4,
+                ] {
+                    if partition != PARTITION_HORZ_4 || bsize != BLOCK_128X128 {
+                        psum += cdf_element_prob(cdf, partition);
+                    }
+                }
+            }
+
+            if self.read_bool_with_prob(psum) {
+                PARTITION_SPLIT
+            } else {
+                PARTITION_VERT
+            }
+        } else {
+            PARTITION_SPLIT
+        };
+
+        let sub_size = PARTITION_SUBSIZE[partition as usize][bsize as usize];
+        let split_size = PARTITION_SUBSIZE[PARTITION_SPLIT as usize][bsize as usize];
+        match partition {
+            PARTITION_NONE => {
+                self.decode_block(r, c, sub_size)?;
+            }
+            PARTITION_HORZ => {
+                self.decode_block(r, c, sub_size)?;
+                if has_rows {
+                    self.decode_block(r + half_block4x4, c, sub_size)?;
+                }
+            }
+            PARTITION_VERT => {
+                self.decode_block(r, c, sub_size)?;
+                if has_cols {
+                    self.decode_block(r, c + half_block4x4, sub_size)?;
+                }
+            }
+            PARTITION_SPLIT => {
+                self.decode_partition(r, c, sub_size)?;
+                self.decode_partition(r, c + half_block4x4, sub_size)?;
+                self.decode_partition(r + half_block4x4, c, sub_size)?;
+                self.decode_partition(r + half_block4x4, c + half_block4x4, sub_size)?;
+            }
+            PARTITION_HORZ_A => {
+                self.decode_block(r, c, split_size)?;
+                self.decode_block(r, c + half_block4x4, split_size)?;
+                self.decode_block(r + half_block4x4, c, sub_size)?;
+            }
+            PARTITION_HORZ_B => {
+                self.decode_block(r, c, sub_size)?;
+                self.decode_block(r + half_block4x4, c, split_size)?;
+                self.decode_block(r + half_block4x4, c + half_block4x4, split_size)?;
+            }
+            PARTITION_VERT_A => {
+                self.decode_block(r, c, split_size)?;
+                self.decode_block(r + half_block4x4, c, split_size)?;
+                self.decode_block(r, c + half_block4x4, sub_size)?;
+            }
+            PARTITION_VERT_B => {
+                self.decode_block(r, c, sub_size)?;
+                self.decode_block(r, c + half_block4x4, split_size)?;
+                self.decode_block(r + half_block4x4, c + half_block4x4, split_size)?;
+            }
+            PARTITION_HORZ_4 => {
+                for i in 0..4 {
+                    let row = r + quarter_block4x4 * i;
+                    if i < 3 || row < self.frame.mi_rows {
+                        self.decode_block(row, c, sub_size)?;
+                    }
+                }
+            }
+            _ => {
+                for i in 0..4 {
+                    let col = c + quarter_block4x4 * i;
+                    if i < 3 || col < self.frame.mi_cols {
+                        self.decode_block(r, col, sub_size)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn partition_ctx(&self, r: u32, c: u32, bsize: u8, avail_u: bool, avail_l: bool) -> usize {
+        let bsl = MI_WIDTH_LOG2[bsize as usize];
+        let above = avail_u && MI_WIDTH_LOG2[self.frame.mi(r - 1, c).mi_size as usize] < bsl;
+        let left = avail_l && MI_HEIGHT_LOG2[self.frame.mi(r, c - 1).mi_size as usize] < bsl;
+        left as usize * 2 + above as usize
+    }
+
+    /// Reads a bool that is equal to 1 with the probability `prob` (out of
+    /// 32768), the cdf is not adapted.
+    fn read_bool_with_prob(&mut self, prob: u32) -> bool {
+        let mut cdf = [((1 << 15) - prob) as u16, 1 << 15, 0];
+        self.sd.read_symbol(&mut cdf) == 1
+    }
+}
+
+/// The cdf of the partition syntax element for the block size `bsize`.
+fn partition_cdf(cdf: &mut CdfContext, bsize: u8, ctx: usize) -> &mut [u16] {
+    match MI_WIDTH_LOG2[bsize as usize] {
+        1 => &mut cdf.partition_w8_cdf[ctx],
+        2 => &mut cdf.partition_w16_cdf[ctx],
+        3 => &mut cdf.partition_w32_cdf[ctx],
+        4 => &mut cdf.partition_w64_cdf[ctx],
+        _ => &mut cdf.partition_w128_cdf[ctx],
+    }
+}
+
+/// The probability of `symbol` in `cdf`, out of 32768.
+#[inline]
+fn cdf_element_prob(cdf: &[u16], symbol: u8) -> u32 {
+    let symbol = symbol as usize;
+    let prev = if symbol > 0 { cdf[symbol - 1] } else { 0 };
+    (cdf[symbol] - prev) as u32
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#decode-subexp-bool-syntax
+fn decode_subexp_bool(sd: &mut SymbolDecoder, num_syms: u32, k: u32) -> u32 {
+    let mut i = 0;
+    let mut mk = 0;
+    loop {
+        let b2 = if i > 0 { k + i - 1 } else { k };
+        let a = 1 << b2;
+        if num_syms <= mk + 3 * a {
+            // subexp_unif_bools	NS(numSyms - mk)
+            return read_ns(sd, num_syms - mk) + mk;
+        }
+
+        // subexp_more_bools	L(1)
+        if sd.read_literal(1) == 1 {
+            i += 1;
+            mk += a;
+        } else {
+            // subexp_bools	L(b2)
+            return sd.read_literal(b2 as usize) + mk;
+        }
+    }
+}
+
+/// Reads a non symmetric unsigned value smaller than `n` with literal bools.
+fn read_ns(sd: &mut SymbolDecoder, n: u32) -> u32 {
+    let w = floor_log2(n) + 1;
+    let m = (1 << w) - n;
+    let v = sd.read_literal(w as usize - 1);
+    if v < m {
+        return v;
+    }
+
+    let extra_bit = sd.read_literal(1);
+    (v << 1) - m + extra_bit
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#decode-unsigned-subexp-with-reference-bool-syntax
+fn decode_unsigned_subexp_with_ref_bool(sd: &mut SymbolDecoder, mx: u32, k: u32, r: u32) -> u32 {
+    let v = decode_subexp_bool(sd, mx, k);
+    if (r << 1) <= mx {
+        inverse_recenter(r, v)
+    } else {
+        mx - 1 - inverse_recenter(mx - 1 - r, v)
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#decode-signed-subexp-with-reference-bool-syntax
+fn decode_signed_subexp_with_ref_bool(
+    sd: &mut SymbolDecoder,
+    low: i32,
+    high: i32,
+    k: u32,
+    r: i32,
+) -> i32 {
+    decode_unsigned_subexp_with_ref_bool(sd, (high - low) as u32, k, (r - low) as u32) as i32 + low
+}
+
+/// Helpers shared by the tests of the tile decoding processes.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    use crate::{buffer::Buffer, obu::frame_header::TileInfo};
+
+    /// The payload of the sequence header of a 128x16 8 bits 4:2:0 stream.
+    const SEQUENCE_HEADER: [u8; 6] = [24, 24, 255, 251, 32, 16];
+
+    /// Returns a context decoding `header`, with a single tile of `mi_rows`
+    /// by `mi_cols` 4x4 blocks.
+    pub(crate) fn context(header: UncompressedHeader, mi_rows: u32, mi_cols: u32) -> ObuContext {
+        let mut ctx = ObuContext::default();
+        let sequence_header =
+            SequenceHeader::decode(&mut ctx, &mut Buffer::new(&SEQUENCE_HEADER)).unwrap();
+        ctx.sequence_header = Some(sequence_header);
+        ctx.frame_header = Some(UncompressedHeader {
+            tile_info: TileInfo {
+                tile_cols: 1,
+                tile_rows: 1,
+                mi_col_starts: vec![0, mi_cols],
+                mi_row_starts: vec![0, mi_rows],
+                ..Default::default()
+            },
+            ..header
+        });
+        ctx
+    }
+
+    /// Returns a decoder of the tile of `ctx`, without any coded symbol.
+    pub(crate) fn decoder<'b>(
+        ctx: &'b ObuContext,
+        frame: &'b mut FrameState,
+    ) -> TileDecoder<'static, 'b> {
+        let tile = Tile {
+            tile_num: 0,
+            tile_row: 0,
+            tile_col: 0,
+            data: &[0; 4],
+        };
+
+        TileDecoder::new(ctx, frame, &tile).unwrap()
+    }
+}
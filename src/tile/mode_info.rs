@@ -0,0 +1,172 @@
+use crate::{
+    constants::{FRAME_LF_COUNT, LAST_FRAME, NONE, PALETTE_COLORS, REFS_PER_FRAME},
+    obu::frame_header::{InterpolationFilter, RestorationType},
+    picture::Picture,
+};
+
+/// The value of a motion field motion vector that has not been projected.
+pub const INVALID_MV: i32 = -1 << 15;
+
+/// A motion vector, the row component followed by the column component, in
+/// units of 1/8 luma sample.
+pub type Mv = [i32; 2];
+
+/// The mode info of a 4x4 luma block, every 4x4 block covered by a block
+/// holds a copy of the mode info of that block.
+#[derive(Debug, Clone, Copy)]
+pub struct ModeInfo {
+    pub mi_size: u8,
+    pub y_mode: u8,
+    pub uv_mode: u8,
+    pub ref_frame: [i8; 2],
+    pub mv: [Mv; 2],
+    pub segment_id: u8,
+    pub tx_size: u8,
+    pub inter_tx_size: u8,
+    pub is_inter: bool,
+    pub skip: bool,
+    pub skip_mode: bool,
+    pub palette_size: [u8; 2],
+    /// The palette colors of the Y and U planes, the colors of the V plane
+    /// are never used by the palette cache.
+    pub palette_colors: [[u16; PALETTE_COLORS as usize]; 2],
+    pub delta_lf: [i8; FRAME_LF_COUNT as usize],
+    pub interp_filter: [InterpolationFilter; 2],
+    pub comp_group_idx: u8,
+    pub compound_idx: u8,
+}
+
+impl Default for ModeInfo {
+    fn default() -> Self {
+        Self {
+            mi_size: 0,
+            y_mode: 0,
+            uv_mode: 0,
+            ref_frame: [NONE, NONE],
+            mv: [[0; 2]; 2],
+            segment_id: 0,
+            tx_size: 0,
+            inter_tx_size: 0,
+            is_inter: false,
+            skip: false,
+            skip_mode: false,
+            palette_size: [0; 2],
+            palette_colors: [[0; PALETTE_COLORS as usize]; 2],
+            delta_lf: [0; FRAME_LF_COUNT as usize],
+            interp_filter: [InterpolationFilter::Eighttap; 2],
+            comp_group_idx: 0,
+            compound_idx: 0,
+        }
+    }
+}
+
+/// The loop restoration parameters of a restoration unit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RestorationUnit {
+    pub lr_type: RestorationType,
+    pub lr_wiener: [[i32; 3]; 2],
+    pub lr_sgr_set: u8,
+    pub lr_sgr_xqd: [i32; 2],
+}
+
+/// The restoration units of a plane.
+#[derive(Debug, Clone, Default)]
+pub struct RestorationUnits {
+    pub unit_rows: u32,
+    pub unit_cols: u32,
+    pub units: Vec<RestorationUnit>,
+}
+
+impl RestorationUnits {
+    pub fn new(unit_rows: u32, unit_cols: u32) -> Self {
+        Self {
+            unit_rows,
+            unit_cols,
+            units: vec![RestorationUnit::default(); (unit_rows * unit_cols) as usize],
+        }
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, row: u32, col: u32) -> &mut RestorationUnit {
+        &mut self.units[(row * self.unit_cols + col) as usize]
+    }
+}
+
+/// The state of the frame being decoded that is shared by all its tiles.
+#[derive(Clone, Default)]
+pub struct FrameState {
+    pub mi_rows: u32,
+    pub mi_cols: u32,
+    pub mode_info: Vec<ModeInfo>,
+    /// The cdef_idx of each 64x64 block, -1 when it has not been read.
+    pub cdef_idx: Vec<i8>,
+    pub lr_units: [RestorationUnits; 3],
+    /// The segment ids of the previous frame used for prediction.
+    pub prev_segment_ids: Vec<u8>,
+    /// The motion vectors projected from the reference frames for each 8x8
+    /// block, indexed by the reference frame minus LAST_FRAME.
+    pub motion_field_mvs: [Vec<Mv>; REFS_PER_FRAME as usize],
+    /// The samples of the frame (CurrFrame) before the in-loop filters, the
+    /// planes are padded to a multiple of 64 luma samples to hold the
+    /// transform blocks that cross the edge of the frame.
+    pub cur_frame: Picture,
+}
+
+impl std::fmt::Debug for FrameState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrameState")
+            .field("mi_rows", &self.mi_rows)
+            .field("mi_cols", &self.mi_cols)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FrameState {
+    pub fn new(mi_rows: u32, mi_cols: u32) -> Self {
+        let cdef_size = (mi_rows.div_ceil(16) * mi_cols.div_ceil(16)) as usize;
+        Self {
+            mi_rows,
+            mi_cols,
+            mode_info: vec![ModeInfo::default(); (mi_rows * mi_cols) as usize],
+            cdef_idx: vec![-1; cdef_size],
+            lr_units: Default::default(),
+            prev_segment_ids: vec![0; (mi_rows * mi_cols) as usize],
+            motion_field_mvs: std::array::from_fn(|_| {
+                vec![[INVALID_MV; 2]; ((mi_rows >> 1) * (mi_cols >> 1)) as usize]
+            }),
+            cur_frame: Picture::default(),
+        }
+    }
+
+    #[inline]
+    pub fn mi(&self, row: u32, col: u32) -> &ModeInfo {
+        &self.mode_info[(row * self.mi_cols + col) as usize]
+    }
+
+    #[inline]
+    pub fn mi_mut(&mut self, row: u32, col: u32) -> &mut ModeInfo {
+        &mut self.mode_info[(row * self.mi_cols + col) as usize]
+    }
+
+    /// The cdef_idx of the 64x64 block containing the 4x4 block at
+    /// `(row, col)`.
+    #[inline]
+    pub fn cdef_idx_mut(&mut self, row: u32, col: u32) -> &mut i8 {
+        let stride = self.mi_cols.div_ceil(16);
+        &mut self.cdef_idx[((row >> 4) * stride + (col >> 4)) as usize]
+    }
+
+    /// The motion field motion vector of the 8x8 block at `(row, col)`, in
+    /// units of 8x8 blocks, for the reference frame `ref_frame`.
+    #[inline]
+    pub fn motion_field_mv(&self, ref_frame: i8, row: u32, col: u32) -> Mv {
+        let stride = self.mi_cols >> 1;
+        self.motion_field_mvs[(ref_frame - LAST_FRAME) as usize][(row * stride + col) as usize]
+    }
+
+    /// The segment id of every 4x4 block, saved for the prediction of the
+    /// segment ids of later frames.
+    pub fn segment_ids(&self) -> Vec<u8> {
+        self.mode_info.iter().map(|mi| mi.segment_id).collect()
+    }
+}
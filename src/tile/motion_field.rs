@@ -0,0 +1,197 @@
+//! The motion field estimation, projects the motion vectors saved with the
+//! reference frames onto the current frame, and the saving of the motion
+//! vectors of the current frame for later frames.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#motion-field-estimation-process
+
+use super::mode_info::{FrameState, Mv, INVALID_MV};
+
+use crate::{
+    constants::*,
+    obu::{frame_header::FrameType, order_hint::get_relative_dist, ObuContext},
+    tables::DIV_MULT,
+};
+
+#[inline]
+fn round2_signed(x: i64, n: u32) -> i64 {
+    if x >= 0 {
+        (x + (1 << (n - 1))) >> n
+    } else {
+        -((-x + (1 << (n - 1))) >> n)
+    }
+}
+
+/// Scales the motion vector `mv` pointing `denominator` frames away so that
+/// it points `numerator` frames away.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#get-mv-projection-process
+pub fn get_mv_projection(mv: Mv, numerator: i32, denominator: i32) -> Mv {
+    let max_frame_distance = MAX_FRAME_DISTANCE as i32;
+    let clipped_denominator = denominator.min(max_frame_distance);
+    let clipped_numerator = numerator.clamp(-max_frame_distance, max_frame_distance);
+    mv.map(|v| {
+        let scaled = round2_signed(
+            v as i64 * clipped_numerator as i64 * DIV_MULT[clipped_denominator as usize] as i64,
+            14,
+        );
+
+        scaled.clamp(-(1 << 14) + 1, (1 << 14) - 1) as i32
+    })
+}
+
+/// Moves the position `v8`, in units of 8x8 blocks, by the projected motion
+/// vector component `delta`, returns None when the projected position is
+/// too far away or outside of the frame.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#project-function
+fn project(v8: u32, delta: i32, dst_sign: i32, max8: u32, max_off8: u32) -> Option<u32> {
+    let base8 = ((v8 >> 3) << 3) as i32;
+    let offset8 = if delta >= 0 {
+        delta >> (3 + 1 + MI_SIZE_LOG2)
+    } else {
+        -((-delta) >> (3 + 1 + MI_SIZE_LOG2))
+    };
+
+    let v8 = v8 as i32 + dst_sign * offset8;
+    if v8 < 0
+        || v8 >= max8 as i32
+        || v8 < base8 - max_off8 as i32
+        || v8 >= base8 + 8 + max_off8 as i32
+    {
+        None
+    } else {
+        Some(v8 as u32)
+    }
+}
+
+/// Fills `MotionFieldMvs` of the current frame from the motion vectors saved
+/// with the LAST_FRAME, BWDREF_FRAME, ALTREF2_FRAME, ALTREF_FRAME and
+/// LAST2_FRAME references, at most MFMV_STACK_SIZE of them are projected.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#motion-field-estimation-process
+pub fn motion_field_estimation(
+    ctx: &mut ObuContext,
+    ref_frame_idx: &[u8; REFS_PER_FRAME as usize],
+) {
+    let mut frame = std::mem::take(&mut ctx.frame_state);
+    for mvs in frame.motion_field_mvs.iter_mut() {
+        mvs.fill([INVALID_MV; 2]);
+    }
+
+    let last_idx = ref_frame_idx[0] as usize;
+    let cur_gold_hint = ctx.order_hints[GOLDEN_FRAME as usize];
+    let last_alt_hint = ctx.saved_order_hints[last_idx][ALTREF_FRAME as usize];
+    if last_alt_hint != cur_gold_hint {
+        projection(ctx, &mut frame, ref_frame_idx, LAST_FRAME, -1);
+    }
+
+    let mut ref_stamp = MFMV_STACK_SIZE as i32 - 2;
+    for src in [BWDREF_FRAME, ALTREF2_FRAME, ALTREF_FRAME] {
+        if get_relative_dist(ctx, ctx.order_hints[src as usize], ctx.order_hint) > 0
+            && (src != ALTREF_FRAME || ref_stamp >= 0)
+            && projection(ctx, &mut frame, ref_frame_idx, src, 1)
+        {
+            ref_stamp -= 1;
+        }
+    }
+
+    if ref_stamp >= 0 {
+        projection(ctx, &mut frame, ref_frame_idx, LAST2_FRAME, -1);
+    }
+
+    ctx.frame_state = frame;
+}
+
+/// Projects the motion vectors saved with the reference frame `src` onto
+/// the current frame, returns false when the reference frame can not be
+/// used.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#projection-process
+fn projection(
+    ctx: &ObuContext,
+    frame: &mut FrameState,
+    ref_frame_idx: &[u8; REFS_PER_FRAME as usize],
+    src: i8,
+    dst_sign: i32,
+) -> bool {
+    let src_idx = ref_frame_idx[(src - LAST_FRAME) as usize] as usize;
+    if ctx.ref_mi_rows[src_idx] != frame.mi_rows
+        || ctx.ref_mi_cols[src_idx] != frame.mi_cols
+        || ctx.ref_frame_type[src_idx] == FrameType::InterOnlyFrame
+    {
+        return false;
+    }
+
+    let max_frame_distance = MAX_FRAME_DISTANCE as i32;
+    let (w8, h8) = (frame.mi_cols >> 1, frame.mi_rows >> 1);
+    let saved_mvs = &ctx.saved_mvs[src_idx];
+    let saved_ref_frames = &ctx.saved_ref_frames[src_idx];
+    for row8 in 0..h8 {
+        for col8 in 0..w8 {
+            let idx = ((2 * row8 + 1) * frame.mi_cols + 2 * col8 + 1) as usize;
+            let mv = saved_mvs[idx];
+            let src_ref = saved_ref_frames[idx];
+            if src_ref <= INTRA_FRAME {
+                continue;
+            }
+
+            let ref_to_cur = get_relative_dist(ctx, ctx.order_hints[src as usize], ctx.order_hint);
+            let ref_offset = get_relative_dist(
+                ctx,
+                ctx.order_hints[src as usize],
+                ctx.saved_order_hints[src_idx][src_ref as usize],
+            );
+
+            if ref_to_cur.abs() > max_frame_distance
+                || ref_offset.abs() > max_frame_distance
+                || ref_offset <= 0
+            {
+                continue;
+            }
+
+            let proj_mv = get_mv_projection(mv, ref_to_cur * dst_sign, ref_offset);
+            let pos_y8 = project(row8, proj_mv[0], dst_sign, h8, MAX_OFFSET_HEIGHT as u32);
+            let pos_x8 = project(col8, proj_mv[1], dst_sign, w8, MAX_OFFSET_WIDTH as u32);
+            if let (Some(pos_y8), Some(pos_x8)) = (pos_y8, pos_x8) {
+                for dst in LAST_FRAME..=ALTREF_FRAME {
+                    let ref_to_dst =
+                        get_relative_dist(ctx, ctx.order_hint, ctx.order_hints[dst as usize]);
+                    frame.motion_field_mvs[(dst - LAST_FRAME) as usize]
+                        [(pos_y8 * w8 + pos_x8) as usize] =
+                        get_mv_projection(mv, ref_to_dst, ref_offset);
+                }
+            }
+        }
+    }
+
+    true
+}
+
+/// Returns the motion vectors of the current frame that can be used by the
+/// motion field estimation of later frames and their reference frames, NONE
+/// for the 4x4 blocks without such a motion vector.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#motion-field-motion-vector-storage-process
+pub fn motion_field_motion_vector_storage(ctx: &ObuContext) -> (Vec<Mv>, Vec<i8>) {
+    let frame = &ctx.frame_state;
+    let mut mf_mvs = vec![[0; 2]; frame.mode_info.len()];
+    let mut mf_ref_frames = vec![NONE; frame.mode_info.len()];
+    for (i, mi) in frame.mode_info.iter().enumerate() {
+        for list in 0..2 {
+            let r = mi.ref_frame[list];
+            if r > INTRA_FRAME {
+                let dist = get_relative_dist(ctx, ctx.order_hints[r as usize], ctx.order_hint);
+                let mv = mi.mv[list];
+                if dist < 0
+                    && mv[0].unsigned_abs() <= REFMVS_LIMIT
+                    && mv[1].unsigned_abs() <= REFMVS_LIMIT
+                {
+                    mf_ref_frames[i] = r;
+                    mf_mvs[i] = mv;
+                }
+            }
+        }
+    }
+
+    (mf_mvs, mf_ref_frames)
+}
@@ -0,0 +1,962 @@
+//! The motion vector prediction processes.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#motion-vector-prediction-processes
+
+use super::{mode_info::Mv, mode_info::INVALID_MV, TileDecoder};
+
+use crate::{constants::*, obu::frame_header::GmType, tables::*};
+
+/// The candidate motion vectors of the current block, built by
+/// find_mv_stack.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MvStack {
+    pub num_mv_found: usize,
+    pub new_mv_count: usize,
+    pub ref_stack_mv: [[Mv; 2]; MAX_REF_MV_STACK_SIZE as usize],
+    pub weight_stack: [u32; MAX_REF_MV_STACK_SIZE as usize],
+    pub global_mvs: [Mv; 2],
+    pub drl_ctx_stack: [u8; MAX_REF_MV_STACK_SIZE as usize],
+    pub new_mv_context: u8,
+    pub ref_mv_context: u8,
+    pub zero_mv_context: u8,
+    found_match: bool,
+    close_matches: u8,
+    total_matches: u8,
+    ref_id_count: [usize; 2],
+    ref_diff_count: [usize; 2],
+    ref_id_mvs: [[Mv; 2]; 2],
+    ref_diff_mvs: [[Mv; 2]; 2],
+}
+
+/// The samples used to estimate the local warp model of the current block.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WarpSamples {
+    pub num_samples: usize,
+    pub num_samples_scanned: usize,
+    pub cand_list: [[i32; 4]; LEAST_SQUARES_SAMPLES_MAX as usize],
+}
+
+/// Returns whether `mode` codes a new motion vector for one of its
+/// references.
+#[inline]
+pub fn has_newmv(mode: u8) -> bool {
+    matches!(
+        mode,
+        NEWMV | NEW_NEWMV | NEAR_NEWMV | NEW_NEARMV | NEAREST_NEWMV | NEW_NEARESTMV
+    )
+}
+
+#[inline]
+fn round2_signed(x: i64, n: u32) -> i64 {
+    if x >= 0 {
+        (x + (1 << (n - 1))) >> n
+    } else {
+        -((-x + (1 << (n - 1))) >> n)
+    }
+}
+
+impl TileDecoder<'_, '_> {
+    /// see: https://aomediacodec.github.io/av1-spec/#find-mv-stack-process
+    pub(crate) fn find_mv_stack(&mut self, is_compound: bool) {
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as i64;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as i64;
+
+        self.mv_stack = MvStack::default();
+        self.mv_stack.global_mvs[0] = self.setup_global_mv(0);
+        if is_compound {
+            self.mv_stack.global_mvs[1] = self.setup_global_mv(1);
+        }
+
+        self.mv_stack.found_match = false;
+        self.scan_row(-1, is_compound);
+        let mut found_above_match = self.mv_stack.found_match;
+        self.mv_stack.found_match = false;
+        self.scan_col(-1, is_compound);
+        let mut found_left_match = self.mv_stack.found_match;
+        self.mv_stack.found_match = false;
+        if bw4.max(bh4) <= 16 {
+            self.scan_point(-1, bw4, is_compound);
+        }
+
+        if self.mv_stack.found_match {
+            found_above_match = true;
+        }
+
+        self.mv_stack.close_matches = found_above_match as u8 + found_left_match as u8;
+        let num_nearest = self.mv_stack.num_mv_found;
+        let num_new = self.mv_stack.new_mv_count;
+        for weight in self.mv_stack.weight_stack.iter_mut().take(num_nearest) {
+            *weight += REF_CAT_LEVEL as u32;
+        }
+
+        self.mv_stack.zero_mv_context = 0;
+        if self.header.use_ref_frame_mvs {
+            self.temporal_scan(is_compound);
+        }
+
+        self.scan_point(-1, -1, is_compound);
+        if self.mv_stack.found_match {
+            found_above_match = true;
+        }
+
+        self.mv_stack.found_match = false;
+        self.scan_row(-3, is_compound);
+        if self.mv_stack.found_match {
+            found_above_match = true;
+        }
+
+        self.mv_stack.found_match = false;
+        self.scan_col(-3, is_compound);
+        if self.mv_stack.found_match {
+            found_left_match = true;
+        }
+
+        self.mv_stack.found_match = false;
+        if bh4 > 1 {
+            self.scan_row(-5, is_compound);
+        }
+
+        if self.mv_stack.found_match {
+            found_above_match = true;
+        }
+
+        self.mv_stack.found_match = false;
+        if bw4 > 1 {
+            self.scan_col(-5, is_compound);
+        }
+
+        if self.mv_stack.found_match {
+            found_left_match = true;
+        }
+
+        self.mv_stack.total_matches = found_above_match as u8 + found_left_match as u8;
+        self.sorting(0, num_nearest);
+        self.sorting(num_nearest, self.mv_stack.num_mv_found);
+        if self.mv_stack.num_mv_found < 2 {
+            self.extra_search(is_compound);
+        }
+
+        self.context_and_clamping(is_compound, num_new);
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#setup-global-mv-process
+    fn setup_global_mv(&self, ref_list: usize) -> Mv {
+        let ref_frame = self.b.ref_frame[ref_list];
+        let gm_params = &self.header.global_motion_params;
+        let gm_type = if ref_frame != INTRA_FRAME {
+            gm_params.gm_type[ref_frame as usize]
+        } else {
+            GmType::Identity
+        };
+
+        let mut mv = if ref_frame == INTRA_FRAME || gm_type == GmType::Identity {
+            [0, 0]
+        } else if gm_type == GmType::Translation {
+            let params = &gm_params.gm_params[ref_frame as usize];
+            [
+                params[0] >> (WARPEDMODEL_PREC_BITS - 3),
+                params[1] >> (WARPEDMODEL_PREC_BITS - 3),
+            ]
+        } else {
+            let params = &gm_params.gm_params[ref_frame as usize];
+            let x = (self.b.mi_col * MI_SIZE as u32 + block_width(self.b.mi_size) / 2) as i64 - 1;
+            let y = (self.b.mi_row * MI_SIZE as u32 + block_height(self.b.mi_size) / 2) as i64 - 1;
+            let xc = (params[2] as i64 - (1 << WARPEDMODEL_PREC_BITS)) * x
+                + params[3] as i64 * y
+                + params[0] as i64;
+            let yc = params[4] as i64 * x
+                + (params[5] as i64 - (1 << WARPEDMODEL_PREC_BITS)) * y
+                + params[1] as i64;
+
+            if self.header.allow_high_precision_mv {
+                [
+                    round2_signed(yc, WARPEDMODEL_PREC_BITS as u32 - 3) as i32,
+                    round2_signed(xc, WARPEDMODEL_PREC_BITS as u32 - 3) as i32,
+                ]
+            } else {
+                [
+                    round2_signed(yc, WARPEDMODEL_PREC_BITS as u32 - 2) as i32 * 2,
+                    round2_signed(xc, WARPEDMODEL_PREC_BITS as u32 - 2) as i32 * 2,
+                ]
+            }
+        };
+
+        self.lower_mv_precision(&mut mv);
+        mv
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#lower-mv-precision-process
+    pub(crate) fn lower_mv_precision(&self, mv: &mut Mv) {
+        if self.header.allow_high_precision_mv {
+            return;
+        }
+
+        for v in mv.iter_mut() {
+            if self.header.force_integer_mv {
+                let a = v.abs();
+                let a_int = (a + 3) >> 3;
+                *v = if *v > 0 { a_int << 3 } else { -(a_int << 3) };
+            } else if *v & 1 == 1 {
+                *v += if *v > 0 { -1 } else { 1 };
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#scan-row-process
+    fn scan_row(&mut self, mut delta_row: i64, is_compound: bool) {
+        let (mi_row, mi_col) = (self.b.mi_row as i64, self.b.mi_col as i64);
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as i64;
+        let end4 = bw4.min(self.frame.mi_cols as i64 - mi_col).min(16);
+        let mut delta_col = 0;
+        let use_step16 = bw4 >= 16;
+        if delta_row.abs() > 1 {
+            delta_row += mi_row & 1;
+            delta_col = 1 - (mi_col & 1);
+        }
+
+        let mut i = 0;
+        while i < end4 {
+            let mv_row = mi_row + delta_row;
+            let mv_col = mi_col + delta_col + i;
+            if !self.is_inside(mv_row, mv_col) {
+                break;
+            }
+
+            let cand_size = self.frame.mi(mv_row as u32, mv_col as u32).mi_size;
+            let mut len = bw4.min(NUM_4X4_BLOCKS_WIDE[cand_size as usize] as i64);
+            if delta_row.abs() > 1 {
+                len = len.max(2);
+            }
+
+            if use_step16 {
+                len = len.max(4);
+            }
+
+            let weight = len as u32 * 2;
+            self.add_ref_mv_candidate(mv_row as u32, mv_col as u32, is_compound, weight);
+            i += len;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#scan-col-process
+    fn scan_col(&mut self, mut delta_col: i64, is_compound: bool) {
+        let (mi_row, mi_col) = (self.b.mi_row as i64, self.b.mi_col as i64);
+        let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as i64;
+        let end4 = bh4.min(self.frame.mi_rows as i64 - mi_row).min(16);
+        let mut delta_row = 0;
+        let use_step16 = bh4 >= 16;
+        if delta_col.abs() > 1 {
+            delta_row = 1 - (mi_row & 1);
+            delta_col += mi_col & 1;
+        }
+
+        let mut i = 0;
+        while i < end4 {
+            let mv_row = mi_row + delta_row + i;
+            let mv_col = mi_col + delta_col;
+            if !self.is_inside(mv_row, mv_col) {
+                break;
+            }
+
+            let cand_size = self.frame.mi(mv_row as u32, mv_col as u32).mi_size;
+            let mut len = bh4.min(NUM_4X4_BLOCKS_HIGH[cand_size as usize] as i64);
+            if delta_col.abs() > 1 {
+                len = len.max(2);
+            }
+
+            if use_step16 {
+                len = len.max(4);
+            }
+
+            let weight = len as u32 * 2;
+            self.add_ref_mv_candidate(mv_row as u32, mv_col as u32, is_compound, weight);
+            i += len;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#scan-point-process
+    fn scan_point(&mut self, delta_row: i64, delta_col: i64, is_compound: bool) {
+        let mv_row = self.b.mi_row as i64 + delta_row;
+        let mv_col = self.b.mi_col as i64 + delta_col;
+        if self.is_inside(mv_row, mv_col) && self.is_decoded(mv_row as u32, mv_col as u32) {
+            self.add_ref_mv_candidate(mv_row as u32, mv_col as u32, is_compound, 4);
+        }
+    }
+
+    /// Returns whether the mode info of the 4x4 block at `(row, col)` has
+    /// been written for the current frame.
+    #[inline]
+    pub(crate) fn is_decoded(&self, row: u32, col: u32) -> bool {
+        self.frame.mi(row, col).ref_frame[0] != NONE
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#temporal-scan-process
+    fn temporal_scan(&mut self, is_compound: bool) {
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as i64;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as i64;
+        let step_w4 = if bw4 >= 16 { 4 } else { 2 };
+        let step_h4 = if bh4 >= 16 { 4 } else { 2 };
+        for delta_row in (0..bh4.min(16)).step_by(step_h4) {
+            for delta_col in (0..bw4.min(16)).step_by(step_w4) {
+                self.add_tpl_ref_mv(delta_row, delta_col, is_compound);
+            }
+        }
+
+        let allow_extension = bh4 >= NUM_4X4_BLOCKS_HIGH[BLOCK_8X8 as usize] as i64
+            && bh4 < NUM_4X4_BLOCKS_HIGH[BLOCK_64X64 as usize] as i64
+            && bw4 >= NUM_4X4_BLOCKS_WIDE[BLOCK_8X8 as usize] as i64
+            && bw4 < NUM_4X4_BLOCKS_WIDE[BLOCK_64X64 as usize] as i64;
+
+        if allow_extension {
+            for (delta_row, delta_col) in [(bh4, -2), (bh4, bw4), (bh4 - 2, bw4)] {
+                if self.check_sb_border(delta_row, delta_col) {
+                    self.add_tpl_ref_mv(delta_row, delta_col, is_compound);
+                }
+            }
+        }
+    }
+
+    fn check_sb_border(&self, delta_row: i64, delta_col: i64) -> bool {
+        let row = (self.b.mi_row as i64 & 15) + delta_row;
+        let col = (self.b.mi_col as i64 & 15) + delta_col;
+        (0..16).contains(&row) && (0..16).contains(&col)
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#temporal-sample-process
+    fn add_tpl_ref_mv(&mut self, delta_row: i64, delta_col: i64, is_compound: bool) {
+        let mv_row = (self.b.mi_row as i64 + delta_row) | 1;
+        let mv_col = (self.b.mi_col as i64 + delta_col) | 1;
+        if !self.is_inside(mv_row, mv_col) {
+            return;
+        }
+
+        let x8 = (mv_col >> 1) as u32;
+        let y8 = (mv_row >> 1) as u32;
+        let at_origin = delta_row == 0 && delta_col == 0;
+        if at_origin {
+            self.mv_stack.zero_mv_context = 1;
+        }
+
+        let num_refs = 1 + is_compound as usize;
+        let mut cand_mvs = [[0; 2]; 2];
+        for (cand_mv, ref_frame) in cand_mvs.iter_mut().zip(self.b.ref_frame).take(num_refs) {
+            *cand_mv = self.frame.motion_field_mv(ref_frame, y8, x8);
+            if cand_mv[0] == INVALID_MV {
+                return;
+            }
+        }
+
+        for cand_mv in cand_mvs.iter_mut().take(num_refs) {
+            self.lower_mv_precision(cand_mv);
+        }
+
+        let stack = &mut self.mv_stack;
+        if at_origin {
+            let far = (0..num_refs).any(|i| {
+                (cand_mvs[i][0] - stack.global_mvs[i][0]).abs() >= 16
+                    || (cand_mvs[i][1] - stack.global_mvs[i][1]).abs() >= 16
+            });
+
+            stack.zero_mv_context = far as u8;
+        }
+
+        let idx = (0..stack.num_mv_found)
+            .find(|idx| stack.ref_stack_mv[*idx][..num_refs] == cand_mvs[..num_refs])
+            .unwrap_or(stack.num_mv_found);
+
+        if idx < stack.num_mv_found {
+            stack.weight_stack[idx] += 2;
+        } else if stack.num_mv_found < MAX_REF_MV_STACK_SIZE as usize {
+            stack.ref_stack_mv[idx] = cand_mvs;
+            stack.weight_stack[idx] = 2;
+            stack.num_mv_found += 1;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#add-reference-motion-vector-process
+    fn add_ref_mv_candidate(&mut self, mv_row: u32, mv_col: u32, is_compound: bool, weight: u32) {
+        let cand = *self.frame.mi(mv_row, mv_col);
+        if !cand.is_inter {
+            return;
+        }
+
+        if !is_compound {
+            for cand_list in 0..2 {
+                if cand.ref_frame[cand_list] == self.b.ref_frame[0] {
+                    self.search_stack(mv_row, mv_col, cand_list, weight);
+                }
+            }
+        } else if cand.ref_frame == self.b.ref_frame {
+            self.compound_search_stack(mv_row, mv_col, weight);
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#search-stack-process
+    fn search_stack(&mut self, mv_row: u32, mv_col: u32, cand_list: usize, weight: u32) {
+        let cand = *self.frame.mi(mv_row, mv_col);
+        let large = block_width(cand.mi_size).min(block_height(cand.mi_size)) >= 8;
+        let gm_type = self.header.global_motion_params.gm_type[self.b.ref_frame[0] as usize];
+        let mut cand_mv = if (cand.y_mode == GLOBALMV || cand.y_mode == GLOBAL_GLOBALMV)
+            && gm_type > GmType::Translation
+            && large
+        {
+            self.mv_stack.global_mvs[0]
+        } else {
+            cand.mv[cand_list]
+        };
+
+        self.lower_mv_precision(&mut cand_mv);
+
+        let stack = &mut self.mv_stack;
+        if has_newmv(cand.y_mode) {
+            stack.new_mv_count += 1;
+        }
+
+        stack.found_match = true;
+        let idx = (0..stack.num_mv_found)
+            .find(|idx| stack.ref_stack_mv[*idx][0] == cand_mv)
+            .unwrap_or(stack.num_mv_found);
+
+        if idx < stack.num_mv_found {
+            stack.weight_stack[idx] += weight;
+        } else if stack.num_mv_found < MAX_REF_MV_STACK_SIZE as usize {
+            stack.ref_stack_mv[idx][0] = cand_mv;
+            stack.weight_stack[idx] = weight;
+            stack.num_mv_found += 1;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#compound-search-stack-process
+    fn compound_search_stack(&mut self, mv_row: u32, mv_col: u32, weight: u32) {
+        let cand = *self.frame.mi(mv_row, mv_col);
+        let mut cand_mvs = cand.mv;
+        if cand.y_mode == GLOBAL_GLOBALMV {
+            for (ref_list, cand_mv) in cand_mvs.iter_mut().enumerate() {
+                let ref_frame = self.b.ref_frame[ref_list];
+                if self.header.global_motion_params.gm_type[ref_frame as usize]
+                    > GmType::Translation
+                {
+                    *cand_mv = self.mv_stack.global_mvs[ref_list];
+                }
+            }
+        }
+
+        for cand_mv in cand_mvs.iter_mut() {
+            self.lower_mv_precision(cand_mv);
+        }
+
+        let stack = &mut self.mv_stack;
+        stack.found_match = true;
+        let idx = (0..stack.num_mv_found)
+            .find(|idx| stack.ref_stack_mv[*idx] == cand_mvs)
+            .unwrap_or(stack.num_mv_found);
+
+        if idx < stack.num_mv_found {
+            stack.weight_stack[idx] += weight;
+        } else if stack.num_mv_found < MAX_REF_MV_STACK_SIZE as usize {
+            stack.ref_stack_mv[idx] = cand_mvs;
+            stack.weight_stack[idx] = weight;
+            stack.num_mv_found += 1;
+        }
+
+        if has_newmv(cand.y_mode) {
+            stack.new_mv_count += 1;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#sorting-process
+    fn sorting(&mut self, start: usize, mut end: usize) {
+        let stack = &mut self.mv_stack;
+        while end > start {
+            let mut new_end = start;
+            for idx in start + 1..end {
+                if stack.weight_stack[idx - 1] < stack.weight_stack[idx] {
+                    stack.weight_stack.swap(idx - 1, idx);
+                    stack.ref_stack_mv.swap(idx - 1, idx);
+                    new_end = idx;
+                }
+            }
+
+            end = new_end;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#extra-search-process
+    fn extra_search(&mut self, is_compound: bool) {
+        let (mi_row, mi_col) = (self.b.mi_row as i64, self.b.mi_col as i64);
+        self.mv_stack.ref_id_count = [0; 2];
+        self.mv_stack.ref_diff_count = [0; 2];
+
+        let w4 = (NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as i64)
+            .min(16)
+            .min(self.frame.mi_cols as i64 - mi_col);
+        let h4 = (NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as i64)
+            .min(16)
+            .min(self.frame.mi_rows as i64 - mi_row);
+        let num4x4 = w4.min(h4);
+
+        for pass in 0..2 {
+            if self.mv_stack.num_mv_found >= 2 {
+                break;
+            }
+
+            let mut idx = 0;
+            while idx < num4x4 && self.mv_stack.num_mv_found < 2 {
+                let (mv_row, mv_col) = if pass == 0 {
+                    (mi_row - 1, mi_col + idx)
+                } else {
+                    (mi_row + idx, mi_col - 1)
+                };
+
+                if !self.is_inside(mv_row, mv_col) {
+                    break;
+                }
+
+                self.add_extra_mv_candidate(mv_row as u32, mv_col as u32, is_compound);
+                let cand_size = self.frame.mi(mv_row as u32, mv_col as u32).mi_size as usize;
+                idx += if pass == 0 {
+                    NUM_4X4_BLOCKS_WIDE[cand_size]
+                } else {
+                    NUM_4X4_BLOCKS_HIGH[cand_size]
+                } as i64;
+            }
+        }
+
+        let stack = &mut self.mv_stack;
+        if is_compound {
+            let mut combined_mvs = [[[0; 2]; 2]; 2];
+            for list in 0..2 {
+                let mvs = stack.ref_id_mvs[list][..stack.ref_id_count[list]]
+                    .iter()
+                    .chain(&stack.ref_diff_mvs[list][..stack.ref_diff_count[list]])
+                    .chain(std::iter::repeat(&stack.global_mvs[list]));
+                for (combined, mv) in combined_mvs.iter_mut().zip(mvs) {
+                    combined[list] = *mv;
+                }
+            }
+
+            if stack.num_mv_found == 1 {
+                stack.ref_stack_mv[1] = if combined_mvs[0] == stack.ref_stack_mv[0] {
+                    combined_mvs[1]
+                } else {
+                    combined_mvs[0]
+                };
+
+                stack.weight_stack[1] = 2;
+                stack.num_mv_found += 1;
+            } else {
+                for mvs in combined_mvs {
+                    stack.ref_stack_mv[stack.num_mv_found] = mvs;
+                    stack.weight_stack[stack.num_mv_found] = 2;
+                    stack.num_mv_found += 1;
+                }
+            }
+        } else {
+            for idx in stack.num_mv_found..2 {
+                stack.ref_stack_mv[idx][0] = stack.global_mvs[0];
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#add-extra-mv-candidate-process
+    fn add_extra_mv_candidate(&mut self, mv_row: u32, mv_col: u32, is_compound: bool) {
+        let cand = *self.frame.mi(mv_row, mv_col);
+        let sign_bias = &self.ctx.ref_frame_sign_bias;
+        let stack = &mut self.mv_stack;
+        for cand_list in 0..2 {
+            let cand_ref = cand.ref_frame[cand_list];
+            if cand_ref <= INTRA_FRAME {
+                continue;
+            }
+
+            if is_compound {
+                for list in 0..2 {
+                    let mut cand_mv = cand.mv[cand_list];
+                    let ref_frame = self.b.ref_frame[list];
+                    if cand_ref == ref_frame && stack.ref_id_count[list] < 2 {
+                        stack.ref_id_mvs[list][stack.ref_id_count[list]] = cand_mv;
+                        stack.ref_id_count[list] += 1;
+                    } else if stack.ref_diff_count[list] < 2 {
+                        if sign_bias[cand_ref as usize] != sign_bias[ref_frame as usize] {
+                            cand_mv = [-cand_mv[0], -cand_mv[1]];
+                        }
+
+                        stack.ref_diff_mvs[list][stack.ref_diff_count[list]] = cand_mv;
+                        stack.ref_diff_count[list] += 1;
+                    }
+                }
+            } else {
+                let mut cand_mv = cand.mv[cand_list];
+                if sign_bias[cand_ref as usize] != sign_bias[self.b.ref_frame[0] as usize] {
+                    cand_mv = [-cand_mv[0], -cand_mv[1]];
+                }
+
+                let idx = (0..stack.num_mv_found)
+                    .find(|idx| stack.ref_stack_mv[*idx][0] == cand_mv)
+                    .unwrap_or(stack.num_mv_found);
+
+                if idx == stack.num_mv_found {
+                    stack.ref_stack_mv[idx][0] = cand_mv;
+                    stack.weight_stack[idx] = 2;
+                    stack.num_mv_found += 1;
+                }
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#context-and-clamping-process
+    fn context_and_clamping(&mut self, is_compound: bool, num_new: usize) {
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as i32;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as i32;
+        let num_mv_found = self.mv_stack.num_mv_found;
+        for idx in 0..num_mv_found {
+            let mut z = 0;
+            if idx + 1 < num_mv_found {
+                let w0 = self.mv_stack.weight_stack[idx];
+                let w1 = self.mv_stack.weight_stack[idx + 1];
+                if w0 >= REF_CAT_LEVEL as u32 {
+                    if w1 < REF_CAT_LEVEL as u32 {
+                        z = 1;
+                    }
+                } else {
+                    z = 2;
+                }
+            }
+
+            self.mv_stack.drl_ctx_stack[idx] = z;
+        }
+
+        for list in 0..1 + is_compound as usize {
+            for idx in 0..num_mv_found {
+                let mut ref_mv = self.mv_stack.ref_stack_mv[idx][list];
+                ref_mv[0] = self.clamp_mv_row(ref_mv[0], MV_BORDER as i32 + bh4 * 4 * 8);
+                ref_mv[1] = self.clamp_mv_col(ref_mv[1], MV_BORDER as i32 + bw4 * 4 * 8);
+                self.mv_stack.ref_stack_mv[idx][list] = ref_mv;
+            }
+        }
+
+        let stack = &mut self.mv_stack;
+        let num_new = num_new.min(1) as u8;
+        (stack.new_mv_context, stack.ref_mv_context) = match stack.close_matches {
+            0 => (stack.total_matches.min(1), stack.total_matches),
+            1 => (3 - num_new, 2 + stack.total_matches),
+            _ => (5 - num_new, 5),
+        };
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#clamp-mv-row-and-col-process
+    pub(crate) fn clamp_mv_row(&self, mvec: i32, border: i32) -> i32 {
+        let bh4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as i32;
+        let mi_row = self.b.mi_row as i32;
+        let mb_to_top_edge = -((mi_row * MI_SIZE as i32) * 8);
+        let mb_to_bottom_edge = ((self.frame.mi_rows as i32 - bh4 - mi_row) * MI_SIZE as i32) * 8;
+        mvec.clamp(mb_to_top_edge - border, mb_to_bottom_edge + border)
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#clamp-mv-row-and-col-process
+    pub(crate) fn clamp_mv_col(&self, mvec: i32, border: i32) -> i32 {
+        let bw4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as i32;
+        let mi_col = self.b.mi_col as i32;
+        let mb_to_left_edge = -((mi_col * MI_SIZE as i32) * 8);
+        let mb_to_right_edge = ((self.frame.mi_cols as i32 - bw4 - mi_col) * MI_SIZE as i32) * 8;
+        mvec.clamp(mb_to_left_edge - border, mb_to_right_edge + border)
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#has-overlappable-candidates-process
+    pub(crate) fn has_overlappable_candidates(&self) -> bool {
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        let (mi_rows, mi_cols) = (self.frame.mi_rows, self.frame.mi_cols);
+        if self.b.avail_u {
+            let w4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32;
+            for x4 in (mi_col..mi_cols.min(mi_col + w4)).step_by(2) {
+                let x5 = (x4 | 1).min(mi_cols - 1);
+                if self.frame.mi(mi_row - 1, x5).ref_frame[0] > INTRA_FRAME {
+                    return true;
+                }
+            }
+        }
+
+        if self.b.avail_l {
+            let h4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as u32;
+            for y4 in (mi_row..mi_rows.min(mi_row + h4)).step_by(2) {
+                let y5 = (y4 | 1).min(mi_rows - 1);
+                if self.frame.mi(y5, mi_col - 1).ref_frame[0] > INTRA_FRAME {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#find-warp-samples-process
+    pub(crate) fn find_warp_samples(&mut self) {
+        self.warp_samples = WarpSamples::default();
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        let w4 = NUM_4X4_BLOCKS_WIDE[self.b.mi_size as usize] as u32;
+        let h4 = NUM_4X4_BLOCKS_HIGH[self.b.mi_size as usize] as u32;
+        let mut do_top_left = true;
+        let mut do_top_right = true;
+
+        if self.b.avail_u {
+            let src_size = self.frame.mi(mi_row - 1, mi_col).mi_size;
+            let src_w = NUM_4X4_BLOCKS_WIDE[src_size as usize] as u32;
+            if w4 <= src_w {
+                let col_offset = -((mi_col & (src_w - 1)) as i64);
+                if col_offset < 0 {
+                    do_top_left = false;
+                }
+
+                if col_offset + src_w as i64 > w4 as i64 {
+                    do_top_right = false;
+                }
+
+                self.add_sample(-1, 0);
+            } else {
+                let mut i = 0;
+                while i < w4.min(self.frame.mi_cols - mi_col) {
+                    let src_size = self.frame.mi(mi_row - 1, mi_col + i).mi_size;
+                    let src_w = NUM_4X4_BLOCKS_WIDE[src_size as usize] as u32;
+                    let step = src_w.max(NUM_4X4_BLOCKS_WIDE[BLOCK_8X8 as usize] as u32);
+                    self.add_sample(-1, i as i64);
+                    i += step;
+                }
+            }
+        }
+
+        if self.b.avail_l {
+            let src_size = self.frame.mi(mi_row, mi_col - 1).mi_size;
+            let src_h = NUM_4X4_BLOCKS_HIGH[src_size as usize] as u32;
+            if h4 <= src_h {
+                let row_offset = -((mi_row & (src_h - 1)) as i64);
+                if row_offset < 0 {
+                    do_top_left = false;
+                }
+
+                self.add_sample(0, -1);
+            } else {
+                let mut i = 0;
+                while i < h4.min(self.frame.mi_rows - mi_row) {
+                    let src_size = self.frame.mi(mi_row + i, mi_col - 1).mi_size;
+                    let src_h = NUM_4X4_BLOCKS_HIGH[src_size as usize] as u32;
+                    let step = src_h.max(NUM_4X4_BLOCKS_HIGH[BLOCK_8X8 as usize] as u32);
+                    self.add_sample(i as i64, -1);
+                    i += step;
+                }
+            }
+        }
+
+        if do_top_left {
+            self.add_sample(-1, -1);
+        }
+
+        if do_top_right && w4.max(h4) <= 16 {
+            self.add_sample(-1, w4 as i64);
+        }
+
+        let samples = &mut self.warp_samples;
+        if samples.num_samples == 0 && samples.num_samples_scanned > 0 {
+            samples.num_samples = 1;
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#add-sample-process
+    fn add_sample(&mut self, delta_row: i64, delta_col: i64) {
+        if self.warp_samples.num_samples_scanned >= LEAST_SQUARES_SAMPLES_MAX as usize {
+            return;
+        }
+
+        let mv_row = self.b.mi_row as i64 + delta_row;
+        let mv_col = self.b.mi_col as i64 + delta_col;
+        if !self.is_inside(mv_row, mv_col) || !self.is_decoded(mv_row as u32, mv_col as u32) {
+            return;
+        }
+
+        let cand = *self.frame.mi(mv_row as u32, mv_col as u32);
+        if cand.ref_frame[0] != self.b.ref_frame[0] || cand.ref_frame[1] != NONE {
+            return;
+        }
+
+        let cand_w4 = NUM_4X4_BLOCKS_WIDE[cand.mi_size as usize] as i64;
+        let cand_h4 = NUM_4X4_BLOCKS_HIGH[cand.mi_size as usize] as i64;
+        let cand_row = mv_row & !(cand_h4 - 1);
+        let cand_col = mv_col & !(cand_w4 - 1);
+        let mid_y = (cand_row * 4 + cand_h4 * 4 / 2 - 1) as i32;
+        let mid_x = (cand_col * 4 + cand_w4 * 4 / 2 - 1) as i32;
+        let threshold = block_width(self.b.mi_size)
+            .max(block_height(self.b.mi_size))
+            .clamp(16, 112) as i32;
+
+        let cand_mv = self.frame.mi(cand_row as u32, cand_col as u32).mv[0];
+        let mv_diff_row = (cand_mv[0] - self.b.mv[0][0]).abs();
+        let mv_diff_col = (cand_mv[1] - self.b.mv[0][1]).abs();
+        let valid = mv_diff_row + mv_diff_col <= threshold;
+
+        let cand = [
+            mid_y * 8,
+            mid_x * 8,
+            mid_y * 8 + cand_mv[0],
+            mid_x * 8 + cand_mv[1],
+        ];
+
+        let samples = &mut self.warp_samples;
+        samples.num_samples_scanned += 1;
+        if !valid && samples.num_samples_scanned > 1 {
+            return;
+        }
+
+        samples.cand_list[samples.num_samples] = cand;
+        if valid {
+            samples.num_samples += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        obu::{frame_header::UncompressedHeader, ObuContext},
+        tile::{
+            block::Block,
+            mode_info::{FrameState, ModeInfo},
+            testing,
+        },
+    };
+
+    /// The size of the test frames in 4x4 blocks.
+    const MI_SIZE_FRAME: u32 = 16;
+
+    fn context(header: UncompressedHeader) -> ObuContext {
+        testing::context(header, MI_SIZE_FRAME, MI_SIZE_FRAME)
+    }
+
+    /// Writes the mode info of an 8x8 block predicted from LAST_FRAME, with
+    /// its top left 4x4 block at `(row, col)`.
+    fn put_block(frame: &mut FrameState, row: u32, col: u32, y_mode: u8, mv: Mv) {
+        for r in row..row + 2 {
+            for c in col..col + 2 {
+                *frame.mi_mut(r, c) = ModeInfo {
+                    mi_size: BLOCK_8X8,
+                    y_mode,
+                    ref_frame: [LAST_FRAME, NONE],
+                    mv: [mv, [0; 2]],
+                    is_inter: true,
+                    ..Default::default()
+                };
+            }
+        }
+    }
+
+    /// Builds the stack of the 8x8 block at (4, 4) predicted from
+    /// LAST_FRAME.
+    fn find_mv_stack(ctx: &ObuContext, frame: &mut FrameState) -> MvStack {
+        let mut decoder = testing::decoder(ctx, frame);
+        decoder.b = Block {
+            mi_row: 4,
+            mi_col: 4,
+            mi_size: BLOCK_8X8,
+            ref_frame: [LAST_FRAME, NONE],
+            ..Default::default()
+        };
+
+        decoder.find_mv_stack(false);
+        decoder.mv_stack
+    }
+
+    /// The motion vectors of the candidates found for the first reference.
+    fn first_mvs(stack: &MvStack) -> Vec<Mv> {
+        stack.ref_stack_mv[..stack.num_mv_found]
+            .iter()
+            .map(|mvs| mvs[0])
+            .collect()
+    }
+
+    fn new_frame() -> FrameState {
+        FrameState::new(MI_SIZE_FRAME, MI_SIZE_FRAME)
+    }
+
+    #[test]
+    fn candidates_are_sorted_by_weight_nearest_first() {
+        let ctx = context(UncompressedHeader::default());
+        let mut frame = new_frame();
+        let (a, b, c) = ([8, 16], [-8, 24], [40, 0]);
+        // Above, left and above left.
+        put_block(&mut frame, 2, 4, NEARESTMV, a);
+        put_block(&mut frame, 4, 2, NEARESTMV, b);
+        put_block(&mut frame, 2, 2, NEARESTMV, b);
+        // The outer rows and columns, scanned after the nearest candidates.
+        put_block(&mut frame, 0, 4, NEARESTMV, c);
+        put_block(&mut frame, 4, 0, NEARESTMV, b);
+
+        let stack = find_mv_stack(&ctx, &mut frame);
+        assert_eq!(stack.num_mv_found, 3);
+        assert_eq!(first_mvs(&stack), [b, a, c]);
+        let nearest = REF_CAT_LEVEL as u32;
+        assert_eq!(
+            stack.weight_stack[..3],
+            [nearest + 4 + 4 + 4, nearest + 4, 4]
+        );
+        assert_eq!(stack.drl_ctx_stack[..3], [0, 1, 0]);
+    }
+
+    #[test]
+    fn missing_candidates_are_filled_with_the_global_motion_vector() {
+        let ctx = context(UncompressedHeader::default());
+        let mut frame = new_frame();
+        let stack = find_mv_stack(&ctx, &mut frame);
+        assert_eq!(stack.num_mv_found, 0);
+        assert_eq!(stack.ref_stack_mv[..2], [[[0; 2]; 2]; 2]);
+        assert_eq!((stack.new_mv_context, stack.ref_mv_context), (0, 0));
+    }
+
+    #[test]
+    fn contexts_count_the_close_and_new_matches() {
+        let ctx = context(UncompressedHeader::default());
+        let contexts = |blocks: &[(u32, u32, u8)]| {
+            let mut frame = new_frame();
+            for (i, &(row, col, y_mode)) in blocks.iter().enumerate() {
+                put_block(&mut frame, row, col, y_mode, [8 * i as i32, 0]);
+            }
+
+            let stack = find_mv_stack(&ctx, &mut frame);
+            (stack.new_mv_context, stack.ref_mv_context)
+        };
+
+        // Only an outer match.
+        assert_eq!(contexts(&[(0, 4, NEWMV)]), (1, 1));
+        // A single close match, the new motion vectors only count there.
+        assert_eq!(contexts(&[(2, 4, NEARESTMV)]), (3, 3));
+        assert_eq!(contexts(&[(2, 4, NEWMV)]), (2, 3));
+        assert_eq!(contexts(&[(2, 4, NEARESTMV), (0, 4, NEWMV)]), (3, 3));
+        // Close matches above and to the left.
+        assert_eq!(contexts(&[(2, 4, NEARESTMV), (4, 2, GLOBALMV)]), (5, 5));
+        assert_eq!(contexts(&[(2, 4, NEWMV), (4, 2, NEARESTMV)]), (4, 5));
+    }
+
+    #[test]
+    fn candidates_follow_the_precision_of_the_frame() {
+        let nearest = |header| {
+            let ctx = context(header);
+            let mut frame = new_frame();
+            put_block(&mut frame, 2, 4, NEARESTMV, [3, -5]);
+            put_block(&mut frame, 4, 2, NEARESTMV, [-13, 4]);
+            let stack = find_mv_stack(&ctx, &mut frame);
+            first_mvs(&stack)
+        };
+
+        let high_precision = UncompressedHeader {
+            allow_high_precision_mv: true,
+            ..Default::default()
+        };
+        assert_eq!(nearest(high_precision), [[3, -5], [-13, 4]]);
+        assert_eq!(nearest(UncompressedHeader::default()), [[2, -4], [-12, 4]]);
+
+        let integer = UncompressedHeader {
+            force_integer_mv: true,
+            ..Default::default()
+        };
+        assert_eq!(nearest(integer), [[0, -8], [-16, 0]]);
+    }
+}
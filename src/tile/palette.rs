@@ -0,0 +1,551 @@
+//! The palette mode: the colors of the palettes, the color index maps of the
+//! blocks and their prediction.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#palette-mode-info-syntax
+
+use super::{read_ns, TileDecoder};
+
+use crate::{constants::*, symbol::SymbolDecoder, tables::*, util::ceil_log2};
+
+/// The palette color cdfs of a palette size, one per palette color context.
+type PaletteColorCdfs = [[u16; PALETTE_COLORS as usize + 1]; PALETTE_COLOR_CONTEXTS as usize];
+
+impl TileDecoder<'_, '_> {
+    /// see: https://aomediacodec.github.io/av1-spec/#palette-mode-info-syntax
+    pub(crate) fn palette_mode_info(&mut self) {
+        let mi_size = self.b.mi_size as usize;
+        let bsize_ctx = (MI_WIDTH_LOG2[mi_size] + MI_HEIGHT_LOG2[mi_size] - 2) as usize;
+        if self.b.y_mode == DC_PRED {
+            let mut ctx = 0;
+            if self.b.avail_u && self.frame.mi(self.b.mi_row - 1, self.b.mi_col).palette_size[0] > 0
+            {
+                ctx += 1;
+            }
+
+            if self.b.avail_l && self.frame.mi(self.b.mi_row, self.b.mi_col - 1).palette_size[0] > 0
+            {
+                ctx += 1;
+            }
+
+            // has_palette_y	S()
+            if self
+                .sd
+                .read_symbol(&mut self.cdf.palette_y_mode_cdf[bsize_ctx][ctx])
+                == 1
+            {
+                // palette_size_y_minus_2	S()
+                self.b.palette_size_y = self
+                    .sd
+                    .read_symbol(&mut self.cdf.palette_y_size_cdf[bsize_ctx])
+                    as u8
+                    + 2;
+
+                self.b.palette_colors_y = self.read_palette_colors(0, self.b.palette_size_y);
+            }
+        }
+
+        if self.b.has_chroma && self.b.uv_mode == DC_PRED {
+            // has_palette_uv	S()
+            let ctx = (self.b.palette_size_y > 0) as usize;
+            if self.sd.read_symbol(&mut self.cdf.palette_uv_mode_cdf[ctx]) == 1 {
+                // palette_size_uv_minus_2	S()
+                self.b.palette_size_uv = self
+                    .sd
+                    .read_symbol(&mut self.cdf.palette_uv_size_cdf[bsize_ctx])
+                    as u8
+                    + 2;
+
+                self.b.palette_colors_u = self.read_palette_colors(1, self.b.palette_size_uv);
+                self.read_palette_colors_v();
+            }
+        }
+    }
+
+    /// Reads the `n` colors of the palette of the Y plane (`plane` 0) or of
+    /// the U plane (`plane` 1), the colors that are not taken from the
+    /// palette cache are coded as increasing deltas.
+    fn read_palette_colors(&mut self, plane: usize, n: u8) -> [u16; PALETTE_COLORS as usize] {
+        let n = n as usize;
+        let bit_depth = self.ctx.bit_depth as u32;
+        let max = (1 << bit_depth) - 1;
+
+        // The deltas of the luma colors are coded minus 1, the luma colors
+        // are all distinct.
+        let delta_offset = (plane == 0) as u32;
+
+        let mut colors = [0; PALETTE_COLORS as usize];
+        let mut idx = 0;
+        let (cache, cache_n) = self.get_palette_cache(plane);
+        for color in &cache[..cache_n] {
+            if idx >= n {
+                break;
+            }
+
+            // use_palette_color_cache_y or use_palette_color_cache_u	L(1)
+            if self.sd.read_literal(1) == 1 {
+                colors[idx] = *color;
+                idx += 1;
+            }
+        }
+
+        if idx < n {
+            // palette_colors_y or palette_colors_u	L(BitDepth)
+            colors[idx] = self.sd.read_literal(bit_depth as usize) as u16;
+            idx += 1;
+        }
+
+        if idx < n {
+            // palette_num_extra_bits_y or palette_num_extra_bits_u	L(2)
+            let mut palette_bits = bit_depth - 3 + self.sd.read_literal(2);
+            while idx < n {
+                // palette_delta_y or palette_delta_u	L(paletteBits)
+                let delta = self.sd.read_literal(palette_bits as usize) + delta_offset;
+                let color = (colors[idx - 1] as u32 + delta).min(max);
+                colors[idx] = color as u16;
+
+                let range = (1 << bit_depth) - color - delta_offset;
+                palette_bits = palette_bits.min(ceil_log2(range));
+                idx += 1;
+            }
+        }
+
+        colors[..n].sort_unstable();
+        colors
+    }
+
+    /// Reads the colors of the palette of the V plane, either as literals or
+    /// as signed deltas that wrap around the range of the samples.
+    fn read_palette_colors_v(&mut self) {
+        let n = self.b.palette_size_uv as usize;
+        let bit_depth = self.ctx.bit_depth as usize;
+        let max_val = 1 << bit_depth;
+        let colors = &mut self.b.palette_colors_v;
+
+        // delta_encode_palette_colors_v	L(1)
+        if self.sd.read_literal(1) == 1 {
+            // palette_num_extra_bits_v	L(2)
+            let palette_bits = bit_depth - 4 + self.sd.read_literal(2) as usize;
+
+            // palette_colors_v	L(BitDepth)
+            colors[0] = self.sd.read_literal(bit_depth) as u16;
+            for idx in 1..n {
+                // palette_delta_v	L(paletteBits)
+                let mut delta = self.sd.read_literal(palette_bits) as i32;
+                if delta != 0 {
+                    // sign_bit	L(1)
+                    if self.sd.read_literal(1) == 1 {
+                        delta = -delta;
+                    }
+                }
+
+                let mut val = colors[idx - 1] as i32 + delta;
+                if val < 0 {
+                    val += max_val;
+                }
+
+                if val >= max_val {
+                    val -= max_val;
+                }
+
+                colors[idx] = val.clamp(0, max_val - 1) as u16;
+            }
+        } else {
+            for color in colors.iter_mut().take(n) {
+                // palette_colors_v	L(BitDepth)
+                *color = self.sd.read_literal(bit_depth) as u16;
+            }
+        }
+    }
+
+    /// Returns the palette colors of the blocks above and to the left merged
+    /// by merge_palette_colors, the colors above are not used across a 64x64
+    /// boundary.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#get-palette-cache-process
+    fn get_palette_cache(&self, plane: usize) -> ([u16; 2 * PALETTE_COLORS as usize], usize) {
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        let above: &[u16] = if !(mi_row * MI_SIZE as u32).is_multiple_of(64) && self.b.avail_u {
+            let mi = self.frame.mi(mi_row - 1, mi_col);
+            &mi.palette_colors[plane][..mi.palette_size[plane] as usize]
+        } else {
+            &[]
+        };
+
+        let left: &[u16] = if self.b.avail_l {
+            let mi = self.frame.mi(mi_row, mi_col - 1);
+            &mi.palette_colors[plane][..mi.palette_size[plane] as usize]
+        } else {
+            &[]
+        };
+
+        merge_palette_colors(above, left)
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#palette-tokens-syntax
+    pub(crate) fn palette_tokens(&mut self) {
+        let mi_size = self.b.mi_size;
+        let mut block_height = block_height(mi_size) as usize;
+        let mut block_width = block_width(mi_size) as usize;
+        let mut onscreen_height =
+            block_height.min(((self.frame.mi_rows - self.b.mi_row) * MI_SIZE as u32) as usize);
+        let mut onscreen_width =
+            block_width.min(((self.frame.mi_cols - self.b.mi_col) * MI_SIZE as u32) as usize);
+
+        let n = self.b.palette_size_y as usize;
+        if n > 0 {
+            let cdfs = &mut self.cdf.palette_y_color_cdf[n - 2];
+            read_color_index_map(
+                &mut self.color_map_y,
+                n,
+                block_width,
+                block_height,
+                onscreen_width,
+                onscreen_height,
+                |ctx| read_color_index(&mut self.sd, cdfs, n, ctx),
+            );
+        }
+
+        let n = self.b.palette_size_uv as usize;
+        if n > 0 {
+            let color_config = &self.sequence_header.color_config;
+            let sub_x = color_config.subsampling_x as usize;
+            let sub_y = color_config.subsampling_y as usize;
+            block_height >>= sub_y;
+            block_width >>= sub_x;
+            onscreen_height >>= sub_y;
+            onscreen_width >>= sub_x;
+            if block_width < 4 {
+                block_width += 2;
+                onscreen_width += 2;
+            }
+
+            if block_height < 4 {
+                block_height += 2;
+                onscreen_height += 2;
+            }
+
+            let cdfs = &mut self.cdf.palette_uv_color_cdf[n - 2];
+            read_color_index_map(
+                &mut self.color_map_uv,
+                n,
+                block_width,
+                block_height,
+                onscreen_width,
+                onscreen_height,
+                |ctx| read_color_index(&mut self.sd, cdfs, n, ctx),
+            );
+        }
+    }
+
+    /// Predicts the transform block at `(start_x, start_y)` in the plane
+    /// `plane` from the palette of the plane, `(x, y)` is the position of the
+    /// transform block in the block in units of 4 samples.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#palette-prediction-process
+    pub(crate) fn predict_palette(
+        &mut self,
+        plane: usize,
+        start_x: u32,
+        start_y: u32,
+        x: u32,
+        y: u32,
+        tx_sz: u8,
+    ) {
+        let w = TX_WIDTH[tx_sz as usize] as usize;
+        let h = TX_HEIGHT[tx_sz as usize] as usize;
+        let (start_x, start_y) = (start_x as usize, start_y as usize);
+        let (x, y) = (x as usize * 4, y as usize * 4);
+        let (palette, map) = match plane {
+            0 => (&self.b.palette_colors_y, &self.color_map_y),
+            1 => (&self.b.palette_colors_u, &self.color_map_uv),
+            _ => (&self.b.palette_colors_v, &self.color_map_uv),
+        };
+
+        let cur = &mut self.frame.cur_frame.planes[plane];
+        for (i, map) in map[y..y + h].iter().enumerate() {
+            for (v, idx) in cur.row_mut(start_y + i)[start_x..start_x + w]
+                .iter_mut()
+                .zip(&map[x..x + w])
+            {
+                *v = palette[*idx as usize];
+            }
+        }
+    }
+}
+
+/// Merges the palette colors `above` and `left`, both in ascending order,
+/// into the palette cache: the colors in ascending order without duplicates,
+/// returned with their number.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#get-palette-cache-process
+fn merge_palette_colors(
+    above: &[u16],
+    left: &[u16],
+) -> ([u16; 2 * PALETTE_COLORS as usize], usize) {
+    let mut cache = [0; 2 * PALETTE_COLORS as usize];
+    let mut n = 0;
+    let (mut above_idx, mut left_idx) = (0, 0);
+    while above_idx < above.len() || left_idx < left.len() {
+        let color = if left_idx >= left.len()
+            || (above_idx < above.len() && above[above_idx] <= left[left_idx])
+        {
+            let color = above[above_idx];
+            above_idx += 1;
+            if left_idx < left.len() && left[left_idx] == color {
+                left_idx += 1;
+            }
+
+            color
+        } else {
+            left_idx += 1;
+            left[left_idx - 1]
+        };
+
+        if n == 0 || color != cache[n - 1] {
+            cache[n] = color;
+            n += 1;
+        }
+    }
+
+    (cache, n)
+}
+
+/// Reads the first index of a color index map (`ctx` is `None`) or a
+/// palette_color_idx with the context `ctx`.
+fn read_color_index(
+    sd: &mut SymbolDecoder,
+    cdfs: &mut PaletteColorCdfs,
+    n: usize,
+    ctx: Option<usize>,
+) -> usize {
+    match ctx {
+        // color_index_map_y or color_index_map_uv	NS(n)
+        None => read_ns(sd, n as u32) as usize,
+        // palette_color_idx_y or palette_color_idx_uv	S()
+        Some(ctx) => sd.read_symbol(&mut cdfs[ctx][..n + 1]),
+    }
+}
+
+/// Reads the color index map of a block of `block_width` by `block_height`
+/// samples with a palette of `n` colors. The indices of the visible part of
+/// the block are read in wavefront order, from the top left corner along the
+/// anti-diagonals, and the rest of the block repeats the last visible column
+/// and row.
+///
+/// `read_index` reads the first index when given `None`, and the
+/// palette_color_idx of the following ones from the palette color context.
+#[allow(clippy::too_many_arguments)]
+fn read_color_index_map(
+    map: &mut [[u8; 64]; 64],
+    n: usize,
+    block_width: usize,
+    block_height: usize,
+    onscreen_width: usize,
+    onscreen_height: usize,
+    mut read_index: impl FnMut(Option<usize>) -> usize,
+) {
+    map[0][0] = read_index(None) as u8;
+    for i in 1..onscreen_height + onscreen_width - 1 {
+        for j in ((i + 1).saturating_sub(onscreen_height)..=i.min(onscreen_width - 1)).rev() {
+            let (ctx, color_order) = get_palette_color_context(map, i - j, j, n);
+            let palette_color_idx = read_index(Some(ctx));
+            map[i - j][j] = color_order[palette_color_idx];
+        }
+    }
+
+    for row in map.iter_mut().take(onscreen_height) {
+        let color = row[onscreen_width - 1];
+        row[onscreen_width..block_width].fill(color);
+    }
+
+    let last_row = map[onscreen_height - 1];
+    map[onscreen_height..block_height].fill(last_row);
+}
+
+/// Returns the context of the palette color index at `(r, c)` and the
+/// colors ordered by decreasing score of the neighboring indices, the left
+/// and above neighbors count twice as much as the above left one.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#get-palette-color-context-process
+fn get_palette_color_context(
+    map: &[[u8; 64]; 64],
+    r: usize,
+    c: usize,
+    n: usize,
+) -> (usize, [u8; PALETTE_COLORS as usize]) {
+    let mut scores = [0; PALETTE_COLORS as usize];
+    let mut color_order = [0, 1, 2, 3, 4, 5, 6, 7];
+    if c > 0 {
+        scores[map[r][c - 1] as usize] += 2;
+    }
+
+    if r > 0 && c > 0 {
+        scores[map[r - 1][c - 1] as usize] += 1;
+    }
+
+    if r > 0 {
+        scores[map[r - 1][c] as usize] += 2;
+    }
+
+    for i in 0..PALETTE_NUM_NEIGHBORS as usize {
+        let mut max_score = scores[i];
+        let mut max_idx = i;
+        for (j, score) in scores.iter().enumerate().take(n).skip(i + 1) {
+            if *score > max_score {
+                max_score = *score;
+                max_idx = j;
+            }
+        }
+
+        // Moves the highest score to i, keeping the order of the others.
+        scores[i..=max_idx].rotate_right(1);
+        color_order[i..=max_idx].rotate_right(1);
+    }
+
+    let hash: u8 = scores
+        .iter()
+        .zip(PALETTE_COLOR_HASH_MULTIPLIERS)
+        .map(|(score, multiplier)| score * multiplier)
+        .sum();
+
+    (PALETTE_COLOR_CONTEXT[hash as usize] as usize, color_order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A color index map with the rows `rows` in its top left corner.
+    fn color_map(rows: &[&[u8]]) -> [[u8; 64]; 64] {
+        let mut map = [[0; 64]; 64];
+        for (row, indices) in map.iter_mut().zip(rows) {
+            row[..indices.len()].copy_from_slice(indices);
+        }
+
+        map
+    }
+
+    #[test]
+    fn color_context_depends_on_the_neighbor_scores() {
+        // Only the left or the above neighbor.
+        let map = color_map(&[&[1, 0], &[2, 0]]);
+        assert_eq!(get_palette_color_context(&map, 0, 1, 3).0, 0);
+        assert_eq!(get_palette_color_context(&map, 1, 0, 3).0, 0);
+
+        // The three neighbors with the same color.
+        let map = color_map(&[&[1, 1], &[1, 0]]);
+        assert_eq!(get_palette_color_context(&map, 1, 1, 3).0, 4);
+
+        // The left and above neighbors with the same color.
+        let map = color_map(&[&[2, 1], &[1, 0]]);
+        assert_eq!(get_palette_color_context(&map, 1, 1, 3).0, 3);
+
+        // The above left and left neighbors with the same color.
+        let map = color_map(&[&[1, 2], &[1, 0]]);
+        assert_eq!(get_palette_color_context(&map, 1, 1, 3).0, 2);
+
+        // Three different colors.
+        let map = color_map(&[&[0, 1], &[2, 0]]);
+        assert_eq!(get_palette_color_context(&map, 1, 1, 3).0, 1);
+    }
+
+    #[test]
+    fn color_order_moves_the_neighbor_colors_first() {
+        // The left and above left neighbor color 3 scores 3, the above neighbor
+        // color 1 scores 2, the other colors keep their order.
+        let map = color_map(&[&[3, 1], &[3, 0]]);
+        let (ctx, color_order) = get_palette_color_context(&map, 1, 1, 5);
+        assert_eq!(ctx, 2);
+        assert_eq!(color_order, [3, 1, 0, 2, 4, 5, 6, 7]);
+
+        // The equal scores of the left and above colors keep the smaller color
+        // first.
+        let map = color_map(&[&[6, 2], &[4, 0]]);
+        let (ctx, color_order) = get_palette_color_context(&map, 1, 1, 8);
+        assert_eq!(ctx, 1);
+        assert_eq!(color_order, [2, 4, 6, 0, 1, 3, 5, 7]);
+
+        // The colors past the palette size are not moved.
+        let map = color_map(&[&[0, 6], &[0, 0]]);
+        let (_, color_order) = get_palette_color_context(&map, 1, 1, 4);
+        assert_eq!(color_order[..4], [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn color_index_map_is_read_in_wavefront_order() {
+        // The visible part of the block is 4x3, every index read is different
+        // so that the map depends on the order of the reads.
+        let mut map = [[0; 64]; 64];
+        let mut reads = vec![];
+        read_color_index_map(&mut map, 3, 8, 4, 4, 3, |ctx| {
+            reads.push(ctx);
+            if ctx.is_none() {
+                2
+            } else {
+                reads.len() % 3
+            }
+        });
+
+        // Each anti-diagonal is read from its top right end.
+        let order = [
+            (0, 1),
+            (1, 0),
+            (0, 2),
+            (1, 1),
+            (2, 0),
+            (0, 3),
+            (1, 2),
+            (2, 1),
+            (1, 3),
+            (2, 2),
+            (2, 3),
+        ];
+
+        let mut expected = [[0; 64]; 64];
+        let mut expected_reads = vec![None];
+        expected[0][0] = 2;
+        for (k, (r, c)) in order.into_iter().enumerate() {
+            let (ctx, color_order) = get_palette_color_context(&expected, r, c, 3);
+            expected_reads.push(Some(ctx));
+            expected[r][c] = color_order[(k + 2) % 3];
+        }
+
+        assert_eq!(reads, expected_reads);
+        for r in 0..3 {
+            assert_eq!(map[r][..4], expected[r][..4], "row {r}");
+
+            // The columns past the visible part repeat the last visible one.
+            assert_eq!(map[r][4..8], [expected[r][3]; 4], "row {r}");
+        }
+
+        // The rows past the visible part repeat the last visible one.
+        assert_eq!(map[3][..8], map[2][..8]);
+        assert!(map[4].iter().all(|v| *v == 0));
+    }
+
+    #[test]
+    fn palette_cache_merges_the_neighbor_colors() {
+        let (cache, n) = merge_palette_colors(&[10, 40, 50], &[20, 40, 60, 70]);
+        assert_eq!(cache[..n], [10, 20, 40, 50, 60, 70]);
+
+        // The duplicates within a palette are also removed.
+        let (cache, n) = merge_palette_colors(&[5, 5, 9], &[1, 5, 9, 9]);
+        assert_eq!(cache[..n], [1, 5, 9]);
+
+        let (cache, n) = merge_palette_colors(&[], &[3, 4]);
+        assert_eq!(cache[..n], [3, 4]);
+
+        let (cache, n) = merge_palette_colors(&[7, 8], &[]);
+        assert_eq!(cache[..n], [7, 8]);
+
+        let (_, n) = merge_palette_colors(&[], &[]);
+        assert_eq!(n, 0);
+
+        // Two full palettes without common colors fill the cache.
+        let above = [0, 2, 4, 6, 8, 10, 12, 14];
+        let left = [1, 3, 5, 7, 9, 11, 13, 15];
+        let (cache, n) = merge_palette_colors(&above, &left);
+        assert_eq!(n, 16);
+        assert!(cache.iter().enumerate().all(|(i, v)| *v == i as u16));
+    }
+}
@@ -0,0 +1,559 @@
+//! The prediction of the blocks from the samples of the current frame and of
+//! the reference frames.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#prediction-processes
+
+use super::{block::is_directional_mode, plane_subsampling, TileDecoder};
+
+use crate::{
+    constants::*,
+    inter_prediction::{
+        block_inter_prediction, difference_weight_mask, distance_weights, motion_vector_scaling,
+        wedge_mask, Mask,
+    },
+    intra::{
+        cfl_luma_ac, filter_corner, filter_edge, intra_edge_filter_strength, intra_edge_upsample,
+        predict_cfl, predict_dc, predict_directional, predict_filter_intra, predict_paeth,
+        predict_smooth, predict_smooth_h, predict_smooth_v, upsample_edge, Edge, Prediction,
+    },
+    obu::{frame_header::GmType, order_hint::get_relative_dist, ObuError},
+    tables::{
+        block_height, block_width, MODE_TO_ANGLE, NUM_4X4_BLOCKS_HIGH, NUM_4X4_BLOCKS_WIDE,
+        SUBSAMPLED_SIZE, TX_HEIGHT, TX_HEIGHT_LOG2, TX_WIDTH, TX_WIDTH_LOG2,
+    },
+    warp::setup_shear,
+};
+
+#[inline]
+fn round2(x: i32, n: u32) -> i32 {
+    (x + (1 << (n - 1))) >> n
+}
+
+/// The weight of the first prediction of the sample at `(x, y)` of a masked
+/// compound block in a plane subsampled by `(sub_x, sub_y)`, the average of
+/// the weights of the luma samples it covers.
+fn mask_weight(mask: &Mask, x: usize, y: usize, sub_x: u32, sub_y: u32) -> i32 {
+    let m = |y: usize, x: usize| mask[y][x] as i32;
+    match (sub_x, sub_y) {
+        (0, 0) => m(y, x),
+        (1, 0) => round2(m(y, 2 * x) + m(y, 2 * x + 1), 1),
+        (0, 1) => round2(m(2 * y, x) + m(2 * y + 1, x), 1),
+        _ => round2(
+            m(2 * y, 2 * x) + m(2 * y, 2 * x + 1) + m(2 * y + 1, 2 * x) + m(2 * y + 1, 2 * x + 1),
+            2,
+        ),
+    }
+}
+
+impl TileDecoder<'_, '_> {
+    /// Predicts the inter blocks, the intra blocks are predicted transform
+    /// block by transform block while reading the residual.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#compute-prediction-process
+    pub(crate) fn compute_prediction(&mut self) -> Result<(), ObuError> {
+        if !self.b.is_inter {
+            return Ok(());
+        }
+
+        // The block warp process, the mask blend of the inter intra
+        // predictions and the overlapped motion compensation are not
+        // implemented.
+        if self.uses_warp() || self.b.interintra || self.b.motion_mode == OBMC {
+            return Err(ObuError::Unsupported);
+        }
+
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        for plane in 0..1 + self.b.has_chroma as usize * 2 {
+            let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+            let plane_sz = SUBSAMPLED_SIZE[self.b.mi_size as usize][sub_x as usize][sub_y as usize];
+            let num4x4_w = NUM_4X4_BLOCKS_WIDE[plane_sz as usize] as u32;
+            let num4x4_h = NUM_4X4_BLOCKS_HIGH[plane_sz as usize] as u32;
+            let base_x = (mi_col >> sub_x) * MI_SIZE as u32;
+            let base_y = (mi_row >> sub_y) * MI_SIZE as u32;
+            let mut cand_row = (mi_row >> sub_y) << sub_y;
+            let mut cand_col = (mi_col >> sub_x) << sub_x;
+
+            // The chroma of the small blocks is predicted with the motion
+            // vectors of each of the luma blocks it covers, unless one of
+            // them is an intra block.
+            let mut pred_w = block_width(self.b.mi_size) >> sub_x;
+            let mut pred_h = block_height(self.b.mi_size) >> sub_y;
+            let row_end = (cand_row + (num4x4_h << sub_y)).min(self.frame.mi_rows);
+            let col_end = (cand_col + (num4x4_w << sub_x)).min(self.frame.mi_cols);
+            let some_use_intra = (cand_row..row_end).any(|r| {
+                (cand_col..col_end).any(|c| self.frame.mi(r, c).ref_frame[0] == INTRA_FRAME)
+            });
+
+            if some_use_intra {
+                pred_w = num4x4_w * 4;
+                pred_h = num4x4_h * 4;
+                cand_row = mi_row;
+                cand_col = mi_col;
+            }
+
+            for (r, y) in (0..num4x4_h * 4).step_by(pred_h as usize).enumerate() {
+                for (c, x) in (0..num4x4_w * 4).step_by(pred_w as usize).enumerate() {
+                    self.predict_inter(
+                        plane,
+                        base_x + x,
+                        base_y + y,
+                        pred_w as usize,
+                        pred_h as usize,
+                        cand_row + r as u32,
+                        cand_col + c as u32,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the current block is predicted with the block warp process,
+    /// with its local warp model or with the global motion of one of its
+    /// references.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#motion-vector-scaling-process
+    fn uses_warp(&self) -> bool {
+        if self.b.motion_mode == LOCALWARP {
+            return true;
+        }
+
+        let is_global = self.b.y_mode == GLOBALMV || self.b.y_mode == GLOBAL_GLOBALMV;
+        if !is_global
+            || self.header.force_integer_mv
+            || block_width(self.b.mi_size).min(block_height(self.b.mi_size)) < 8
+        {
+            return false;
+        }
+
+        let gm = &self.header.global_motion_params;
+        let is_compound = self.b.ref_frame[1] > INTRA_FRAME;
+        self.b.ref_frame[..1 + is_compound as usize]
+            .iter()
+            .any(|&ref_frame| {
+                gm.gm_type[ref_frame as usize] > GmType::Translation
+                    && !self.is_scaled(ref_frame)
+                    && setup_shear(&gm.gm_params[ref_frame as usize]).warp_valid
+            })
+    }
+
+    /// Predicts the `w` by `h` samples at `(x, y)` in the plane `plane` with
+    /// the motion vectors of the block at `(cand_row, cand_col)`.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#predict-inter-process
+    #[allow(clippy::too_many_arguments)]
+    fn predict_inter(
+        &mut self,
+        plane: usize,
+        x: u32,
+        y: u32,
+        w: usize,
+        h: usize,
+        cand_row: u32,
+        cand_col: u32,
+    ) {
+        let cand = *self.frame.mi(cand_row, cand_col);
+        let is_compound = cand.ref_frame[1] > INTRA_FRAME;
+        let bit_depth = self.ctx.bit_depth;
+        let inter_round = [
+            if bit_depth == 12 { 5 } else { 3 },
+            if is_compound {
+                7
+            } else if bit_depth == 12 {
+                9
+            } else {
+                11
+            },
+        ];
+
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+        for ref_list in 0..1 + is_compound as usize {
+            // The intra block copy predicts from the current frame before
+            // the in-loop filters, which are disabled, without scaling.
+            let (reference, ref_upscaled_width, ref_frame_height, ref_width, ref_height) =
+                if self.header.allow_intrabc {
+                    let width = self.frame.mi_cols * MI_SIZE as u32;
+                    let height = self.frame.mi_rows * MI_SIZE as u32;
+                    (
+                        &self.frame.cur_frame,
+                        self.ctx.upscaled_width,
+                        self.ctx.frame_height,
+                        width,
+                        height,
+                    )
+                } else {
+                    let ref_frame = cand.ref_frame[ref_list] - LAST_FRAME;
+                    let ref_idx = self.header.ref_frame_idx[ref_frame as usize] as usize;
+                    (
+                        &self.ctx.frame_store[ref_idx],
+                        self.ctx.ref_upscaled_width[ref_idx],
+                        self.ctx.ref_frame_height[ref_idx],
+                        self.ctx.ref_upscaled_width[ref_idx] as u32,
+                        self.ctx.ref_frame_height[ref_idx] as u32,
+                    )
+                };
+
+            let (start_x, start_y, x_step, y_step) = motion_vector_scaling(
+                (self.ctx.frame_width, self.ctx.frame_height),
+                (ref_upscaled_width, ref_frame_height),
+                (sub_x, sub_y),
+                x,
+                y,
+                cand.mv[ref_list],
+            );
+
+            block_inter_prediction(
+                &mut self.preds[ref_list],
+                &reference.planes[plane],
+                ((ref_width + sub_x) >> sub_x) as i32 - 1,
+                ((ref_height + sub_y) >> sub_y) as i32 - 1,
+                start_x,
+                start_y,
+                x_step,
+                y_step,
+                w,
+                h,
+                cand.interp_filter,
+                inter_round,
+            );
+        }
+
+        let inter_post_round = 2 * FILTER_BITS as u32 - (inter_round[0] + inter_round[1]);
+        let compound_type = self.b.compound_type;
+        if compound_type == COMPOUND_WEDGE && plane == 0 {
+            wedge_mask(
+                &mut self.mask,
+                self.b.mi_size,
+                self.b.wedge_sign,
+                self.b.wedge_index,
+            );
+        } else if compound_type == COMPOUND_DIFFWTD && plane == 0 {
+            difference_weight_mask(
+                &mut self.mask,
+                &self.preds,
+                w,
+                h,
+                self.b.mask_type,
+                bit_depth,
+                inter_post_round,
+            );
+        }
+
+        let (fwd_weight, bck_weight) = if compound_type == COMPOUND_DISTANCE {
+            let dist = cand.ref_frame.map(|ref_frame| {
+                let hint = self.ctx.order_hints[ref_frame as usize];
+                get_relative_dist(self.ctx, hint, self.ctx.order_hint)
+            });
+
+            distance_weights(dist)
+        } else {
+            (0, 0)
+        };
+
+        let max = (1 << bit_depth) - 1;
+        let cur = &mut self.frame.cur_frame.planes[plane];
+        let (x, y) = (x as usize, y as usize);
+        let w = w.min(cur.width - x);
+        let h = h.min(cur.height - y);
+        let preds = self.preds[0].iter().zip(&self.preds[1]).take(h);
+        for (i, (pred0, pred1)) in preds.enumerate() {
+            let row = cur.row_mut(y + i)[x..x + w].iter_mut();
+            for (j, ((v, p0), p1)) in row.zip(pred0).zip(pred1).enumerate() {
+                let p = if !is_compound {
+                    *p0
+                } else if compound_type == COMPOUND_AVERAGE {
+                    round2(p0 + p1, 1 + inter_post_round)
+                } else if compound_type == COMPOUND_DISTANCE {
+                    round2(fwd_weight * p0 + bck_weight * p1, 4 + inter_post_round)
+                } else {
+                    let m = mask_weight(&self.mask, j, i, sub_x, sub_y);
+                    round2(m * p0 + (64 - m) * p1, 6 + inter_post_round)
+                };
+
+                *v = p.clamp(0, max) as u16;
+            }
+        }
+    }
+
+    /// Predicts the transform block of `1 << log2_w` by `1 << log2_h`
+    /// samples at `(x, y)` in the plane `plane` with the intra prediction
+    /// mode `mode`, the edges are read from the samples of the current frame
+    /// that are available.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#intra-prediction-process
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn predict_intra(
+        &mut self,
+        plane: usize,
+        x: u32,
+        y: u32,
+        have_left: bool,
+        have_above: bool,
+        have_above_rt: bool,
+        have_below_lft: bool,
+        mode: u8,
+        log2_w: u32,
+        log2_h: u32,
+    ) {
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+        let max_x = ((self.frame.mi_cols * MI_SIZE as u32) >> sub_x) as usize - 1;
+        let max_y = ((self.frame.mi_rows * MI_SIZE as u32) >> sub_y) as usize - 1;
+        let (x, y) = (x as usize, y as usize);
+        let (w, h) = (1 << log2_w, 1 << log2_h);
+        let bit_depth = self.ctx.bit_depth;
+        let cur = &self.frame.cur_frame.planes[plane];
+
+        let mut above = Edge::default();
+        let mut left = Edge::default();
+        if have_above {
+            let above_limit = max_x.min(x + if have_above_rt { 2 * w } else { w } - 1);
+            for i in 0..w + h {
+                above[i as i32] = cur.get(above_limit.min(x + i), y - 1);
+            }
+        } else {
+            let v = if have_left {
+                cur.get(x - 1, y)
+            } else {
+                (1 << (bit_depth - 1)) - 1
+            };
+
+            for i in 0..w + h {
+                above[i as i32] = v;
+            }
+        }
+
+        if have_left {
+            let left_limit = max_y.min(y + if have_below_lft { 2 * h } else { h } - 1);
+            for i in 0..w + h {
+                left[i as i32] = cur.get(x - 1, left_limit.min(y + i));
+            }
+        } else {
+            let v = if have_above {
+                cur.get(x, y - 1)
+            } else {
+                (1 << (bit_depth - 1)) + 1
+            };
+
+            for i in 0..w + h {
+                left[i as i32] = v;
+            }
+        }
+
+        above[-1] = match (have_above, have_left) {
+            (true, true) => cur.get(x - 1, y - 1),
+            (true, false) => cur.get(x, y - 1),
+            (false, true) => cur.get(x - 1, y),
+            (false, false) => 1 << (bit_depth - 1),
+        };
+
+        left[-1] = above[-1];
+
+        let mut pred: Prediction = [[0; 64]; 64];
+        if plane == 0 && self.b.use_filter_intra {
+            predict_filter_intra(
+                &mut pred,
+                &above,
+                &left,
+                log2_w,
+                log2_h,
+                self.b.filter_intra_mode,
+                bit_depth,
+            );
+        } else if is_directional_mode(mode) {
+            let angle_delta = if plane == 0 {
+                self.b.angle_delta_y
+            } else {
+                self.b.angle_delta_uv
+            };
+
+            let p_angle = MODE_TO_ANGLE[mode as usize] + angle_delta as i32 * ANGLE_STEP as i32;
+
+            let mut upsample_above = false;
+            let mut upsample_left = false;
+            if self.sequence_header.enable_intra_edge_filter {
+                let (w, h) = (w as u32, h as u32);
+                let filter_type = self.get_filter_type(plane);
+                if p_angle != 90 && p_angle != 180 {
+                    if p_angle > 90 && p_angle < 180 && w + h >= 24 {
+                        above[-1] = filter_corner(&above, &left);
+                        left[-1] = above[-1];
+                    }
+
+                    if have_above {
+                        let strength = intra_edge_filter_strength(w, h, filter_type, p_angle - 90);
+                        let num_px =
+                            w.min((max_x - x + 1) as u32) + if p_angle < 90 { h } else { 0 } + 1;
+                        filter_edge(&mut above, num_px as i32, strength);
+                    }
+
+                    if have_left {
+                        let strength = intra_edge_filter_strength(w, h, filter_type, p_angle - 180);
+                        let num_px =
+                            h.min((max_y - y + 1) as u32) + if p_angle > 180 { w } else { 0 } + 1;
+                        filter_edge(&mut left, num_px as i32, strength);
+                    }
+                }
+
+                upsample_above = intra_edge_upsample(w, h, filter_type, p_angle - 90);
+                if upsample_above {
+                    let num_px = w + if p_angle < 90 { h } else { 0 };
+                    upsample_edge(&mut above, num_px as i32, bit_depth);
+                }
+
+                upsample_left = intra_edge_upsample(w, h, filter_type, p_angle - 180);
+                if upsample_left {
+                    let num_px = h + if p_angle > 180 { w } else { 0 };
+                    upsample_edge(&mut left, num_px as i32, bit_depth);
+                }
+            }
+
+            predict_directional(
+                &mut pred,
+                &above,
+                &left,
+                log2_w,
+                log2_h,
+                p_angle,
+                upsample_above,
+                upsample_left,
+            );
+        } else {
+            match mode {
+                SMOOTH_PRED => predict_smooth(&mut pred, &above, &left, log2_w, log2_h),
+                SMOOTH_V_PRED => predict_smooth_v(&mut pred, &above, &left, log2_w, log2_h),
+                SMOOTH_H_PRED => predict_smooth_h(&mut pred, &above, &left, log2_w, log2_h),
+                DC_PRED => predict_dc(
+                    &mut pred, &above, &left, have_above, have_left, log2_w, log2_h, bit_depth,
+                ),
+                _ => predict_paeth(&mut pred, &above, &left, log2_w, log2_h),
+            }
+        }
+
+        self.write_prediction(plane, x, y, w, h, &pred);
+    }
+
+    /// Adds the scaled AC contribution of the reconstructed luma to the DC
+    /// prediction of the chroma transform block at `(x, y)` in the plane
+    /// `plane`.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#predict-chroma-from-luma-process
+    pub(crate) fn predict_chroma_from_luma(&mut self, plane: usize, x: u32, y: u32, tx_sz: u8) {
+        let w = TX_WIDTH[tx_sz as usize] as usize;
+        let h = TX_HEIGHT[tx_sz as usize] as usize;
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+        let alpha = if plane == 1 {
+            self.b.cfl_alpha_u
+        } else {
+            self.b.cfl_alpha_v
+        } as i32;
+
+        // The chroma samples past the decoded luma reuse the last decoded
+        // luma samples.
+        let max_x = ((self.max_luma_w >> sub_x) - 1) as usize;
+        let max_y = ((self.max_luma_h >> sub_y) - 1) as usize;
+        let (x, y) = (x as usize, y as usize);
+        let ac = cfl_luma_ac(
+            &self.frame.cur_frame.planes[0],
+            x,
+            y,
+            TX_WIDTH_LOG2[tx_sz as usize] as u32,
+            TX_HEIGHT_LOG2[tx_sz as usize] as u32,
+            sub_x,
+            sub_y,
+            max_x,
+            max_y,
+        );
+
+        predict_cfl(
+            &mut self.frame.cur_frame.planes[plane],
+            x,
+            y,
+            w,
+            h,
+            &ac,
+            alpha,
+            self.ctx.bit_depth,
+        );
+    }
+
+    /// Whether the neighboring blocks use a smooth prediction, which selects
+    /// the filter type of the intra edge filter.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#intra-filter-type-process
+    fn get_filter_type(&self, plane: usize) -> bool {
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+        let (mi_row, mi_col) = (self.b.mi_row, self.b.mi_col);
+        let (avail_u, avail_l) = if plane == 0 {
+            (self.b.avail_u, self.b.avail_l)
+        } else {
+            (self.b.avail_u_chroma, self.b.avail_l_chroma)
+        };
+
+        let above_smooth = avail_u && {
+            let mut r = mi_row - 1;
+            let mut c = mi_col;
+            if plane > 0 {
+                if sub_x == 1 && mi_col & 1 == 0 {
+                    c += 1;
+                }
+
+                if sub_y == 1 && mi_row & 1 == 1 {
+                    r -= 1;
+                }
+            }
+
+            self.is_smooth(r, c, plane)
+        };
+
+        let left_smooth = avail_l && {
+            let mut r = mi_row;
+            let mut c = mi_col - 1;
+            if plane > 0 {
+                if sub_x == 1 && mi_col & 1 == 1 {
+                    c -= 1;
+                }
+
+                if sub_y == 1 && mi_row & 1 == 0 {
+                    r += 1;
+                }
+            }
+
+            self.is_smooth(r, c, plane)
+        };
+
+        above_smooth || left_smooth
+    }
+
+    /// Whether the block at `(row, col)` uses a smooth prediction in the
+    /// plane `plane`.
+    fn is_smooth(&self, row: u32, col: u32, plane: usize) -> bool {
+        let mi = self.frame.mi(row, col);
+        let mode = if plane == 0 {
+            mi.y_mode
+        } else if mi.ref_frame[0] > INTRA_FRAME {
+            return false;
+        } else {
+            mi.uv_mode
+        };
+
+        matches!(mode, SMOOTH_PRED | SMOOTH_V_PRED | SMOOTH_H_PRED)
+    }
+
+    /// Copies the `w` by `h` predicted samples to `(x, y)` in the plane
+    /// `plane` of the current frame.
+    fn write_prediction(
+        &mut self,
+        plane: usize,
+        x: usize,
+        y: usize,
+        w: usize,
+        h: usize,
+        pred: &Prediction,
+    ) {
+        let cur = &mut self.frame.cur_frame.planes[plane];
+        for (i, pred) in pred.iter().take(h).enumerate() {
+            cur.row_mut(y + i)[x..x + w].copy_from_slice(&pred[..w]);
+        }
+    }
+}
@@ -0,0 +1,1128 @@
+//! The transform sizes of the blocks and the coefficients of their residual.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#residual-syntax
+
+use super::{plane_subsampling, TileDecoder};
+
+use crate::{
+    constants::*,
+    obu::{frame_header::TxMode, ObuError},
+    quantizer::{ac_q, dc_q},
+    quantizer_matrix::{QM_OFFSET, QUANTIZER_MATRIX},
+    scan::{get_default_scan, get_mcol_scan, get_mrow_scan},
+    tables::*,
+    transform::inverse_transform_2d,
+};
+
+/// Returns the transform class of the transform type `tx_type`.
+#[inline]
+pub fn get_tx_class(tx_type: u8) -> u8 {
+    match tx_type {
+        V_DCT | V_ADST | V_FLIPADST => TX_CLASS_VERT,
+        H_DCT | H_ADST | H_FLIPADST => TX_CLASS_HORIZ,
+        _ => TX_CLASS_2D,
+    }
+}
+
+/// The square transform size of `size` by `size` samples.
+fn find_square_tx_size(size: u32) -> u8 {
+    match size {
+        4 => TX_4X4,
+        8 => TX_8X8,
+        16 => TX_16X16,
+        32 => TX_32X32,
+        _ => TX_64X64,
+    }
+}
+
+/// The size of the cdf of eob_pt (eobMultisize, from 0 for eob_pt_16 to 6
+/// for eob_pt_1024) and its context.
+fn eob_pt_ctx(tx_sz: u8, tx_class: u8) -> (u8, usize) {
+    let eob_multisize =
+        TX_WIDTH_LOG2[tx_sz as usize].min(5) + TX_HEIGHT_LOG2[tx_sz as usize].min(5) - 4;
+    (eob_multisize, (tx_class != TX_CLASS_2D) as usize)
+}
+
+/// The context of coeff_base_eob for the last coefficient `c` of the scan.
+fn coeff_base_eob_ctx(tx_sz: u8, c: u32) -> usize {
+    let adj_tx_sz = ADJUSTED_TX_SIZE[tx_sz as usize] as usize;
+    let area = (TX_HEIGHT[adj_tx_sz] as u32) << TX_WIDTH_LOG2[adj_tx_sz];
+    if c == 0 {
+        0
+    } else if c <= area / 8 {
+        1
+    } else if c <= area / 4 {
+        2
+    } else {
+        3
+    }
+}
+
+impl TileDecoder<'_, '_> {
+    /// see: https://aomediacodec.github.io/av1-spec/#block-tx-size-syntax
+    pub(crate) fn read_block_tx_size(&mut self) {
+        let mi_size = self.b.mi_size as usize;
+        let bw4 = NUM_4X4_BLOCKS_WIDE[mi_size] as u32;
+        let bh4 = NUM_4X4_BLOCKS_HIGH[mi_size] as u32;
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        if self.header.tx_mode == TxMode::Select
+            && self.b.mi_size > BLOCK_4X4
+            && self.b.is_inter
+            && !self.b.skip
+            && !self.b.lossless
+        {
+            let max_tx_sz = MAX_TX_SIZE_RECT[mi_size];
+            let tx_w4 = (TX_WIDTH[max_tx_sz as usize] / MI_SIZE) as usize;
+            let tx_h4 = (TX_HEIGHT[max_tx_sz as usize] / MI_SIZE) as usize;
+            for row in (r..r + bh4).step_by(tx_h4) {
+                for col in (c..c + bw4).step_by(tx_w4) {
+                    self.read_var_tx_size(row, col, max_tx_sz, 0);
+                }
+            }
+        } else {
+            self.read_tx_size(!self.b.skip || !self.b.is_inter);
+            for row in r..(r + bh4).min(self.frame.mi_rows) {
+                for col in c..(c + bw4).min(self.frame.mi_cols) {
+                    self.frame.mi_mut(row, col).inter_tx_size = self.b.tx_size;
+                }
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#var-tx-size-syntax
+    fn read_var_tx_size(&mut self, row: u32, col: u32, tx_sz: u8, depth: u8) {
+        if row >= self.frame.mi_rows || col >= self.frame.mi_cols {
+            return;
+        }
+
+        let txfm_split = if tx_sz == TX_4X4 || depth == MAX_VARTX_DEPTH {
+            false
+        } else {
+            // txfm_split	S()
+            let ctx = self.txfm_split_ctx(row, col, tx_sz);
+            self.sd.read_symbol(&mut self.cdf.txfm_split_cdf[ctx]) == 1
+        };
+
+        let w4 = (TX_WIDTH[tx_sz as usize] / MI_SIZE) as u32;
+        let h4 = (TX_HEIGHT[tx_sz as usize] / MI_SIZE) as u32;
+        if txfm_split {
+            let sub_tx_sz = SPLIT_TX_SIZE[tx_sz as usize];
+            let step_w = (TX_WIDTH[sub_tx_sz as usize] / MI_SIZE) as usize;
+            let step_h = (TX_HEIGHT[sub_tx_sz as usize] / MI_SIZE) as usize;
+            for i in (0..h4).step_by(step_h) {
+                for j in (0..w4).step_by(step_w) {
+                    self.read_var_tx_size(row + i, col + j, sub_tx_sz, depth + 1);
+                }
+            }
+        } else {
+            for i in row..(row + h4).min(self.frame.mi_rows) {
+                for j in col..(col + w4).min(self.frame.mi_cols) {
+                    self.frame.mi_mut(i, j).inter_tx_size = tx_sz;
+                }
+            }
+
+            self.b.tx_size = tx_sz;
+        }
+    }
+
+    fn txfm_split_ctx(&self, row: u32, col: u32, tx_sz: u8) -> usize {
+        let above = self.get_above_tx_width(row, col) < TX_WIDTH[tx_sz as usize] as u32;
+        let left = self.get_left_tx_height(row, col) < TX_HEIGHT[tx_sz as usize] as u32;
+        let mi_size = self.b.mi_size;
+        let size = block_width(mi_size).max(block_height(mi_size)).min(64);
+        let max_tx_sz = find_square_tx_size(size);
+        let tx_sz_sqr_up = TX_SIZE_SQR_UP[tx_sz as usize];
+        (tx_sz_sqr_up != max_tx_sz) as usize * 3
+            + (TX_SIZES - 1 - max_tx_sz) as usize * 6
+            + above as usize
+            + left as usize
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#transform-size-syntax
+    fn read_tx_size(&mut self, allow_select: bool) {
+        if self.b.lossless {
+            self.b.tx_size = TX_4X4;
+            return;
+        }
+
+        let mi_size = self.b.mi_size;
+        let max_rect_tx_size = MAX_TX_SIZE_RECT[mi_size as usize];
+        self.b.tx_size = max_rect_tx_size;
+        if mi_size > BLOCK_4X4 && allow_select && self.header.tx_mode == TxMode::Select {
+            // tx_depth	S()
+            let ctx = self.tx_depth_ctx(max_rect_tx_size);
+            let cdf: &mut [u16] = match MAX_TX_DEPTHS[mi_size as usize] {
+                4 => &mut self.cdf.tx_64x64_cdf[ctx],
+                3 => &mut self.cdf.tx_32x32_cdf[ctx],
+                2 => &mut self.cdf.tx_16x16_cdf[ctx],
+                _ => &mut self.cdf.tx_8x8_cdf[ctx],
+            };
+
+            let tx_depth = self.sd.read_symbol(cdf);
+            for _ in 0..tx_depth {
+                self.b.tx_size = SPLIT_TX_SIZE[self.b.tx_size as usize];
+            }
+        }
+    }
+
+    fn tx_depth_ctx(&self, max_rect_tx_size: u8) -> usize {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        let max_tx_width = TX_WIDTH[max_rect_tx_size as usize] as u32;
+        let max_tx_height = TX_HEIGHT[max_rect_tx_size as usize] as u32;
+        let above_w = if self.b.avail_u {
+            let mi = self.frame.mi(r - 1, c);
+            if mi.is_inter {
+                block_width(mi.mi_size)
+            } else {
+                self.get_above_tx_width(r, c)
+            }
+        } else {
+            0
+        };
+
+        let left_h = if self.b.avail_l {
+            let mi = self.frame.mi(r, c - 1);
+            if mi.is_inter {
+                block_height(mi.mi_size)
+            } else {
+                self.get_left_tx_height(r, c)
+            }
+        } else {
+            0
+        };
+
+        (above_w >= max_tx_width) as usize + (left_h >= max_tx_height) as usize
+    }
+
+    /// The width of the transform above the 4x4 block at `(row, col)`.
+    fn get_above_tx_width(&self, row: u32, col: u32) -> u32 {
+        if row == self.b.mi_row {
+            if !self.b.avail_u {
+                return 64;
+            }
+
+            let mi = self.frame.mi(row - 1, col);
+            if mi.skip && mi.is_inter {
+                return block_width(mi.mi_size);
+            }
+        }
+
+        TX_WIDTH[self.frame.mi(row - 1, col).inter_tx_size as usize] as u32
+    }
+
+    /// The height of the transform to the left of the 4x4 block at
+    /// `(row, col)`.
+    fn get_left_tx_height(&self, row: u32, col: u32) -> u32 {
+        if col == self.b.mi_col {
+            if !self.b.avail_l {
+                return 64;
+            }
+
+            let mi = self.frame.mi(row, col - 1);
+            if mi.skip && mi.is_inter {
+                return block_height(mi.mi_size);
+            }
+        }
+
+        TX_HEIGHT[self.frame.mi(row, col - 1).inter_tx_size as usize] as u32
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#reset-block-context-process
+    pub(crate) fn reset_block_context(&mut self, bw4: u32, bh4: u32) {
+        let (r, c) = (self.b.mi_row, self.b.mi_col);
+        for plane in 0..1 + 2 * self.b.has_chroma as usize {
+            let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+            let cols = (c >> sub_x) as usize..((c + bw4) >> sub_x) as usize;
+            self.above_level_context[plane][cols.clone()].fill(0);
+            self.above_dc_context[plane][cols].fill(0);
+
+            let rows = (r >> sub_y) as usize..((r + bh4) >> sub_y) as usize;
+            self.left_level_context[plane][rows.clone()].fill(0);
+            self.left_dc_context[plane][rows].fill(0);
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#residual-syntax
+    pub(crate) fn residual(&mut self) -> Result<(), ObuError> {
+        let mi_size = self.b.mi_size;
+        let width_chunks = (block_width(mi_size) >> 6).max(1);
+        let height_chunks = (block_height(mi_size) >> 6).max(1);
+        let mi_size_chunk = if width_chunks > 1 || height_chunks > 1 {
+            BLOCK_64X64
+        } else {
+            mi_size
+        };
+
+        for chunk_y in 0..height_chunks {
+            for chunk_x in 0..width_chunks {
+                let mi_row_chunk = self.b.mi_row + (chunk_y << 4);
+                let mi_col_chunk = self.b.mi_col + (chunk_x << 4);
+                for plane in 0..1 + 2 * self.b.has_chroma as usize {
+                    let tx_sz = if self.b.lossless {
+                        TX_4X4
+                    } else {
+                        self.get_tx_size(plane, self.b.tx_size)
+                    };
+
+                    let step_x = (TX_WIDTH[tx_sz as usize] >> 2) as usize;
+                    let step_y = (TX_HEIGHT[tx_sz as usize] >> 2) as usize;
+                    let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+                    let plane_sz =
+                        SUBSAMPLED_SIZE[mi_size_chunk as usize][sub_x as usize][sub_y as usize];
+                    let num4x4_w = NUM_4X4_BLOCKS_WIDE[plane_sz as usize] as u32;
+                    let num4x4_h = NUM_4X4_BLOCKS_HIGH[plane_sz as usize] as u32;
+                    if self.b.is_inter && !self.b.lossless && plane == 0 {
+                        let base_x = mi_col_chunk * MI_SIZE as u32;
+                        let base_y = mi_row_chunk * MI_SIZE as u32;
+                        self.transform_tree(base_x, base_y, num4x4_w * 4, num4x4_h * 4)?;
+                    } else {
+                        let base_x_block = (self.b.mi_col >> sub_x) * MI_SIZE as u32;
+                        let base_y_block = (self.b.mi_row >> sub_y) * MI_SIZE as u32;
+                        for y in (0..num4x4_h).step_by(step_y) {
+                            for x in (0..num4x4_w).step_by(step_x) {
+                                self.transform_block(
+                                    plane,
+                                    base_x_block,
+                                    base_y_block,
+                                    tx_sz,
+                                    x + ((chunk_x << 4) >> sub_x),
+                                    y + ((chunk_y << 4) >> sub_y),
+                                )?;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The transform size used by the plane `plane` of the current block.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#get-tx-size-function
+    fn get_tx_size(&self, plane: usize, tx_sz: u8) -> u8 {
+        if plane == 0 {
+            return tx_sz;
+        }
+
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+        let plane_sz = SUBSAMPLED_SIZE[self.b.mi_size as usize][sub_x as usize][sub_y as usize];
+        let uv_tx = MAX_TX_SIZE_RECT[plane_sz as usize];
+        let (w, h) = (TX_WIDTH[uv_tx as usize], TX_HEIGHT[uv_tx as usize]);
+        if w == 64 || h == 64 {
+            if w == 16 {
+                TX_16X32
+            } else if h == 16 {
+                TX_32X16
+            } else {
+                TX_32X32
+            }
+        } else {
+            uv_tx
+        }
+    }
+
+    /// Splits the luma of an inter block into the transform blocks read by
+    /// read_var_tx_size.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#transform-tree-syntax
+    fn transform_tree(
+        &mut self,
+        start_x: u32,
+        start_y: u32,
+        w: u32,
+        h: u32,
+    ) -> Result<(), ObuError> {
+        let max_x = self.frame.mi_cols * MI_SIZE as u32;
+        let max_y = self.frame.mi_rows * MI_SIZE as u32;
+        if start_x >= max_x || start_y >= max_y {
+            return Ok(());
+        }
+
+        let row = start_y >> MI_SIZE_LOG2;
+        let col = start_x >> MI_SIZE_LOG2;
+        let tx_sz = self.frame.mi(row, col).inter_tx_size;
+        if w == TX_WIDTH[tx_sz as usize] as u32 && h == TX_HEIGHT[tx_sz as usize] as u32 {
+            self.transform_block(0, start_x, start_y, tx_sz, 0, 0)
+        } else if w > h {
+            self.transform_tree(start_x, start_y, w / 2, h)?;
+            self.transform_tree(start_x + w / 2, start_y, w / 2, h)
+        } else if w < h {
+            self.transform_tree(start_x, start_y, w, h / 2)?;
+            self.transform_tree(start_x, start_y + h / 2, w, h / 2)
+        } else {
+            self.transform_tree(start_x, start_y, w / 2, h / 2)?;
+            self.transform_tree(start_x + w / 2, start_y, w / 2, h / 2)?;
+            self.transform_tree(start_x, start_y + h / 2, w / 2, h / 2)?;
+            self.transform_tree(start_x + w / 2, start_y + h / 2, w / 2, h / 2)
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#transform-block-syntax
+    fn transform_block(
+        &mut self,
+        plane: usize,
+        base_x: u32,
+        base_y: u32,
+        tx_sz: u8,
+        x: u32,
+        y: u32,
+    ) -> Result<(), ObuError> {
+        let start_x = base_x + 4 * x;
+        let start_y = base_y + 4 * y;
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+        let row = (start_y << sub_y) >> MI_SIZE_LOG2;
+        let col = (start_x << sub_x) >> MI_SIZE_LOG2;
+        let sb_mask = if self.sequence_header.use_128x128_superblock {
+            31
+        } else {
+            15
+        };
+
+        let sub_block_mi_row = ((row & sb_mask) >> sub_y) as i32;
+        let sub_block_mi_col = ((col & sb_mask) >> sub_x) as i32;
+        let step_x = (TX_WIDTH[tx_sz as usize] >> MI_SIZE_LOG2) as i32;
+        let step_y = (TX_HEIGHT[tx_sz as usize] >> MI_SIZE_LOG2) as i32;
+        let max_x = (self.frame.mi_cols * MI_SIZE as u32) >> sub_x;
+        let max_y = (self.frame.mi_rows * MI_SIZE as u32) >> sub_y;
+        if start_x >= max_x || start_y >= max_y {
+            return Ok(());
+        }
+
+        if !self.b.is_inter {
+            let palette_size = if plane == 0 {
+                self.b.palette_size_y
+            } else {
+                self.b.palette_size_uv
+            };
+
+            if palette_size > 0 {
+                self.predict_palette(plane, start_x, start_y, x, y, tx_sz);
+            } else {
+                let is_cfl = plane > 0 && self.b.uv_mode == UV_CFL_PRED;
+                let mode = if plane == 0 {
+                    self.b.y_mode
+                } else if is_cfl {
+                    DC_PRED
+                } else {
+                    self.b.uv_mode
+                };
+
+                let (avail_l, avail_u) = if plane == 0 {
+                    (self.b.avail_l, self.b.avail_u)
+                } else {
+                    (self.b.avail_l_chroma, self.b.avail_u_chroma)
+                };
+
+                self.predict_intra(
+                    plane,
+                    start_x,
+                    start_y,
+                    avail_l || x > 0,
+                    avail_u || y > 0,
+                    self.block_decoded(plane, sub_block_mi_row - 1, sub_block_mi_col + step_x),
+                    self.block_decoded(plane, sub_block_mi_row + step_y, sub_block_mi_col - 1),
+                    mode,
+                    TX_WIDTH_LOG2[tx_sz as usize] as u32,
+                    TX_HEIGHT_LOG2[tx_sz as usize] as u32,
+                );
+
+                if is_cfl {
+                    self.predict_chroma_from_luma(plane, start_x, start_y, tx_sz);
+                }
+            }
+
+            if plane == 0 {
+                self.max_luma_w = start_x + step_x as u32 * 4;
+                self.max_luma_h = start_y + step_y as u32 * 4;
+            }
+        }
+
+        if !self.b.skip {
+            let eob = self.coeffs(start_x, start_y, plane, tx_sz)?;
+            if eob > 0 {
+                self.reconstruct(plane, start_x, start_y, tx_sz);
+            }
+        }
+
+        for i in 0..step_y {
+            for j in 0..step_x {
+                *self.block_decoded_mut(plane, sub_block_mi_row + i, sub_block_mi_col + j) = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the coefficients of a transform block into `self.quant` and
+    /// returns the end of block position.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#coefficients-syntax
+    fn coeffs(
+        &mut self,
+        start_x: u32,
+        start_y: u32,
+        plane: usize,
+        tx_sz: u8,
+    ) -> Result<u32, ObuError> {
+        let x4 = start_x >> 2;
+        let y4 = start_y >> 2;
+        let w4 = (TX_WIDTH[tx_sz as usize] >> 2) as u32;
+        let h4 = (TX_HEIGHT[tx_sz as usize] >> 2) as u32;
+        let tx_sz_ctx =
+            ((TX_SIZE_SQR[tx_sz as usize] + TX_SIZE_SQR_UP[tx_sz as usize] + 1) >> 1) as usize;
+        let ptype = (plane > 0) as usize;
+        let seg_eob = if tx_sz == TX_16X64 || tx_sz == TX_64X16 {
+            512
+        } else {
+            (w4 * h4 * 16).min(1024) as usize
+        };
+
+        self.quant[..seg_eob].fill(0);
+
+        let mut eob = 0;
+        let mut cul_level = 0;
+        let mut dc_category = 0;
+
+        // all_zero	S()
+        let ctx = self.all_zero_ctx(plane, tx_sz, x4, y4, w4, h4);
+        let all_zero = self
+            .sd
+            .read_symbol(&mut self.cdf.coeff.txb_skip_cdf[tx_sz_ctx][ctx])
+            == 1;
+
+        if all_zero {
+            if plane == 0 {
+                self.set_tx_types(x4, y4, w4, h4, DCT_DCT);
+            }
+        } else {
+            if plane == 0 {
+                self.transform_type(x4, y4, tx_sz);
+            }
+
+            self.plane_tx_type = self.compute_tx_type(plane, tx_sz, x4, y4);
+            let tx_class = get_tx_class(self.plane_tx_type);
+            let scan = self.get_scan(tx_sz);
+            // eob_pt_16, eob_pt_32, eob_pt_64, eob_pt_128, eob_pt_256,
+            // eob_pt_512 and eob_pt_1024	S()
+            let (eob_multisize, ctx) = eob_pt_ctx(tx_sz, tx_class);
+            let coeff = &mut self.cdf.coeff;
+            let eob_pt = 1 + match eob_multisize {
+                0 => self.sd.read_symbol(&mut coeff.eob_pt_16_cdf[ptype][ctx]),
+                1 => self.sd.read_symbol(&mut coeff.eob_pt_32_cdf[ptype][ctx]),
+                2 => self.sd.read_symbol(&mut coeff.eob_pt_64_cdf[ptype][ctx]),
+                3 => self.sd.read_symbol(&mut coeff.eob_pt_128_cdf[ptype][ctx]),
+                4 => self.sd.read_symbol(&mut coeff.eob_pt_256_cdf[ptype][ctx]),
+                5 => self.sd.read_symbol(&mut coeff.eob_pt_512_cdf[ptype]),
+                _ => self.sd.read_symbol(&mut coeff.eob_pt_1024_cdf[ptype]),
+            } as u32;
+
+            eob = if eob_pt < 2 {
+                eob_pt
+            } else {
+                (1 << (eob_pt - 2)) + 1
+            };
+
+            if eob_pt >= 3 {
+                // eob_extra	S()
+                let eob_shift = eob_pt - 3;
+                let cdf = &mut coeff.eob_extra_cdf[tx_sz_ctx][ptype][eob_shift as usize];
+                if self.sd.read_symbol(cdf) == 1 {
+                    eob += 1 << eob_shift;
+                }
+
+                for i in 1..eob_pt - 2 {
+                    // eob_extra_bit	L(1)
+                    if self.sd.read_literal(1) == 1 {
+                        eob += 1 << (eob_shift - i);
+                    }
+                }
+            }
+
+            for c in (0..eob).rev() {
+                let pos = scan[c as usize] as usize;
+                let mut level = if c == eob - 1 {
+                    // coeff_base_eob	S()
+                    let ctx = coeff_base_eob_ctx(tx_sz, c);
+                    let cdf = &mut self.cdf.coeff.coeff_base_eob_cdf[tx_sz_ctx][ptype][ctx];
+                    self.sd.read_symbol(cdf) as i32 + 1
+                } else {
+                    // coeff_base	S()
+                    let ctx = self.coeff_base_ctx(tx_sz, tx_class, pos);
+                    let cdf = &mut self.cdf.coeff.coeff_base_cdf[tx_sz_ctx][ptype][ctx];
+                    self.sd.read_symbol(cdf) as i32
+                };
+
+                if level > NUM_BASE_LEVELS as i32 {
+                    for _ in 0..COEFF_BASE_RANGE / (BR_CDF_SIZE - 1) {
+                        // coeff_br	S()
+                        let ctx = self.coeff_br_ctx(tx_sz, tx_class, pos);
+                        let cdf = &mut self.cdf.coeff.coeff_br_cdf
+                            [tx_sz_ctx.min(TX_32X32 as usize)][ptype][ctx];
+                        let coeff_br = self.sd.read_symbol(cdf) as i32;
+                        level += coeff_br;
+                        if coeff_br < (BR_CDF_SIZE - 1) as i32 {
+                            break;
+                        }
+                    }
+                }
+
+                self.quant[pos] = level;
+            }
+
+            for c in 0..eob {
+                let pos = scan[c as usize] as usize;
+                let sign = if self.quant[pos] != 0 {
+                    if c == 0 {
+                        // dc_sign	S()
+                        let ctx = self.dc_sign_ctx(plane, x4, y4, w4, h4);
+                        self.sd
+                            .read_symbol(&mut self.cdf.coeff.dc_sign_cdf[ptype][ctx])
+                            == 1
+                    } else {
+                        // sign_bit	L(1)
+                        self.sd.read_literal(1) == 1
+                    }
+                } else {
+                    false
+                };
+
+                if self.quant[pos] > (NUM_BASE_LEVELS + COEFF_BASE_RANGE) as i32 {
+                    let golomb = self.sd.read_golomb()? as i32;
+                    self.quant[pos] = golomb + (COEFF_BASE_RANGE + NUM_BASE_LEVELS + 1) as i32;
+                }
+
+                if pos == 0 && self.quant[pos] > 0 {
+                    dc_category = if sign { 1 } else { 2 };
+                }
+
+                self.quant[pos] &= 0xFFFFF;
+                cul_level += self.quant[pos];
+                if sign {
+                    self.quant[pos] = -self.quant[pos];
+                }
+            }
+
+            cul_level = cul_level.min(63);
+        }
+
+        for i in x4..x4 + w4 {
+            self.above_level_context[plane][i as usize] = cul_level as u8;
+            self.above_dc_context[plane][i as usize] = dc_category;
+        }
+
+        for i in y4..y4 + h4 {
+            self.left_level_context[plane][i as usize] = cul_level as u8;
+            self.left_dc_context[plane][i as usize] = dc_category;
+        }
+
+        Ok(eob)
+    }
+
+    /// The number of 4x4 blocks of the plane `plane` in a row and in a column
+    /// of the frame.
+    #[inline]
+    fn max_4x4(&self, plane: usize) -> (u32, u32) {
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+        (self.frame.mi_cols >> sub_x, self.frame.mi_rows >> sub_y)
+    }
+
+    /// The context of all_zero.
+    fn all_zero_ctx(&self, plane: usize, tx_sz: u8, x4: u32, y4: u32, w4: u32, h4: u32) -> usize {
+        let (max_x4, max_y4) = self.max_4x4(plane);
+        let cols = x4 as usize..(x4 + w4).min(max_x4) as usize;
+        let rows = y4 as usize..(y4 + h4).min(max_y4) as usize;
+        let w = TX_WIDTH[tx_sz as usize] as u32;
+        let h = TX_HEIGHT[tx_sz as usize] as u32;
+        let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+        let bsize = SUBSAMPLED_SIZE[self.b.mi_size as usize][sub_x as usize][sub_y as usize];
+        let (bw, bh) = (block_width(bsize), block_height(bsize));
+
+        if plane == 0 {
+            let top = self.above_level_context[plane][cols]
+                .iter()
+                .fold(0, |top, v| top.max(*v));
+            let left = self.left_level_context[plane][rows]
+                .iter()
+                .fold(0, |left, v| left.max(*v));
+
+            if bw == w && bh == h {
+                0
+            } else if top == 0 && left == 0 {
+                1
+            } else if top == 0 || left == 0 {
+                2 + (top.max(left) > 3) as usize
+            } else if top.max(left) <= 3 {
+                4
+            } else if top.min(left) <= 3 {
+                5
+            } else {
+                6
+            }
+        } else {
+            let above = self.above_level_context[plane][cols.clone()]
+                .iter()
+                .chain(&self.above_dc_context[plane][cols])
+                .any(|v| *v != 0);
+            let left = self.left_level_context[plane][rows.clone()]
+                .iter()
+                .chain(&self.left_dc_context[plane][rows])
+                .any(|v| *v != 0);
+
+            let mut ctx = 7 + above as usize + left as usize;
+            if bw * bh > w * h {
+                ctx += 3;
+            }
+
+            ctx
+        }
+    }
+
+    /// The context of dc_sign.
+    fn dc_sign_ctx(&self, plane: usize, x4: u32, y4: u32, w4: u32, h4: u32) -> usize {
+        let (max_x4, max_y4) = self.max_4x4(plane);
+        let cols = x4 as usize..(x4 + w4).min(max_x4) as usize;
+        let rows = y4 as usize..(y4 + h4).min(max_y4) as usize;
+        let dc_sign = self.above_dc_context[plane][cols]
+            .iter()
+            .chain(&self.left_dc_context[plane][rows])
+            .fold(0i32, |dc_sign, v| match v {
+                1 => dc_sign - 1,
+                2 => dc_sign + 1,
+                _ => dc_sign,
+            });
+
+        match dc_sign.cmp(&0) {
+            std::cmp::Ordering::Less => 1,
+            std::cmp::Ordering::Greater => 2,
+            std::cmp::Ordering::Equal => 0,
+        }
+    }
+
+    /// Sets the transform type of the 4x4 luma blocks covered by a
+    /// transform block.
+    fn set_tx_types(&mut self, x4: u32, y4: u32, w4: u32, h4: u32, tx_type: u8) {
+        let mi_cols = self.frame.mi_cols;
+        for row in y4..(y4 + h4).min(self.frame.mi_rows) {
+            for col in x4..(x4 + w4).min(mi_cols) {
+                self.tx_types[(row * mi_cols + col) as usize] = tx_type;
+            }
+        }
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#transform-type-syntax
+    fn transform_type(&mut self, x4: u32, y4: u32, tx_sz: u8) {
+        let set = self.get_tx_set(tx_sz);
+        let qidx = self.header.segmentation_params.qidx(
+            self.b.segment_id,
+            self.header.quantization_params.base_q_idx,
+        );
+
+        let tx_type = if set > 0 && qidx > 0 {
+            let tx_sz_sqr = TX_SIZE_SQR[tx_sz as usize] as usize;
+            if self.b.is_inter {
+                // inter_tx_type	S()
+                match set {
+                    TX_SET_INTER_1 => {
+                        let cdf = &mut self.cdf.inter_tx_type_set1_cdf[tx_sz_sqr];
+                        TX_TYPE_INTER_INV_SET1[self.sd.read_symbol(cdf)]
+                    }
+                    TX_SET_INTER_2 => {
+                        let cdf = &mut self.cdf.inter_tx_type_set2_cdf;
+                        TX_TYPE_INTER_INV_SET2[self.sd.read_symbol(cdf)]
+                    }
+                    _ => {
+                        let cdf = &mut self.cdf.inter_tx_type_set3_cdf[tx_sz_sqr];
+                        TX_TYPE_INTER_INV_SET3[self.sd.read_symbol(cdf)]
+                    }
+                }
+            } else {
+                // intra_tx_type	S()
+                let intra_dir = if self.b.use_filter_intra {
+                    FILTER_INTRA_MODE_TO_INTRA_DIR[self.b.filter_intra_mode as usize]
+                } else {
+                    self.b.y_mode
+                } as usize;
+
+                if set == TX_SET_INTRA_1 {
+                    let cdf = &mut self.cdf.intra_tx_type_set1_cdf[tx_sz_sqr][intra_dir];
+                    TX_TYPE_INTRA_INV_SET1[self.sd.read_symbol(cdf)]
+                } else {
+                    let cdf = &mut self.cdf.intra_tx_type_set2_cdf[tx_sz_sqr][intra_dir];
+                    TX_TYPE_INTRA_INV_SET2[self.sd.read_symbol(cdf)]
+                }
+            }
+        } else {
+            DCT_DCT
+        };
+
+        let w4 = (TX_WIDTH[tx_sz as usize] >> 2) as u32;
+        let h4 = (TX_HEIGHT[tx_sz as usize] >> 2) as u32;
+        self.set_tx_types(x4, y4, w4, h4, tx_type);
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#get-transform-set-function
+    fn get_tx_set(&self, tx_sz: u8) -> u8 {
+        let tx_sz_sqr = TX_SIZE_SQR[tx_sz as usize];
+        let tx_sz_sqr_up = TX_SIZE_SQR_UP[tx_sz as usize];
+        let reduced_tx_set = self.header.reduced_tx_set;
+        if tx_sz_sqr_up > TX_32X32 {
+            TX_SET_DCTONLY
+        } else if self.b.is_inter {
+            if reduced_tx_set || tx_sz_sqr_up == TX_32X32 {
+                TX_SET_INTER_3
+            } else if tx_sz_sqr == TX_16X16 {
+                TX_SET_INTER_2
+            } else {
+                TX_SET_INTER_1
+            }
+        } else if tx_sz_sqr_up == TX_32X32 {
+            TX_SET_DCTONLY
+        } else if reduced_tx_set || tx_sz_sqr == TX_16X16 {
+            TX_SET_INTRA_2
+        } else {
+            TX_SET_INTRA_1
+        }
+    }
+
+    /// Returns whether `tx_type` belongs to the transform set `tx_set` of
+    /// the current block.
+    fn is_tx_type_in_set(&self, tx_set: u8, tx_type: u8) -> bool {
+        let set: &[u8] = match (self.b.is_inter, tx_set) {
+            (_, TX_SET_DCTONLY) => &[DCT_DCT],
+            (true, TX_SET_INTER_1) => &TX_TYPE_INTER_INV_SET1,
+            (true, TX_SET_INTER_2) => &TX_TYPE_INTER_INV_SET2,
+            (true, _) => &TX_TYPE_INTER_INV_SET3,
+            (false, TX_SET_INTRA_1) => &TX_TYPE_INTRA_INV_SET1,
+            (false, _) => &TX_TYPE_INTRA_INV_SET2,
+        };
+
+        set.contains(&tx_type)
+    }
+
+    /// see: https://aomediacodec.github.io/av1-spec/#compute-transform-type-function
+    fn compute_tx_type(&self, plane: usize, tx_sz: u8, block_x: u32, block_y: u32) -> u8 {
+        if self.b.lossless || TX_SIZE_SQR_UP[tx_sz as usize] > TX_32X32 {
+            return DCT_DCT;
+        }
+
+        let mi_cols = self.frame.mi_cols;
+        if plane == 0 {
+            return self.tx_types[(block_y * mi_cols + block_x) as usize];
+        }
+
+        let tx_type = if self.b.is_inter {
+            let (sub_x, sub_y) = plane_subsampling(self.sequence_header, plane);
+            let x4 = self.b.mi_col.max(block_x << sub_x);
+            let y4 = self.b.mi_row.max(block_y << sub_y);
+            self.tx_types[(y4 * mi_cols + x4) as usize]
+        } else {
+            MODE_TO_TXFM[self.b.uv_mode as usize]
+        };
+
+        if self.is_tx_type_in_set(self.get_tx_set(tx_sz), tx_type) {
+            tx_type
+        } else {
+            DCT_DCT
+        }
+    }
+
+    /// The scan order of the coefficients of the transform block.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#get-scan-function
+    fn get_scan(&self, tx_sz: u8) -> &'static [u16] {
+        if TX_SIZE_SQR_UP[tx_sz as usize] == TX_64X64 {
+            return get_default_scan(tx_sz);
+        }
+
+        match self.plane_tx_type {
+            V_DCT | V_ADST | V_FLIPADST => get_mrow_scan(tx_sz),
+            H_DCT | H_ADST | H_FLIPADST => get_mcol_scan(tx_sz),
+            _ => get_default_scan(tx_sz),
+        }
+    }
+
+    /// The context of coeff_base for the coefficient at `pos`.
+    fn coeff_base_ctx(&self, tx_sz: u8, tx_class: u8, pos: usize) -> usize {
+        let adj_tx_sz = ADJUSTED_TX_SIZE[tx_sz as usize] as usize;
+        let bwl = TX_WIDTH_LOG2[adj_tx_sz] as usize;
+        let txh = TX_HEIGHT[adj_tx_sz] as usize;
+        let row = pos >> bwl;
+        let col = pos - (row << bwl);
+        let mut mag = 0;
+        for [row_offset, col_offset] in SIG_REF_DIFF_OFFSET[tx_class as usize] {
+            let ref_row = row + row_offset as usize;
+            let ref_col = col + col_offset as usize;
+            if ref_row < txh && ref_col < (1 << bwl) {
+                mag += self.quant[(ref_row << bwl) + ref_col].min(3) as usize;
+            }
+        }
+
+        let ctx = ((mag + 1) >> 1).min(4);
+        if tx_class == TX_CLASS_2D {
+            if row == 0 && col == 0 {
+                return 0;
+            }
+
+            return ctx + COEFF_BASE_CTX_OFFSET[tx_sz as usize][row.min(4)][col.min(4)] as usize;
+        }
+
+        let idx = if tx_class == TX_CLASS_VERT { row } else { col };
+        ctx + COEFF_BASE_POS_CTX_OFFSET[idx.min(2)] as usize
+    }
+
+    /// The context of coeff_br for the coefficient at `pos`.
+    fn coeff_br_ctx(&self, tx_sz: u8, tx_class: u8, pos: usize) -> usize {
+        let adj_tx_sz = ADJUSTED_TX_SIZE[tx_sz as usize] as usize;
+        let bwl = TX_WIDTH_LOG2[adj_tx_sz] as usize;
+        let txh = TX_HEIGHT[adj_tx_sz] as usize;
+        let row = pos >> bwl;
+        let col = pos - (row << bwl);
+        let mut mag = 0;
+        for [row_offset, col_offset] in MAG_REF_OFFSET_WITH_TX_CLASS[tx_class as usize] {
+            let ref_row = row + row_offset as usize;
+            let ref_col = col + col_offset as usize;
+            if ref_row < txh && ref_col < (1 << bwl) {
+                mag += self.quant[(ref_row << bwl) + ref_col]
+                    .min((COEFF_BASE_RANGE + NUM_BASE_LEVELS + 1) as i32)
+                    as usize;
+            }
+        }
+
+        let mag = ((mag + 1) >> 1).min(6);
+        if pos == 0 {
+            mag
+        } else if tx_class == TX_CLASS_2D {
+            if row < 2 && col < 2 {
+                mag + 7
+            } else {
+                mag + 14
+            }
+        } else if (tx_class == TX_CLASS_HORIZ && col == 0)
+            || (tx_class == TX_CLASS_VERT && row == 0)
+        {
+            mag + 7
+        } else {
+            mag + 14
+        }
+    }
+
+    /// Dequantizes the coefficients of the transform block from `self.quant`
+    /// into `self.dequant`.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#dequantization-functions
+    fn dequantize(&mut self, plane: usize, tx_sz: u8) {
+        let bit_depth = self.ctx.bit_depth;
+        let params = &self.header.quantization_params;
+        let (delta_dc, delta_ac) = match plane {
+            0 => (params.delta_q_y_dc, 0),
+            1 => (params.delta_q_u_dc, params.delta_q_u_ac),
+            _ => (params.delta_q_v_dc, params.delta_q_v_ac),
+        };
+
+        // get_qidx( 0, segment_id ), CurrentQIndex is base_q_idx when
+        // delta_q_present is equal to 0.
+        let segment_id = self.b.segment_id;
+        let qindex = self
+            .header
+            .segmentation_params
+            .qidx(segment_id, self.current_q_index) as i32;
+
+        let dc_quant = dc_q(bit_depth, qindex + delta_dc as i32);
+        let ac_quant = ac_q(bit_depth, qindex + delta_ac as i32);
+
+        let w = TX_WIDTH[tx_sz as usize] as usize;
+        let h = TX_HEIGHT[tx_sz as usize] as usize;
+        let tw = w.min(32);
+        let th = h.min(32);
+        let dq_denom = (w * h > 256) as u32 + (w * h > 1024) as u32;
+        let qm_level = self.header.seg_qm_level[plane][segment_id as usize] as usize;
+        let qm = (params.using_qmatrix && self.plane_tx_type < IDTX && qm_level < 15).then(|| {
+            &QUANTIZER_MATRIX[qm_level][(plane > 0) as usize][QM_OFFSET[tx_sz as usize] as usize..]
+        });
+
+        let max = 1 << (7 + bit_depth);
+        for i in 0..h {
+            for j in 0..w {
+                self.dequant[i][j] = if i < th && j < tw {
+                    let coef = self.quant[i * tw + j];
+                    let mut q = if i == 0 && j == 0 { dc_quant } else { ac_quant };
+                    if let Some(qm) = qm {
+                        q = (q * qm[i * tw + j] as i32 + (1 << (AOM_QM_BITS - 1))) >> AOM_QM_BITS;
+                    }
+
+                    let dq = ((coef.unsigned_abs() as u64 * q as u64) & 0xFFFFFF) >> dq_denom;
+                    let dq = if coef < 0 { -(dq as i32) } else { dq as i32 };
+                    dq.clamp(-max, max - 1)
+                } else {
+                    0
+                };
+            }
+        }
+    }
+
+    /// Adds the residual of the transform block at `(x, y)` in the plane
+    /// `plane` to its prediction in the current frame.
+    ///
+    /// see: https://aomediacodec.github.io/av1-spec/#reconstruction-process
+    fn reconstruct(&mut self, plane: usize, x: u32, y: u32, tx_sz: u8) {
+        let bit_depth = self.ctx.bit_depth;
+        self.dequantize(plane, tx_sz);
+        inverse_transform_2d(
+            &self.dequant,
+            &mut self.residual,
+            tx_sz,
+            self.plane_tx_type,
+            self.b.lossless,
+            bit_depth,
+        );
+
+        let cur = &mut self.frame.cur_frame.planes[plane];
+        let (x, y) = (x as usize, y as usize);
+        let w = TX_WIDTH[tx_sz as usize] as usize;
+        let h = TX_HEIGHT[tx_sz as usize] as usize;
+        let max = (1 << bit_depth) - 1;
+        for (i, residual) in self.residual.iter().take(h).enumerate() {
+            for (v, r) in cur.row_mut(y + i)[x..x + w].iter_mut().zip(residual) {
+                *v = (*v as i32 + r).clamp(0, max) as u16;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        obu::frame_header::UncompressedHeader,
+        scan::{DEFAULT_SCAN_16X32, DEFAULT_SCAN_8X8, MCOL_SCAN_8X8, MROW_SCAN_8X8},
+        tile::{mode_info::FrameState, testing},
+    };
+
+    /// Runs `f` with the decoder of a 64x64 frame.
+    fn with_decoder(f: impl FnOnce(&mut TileDecoder)) {
+        let ctx = testing::context(UncompressedHeader::default(), 16, 16);
+        let mut frame = FrameState::new(16, 16);
+        f(&mut testing::decoder(&ctx, &mut frame));
+    }
+
+    #[test]
+    fn eob_pt_depends_on_the_area_and_the_class() {
+        assert_eq!(eob_pt_ctx(TX_4X4, TX_CLASS_2D), (0, 0));
+        assert_eq!(eob_pt_ctx(TX_4X8, TX_CLASS_2D), (1, 0));
+        assert_eq!(eob_pt_ctx(TX_8X8, TX_CLASS_VERT), (2, 1));
+        assert_eq!(eob_pt_ctx(TX_4X16, TX_CLASS_HORIZ), (2, 1));
+        assert_eq!(eob_pt_ctx(TX_16X16, TX_CLASS_2D), (4, 0));
+        assert_eq!(eob_pt_ctx(TX_32X32, TX_CLASS_2D), (6, 0));
+        // The 64 sample wide or high sizes only code their top left 32x32
+        // coefficients.
+        assert_eq!(eob_pt_ctx(TX_16X64, TX_CLASS_2D), (5, 0));
+        assert_eq!(eob_pt_ctx(TX_64X64, TX_CLASS_2D), (6, 0));
+    }
+
+    #[test]
+    fn coeff_base_eob_depends_on_the_position_in_the_scan() {
+        let ctxs = |tx_sz, c: [u32; 6]| c.map(|c| coeff_base_eob_ctx(tx_sz, c));
+        assert_eq!(ctxs(TX_8X8, [0, 1, 8, 9, 16, 17]), [0, 1, 1, 2, 2, 3]);
+        assert_eq!(ctxs(TX_4X4, [0, 1, 2, 3, 4, 5]), [0, 1, 1, 2, 2, 3]);
+        assert_eq!(coeff_base_eob_ctx(TX_64X64, 128), 1);
+        assert_eq!(coeff_base_eob_ctx(TX_64X64, 129), 2);
+    }
+
+    #[test]
+    fn coeff_base_depends_on_the_neighbors_and_the_position() {
+        with_decoder(|decoder| {
+            // The dc coefficient has its own context.
+            decoder.quant[1] = 3;
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_2D, 0), 0);
+            decoder.quant[1] = 0;
+
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_2D, 1), 1);
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_2D, 5), 6);
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_2D, 15), 21);
+
+            // The magnitudes to the right and below, clamped to 3.
+            decoder.quant[2] = 5;
+            decoder.quant[5] = 1;
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_2D, 1), 2 + 1);
+            for pos in [3, 6, 9] {
+                decoder.quant[pos] = 3;
+            }
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_2D, 1), 4 + 1);
+
+            // The one dimensional classes use the row or the column.
+            decoder.quant.fill(0);
+            decoder.quant[8] = 1;
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_VERT, 0), 1 + 26);
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_VERT, 4), 1 + 31);
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_VERT, 12), 36);
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_HORIZ, 4), 1 + 26);
+            assert_eq!(decoder.coeff_base_ctx(TX_4X4, TX_CLASS_HORIZ, 1), 31);
+        });
+    }
+
+    #[test]
+    fn coeff_br_depends_on_the_neighbors_and_the_position() {
+        with_decoder(|decoder| {
+            // The magnitudes are clamped to 15 and the sum to 6.
+            decoder.quant[1] = 15;
+            decoder.quant[8] = 20;
+            decoder.quant[9] = 3;
+            assert_eq!(decoder.coeff_br_ctx(TX_8X8, TX_CLASS_2D, 0), 6);
+            decoder.quant.fill(0);
+
+            decoder.quant[10] = 4;
+            assert_eq!(decoder.coeff_br_ctx(TX_8X8, TX_CLASS_2D, 9), 2 + 7);
+            assert_eq!(decoder.coeff_br_ctx(TX_8X8, TX_CLASS_2D, 18), 14);
+            decoder.quant.fill(0);
+
+            assert_eq!(decoder.coeff_br_ctx(TX_8X8, TX_CLASS_HORIZ, 8), 7);
+            assert_eq!(decoder.coeff_br_ctx(TX_8X8, TX_CLASS_HORIZ, 1), 14);
+            assert_eq!(decoder.coeff_br_ctx(TX_8X8, TX_CLASS_VERT, 1), 7);
+            assert_eq!(decoder.coeff_br_ctx(TX_8X8, TX_CLASS_VERT, 8), 14);
+        });
+    }
+
+    #[test]
+    fn dc_sign_counts_the_signs_of_the_neighbors() {
+        with_decoder(|decoder| {
+            assert_eq!(decoder.dc_sign_ctx(0, 4, 2, 2, 2), 0);
+
+            // 1 is a negative dc coefficient and 2 a positive one.
+            decoder.above_dc_context[0][4..6].copy_from_slice(&[1, 1]);
+            decoder.left_dc_context[0][2] = 2;
+            assert_eq!(decoder.dc_sign_ctx(0, 4, 2, 2, 2), 1);
+            decoder.left_dc_context[0][3] = 2;
+            assert_eq!(decoder.dc_sign_ctx(0, 4, 2, 2, 2), 0);
+            decoder.above_dc_context[0][5] = 2;
+            assert_eq!(decoder.dc_sign_ctx(0, 4, 2, 2, 2), 2);
+
+            // The contexts past the edge of the plane are ignored.
+            decoder.above_dc_context[1][8] = 2;
+            assert_eq!(decoder.dc_sign_ctx(1, 6, 0, 4, 1), 0);
+            decoder.above_dc_context[1][7] = 1;
+            assert_eq!(decoder.dc_sign_ctx(1, 6, 0, 4, 1), 1);
+        });
+    }
+
+    #[test]
+    fn scan_follows_the_transform_type() {
+        with_decoder(|decoder| {
+            for (tx_type, scan) in [
+                (DCT_DCT, &DEFAULT_SCAN_8X8),
+                (IDTX, &DEFAULT_SCAN_8X8),
+                (V_DCT, &MROW_SCAN_8X8),
+                (V_FLIPADST, &MROW_SCAN_8X8),
+                (H_ADST, &MCOL_SCAN_8X8),
+            ] {
+                decoder.plane_tx_type = tx_type;
+                assert_eq!(decoder.get_scan(TX_8X8), scan);
+            }
+
+            decoder.plane_tx_type = DCT_DCT;
+            assert_eq!(decoder.get_scan(TX_16X64), &DEFAULT_SCAN_16X32);
+        });
+    }
+}
@@ -0,0 +1,613 @@
+//! The 1D inverse transforms and the 2D inverse transform process that
+//! turns the dequantized coefficients of a transform block into residuals.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#inverse-transform-process
+
+use crate::{constants::*, tables::*};
+
+#[inline]
+fn round2(x: i64, n: u32) -> i64 {
+    if n == 0 {
+        x
+    } else {
+        (x + (1 << (n - 1))) >> n
+    }
+}
+
+/// Reverses the order of the lowest `num_bits` bits of `x`.
+#[inline]
+fn brev(num_bits: u32, x: usize) -> usize {
+    x.reverse_bits() >> (usize::BITS - num_bits)
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#butterfly-functions
+#[inline]
+fn cos128(angle: i32) -> i64 {
+    let angle2 = angle & 255;
+    (match angle2 {
+        0..=64 => COS128_LOOKUP[angle2 as usize],
+        65..=128 => -COS128_LOOKUP[(128 - angle2) as usize],
+        129..=192 => -COS128_LOOKUP[(angle2 - 128) as usize],
+        _ => COS128_LOOKUP[(256 - angle2) as usize],
+    }) as i64
+}
+
+#[inline]
+fn sin128(angle: i32) -> i64 {
+    cos128(angle - 64)
+}
+
+/// The butterfly rotation of `t[a]` and `t[b]` by `angle`, the results are
+/// exchanged when `flip` is set.
+#[inline]
+fn b(t: &mut [i32], a: usize, b: usize, angle: i32, flip: bool) {
+    let (ta, tb) = (t[a] as i64, t[b] as i64);
+    let x = ta * cos128(angle) - tb * sin128(angle);
+    let y = ta * sin128(angle) + tb * cos128(angle);
+    t[a] = round2(x, 12) as i32;
+    t[b] = round2(y, 12) as i32;
+    if flip {
+        t.swap(a, b);
+    }
+}
+
+/// The Hadamard rotation of `t[a]` and `t[b]`, the inputs are exchanged
+/// when `flip` is set.
+#[inline]
+fn h(t: &mut [i32], a: usize, b: usize, flip: bool) {
+    let (a, b) = if flip { (b, a) } else { (a, b) };
+    let (x, y) = (t[a], t[b]);
+    t[a] = x.wrapping_add(y);
+    t[b] = x.wrapping_sub(y);
+}
+
+/// Applies the inverse DCT of `1 << n` points in place, `n` is 2 to 6.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-dct-process
+pub fn inverse_dct(t: &mut [i32], n: u32) {
+    let n0 = 1 << n;
+
+    // The inverse DCT array permutation process.
+    let mut copy_t = [0; 64];
+    copy_t[..n0].copy_from_slice(&t[..n0]);
+    for (i, v) in t[..n0].iter_mut().enumerate() {
+        *v = copy_t[brev(n, i)];
+    }
+
+    if n == 6 {
+        for i in 0..16 {
+            b(t, 32 + i, 63 - i, 63 - 4 * brev(4, i) as i32, false);
+        }
+    }
+
+    if n >= 5 {
+        for i in 0..8 {
+            b(t, 16 + i, 31 - i, 6 + ((brev(3, 7 - i) as i32) << 3), false);
+        }
+    }
+
+    if n == 6 {
+        for i in 0..16 {
+            h(t, 32 + i * 2, 33 + i * 2, i & 1 == 1);
+        }
+    }
+
+    if n >= 4 {
+        for i in 0..4 {
+            b(t, 8 + i, 15 - i, 12 + ((brev(2, 3 - i) as i32) << 4), false);
+        }
+    }
+
+    if n >= 5 {
+        for i in 0..8 {
+            h(t, 16 + 2 * i, 17 + 2 * i, i & 1 == 1);
+        }
+    }
+
+    if n == 6 {
+        for i in 0..4 {
+            for j in 0..2 {
+                let angle = 60 - 16 * brev(2, i) as i32 + 64 * j as i32;
+                b(t, 62 - i * 4 - j, 33 + i * 4 + j, angle, true);
+            }
+        }
+    }
+
+    if n >= 3 {
+        for i in 0..2 {
+            b(t, 4 + i, 7 - i, 56 - 32 * i as i32, false);
+        }
+    }
+
+    if n >= 4 {
+        for i in 0..4 {
+            h(t, 8 + 2 * i, 9 + 2 * i, i & 1 == 1);
+        }
+    }
+
+    if n >= 5 {
+        for i in 0..2 {
+            for j in 0..2 {
+                let angle = 24 + ((j as i32) << 6) + ((1 - i as i32) << 5);
+                b(t, 30 - 4 * i - j, 17 + 4 * i + j, angle, true);
+            }
+        }
+    }
+
+    if n == 6 {
+        for i in 0..8 {
+            for j in 0..2 {
+                h(t, 32 + i * 4 + j, 35 + i * 4 - j, i & 1 == 1);
+            }
+        }
+    }
+
+    for i in 0..2 {
+        b(t, 2 * i, 1 + 2 * i, 32 + 16 * i as i32, i == 0);
+    }
+
+    if n >= 3 {
+        for i in 0..2 {
+            h(t, 4 + 2 * i, 5 + 2 * i, i == 1);
+        }
+    }
+
+    if n >= 4 {
+        for i in 0..2 {
+            b(t, 14 - i, 9 + i, 48 + 64 * i as i32, true);
+        }
+    }
+
+    if n >= 5 {
+        for i in 0..4 {
+            for j in 0..2 {
+                h(t, 16 + 4 * i + j, 19 + 4 * i - j, i & 1 == 1);
+            }
+        }
+    }
+
+    if n == 6 {
+        for i in 0..2 {
+            for j in 0..4 {
+                let angle = 56 - 32 * i as i32 + (j as i32 >> 1) * 64;
+                b(t, 61 - i * 8 - j, 34 + i * 8 + j, angle, true);
+            }
+        }
+    }
+
+    for i in 0..2 {
+        h(t, i, 3 - i, false);
+    }
+
+    if n >= 3 {
+        b(t, 6, 5, 32, true);
+    }
+
+    if n >= 4 {
+        for i in 0..2 {
+            for j in 0..2 {
+                h(t, 8 + 4 * i + j, 11 + 4 * i - j, i == 1);
+            }
+        }
+    }
+
+    if n >= 5 {
+        for i in 0..4 {
+            b(t, 29 - i, 18 + i, 48 + (i as i32 >> 1) * 64, true);
+        }
+    }
+
+    if n == 6 {
+        for i in 0..4 {
+            for j in 0..4 {
+                h(t, 32 + 8 * i + j, 39 + 8 * i - j, i & 1 == 1);
+            }
+        }
+    }
+
+    if n >= 3 {
+        for i in 0..4 {
+            h(t, i, 7 - i, false);
+        }
+    }
+
+    if n >= 4 {
+        for i in 0..2 {
+            b(t, 13 - i, 10 + i, 32, true);
+        }
+    }
+
+    if n >= 5 {
+        for i in 0..2 {
+            for j in 0..4 {
+                h(t, 16 + i * 8 + j, 23 + i * 8 - j, i == 1);
+            }
+        }
+    }
+
+    if n == 6 {
+        for i in 0..8 {
+            b(t, 59 - i, 36 + i, if i < 4 { 48 } else { 112 }, true);
+        }
+    }
+
+    if n >= 4 {
+        for i in 0..8 {
+            h(t, i, 15 - i, false);
+        }
+    }
+
+    if n >= 5 {
+        for i in 0..4 {
+            b(t, 27 - i, 20 + i, 32, true);
+        }
+    }
+
+    if n == 6 {
+        for i in 0..8 {
+            h(t, 32 + i, 47 - i, false);
+            h(t, 48 + i, 63 - i, true);
+        }
+    }
+
+    if n >= 5 {
+        for i in 0..16 {
+            h(t, i, 31 - i, false);
+        }
+    }
+
+    if n == 6 {
+        for i in 0..8 {
+            b(t, 55 - i, 40 + i, 32, true);
+        }
+    }
+
+    if n == 6 {
+        for i in 0..32 {
+            h(t, i, 63 - i, false);
+        }
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-adst4-process
+fn inverse_adst4(t: &mut [i32]) {
+    let [x0, x1, x2, x3] = [t[0], t[1], t[2], t[3]].map(|x| x as i64);
+    let sinpi = |i: usize| SINPI_LOOKUP[i - 1] as i64;
+
+    let mut s0 = sinpi(1) * x0;
+    let mut s1 = sinpi(2) * x0;
+    let mut s2 = sinpi(3) * x1;
+    let mut s3 = sinpi(4) * x2;
+    let s4 = sinpi(1) * x2;
+    let s5 = sinpi(2) * x3;
+    let s6 = sinpi(4) * x3;
+
+    let a7 = x0 - x2;
+    let b7 = a7 + x3;
+
+    s0 += s3;
+    s1 -= s4;
+    s3 = s2;
+    s2 = sinpi(3) * b7;
+
+    s0 += s5;
+    s1 -= s6;
+
+    let x0 = s0 + s3;
+    let x1 = s1 + s3;
+    let x2 = s2;
+    let x3 = s0 + s1 - s3;
+
+    for (v, x) in t.iter_mut().zip([x0, x1, x2, x3]) {
+        *v = round2(x, 12) as i32;
+    }
+}
+
+/// The input array permutation of the inverse ADST8 and ADST16 processes.
+fn inverse_adst_input_permutation(t: &mut [i32], n: u32) {
+    let n0 = 1 << n;
+    let mut copy_t = [0; 16];
+    copy_t[..n0].copy_from_slice(&t[..n0]);
+    for (i, v) in t[..n0].iter_mut().enumerate() {
+        let idx = if i & 1 == 1 { i - 1 } else { n0 - i - 1 };
+        *v = copy_t[idx];
+    }
+}
+
+/// The output array permutation of the inverse ADST8 and ADST16 processes,
+/// the odd outputs are negated.
+fn inverse_adst_output_permutation(t: &mut [i32], n: u32) {
+    let n0 = 1 << n;
+    let mut copy_t = [0; 16];
+    copy_t[..n0].copy_from_slice(&t[..n0]);
+    for (i, v) in t[..n0].iter_mut().enumerate() {
+        let a = (i >> 3) & 1;
+        let b = ((i >> 2) & 1) ^ ((i >> 3) & 1);
+        let c = ((i >> 1) & 1) ^ ((i >> 2) & 1);
+        let d = (i & 1) ^ ((i >> 1) & 1);
+        let idx = ((d << 3) | (c << 2) | (b << 1) | a) >> (4 - n);
+        *v = if i & 1 == 1 {
+            copy_t[idx].wrapping_neg()
+        } else {
+            copy_t[idx]
+        };
+    }
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-adst8-process
+fn inverse_adst8(t: &mut [i32]) {
+    inverse_adst_input_permutation(t, 3);
+
+    for i in 0..4 {
+        b(t, 2 * i, 2 * i + 1, 60 - 16 * i as i32, true);
+    }
+
+    for i in 0..4 {
+        h(t, i, 4 + i, false);
+    }
+
+    for i in 0..2 {
+        b(t, 4 + 3 * i, 5 + i, 48 - 32 * i as i32, true);
+    }
+
+    for i in 0..2 {
+        for j in 0..2 {
+            h(t, 4 * j + i, 2 + 4 * j + i, false);
+        }
+    }
+
+    for i in 0..2 {
+        b(t, 2 + 4 * i, 3 + 4 * i, 32, true);
+    }
+
+    inverse_adst_output_permutation(t, 3);
+}
+
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-adst16-process
+fn inverse_adst16(t: &mut [i32]) {
+    inverse_adst_input_permutation(t, 4);
+
+    for i in 0..8 {
+        b(t, 2 * i, 2 * i + 1, 62 - 8 * i as i32, true);
+    }
+
+    for i in 0..8 {
+        h(t, i, 8 + i, false);
+    }
+
+    for i in 0..2 {
+        b(t, 8 + 2 * i, 9 + 2 * i, 56 - 32 * i as i32, true);
+        b(t, 13 + 2 * i, 12 + 2 * i, 8 + 32 * i as i32, true);
+    }
+
+    for i in 0..4 {
+        for j in 0..2 {
+            h(t, 8 * j + i, 4 + 8 * j + i, false);
+        }
+    }
+
+    for i in 0..2 {
+        b(t, 4 + 8 * i, 5 + 8 * i, 48, true);
+        b(t, 7 + 8 * i, 6 + 8 * i, 16, true);
+    }
+
+    for i in 0..2 {
+        for j in 0..4 {
+            h(t, 4 * j + i, 2 + 4 * j + i, false);
+        }
+    }
+
+    for i in 0..4 {
+        b(t, 2 + 4 * i, 3 + 4 * i, 32, true);
+    }
+
+    inverse_adst_output_permutation(t, 4);
+}
+
+/// Applies the inverse ADST of `1 << n` points in place, `n` is 2 to 4.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-adst-process
+pub fn inverse_adst(t: &mut [i32], n: u32) {
+    match n {
+        2 => inverse_adst4(t),
+        3 => inverse_adst8(t),
+        _ => inverse_adst16(t),
+    }
+}
+
+/// Applies the inverse identity transform of `1 << n` points in place, `n`
+/// is 2 to 5.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-identity-transform-process
+pub fn inverse_identity(t: &mut [i32], n: u32) {
+    let n0 = 1 << n;
+    for v in &mut t[..n0] {
+        *v = match n {
+            2 => round2(*v as i64 * 5793, 12) as i32,
+            3 => v.wrapping_mul(2),
+            4 => round2(*v as i64 * 11586, 12) as i32,
+            _ => v.wrapping_mul(4),
+        };
+    }
+}
+
+/// Applies the inverse Walsh-Hadamard transform used by lossless blocks in
+/// place, the inputs are first shifted right by `shift`.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#inverse-walsh-hadamard-transform-process
+pub fn inverse_wht(t: &mut [i32], shift: u32) {
+    let mut a = t[0] >> shift;
+    let mut c = t[1] >> shift;
+    let mut d = t[2] >> shift;
+    let mut b = t[3] >> shift;
+    a += c;
+    d -= b;
+    let e = (a - d) >> 1;
+    b = e - b;
+    c = e - c;
+    a -= b;
+    d += c;
+    t[0] = a;
+    t[1] = b;
+    t[2] = c;
+    t[3] = d;
+}
+
+/// The 1D transform applied to the rows or the columns of a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transform1D {
+    Dct,
+    Adst,
+    FlipAdst,
+    Identity,
+}
+
+impl Transform1D {
+    /// The transforms applied to the columns and the rows by `tx_type`.
+    fn from_tx_type(tx_type: u8) -> (Self, Self) {
+        use Transform1D::*;
+
+        match tx_type {
+            DCT_DCT => (Dct, Dct),
+            ADST_DCT => (Adst, Dct),
+            DCT_ADST => (Dct, Adst),
+            ADST_ADST => (Adst, Adst),
+            FLIPADST_DCT => (FlipAdst, Dct),
+            DCT_FLIPADST => (Dct, FlipAdst),
+            FLIPADST_FLIPADST => (FlipAdst, FlipAdst),
+            ADST_FLIPADST => (Adst, FlipAdst),
+            FLIPADST_ADST => (FlipAdst, Adst),
+            V_DCT => (Dct, Identity),
+            H_DCT => (Identity, Dct),
+            V_ADST => (Adst, Identity),
+            H_ADST => (Identity, Adst),
+            V_FLIPADST => (FlipAdst, Identity),
+            H_FLIPADST => (Identity, FlipAdst),
+            _ => (Identity, Identity),
+        }
+    }
+
+    fn apply(self, t: &mut [i32], n: u32) {
+        match self {
+            Self::Dct => inverse_dct(t, n),
+            Self::Adst | Self::FlipAdst => inverse_adst(t, n),
+            Self::Identity => inverse_identity(t, n),
+        }
+    }
+}
+
+/// Computes the residuals of a transform block of size `tx_sz` from its
+/// dequantized coefficients, both are indexed by row then column.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#2d-inverse-transform-process
+pub fn inverse_transform_2d(
+    dequant: &[[i32; 64]; 64],
+    residual: &mut [[i32; 64]; 64],
+    tx_sz: u8,
+    tx_type: u8,
+    lossless: bool,
+    bit_depth: u8,
+) {
+    let log2w = TX_WIDTH_LOG2[tx_sz as usize] as u32;
+    let log2h = TX_HEIGHT_LOG2[tx_sz as usize] as u32;
+    let w = 1 << log2w;
+    let h = 1 << log2h;
+    let row_shift = if lossless {
+        0
+    } else {
+        TRANSFORM_ROW_SHIFT[tx_sz as usize] as u32
+    };
+
+    let col_shift = if lossless { 0 } else { 4 };
+    let row_clamp_range = bit_depth as u32 + 8;
+    let col_clamp_range = (bit_depth as u32 + 6).max(16);
+    let (col_tx, row_tx) = Transform1D::from_tx_type(tx_type);
+
+    let mut t = [0; 64];
+    for i in 0..h {
+        // Only the top left 32x32 coefficients can be non zero, the rows
+        // below them transform to zero.
+        if i >= 32 {
+            residual[i][..w].fill(0);
+            continue;
+        }
+
+        for j in 0..w {
+            t[j] = if j < 32 { dequant[i][j] } else { 0 };
+        }
+
+        if log2w.abs_diff(log2h) == 1 {
+            for v in &mut t[..w] {
+                *v = round2(*v as i64 * 2896, 12) as i32;
+            }
+        }
+
+        if lossless {
+            inverse_wht(&mut t, 2);
+        } else {
+            let max = (1 << (row_clamp_range - 1)) - 1;
+            for v in &mut t[..w] {
+                *v = (*v).clamp(-max - 1, max);
+            }
+
+            row_tx.apply(&mut t, log2w);
+        }
+
+        for j in 0..w {
+            let v = if row_tx == Transform1D::FlipAdst {
+                t[w - 1 - j]
+            } else {
+                t[j]
+            };
+
+            residual[i][j] = round2(v as i64, row_shift) as i32;
+        }
+    }
+
+    if !lossless {
+        let max = (1 << (col_clamp_range - 1)) - 1;
+        for row in &mut residual[..h] {
+            for v in &mut row[..w] {
+                *v = (*v).clamp(-max - 1, max);
+            }
+        }
+    }
+
+    for j in 0..w {
+        for (v, row) in t.iter_mut().zip(&residual[..h]) {
+            *v = row[j];
+        }
+
+        if lossless {
+            inverse_wht(&mut t, 0);
+        } else {
+            col_tx.apply(&mut t, log2h);
+        }
+
+        for (i, row) in residual[..h].iter_mut().enumerate() {
+            let v = if col_tx == Transform1D::FlipAdst {
+                t[h - 1 - i]
+            } else {
+                t[i]
+            };
+
+            row[j] = round2(v as i64, col_shift) as i32;
+        }
+    }
+}
+
+/// `Cos128_Lookup[ i ]` is `4096 * cos( i * PI / 128 )` rounded to the
+/// nearest integer.
+const COS128_LOOKUP: [i32; 65] = [
+    4096, 4095, 4091, 4085, 4076, 4065, 4052, 4036, 4017, 3996, 3973, 3948, 3920, 3889, 3857, 3822,
+    3784, 3745, 3703, 3659, 3612, 3564, 3513, 3461, 3406, 3349, 3290, 3229, 3166, 3102, 3035, 2967,
+    2896, 2824, 2751, 2675, 2598, 2520, 2440, 2359, 2276, 2191, 2106, 2019, 1931, 1842, 1751, 1660,
+    1567, 1474, 1380, 1285, 1189, 1092, 995, 897, 799, 700, 601, 501, 401, 301, 201, 101, 0,
+];
+
+/// `SINPI( i )` of the inverse ADST4 process for `i` from 1 to 4.
+const SINPI_LOOKUP: [i32; 4] = [1321, 2482, 3344, 3803];
+
+/// The rounding shift applied to the outputs of the row transforms.
+const TRANSFORM_ROW_SHIFT: [u8; TX_SIZES_ALL as usize] =
+    [0, 1, 2, 2, 2, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2];
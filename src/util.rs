@@ -73,3 +73,22 @@ impl<T> AtomicOption<T> {
         )
     }
 }
+
+/// Returns the floor of the base 2 logarithm of the input `x`.
+///
+/// Note: `x` must be greater than 0.
+#[inline]
+pub fn floor_log2(x: u32) -> u32 {
+    31 - x.leading_zeros()
+}
+
+/// Returns the ceiling of the base 2 logarithm of the input `x`, 0 when `x`
+/// is less than 2.
+#[inline]
+pub fn ceil_log2(x: u32) -> u32 {
+    if x < 2 {
+        0
+    } else {
+        32 - (x - 1).leading_zeros()
+    }
+}
@@ -0,0 +1,120 @@
+//! Warp model helpers shared by global and local warped motion.
+//!
+//! see: https://aomediacodec.github.io/av1-spec/#setup-shear-process
+
+use crate::{
+    constants::{
+        DIV_LUT_BITS, DIV_LUT_NUM, DIV_LUT_PREC_BITS, WARPEDMODEL_PREC_BITS, WARP_PARAM_REDUCE_BITS,
+    },
+    util::floor_log2,
+};
+
+/// The shear parameters of a warp model, as produced by the setup shear
+/// process.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Shear {
+    pub alpha: i32,
+    pub beta: i32,
+    pub gamma: i32,
+    pub delta: i32,
+    /// Whether the warp model can be used for the block warp process.
+    pub warp_valid: bool,
+}
+
+#[inline]
+fn round2_signed(x: i64, n: u32) -> i64 {
+    if n == 0 {
+        x
+    } else if x >= 0 {
+        (x + (1 << (n - 1))) >> n
+    } else {
+        -((-x + (1 << (n - 1))) >> n)
+    }
+}
+
+/// Returns `(divShift, divFactor)` such that dividing by `d` can be
+/// approximated by multiplying by divFactor and shifting right by divShift.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#resolve-divisor-process
+pub fn resolve_divisor(d: i32) -> (u32, i32) {
+    let abs = d.unsigned_abs();
+    let n = floor_log2(abs);
+    let e = abs - (1 << n);
+    let f = if n > DIV_LUT_BITS as u32 {
+        round2_signed(e as i64, n - DIV_LUT_BITS as u32) as usize
+    } else {
+        (e << (DIV_LUT_BITS as u32 - n)) as usize
+    };
+
+    let div_shift = n + DIV_LUT_PREC_BITS as u32;
+    let div_factor = if d < 0 {
+        -(DIV_LUT[f] as i32)
+    } else {
+        DIV_LUT[f] as i32
+    };
+
+    (div_shift, div_factor)
+}
+
+/// Computes the shear parameters of the warp model `warp_params` and checks
+/// whether they are within the range supported by the block warp process.
+///
+/// see: https://aomediacodec.github.io/av1-spec/#setup-shear-process
+pub fn setup_shear(warp_params: &[i32; 6]) -> Shear {
+    let clip = |v: i64| v.clamp(-32768, 32767);
+    let reduce =
+        |v: i64| (round2_signed(v, WARP_PARAM_REDUCE_BITS as u32) << WARP_PARAM_REDUCE_BITS) as i32;
+
+    let alpha0 = clip(warp_params[2] as i64 - (1 << WARPEDMODEL_PREC_BITS));
+    let beta0 = clip(warp_params[3] as i64);
+    let (div_shift, div_factor) = resolve_divisor(warp_params[2]);
+    let v = (warp_params[4] as i64) << WARPEDMODEL_PREC_BITS;
+    let gamma0 = clip(round2_signed(v * div_factor as i64, div_shift));
+    let w = warp_params[3] as i64 * warp_params[4] as i64;
+    let delta0 = clip(
+        warp_params[5] as i64
+            - round2_signed(w * div_factor as i64, div_shift)
+            - (1 << WARPEDMODEL_PREC_BITS),
+    );
+
+    let alpha = reduce(alpha0);
+    let beta = reduce(beta0);
+    let gamma = reduce(gamma0);
+    let delta = reduce(delta0);
+
+    let limit = 1 << WARPEDMODEL_PREC_BITS;
+    let warp_valid =
+        4 * alpha.abs() + 7 * beta.abs() < limit && 4 * gamma.abs() + 4 * delta.abs() < limit;
+
+    Shear {
+        alpha,
+        beta,
+        gamma,
+        delta,
+        warp_valid,
+    }
+}
+
+/// Divisor lookup table, `Div_Lut[ i ]` is `(1 << 14) * 256 / (256 + i)`
+/// rounded to the nearest integer.
+pub const DIV_LUT: [u16; DIV_LUT_NUM as usize] = [
+    16384, 16320, 16257, 16194, 16132, 16070, 16009, 15948, 15888, 15828, 15768, 15709, 15650,
+    15592, 15534, 15477, 15420, 15364, 15308, 15252, 15197, 15142, 15087, 15033, 14980, 14926,
+    14873, 14821, 14769, 14717, 14665, 14614, 14564, 14513, 14463, 14413, 14364, 14315, 14266,
+    14218, 14170, 14122, 14075, 14028, 13981, 13935, 13888, 13843, 13797, 13752, 13707, 13662,
+    13618, 13574, 13530, 13487, 13443, 13400, 13358, 13315, 13273, 13231, 13190, 13148, 13107,
+    13066, 13026, 12985, 12945, 12906, 12866, 12827, 12788, 12749, 12710, 12672, 12633, 12596,
+    12558, 12520, 12483, 12446, 12409, 12373, 12336, 12300, 12264, 12228, 12193, 12157, 12122,
+    12087, 12053, 12018, 11984, 11950, 11916, 11882, 11848, 11815, 11782, 11749, 11716, 11683,
+    11651, 11619, 11586, 11555, 11523, 11491, 11460, 11429, 11398, 11367, 11336, 11305, 11275,
+    11245, 11215, 11185, 11155, 11125, 11096, 11067, 11038, 11009, 10980, 10951, 10923, 10894,
+    10866, 10838, 10810, 10782, 10755, 10727, 10700, 10673, 10645, 10618, 10592, 10565, 10538,
+    10512, 10486, 10460, 10434, 10408, 10382, 10356, 10331, 10305, 10280, 10255, 10230, 10205,
+    10180, 10156, 10131, 10107, 10082, 10058, 10034, 10010, 9986, 9963, 9939, 9916, 9892, 9869,
+    9846, 9823, 9800, 9777, 9754, 9732, 9709, 9687, 9664, 9642, 9620, 9598, 9576, 9554, 9533, 9511,
+    9489, 9468, 9447, 9425, 9404, 9383, 9362, 9341, 9321, 9300, 9279, 9259, 9239, 9218, 9198, 9178,
+    9158, 9138, 9118, 9098, 9079, 9059, 9039, 9020, 9001, 8981, 8962, 8943, 8924, 8905, 8886, 8867,
+    8849, 8830, 8812, 8793, 8775, 8756, 8738, 8720, 8702, 8684, 8666, 8648, 8630, 8613, 8595, 8577,
+    8560, 8542, 8525, 8508, 8490, 8473, 8456, 8439, 8422, 8405, 8389, 8372, 8355, 8339, 8322, 8306,
+    8289, 8273, 8257, 8240, 8224, 8208, 8192,
+];
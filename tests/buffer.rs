@@ -0,0 +1,21 @@
+use davone::buffer::Buffer;
+
+#[test]
+fn su_values_with_the_sign_bit_set_are_negative() {
+    // 1111111 0000001 1000000 0111111
+    let data = [0b1111_1110, 0b0000_0110, 0b0000_0011, 0b1111_0000];
+    let mut buf = Buffer::new(&data);
+    assert_eq!(buf.get_su(7), -1);
+    assert_eq!(buf.get_su(7), 1);
+    assert_eq!(buf.get_su(7), -64);
+    assert_eq!(buf.get_su(7), 63);
+}
+
+#[test]
+fn zero_width_reads_do_not_consume_bits() {
+    let data = [0b1010_0000];
+    let mut buf = Buffer::new(&data);
+    assert_eq!(buf.get_bits(0), 0);
+    assert_eq!(buf.get_position(), 0);
+    assert_eq!(buf.get_bits(3), 0b101);
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{decode_frame_header, KEY_FRAME_TWO_TILES};
+use davone::{
+    buffer::Buffer,
+    cdf::{frame_end_update_cdf, load_cdfs, save_cdfs, CdfContext},
+    obu::{tile_group::TileGroup, Obu, ObuContext, ObuParser},
+    tile::{mode_info::FrameState, TileDecoder},
+};
+
+/// Returns cdfs that differ from the defaults, with non zero counters.
+fn adapted_cdfs() -> CdfContext {
+    let mut cdf = CdfContext::default();
+    cdf.skip_cdf[1] = [12345, 1 << 15, 17];
+    cdf.partition_w128_cdf[3][8] = 32;
+    // The last symbols of a cdf may never occur.
+    cdf.mv_joint_cdf[1] = [4096, 1 << 15, 1 << 15, 1 << 15, 20];
+    cdf.coeff.dc_sign_cdf[1][2] = [23456, 1 << 15, 4];
+    // The counter of a palette of 2 colors is followed by padding.
+    cdf.palette_y_color_cdf[0][4][..3].copy_from_slice(&[9876, 1 << 15, 5]);
+    cdf.palette_uv_color_cdf[6][0][8] = 31;
+    cdf
+}
+
+#[test]
+fn loading_saved_cdfs_clears_their_counters() {
+    let mut ctx = ObuContext {
+        cdf: adapted_cdfs(),
+        ..Default::default()
+    };
+    save_cdfs(&mut ctx, 3);
+    assert!(ctx.saved_cdfs[3] == adapted_cdfs());
+
+    ctx.cdf = CdfContext::default();
+    load_cdfs(&mut ctx, 3);
+    let cdf = &ctx.cdf;
+    assert_eq!(cdf.skip_cdf[1], [12345, 1 << 15, 0]);
+    assert_eq!(cdf.partition_w128_cdf[3][8], 0);
+    assert_eq!(cdf.mv_joint_cdf[1], [4096, 1 << 15, 1 << 15, 1 << 15, 0]);
+    assert_eq!(cdf.coeff.dc_sign_cdf[1][2], [23456, 1 << 15, 0]);
+    assert_eq!(cdf.palette_y_color_cdf[0][4][..3], [9876, 1 << 15, 0]);
+    assert_eq!(cdf.palette_uv_color_cdf[6][0][8], 0);
+
+    // Only the counters differ from the saved cdfs.
+    let mut expected = adapted_cdfs();
+    expected.clear_counters();
+    assert!(*cdf == expected);
+
+    // The other slots are untouched.
+    assert!(ctx.saved_cdfs[2] == CdfContext::default());
+}
+
+#[test]
+fn frame_end_update_takes_the_saved_tile_cdfs() {
+    let mut ctx = ObuContext::default();
+    frame_end_update_cdf(&mut ctx);
+    assert!(ctx.cdf == CdfContext::default());
+
+    ctx.context_update_cdf = Some(adapted_cdfs());
+    frame_end_update_cdf(&mut ctx);
+    assert!(ctx.context_update_cdf.is_none());
+
+    let mut expected = adapted_cdfs();
+    expected.clear_counters();
+    assert!(ctx.cdf == expected);
+}
+
+#[test]
+fn frame_end_update_depends_on_disable_frame_end_update_cdf() {
+    // The key frame has disable_frame_end_update_cdf equal to 1, it saves the
+    // cdfs of its frame header into every slot.
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&KEY_FRAME_TWO_TILES);
+    let mut tiles = vec![];
+    while buf.remaining() > 0 {
+        if let Obu::Frame(frame) = parser.parse(&mut buf).unwrap() {
+            assert!(
+                frame
+                    .frame_header
+                    .uncompressed_header
+                    .disable_frame_end_update_cdf
+            );
+            tiles = frame.tile_group.tiles;
+        }
+    }
+
+    let mut buf = Buffer::new(&KEY_FRAME_TWO_TILES);
+    let (mut ctx, size) = decode_frame_header(&mut buf);
+    assert!(parser.ctx.cdf == ctx.cdf);
+    assert!(parser.ctx.saved_cdfs.iter().all(|cdf| *cdf == ctx.cdf));
+
+    // The cdfs at the end of each tile, from the cdfs of the frame header.
+    let tile_cdfs: Vec<_> = tiles
+        .iter()
+        .map(|tile| {
+            let mut frame = FrameState::new(ctx.mi_rows, ctx.mi_cols);
+            frame.cur_frame = ctx.frame_state.cur_frame.clone();
+            TileDecoder::new(&ctx, &mut frame, tile)
+                .unwrap()
+                .decode()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(tile_cdfs.len(), 2);
+    assert!(tile_cdfs[0] != tile_cdfs[1]);
+
+    // With disable_frame_end_update_cdf equal to 0, the cdfs of the tile
+    // context_update_tile_id are saved instead.
+    let frame_header = ctx.frame_header.as_mut().unwrap();
+    frame_header.disable_frame_end_update_cdf = false;
+    let mut expected = tile_cdfs[frame_header.tile_info.context_update_tile_id as usize].clone();
+    expected.clear_counters();
+
+    TileGroup::decode(&mut ctx, &mut buf, size).unwrap();
+    assert!(ctx.cdf == expected);
+    assert!(ctx.saved_cdfs.iter().all(|cdf| *cdf == expected));
+}
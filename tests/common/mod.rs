@@ -0,0 +1,185 @@
+//! Helpers shared by the integration tests, each test crate only uses some
+//! of them.
+#![allow(dead_code)]
+
+use davone::{
+    buffer::Buffer,
+    obu::{frame_header::FrameHeader, ObuContext, ObuParser},
+    symbol::update_cdf,
+};
+
+/// A 128x16 8 bits 4:2:0 key frame with two 64x16 tiles, coded by libaom.
+#[rustfmt::skip]
+pub const KEY_FRAME_TWO_TILES: [u8; 74] = [
+    18, 0, 10, 6, 24, 24, 255, 251, 32, 16, 50, 62, 28, 200, 0, 240, 16, 0, 64, 64, 0, 24, 179, 189,
+    161, 130, 86, 173, 56, 135, 115, 67, 24, 56, 28, 78, 198, 47, 178, 80, 132, 187, 244, 193, 106,
+    54, 32, 6, 25, 116, 126, 13, 135, 91, 241, 248, 218, 214, 71, 116, 32, 191, 197, 237, 131, 137,
+    255, 43, 241, 125, 47, 159, 115, 224,
+];
+
+/// Decodes the sequence header and the frame header of the key frame, and
+/// returns the context with the buffer at the start of its tile group.
+pub fn decode_frame_header(buf: &mut Buffer) -> (ObuContext, usize) {
+    let mut parser = ObuParser::default();
+    for _ in 0..2 {
+        parser.parse(buf).unwrap();
+    }
+
+    // obu_header and obu_size of the frame obu.
+    let size = buf.get_bytes(2)[1] as usize;
+    let start = buf.get_position();
+    FrameHeader::decode(&mut parser.ctx, buf).unwrap();
+    buf.byte_alignment();
+
+    let header_bytes = (buf.get_position() - start) / 8;
+    (parser.ctx, size - header_bytes)
+}
+
+/// A range encoder matching the symbol decoder, only used to produce test
+/// data.
+pub struct SymbolEncoder {
+    precarry: Vec<u16>,
+    low: u64,
+    rng: u32,
+    cnt: i32,
+    disable_cdf_update: bool,
+}
+
+impl SymbolEncoder {
+    pub fn new(disable_cdf_update: bool) -> Self {
+        Self {
+            precarry: Vec::new(),
+            low: 0,
+            rng: 0x8000,
+            cnt: -9,
+            disable_cdf_update,
+        }
+    }
+
+    pub fn encode(&mut self, cdf: &[u16], s: usize) {
+        let n = (cdf.len() - 2) as u32;
+        let fl = if s > 0 {
+            32768 - cdf[s - 1] as u32
+        } else {
+            32768
+        };
+        let fh = 32768 - cdf[s] as u32;
+        let mut l = self.low;
+        let mut r = self.rng;
+        if fl < 32768 {
+            let u = (((r >> 8) * (fl >> 6)) >> 1) + 4 * (n + 1 - s as u32);
+            let v = (((r >> 8) * (fh >> 6)) >> 1) + 4 * (n - s as u32);
+            l += (r - u) as u64;
+            r = u - v;
+        } else {
+            r -= (((r >> 8) * (fh >> 6)) >> 1) + 4 * (n - s as u32);
+        }
+
+        self.normalize(l, r);
+    }
+
+    pub fn write_symbol(&mut self, cdf: &mut [u16], s: usize) {
+        self.encode(cdf, s);
+        if !self.disable_cdf_update {
+            update_cdf(cdf, s);
+        }
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.encode(&[1 << 14, 1 << 15, 0], value as usize);
+    }
+
+    pub fn write_literal(&mut self, n: usize, value: u32) {
+        for i in (0..n).rev() {
+            self.write_bool((value >> i) & 1 == 1);
+        }
+    }
+
+    fn normalize(&mut self, mut low: u64, rng: u32) {
+        let d = 15 - (31 - rng.leading_zeros()) as i32;
+        let mut c = self.cnt;
+        let mut s = c + d;
+        if s >= 0 {
+            c += 16;
+            let mut m = (1u64 << c) - 1;
+            if s >= 8 {
+                self.precarry.push((low >> c) as u16);
+                low &= m;
+                c -= 8;
+                m >>= 8;
+            }
+
+            self.precarry.push((low >> c) as u16);
+            s = c + d - 24;
+            low &= m;
+        }
+
+        self.low = low << d;
+        self.rng = rng << d;
+        self.cnt = s;
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let m = 0x3fff;
+        let mut c = self.cnt;
+        let mut s = 10 + c;
+        let mut e = ((self.low + m) & !m) | (m + 1);
+        if s > 0 {
+            let mut n = (1u64 << (c + 16)) - 1;
+            while s > 0 {
+                self.precarry.push((e >> (c + 16)) as u16);
+                e &= n;
+                s -= 8;
+                c -= 8;
+                n >>= 8;
+            }
+        }
+
+        let mut out = vec![0u8; self.precarry.len()];
+        let mut carry = 0u32;
+        for (i, value) in self.precarry.iter().enumerate().rev() {
+            carry += *value as u32;
+            out[i] = carry as u8;
+            carry >>= 8;
+        }
+
+        out
+    }
+}
+
+/// A xorshift generator, for reproducible random test data.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 16) as u32
+    }
+
+    pub fn below(&mut self, n: u32) -> u32 {
+        self.next() % n
+    }
+
+    /// A random value in `-max..=max`.
+    pub fn signed(&mut self, max: i32) -> i32 {
+        (self.next() % (2 * max as u32 + 1)) as i32 - max
+    }
+
+    /// A random cdf for an alphabet of `n` symbols, with a zero counter.
+    pub fn cdf(&mut self, n: usize) -> Vec<u16> {
+        let mut points = Vec::with_capacity(n - 1);
+        while points.len() < n - 1 {
+            let point = 1 + self.below(32767) as u16;
+            if !points.contains(&point) {
+                points.push(point);
+            }
+        }
+
+        points.sort_unstable();
+        points.push(32768);
+        points.push(0);
+        points
+    }
+}
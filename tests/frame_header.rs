@@ -0,0 +1,801 @@
+mod common;
+
+use common::{decode_frame_header, KEY_FRAME_TWO_TILES};
+use davone::{
+    buffer::Buffer,
+    constants::PRIMARY_REF_NONE,
+    obu::{
+        frame_header::{
+            reference_frame_loading, reference_frame_update, CdefParams, DeltaLfParams,
+            DeltaQParams, FilmGrainParams, FrameHeader, FrameType, GlobalMotionParams, GmType,
+            LoopFilterParams, LrParams, QuantizationParams, RestorationType, SegmentationParams,
+            SkipModeParams, UncompressedHeader,
+        },
+        sequence_header::SequenceHeader,
+        tile_group::TileGroup,
+        ObuContext, ObuError,
+    },
+};
+
+/// Writes the syntax elements of hand built headers, most significant bit
+/// first.
+#[derive(Default)]
+struct BitWriter {
+    data: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn put(&mut self, count: usize, value: u32) -> &mut Self {
+        for i in (0..count).rev() {
+            if self.bits.is_multiple_of(8) {
+                self.data.push(0);
+            }
+
+            let bit = ((value >> i) & 1) as u8;
+            *self.data.last_mut().unwrap() |= bit << (7 - self.bits % 8);
+            self.bits += 1;
+        }
+
+        self
+    }
+}
+
+/// The options of the hand built sequence headers.
+#[derive(Default)]
+struct SequenceOptions {
+    use_128x128_superblock: bool,
+    enable_order_hint: bool,
+    enable_restoration: bool,
+    separate_uv_delta_q: bool,
+    film_grain_params_present: bool,
+}
+
+/// Decodes a 8 bits 4:2:0 sequence header into a new context, with 7 bits
+/// order hints when they are enabled.
+fn context_with_sequence_header(options: SequenceOptions) -> ObuContext {
+    let mut w = BitWriter::default();
+    // seq_profile, still_picture, reduced_still_picture_header,
+    // timing_info_present_flag and initial_display_delay_present_flag.
+    w.put(3, 0).put(1, 0).put(1, 0).put(1, 0).put(1, 0);
+    // operating_points_cnt_minus_1, operating_point_idc[ 0 ] and
+    // seq_level_idx[ 0 ].
+    w.put(5, 0).put(12, 0).put(5, 0);
+    // frame_width_bits_minus_1, frame_height_bits_minus_1,
+    // max_frame_width_minus_1 and max_frame_height_minus_1.
+    w.put(4, 15).put(4, 15).put(16, 255).put(16, 255);
+    // frame_id_numbers_present_flag, use_128x128_superblock,
+    // enable_filter_intra and enable_intra_edge_filter.
+    w.put(1, 0)
+        .put(1, options.use_128x128_superblock as u32)
+        .put(1, 1)
+        .put(1, 1);
+    // enable_interintra_compound, enable_masked_compound,
+    // enable_warped_motion, enable_dual_filter and enable_order_hint.
+    w.put(4, 0).put(1, options.enable_order_hint as u32);
+    if options.enable_order_hint {
+        // enable_jnt_comp and enable_ref_frame_mvs.
+        w.put(1, 1).put(1, 1);
+    }
+
+    // seq_choose_screen_content_tools and seq_force_screen_content_tools.
+    w.put(1, 0).put(1, 0);
+    if options.enable_order_hint {
+        // order_hint_bits_minus_1
+        w.put(3, 6);
+    }
+
+    // enable_superres, enable_cdef and enable_restoration.
+    w.put(1, 0)
+        .put(1, 1)
+        .put(1, options.enable_restoration as u32);
+    // high_bitdepth, mono_chrome, color_description_present_flag,
+    // color_range, chroma_sample_position and separate_uv_delta_q.
+    w.put(1, 0)
+        .put(1, 0)
+        .put(1, 0)
+        .put(1, 0)
+        .put(2, 0)
+        .put(1, options.separate_uv_delta_q as u32);
+    // film_grain_params_present
+    w.put(1, options.film_grain_params_present as u32);
+
+    let mut ctx = ObuContext::default();
+    let sequence_header = SequenceHeader::decode(&mut ctx, &mut Buffer::new(&w.data)).unwrap();
+    ctx.sequence_header = Some(sequence_header);
+    ctx
+}
+
+/// A context where the reference slot 3 holds a 64x32 frame with the order
+/// hint 5, while the previously decoded frame is 128x128 with the order hint
+/// 9.
+fn context_with_saved_slot() -> ObuContext {
+    let mut ctx = ObuContext {
+        upscaled_width: 128,
+        frame_width: 128,
+        frame_height: 128,
+        render_width: 128,
+        render_height: 128,
+        mi_cols: 32,
+        mi_rows: 32,
+        order_hint: 9,
+        order_hints: [9; 8],
+        ..Default::default()
+    };
+
+    ctx.ref_frame_type[3] = FrameType::KeyFrame;
+    ctx.ref_upscaled_width[3] = 64;
+    ctx.ref_frame_width[3] = 64;
+    ctx.ref_frame_height[3] = 32;
+    ctx.ref_render_width[3] = 60;
+    ctx.ref_render_height[3] = 30;
+    ctx.ref_mi_cols[3] = 16;
+    ctx.ref_mi_rows[3] = 8;
+    ctx.ref_order_hint[3] = 5;
+    ctx.saved_order_hints[3] = [5, 1, 2, 3, 4, 5, 6, 7];
+    ctx.saved_segment_ids[3] = vec![0; 16 * 8];
+    ctx.saved_mvs[3] = (0..16 * 8).map(|i| [i, -i]).collect();
+    ctx.saved_ref_frames[3] = (0..16 * 8).map(|i| (i % 8) as i8 - 1).collect();
+
+    ctx
+}
+
+/// The header of a shown key frame, which refreshes every slot.
+fn show_existing_key_frame(idx: u8) -> UncompressedHeader {
+    UncompressedHeader {
+        show_existing_frame: true,
+        frame_to_show_map_idx: idx,
+        frame_type: FrameType::KeyFrame,
+        show_frame: true,
+        refresh_frame_flags: 0xff,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn shown_key_frames_refresh_every_slot_with_the_loaded_frame() {
+    let mut ctx = context_with_saved_slot();
+    reference_frame_loading(&mut ctx, 3);
+    assert_eq!(
+        (ctx.upscaled_width, ctx.frame_width, ctx.frame_height),
+        (64, 64, 32)
+    );
+    assert_eq!((ctx.render_width, ctx.render_height), (60, 30));
+    assert_eq!((ctx.mi_cols, ctx.mi_rows), (16, 8));
+    assert_eq!((ctx.frame_state.mi_cols, ctx.frame_state.mi_rows), (16, 8));
+    assert_eq!(ctx.order_hint, 5);
+
+    reference_frame_update(&mut ctx, &show_existing_key_frame(3));
+    for i in 0..8 {
+        assert!(ctx.ref_frame_marking[i]);
+        assert_eq!(ctx.ref_frame_type[i], FrameType::KeyFrame);
+        assert_eq!(ctx.ref_upscaled_width[i], 64);
+        assert_eq!(ctx.ref_frame_width[i], 64);
+        assert_eq!(ctx.ref_frame_height[i], 32);
+        assert_eq!(ctx.ref_render_width[i], 60);
+        assert_eq!(ctx.ref_render_height[i], 30);
+        assert_eq!((ctx.ref_mi_cols[i], ctx.ref_mi_rows[i]), (16, 8));
+        assert_eq!(ctx.ref_order_hint[i], 5);
+        assert_eq!(ctx.saved_order_hints[i], [5, 1, 2, 3, 4, 5, 6, 7]);
+    }
+}
+
+#[test]
+fn shown_key_frames_save_the_motion_vectors_of_the_loaded_frame() {
+    let mut ctx = context_with_saved_slot();
+    let mvs = ctx.saved_mvs[3].clone();
+    let ref_frames = ctx.saved_ref_frames[3].clone();
+
+    reference_frame_loading(&mut ctx, 3);
+    reference_frame_update(&mut ctx, &show_existing_key_frame(3));
+    for i in 0..8 {
+        assert_eq!(ctx.saved_mvs[i], mvs);
+        assert_eq!(ctx.saved_ref_frames[i], ref_frames);
+    }
+}
+
+/// Decodes the quantization params of a frame, and checks that all the bits
+/// written are read.
+fn quantization_params(separate_uv_delta_q: bool, w: &BitWriter) -> QuantizationParams {
+    let ctx = context_with_sequence_header(SequenceOptions {
+        separate_uv_delta_q,
+        ..Default::default()
+    });
+
+    let mut buf = Buffer::new(&w.data);
+    let params = QuantizationParams::decode(&ctx, &mut buf);
+    assert_eq!(buf.get_position(), w.bits);
+    params
+}
+
+#[test]
+fn v_deltas_and_matrix_follow_u_without_separate_uv_delta_q() {
+    // base_q_idx, delta_coded and delta_q (-3 in su(1+6)) of the luma dc.
+    let mut w = BitWriter::default();
+    w.put(8, 100).put(1, 1).put(7, 0b111_1101);
+    // The u dc delta is 5, the u ac delta is not coded.
+    w.put(1, 1).put(7, 5).put(1, 0);
+    // using_qmatrix, qm_y and qm_u.
+    w.put(1, 1).put(4, 4).put(4, 7);
+
+    let params = quantization_params(false, &w);
+    assert_eq!(params.base_q_idx, 100);
+    assert_eq!(params.delta_q_y_dc, -3);
+    assert!(!params.diff_uv_delta);
+    assert_eq!((params.delta_q_u_dc, params.delta_q_u_ac), (5, 0));
+    assert_eq!((params.delta_q_v_dc, params.delta_q_v_ac), (5, 0));
+    assert!(params.using_qmatrix);
+    assert_eq!((params.qm_y, params.qm_u, params.qm_v), (4, 7, 7));
+}
+
+#[test]
+fn separate_uv_delta_q_codes_the_v_deltas_and_matrix() {
+    // base_q_idx and the luma dc delta, which is not coded.
+    let mut w = BitWriter::default();
+    w.put(8, 20).put(1, 0);
+    // diff_uv_delta, then the u deltas 1 and -1 and the v deltas -64 and 63.
+    w.put(1, 1)
+        .put(1, 1)
+        .put(7, 1)
+        .put(1, 1)
+        .put(7, 0b111_1111)
+        .put(1, 1)
+        .put(7, 0b100_0000)
+        .put(1, 1)
+        .put(7, 63);
+    // using_qmatrix, qm_y, qm_u and qm_v.
+    w.put(1, 1).put(4, 15).put(4, 3).put(4, 9);
+
+    let params = quantization_params(true, &w);
+    assert!(params.diff_uv_delta);
+    assert_eq!((params.delta_q_u_dc, params.delta_q_u_ac), (1, -1));
+    assert_eq!((params.delta_q_v_dc, params.delta_q_v_ac), (-64, 63));
+    assert_eq!((params.qm_y, params.qm_u, params.qm_v), (15, 3, 9));
+
+    // With diff_uv_delta equal to 0, the v deltas are copied from the u
+    // deltas, the matrices are not read without using_qmatrix.
+    let mut w = BitWriter::default();
+    w.put(8, 20).put(1, 0);
+    w.put(1, 0).put(1, 1).put(7, 2).put(1, 1).put(7, 3);
+    w.put(1, 0);
+
+    let params = quantization_params(true, &w);
+    assert!(!params.diff_uv_delta);
+    assert_eq!((params.delta_q_v_dc, params.delta_q_v_ac), (2, 3));
+    assert!(!params.using_qmatrix);
+    assert_eq!((params.qm_y, params.qm_u, params.qm_v), (0, 0, 0));
+}
+
+#[test]
+fn delta_q_and_delta_lf_are_only_read_with_a_non_zero_base_q_idx() {
+    // delta_q_present and delta_q_res.
+    let data = [0b1100_0000];
+    let mut buf = Buffer::new(&data);
+    assert_eq!(DeltaQParams::decode(&mut buf, 0), DeltaQParams::default());
+    assert_eq!(buf.get_position(), 0);
+
+    let params = DeltaQParams::decode(&mut buf, 1);
+    assert!(params.delta_q_present);
+    assert_eq!(params.delta_q_res, 2);
+    assert_eq!(buf.get_position(), 3);
+
+    // delta_lf_present, delta_lf_res and delta_lf_multi, only read along
+    // delta_q.
+    let data = [0b1011_0000];
+    let mut buf = Buffer::new(&data);
+    assert_eq!(
+        DeltaLfParams::decode(&mut buf, false, false),
+        DeltaLfParams::default()
+    );
+    assert_eq!(buf.get_position(), 0);
+
+    let params = DeltaLfParams::decode(&mut buf, true, false);
+    assert!(params.delta_lf_present && params.delta_lf_multi);
+    assert_eq!(params.delta_lf_res, 1);
+    assert_eq!(buf.get_position(), 4);
+
+    // The loop filter deltas are not used with intra block copy.
+    let mut buf = Buffer::new(&data);
+    assert_eq!(
+        DeltaLfParams::decode(&mut buf, true, true),
+        DeltaLfParams::default()
+    );
+    assert_eq!(buf.get_position(), 0);
+}
+
+/// Writes the feature data of the 8 segments, `features` lists the enabled
+/// features of each segment with their coded values.
+fn put_segmentation_features(w: &mut BitWriter, features: &[(usize, usize, u32)]) {
+    const BITS: [usize; 8] = [9, 7, 7, 7, 7, 3, 0, 0];
+    for segment in 0..8 {
+        for (feature, bits) in BITS.iter().enumerate() {
+            let value = features
+                .iter()
+                .find(|(s, f, _)| *s == segment && *f == feature)
+                .map(|(_, _, value)| *value);
+
+            // feature_enabled and feature_value.
+            w.put(1, value.is_some() as u32);
+            if let Some(value) = value {
+                w.put(*bits, value);
+            }
+        }
+    }
+}
+
+#[test]
+fn segmentation_feature_values_are_clamped_to_their_range() {
+    // segmentation_enabled, the map and the data are updated without a
+    // primary reference frame.
+    let mut w = BitWriter::default();
+    w.put(1, 1);
+    put_segmentation_features(
+        &mut w,
+        &[
+            // -256 in su(1+8), the quantizer delta is at least -255.
+            (0, 0, 0b1_0000_0000),
+            // 255 is kept.
+            (1, 0, 0b0_1111_1111),
+            // -64 in su(1+6), the loop filter deltas are at least -63.
+            (1, 1, 0b100_0000),
+            // -5 is kept.
+            (1, 4, 0b111_1011),
+            (2, 5, 6),
+            (2, 6, 0),
+        ],
+    );
+
+    let mut buf = Buffer::new(&w.data);
+    let params = SegmentationParams::decode(&mut buf, PRIMARY_REF_NONE, None);
+    assert_eq!(buf.get_position(), w.bits);
+    assert!(params.segmentation_enabled);
+    assert!(params.segmentation_update_map && params.segmentation_update_data);
+    assert!(!params.segmentation_temporal_update);
+    assert_eq!(params.feature_data[0][0], -255);
+    assert_eq!(params.feature_data[1][..5], [255, -63, 0, 0, -5]);
+    assert_eq!(params.feature_data[2][5], 6);
+    assert!(params.feature_enabled[2][6]);
+    assert!(!params.feature_enabled[2][7]);
+
+    // The reference frame feature is read before skip.
+    assert_eq!(params.last_active_seg_id, 2);
+    assert!(params.seg_id_pre_skip);
+
+    assert_eq!(params.qidx(0, 100), 0);
+    assert_eq!(params.qidx(1, 100), 255);
+    assert_eq!(params.qidx(3, 100), 100);
+}
+
+#[test]
+fn last_active_segment_ignores_the_disabled_segments() {
+    let mut w = BitWriter::default();
+    w.put(1, 1);
+    put_segmentation_features(&mut w, &[(3, 0, 12), (1, 2, 1)]);
+
+    let params = SegmentationParams::decode(&mut Buffer::new(&w.data), PRIMARY_REF_NONE, None);
+    assert_eq!(params.last_active_seg_id, 3);
+    assert!(!params.seg_id_pre_skip);
+
+    // Nothing else is read when segmentation is disabled.
+    let mut buf = Buffer::new(&[0x7f]);
+    let params = SegmentationParams::decode(&mut buf, PRIMARY_REF_NONE, None);
+    assert_eq!(params, SegmentationParams::default());
+    assert_eq!(buf.get_position(), 1);
+}
+
+#[test]
+fn segmentation_data_is_inherited_without_update_data() {
+    let mut w = BitWriter::default();
+    w.put(1, 1);
+    put_segmentation_features(&mut w, &[(4, 5, 2), (6, 1, 9)]);
+    let prev = SegmentationParams::decode(&mut Buffer::new(&w.data), PRIMARY_REF_NONE, None);
+
+    // segmentation_enabled, segmentation_update_map,
+    // segmentation_temporal_update and segmentation_update_data.
+    let data = [0b1110_0000];
+    let mut buf = Buffer::new(&data);
+    let params = SegmentationParams::decode(&mut buf, 0, Some(&prev));
+    assert_eq!(buf.get_position(), 4);
+    assert!(params.segmentation_update_map && params.segmentation_temporal_update);
+    assert!(!params.segmentation_update_data);
+    assert_eq!(params.feature_enabled, prev.feature_enabled);
+    assert_eq!(params.feature_data, prev.feature_data);
+    assert_eq!(params.last_active_seg_id, 6);
+    assert!(params.seg_id_pre_skip);
+
+    // Without segmentation_update_map, segmentation_temporal_update is not
+    // read.
+    let data = [0b1000_0000];
+    let mut buf = Buffer::new(&data);
+    let params = SegmentationParams::decode(&mut buf, 0, Some(&prev));
+    assert_eq!(buf.get_position(), 3);
+    assert!(!params.segmentation_update_map && !params.segmentation_temporal_update);
+    assert_eq!(params.feature_data, prev.feature_data);
+
+    // Without a primary reference frame, nothing is inherited.
+    let params = SegmentationParams::decode(&mut Buffer::new(&data), 0, None);
+    assert_eq!(params.feature_enabled, [[false; 8]; 8]);
+    assert_eq!(params.last_active_seg_id, 0);
+}
+
+#[test]
+fn loop_filter_deltas_are_updated_from_the_primary_reference_frame() {
+    let ctx = context_with_sequence_header(SequenceOptions::default());
+
+    // loop_filter_level[ 0 ] and [ 1 ], the chroma levels are not read when
+    // both are 0.
+    let mut w = BitWriter::default();
+    w.put(6, 0).put(6, 0);
+    // loop_filter_sharpness, loop_filter_delta_enabled and
+    // loop_filter_delta_update.
+    w.put(3, 5).put(1, 1).put(1, 1);
+    // Only the delta of LAST_FRAME is updated, to -5.
+    w.put(1, 0).put(1, 1).put(7, 0b111_1011);
+    for _ in 2..8 {
+        w.put(1, 0);
+    }
+    // Only the second mode delta is updated, to 3.
+    w.put(1, 0).put(1, 1).put(7, 3);
+
+    let mut buf = Buffer::new(&w.data);
+    let params = LoopFilterParams::decode(&ctx, &mut buf, false, false, None);
+    assert_eq!(buf.get_position(), w.bits);
+    assert_eq!(params.loop_filter_level, [0; 4]);
+    assert_eq!(params.loop_filter_sharpness, 5);
+    assert!(params.loop_filter_delta_enabled && params.loop_filter_delta_update);
+    assert_eq!(params.loop_filter_ref_deltas, [1, -5, 0, 0, -1, 0, -1, -1]);
+    assert_eq!(params.loop_filter_mode_deltas, [0, 3]);
+
+    // Without loop_filter_delta_update, the deltas of the primary reference
+    // frame are kept.
+    let mut w = BitWriter::default();
+    w.put(6, 10).put(6, 0).put(6, 20).put(6, 30);
+    w.put(3, 0).put(1, 1).put(1, 0);
+
+    let mut buf = Buffer::new(&w.data);
+    let prev = params;
+    let params = LoopFilterParams::decode(&ctx, &mut buf, false, false, Some(&prev));
+    assert_eq!(buf.get_position(), w.bits);
+    assert_eq!(params.loop_filter_level, [10, 0, 20, 30]);
+    assert!(!params.loop_filter_delta_update);
+    assert_eq!(params.loop_filter_ref_deltas, prev.loop_filter_ref_deltas);
+    assert_eq!(params.loop_filter_mode_deltas, prev.loop_filter_mode_deltas);
+
+    // And the default deltas without a primary reference frame.
+    let params = LoopFilterParams::decode(&ctx, &mut Buffer::new(&w.data), false, false, None);
+    let defaults = LoopFilterParams::default();
+    assert_eq!(
+        params.loop_filter_ref_deltas,
+        defaults.loop_filter_ref_deltas
+    );
+    assert_eq!(
+        params.loop_filter_mode_deltas,
+        defaults.loop_filter_mode_deltas
+    );
+}
+
+#[test]
+fn cdef_secondary_strengths_of_3_mean_4() {
+    let ctx = context_with_sequence_header(SequenceOptions::default());
+
+    // cdef_damping_minus_3 and cdef_bits.
+    let mut w = BitWriter::default();
+    w.put(2, 2).put(2, 1);
+    // The luma and chroma primary and secondary strengths of the 2 presets.
+    w.put(4, 15).put(2, 3).put(4, 1).put(2, 2);
+    w.put(4, 0).put(2, 1).put(4, 7).put(2, 3);
+
+    let mut buf = Buffer::new(&w.data);
+    let params = CdefParams::decode(&ctx, &mut buf, false, false);
+    assert_eq!(buf.get_position(), w.bits);
+    assert_eq!(params.cdef_damping, 5);
+    assert_eq!(params.cdef_bits, 1);
+    assert_eq!(params.cdef_y_pri_strength[..2], [15, 0]);
+    assert_eq!(params.cdef_y_sec_strength[..2], [4, 1]);
+    assert_eq!(params.cdef_uv_pri_strength[..2], [1, 7]);
+    assert_eq!(params.cdef_uv_sec_strength[..2], [2, 4]);
+}
+
+#[test]
+fn lossless_and_intrabc_frames_skip_the_loop_filter_and_cdef() {
+    let ctx = context_with_sequence_header(SequenceOptions {
+        enable_restoration: true,
+        ..Default::default()
+    });
+
+    let data = [0xff; 8];
+    for (coded_lossless, allow_intrabc) in [(true, false), (false, true)] {
+        let mut buf = Buffer::new(&data);
+        let params = LoopFilterParams::decode(&ctx, &mut buf, coded_lossless, allow_intrabc, None);
+        assert_eq!(params, LoopFilterParams::default());
+
+        let params = CdefParams::decode(&ctx, &mut buf, coded_lossless, allow_intrabc);
+        assert_eq!(params, CdefParams::default());
+        assert_eq!(buf.get_position(), 0);
+    }
+
+    // The loop restoration is skipped with intra block copy and when all the
+    // planes are lossless.
+    let mut buf = Buffer::new(&data);
+    assert!(!LrParams::decode(&ctx, &mut buf, true, false).uses_lr);
+    assert!(!LrParams::decode(&ctx, &mut buf, false, true).uses_lr);
+    assert_eq!(buf.get_position(), 0);
+}
+
+#[test]
+fn loop_restoration_units_follow_the_unit_shifts() {
+    let mut ctx = context_with_sequence_header(SequenceOptions {
+        enable_restoration: true,
+        ..Default::default()
+    });
+
+    // lr_type of each plane, lr_unit_shift, lr_unit_extra_shift and
+    // lr_uv_shift.
+    let mut w = BitWriter::default();
+    w.put(2, 1)
+        .put(2, 2)
+        .put(2, 0)
+        .put(1, 1)
+        .put(1, 1)
+        .put(1, 1);
+    let params = LrParams::decode(&ctx, &mut Buffer::new(&w.data), false, false);
+    assert_eq!(
+        params.frame_restoration_type,
+        [
+            RestorationType::Switchable,
+            RestorationType::Wiener,
+            RestorationType::None
+        ]
+    );
+    assert!(params.uses_lr && params.uses_chroma_lr);
+    assert_eq!(params.loop_restoration_size, [256, 128, 128]);
+
+    // Without chroma restoration, lr_uv_shift is not read.
+    let mut w = BitWriter::default();
+    w.put(2, 3).put(2, 0).put(2, 0).put(1, 0).put(1, 1);
+    let mut buf = Buffer::new(&w.data);
+    let params = LrParams::decode(&ctx, &mut buf, false, false);
+    assert!(params.uses_lr && !params.uses_chroma_lr);
+    assert_eq!(params.loop_restoration_size, [64, 64, 64]);
+    assert_eq!(buf.get_position(), 7);
+
+    // The restoration units of 128x128 superblocks are at least 128x128.
+    ctx = context_with_sequence_header(SequenceOptions {
+        use_128x128_superblock: true,
+        enable_restoration: true,
+        ..Default::default()
+    });
+
+    let mut w = BitWriter::default();
+    w.put(2, 2).put(2, 2).put(2, 2).put(1, 0).put(1, 0);
+    let params = LrParams::decode(&ctx, &mut Buffer::new(&w.data), false, false);
+    assert_eq!(params.loop_restoration_size, [128, 128, 128]);
+
+    // Nothing is read for intra block copy frames.
+    let mut buf = Buffer::new(&w.data);
+    let params = LrParams::decode(&ctx, &mut buf, false, true);
+    assert!(!params.uses_lr);
+    assert_eq!(buf.get_position(), 0);
+}
+
+/// Returns the skip mode params of an inter frame with the order hint 10,
+/// whose references have the given order hints.
+fn skip_mode_params(hints: [u32; 7], data: &[u8]) -> (SkipModeParams, usize) {
+    let mut ctx = context_with_sequence_header(SequenceOptions {
+        enable_order_hint: true,
+        ..Default::default()
+    });
+
+    ctx.order_hint = 10;
+    ctx.ref_order_hint[..7].copy_from_slice(&hints);
+
+    let mut buf = Buffer::new(data);
+    let params = SkipModeParams::decode(&ctx, &mut buf, true, &[0, 1, 2, 3, 4, 5, 6]);
+    (params, buf.get_position())
+}
+
+#[test]
+fn skip_mode_uses_the_closest_forward_and_backward_references() {
+    // LAST2_FRAME (9) and ALTREF2_FRAME (11).
+    let (params, bits) = skip_mode_params([8, 9, 4, 12, 15, 11, 9], &[0x80]);
+    assert!(params.skip_mode_present);
+    assert_eq!(params.skip_mode_frame, [2, 6]);
+    assert_eq!(bits, 1);
+
+    // Order hints wrap around with 7 bits, 120 is before 10.
+    let (params, _) = skip_mode_params([120, 4, 4, 12, 15, 11, 9], &[0x00]);
+    assert!(!params.skip_mode_present);
+    assert_eq!(params.skip_mode_frame, [6, 7]);
+}
+
+#[test]
+fn skip_mode_falls_back_to_the_two_closest_forward_references() {
+    // LAST_FRAME (8) and LAST2_FRAME (9).
+    let (params, _) = skip_mode_params([8, 9, 4, 7, 6, 5, 3], &[0x80]);
+    assert!(params.skip_mode_present);
+    assert_eq!(params.skip_mode_frame, [1, 2]);
+
+    // With a single forward order hint, skip_mode_present is not read.
+    let (params, bits) = skip_mode_params([9, 9, 9, 9, 9, 9, 9], &[0x80]);
+    assert_eq!(params, SkipModeParams::default());
+    assert_eq!(bits, 0);
+}
+
+/// Decodes the global motion params of an inter frame.
+fn global_motion_params(
+    w: &BitWriter,
+    allow_high_precision_mv: bool,
+    prev: Option<&GlobalMotionParams>,
+) -> GlobalMotionParams {
+    let ctx = context_with_sequence_header(SequenceOptions::default());
+    let mut buf = Buffer::new(&w.data);
+    let params = GlobalMotionParams::decode(&ctx, &mut buf, allow_high_precision_mv, prev);
+    assert_eq!(buf.get_position(), w.bits);
+    params
+}
+
+#[test]
+fn translations_are_coded_with_subexponential_codes() {
+    // is_global, is_rot_zoom and is_translation of LAST_FRAME, then
+    // subexp_more_bits and subexp_bits of each parameter.
+    let mut w = BitWriter::default();
+    w.put(1, 1).put(1, 0).put(1, 1);
+    w.put(1, 0).put(3, 5);
+    w.put(1, 1).put(1, 0).put(3, 7);
+    // is_global of LAST2_FRAME..=ALTREF_FRAME.
+    w.put(6, 0);
+
+    let params = global_motion_params(&w, false, None);
+    assert_eq!(params.gm_type[1], GmType::Translation);
+    assert_eq!(
+        params.gm_params[1],
+        [-3 << 14, -8 << 14, 1 << 16, 0, 0, 1 << 16]
+    );
+    for ref_frame in 2..8 {
+        assert_eq!(params.gm_type[ref_frame], GmType::Identity);
+    }
+
+    // The high precision translations have one more bit of precision.
+    let params = global_motion_params(&w, true, None);
+    assert_eq!(params.gm_params[1][..2], [-3 << 13, -8 << 13]);
+}
+
+#[test]
+fn parameters_are_coded_relative_to_the_primary_reference_frame() {
+    let mut prev = GlobalMotionParams::default();
+    prev.gm_params[1][0] = 3 << 14;
+    prev.gm_params[1][1] = -5 << 14;
+
+    // A zero code is the reference value itself.
+    let mut w = BitWriter::default();
+    w.put(1, 1).put(1, 0).put(1, 1);
+    w.put(1, 0).put(3, 0);
+    w.put(1, 0).put(3, 0);
+    w.put(6, 0);
+
+    let params = global_motion_params(&w, false, Some(&prev));
+    assert_eq!(params.gm_params[1][..2], [3 << 14, -5 << 14]);
+
+    let params = global_motion_params(&w, false, None);
+    assert_eq!(params.gm_params[1][..2], [0, 0]);
+}
+
+#[test]
+fn rotation_and_zoom_models_mirror_their_parameters() {
+    // The parameters 2, 3, 0 and 1, the diagonal is coded relative to
+    // 1 << WARPEDMODEL_PREC_BITS.
+    let mut w = BitWriter::default();
+    w.put(1, 1).put(1, 1);
+    w.put(1, 0).put(3, 0);
+    w.put(1, 0).put(3, 5);
+    w.put(1, 0).put(3, 0);
+    w.put(1, 0).put(3, 0);
+    w.put(6, 0);
+
+    let params = global_motion_params(&w, false, None);
+    assert_eq!(params.gm_type[1], GmType::RotZoom);
+    assert_eq!(params.gm_params[1], [0, 0, 1 << 16, -6, 6, 1 << 16]);
+}
+
+#[test]
+fn film_grain_params_are_loaded_from_the_reference_slot() {
+    let mut ctx = context_with_sequence_header(SequenceOptions {
+        film_grain_params_present: true,
+        ..Default::default()
+    });
+
+    let mut saved = FilmGrainParams {
+        apply_grain: true,
+        grain_seed: 7,
+        update_grain: true,
+        num_y_points: 2,
+        grain_scaling: 9,
+        ar_coeff_shift: 7,
+        ..Default::default()
+    };
+    saved.point_y_value[..2].copy_from_slice(&[16, 200]);
+    saved.point_y_scaling[..2].copy_from_slice(&[40, 80]);
+    ctx.saved_film_grain_params[5] = saved;
+
+    // apply_grain, grain_seed, update_grain and film_grain_params_ref_idx.
+    let mut w = BitWriter::default();
+    w.put(1, 1).put(16, 0x1234).put(1, 0).put(3, 5);
+    let mut buf = Buffer::new(&w.data);
+    let params =
+        FilmGrainParams::decode(&ctx, &mut buf, FrameType::InterFrame, true, false).unwrap();
+    assert_eq!(buf.get_position(), w.bits);
+    assert_eq!(
+        params,
+        FilmGrainParams {
+            grain_seed: 0x1234,
+            update_grain: false,
+            film_grain_params_ref_idx: Some(5),
+            ..saved
+        }
+    );
+
+    // update_grain is only read for inter frames, the other frames code
+    // their parameters, all zero here.
+    let mut w = BitWriter::default();
+    w.put(1, 1).put(16, 0x1234).put(15, 0);
+    let params = FilmGrainParams::decode(
+        &ctx,
+        &mut Buffer::new(&w.data),
+        FrameType::InterOnlyFrame,
+        true,
+        false,
+    )
+    .unwrap();
+    assert!(params.update_grain);
+    assert_eq!(params.film_grain_params_ref_idx, None);
+}
+
+/// The offset in bytes of the frame header of the key frame, after the
+/// temporal delimiter, the sequence header and the header of the frame obu.
+const KEY_FRAME_HEADER_OFFSET: usize = 2 + 8 + 2;
+
+#[test]
+fn a_failed_frame_header_is_not_copied() {
+    let mut ctx = ObuContext::default();
+    let mut buf = Buffer::new(&KEY_FRAME_TWO_TILES[KEY_FRAME_HEADER_OFFSET..]);
+    assert!(matches!(
+        FrameHeader::decode(&mut ctx, &mut buf),
+        Err(ObuError::NotFoundSequenceHeader)
+    ));
+    assert!(!ctx.seen_frame_header);
+}
+
+#[test]
+fn frame_header_copies_must_match_the_first_header() {
+    let mut buf = Buffer::new(&KEY_FRAME_TWO_TILES);
+    let (mut ctx, _) = decode_frame_header(&mut buf);
+    assert!(ctx.seen_frame_header);
+
+    let header = &KEY_FRAME_TWO_TILES[KEY_FRAME_HEADER_OFFSET..];
+    let mut buf = Buffer::new(header);
+    let copy = FrameHeader::decode(&mut ctx, &mut buf).unwrap();
+    assert_eq!(buf.get_position(), ctx.frame_header_size);
+    assert!(copy.uncompressed_header.disable_frame_end_update_cdf);
+    assert_eq!(copy.uncompressed_header.frame_type, FrameType::KeyFrame);
+
+    // Flip the last bit of the header.
+    let last = ctx.frame_header_size - 1;
+    let mut data = header.to_vec();
+    data[last / 8] ^= 0x80 >> (last % 8);
+    let mut buf = Buffer::new(&data);
+    assert!(matches!(
+        FrameHeader::decode(&mut ctx, &mut buf),
+        Err(ObuError::FrameHeaderMismatch)
+    ));
+}
+
+#[test]
+fn the_last_tile_group_ends_the_frame_header() {
+    let mut buf = Buffer::new(&KEY_FRAME_TWO_TILES);
+    let (mut ctx, size) = decode_frame_header(&mut buf);
+    assert!(ctx.seen_frame_header);
+
+    let tile_group = TileGroup::decode(&mut ctx, &mut buf, size).unwrap();
+    assert_eq!(tile_group.tg_end, 1);
+    assert!(!ctx.seen_frame_header);
+}
@@ -0,0 +1,237 @@
+use davone::{
+    inter_prediction::{
+        block_inter_prediction, difference_weight_mask, distance_weights, motion_vector_scaling,
+        wedge_mask, InterPrediction, Mask,
+    },
+    obu::frame_header::InterpolationFilter,
+    picture::Plane,
+};
+
+/// A 16x16 plane where each sample is `x + 16 * y`.
+fn ramp() -> Plane {
+    let mut plane = Plane::new(16, 16);
+    for y in 0..16 {
+        for x in 0..16 {
+            plane.set(x, y, (x + 16 * y) as u16);
+        }
+    }
+
+    plane
+}
+
+#[test]
+fn integer_positions_copy_the_reference() {
+    let reference = ramp();
+    for filter in [
+        InterpolationFilter::Eighttap,
+        InterpolationFilter::EighttapSmooth,
+        InterpolationFilter::EighttapSharp,
+        InterpolationFilter::Bilinear,
+    ] {
+        let mut pred: InterPrediction = [[0; 128]; 128];
+        block_inter_prediction(
+            &mut pred,
+            &reference,
+            15,
+            15,
+            3 << 10,
+            2 << 10,
+            1 << 10,
+            1 << 10,
+            8,
+            4,
+            [filter; 2],
+            [3, 11],
+        );
+
+        for (i, row) in pred.iter().take(4).enumerate() {
+            for (j, p) in row.iter().take(8).enumerate() {
+                assert_eq!(*p, (3 + j + 16 * (2 + i)) as i32);
+            }
+        }
+    }
+}
+
+#[test]
+fn samples_outside_the_reference_repeat_its_edges() {
+    let reference = ramp();
+    let mut pred: InterPrediction = [[0; 128]; 128];
+    block_inter_prediction(
+        &mut pred,
+        &reference,
+        15,
+        15,
+        -2 << 10,
+        13 << 10,
+        1 << 10,
+        1 << 10,
+        4,
+        4,
+        [InterpolationFilter::Bilinear; 2],
+        [3, 11],
+    );
+
+    assert_eq!(pred[0][..4], [208, 208, 208, 209]);
+    assert_eq!(pred[3][..4], [240, 240, 240, 241]);
+}
+
+#[test]
+fn bilinear_half_sample_positions_average_the_neighbors() {
+    let reference = ramp();
+    let mut pred: InterPrediction = [[0; 128]; 128];
+
+    // Half a sample to the right and below, the ramp is interpolated
+    // exactly up to the rounding of the intermediate results.
+    block_inter_prediction(
+        &mut pred,
+        &reference,
+        15,
+        15,
+        (4 << 10) + 512,
+        (4 << 10) + 512,
+        1 << 10,
+        1 << 10,
+        4,
+        4,
+        [InterpolationFilter::Bilinear; 2],
+        [3, 7],
+    );
+
+    for (i, row) in pred.iter().take(4).enumerate() {
+        for (j, p) in row.iter().take(4).enumerate() {
+            let v = (4 + j) * 2 + 1 + 16 * ((4 + i) * 2 + 1);
+            assert_eq!(*p, (v << 3) as i32);
+        }
+    }
+}
+
+#[test]
+fn small_blocks_use_the_4_taps_filters() {
+    // The regular and sharp filters only differ by their outer taps.
+    let mut reference = Plane::new(16, 16);
+    for y in 0..16 {
+        for x in 0..16 {
+            reference.set(x, y, if (x + y) % 3 == 0 { 200 } else { 10 });
+        }
+    }
+
+    let predict = |filter, w| {
+        let mut pred: InterPrediction = [[0; 128]; 128];
+        block_inter_prediction(
+            &mut pred,
+            &reference,
+            15,
+            15,
+            (4 << 10) + 320,
+            (4 << 10) + 704,
+            1 << 10,
+            1 << 10,
+            w,
+            4,
+            [filter; 2],
+            [3, 11],
+        );
+
+        pred
+    };
+
+    let regular = predict(InterpolationFilter::Eighttap, 4);
+    let sharp = predict(InterpolationFilter::EighttapSharp, 4);
+    assert_eq!(regular[..4], sharp[..4]);
+
+    let regular = predict(InterpolationFilter::Eighttap, 8);
+    let sharp = predict(InterpolationFilter::EighttapSharp, 8);
+    assert_ne!(regular[..4], sharp[..4]);
+}
+
+#[test]
+fn motion_vectors_are_scaled_to_the_reference_size() {
+    // 1/8 luma sample motion vectors, the chroma of 4:2:0 moves by half.
+    // The positions are offset by half of a 1/16 sample filter position.
+    let (x, y, x_step, y_step) = motion_vector_scaling((64, 64), (64, 64), (0, 0), 8, 4, [-12, 20]);
+    assert_eq!((x, y), ((8 << 10) + 2560 + 32, (4 << 10) - 1536 + 32));
+    assert_eq!((x_step, y_step), (1 << 10, 1 << 10));
+
+    let (x, y, _, _) = motion_vector_scaling((64, 64), (64, 64), (1, 1), 8, 4, [-12, 20]);
+    assert_eq!((x, y), ((8 << 10) + 1280 + 32, (4 << 10) - 768 + 32));
+
+    // A reference twice as large is sampled every two samples.
+    let (x, y, x_step, y_step) = motion_vector_scaling((64, 32), (128, 64), (0, 0), 8, 4, [0, 0]);
+    assert_eq!((x_step, y_step), (2 << 10, 2 << 10));
+    assert_eq!((x, y), ((16 << 10) + 512 + 32, (8 << 10) + 512 + 32));
+}
+
+#[test]
+fn wedge_signs_select_complementary_masks() {
+    // BLOCK_8X8, BLOCK_16X32, BLOCK_32X16 and BLOCK_8X32.
+    for (bsize, w, h) in [(3, 8, 8), (7, 16, 32), (8, 32, 16), (18, 8, 32)] {
+        for index in 0..16 {
+            let mut mask0: Mask = [[0; 128]; 128];
+            let mut mask1: Mask = [[0; 128]; 128];
+            wedge_mask(&mut mask0, bsize, 0, index);
+            wedge_mask(&mut mask1, bsize, 1, index);
+            for (row0, row1) in mask0.iter().zip(&mask1).take(h) {
+                for (m0, m1) in row0.iter().zip(row1).take(w) {
+                    assert!(*m0 <= 64);
+                    assert_eq!(m0 + m1, 64);
+                }
+            }
+
+            // The first prediction weighs the most along the top and left
+            // edges with the sign 0.
+            let edge = mask0[0][..w]
+                .iter()
+                .chain(mask0[1..h].iter().map(|row| &row[0]))
+                .map(|m| *m as usize)
+                .sum::<usize>();
+            assert!(edge * 2 >= 64 * (w + h - 1) - (w + h - 1));
+        }
+    }
+}
+
+#[test]
+fn vertical_wedge_splits_the_block_in_halves() {
+    // The wedge 5 of the 32x16 blocks is vertical, through the center.
+    let mut mask: Mask = [[0; 128]; 128];
+    wedge_mask(&mut mask, 8, 0, 5);
+    for row in mask.iter().take(16) {
+        assert_eq!(row[..32], mask[0][..32]);
+        assert!(row[..13].iter().all(|m| *m == 64));
+        assert!(row[19..32].iter().all(|m| *m == 0));
+    }
+}
+
+#[test]
+fn difference_weights_grow_with_the_difference() {
+    let mut preds = Box::new([[[0; 128]; 128]; 2]);
+    preds[0][0][..4].copy_from_slice(&[1000, 1000, 1000, 1000]);
+    preds[1][0][..4].copy_from_slice(&[1000, 1000 + 16 * 16, 1000 - 16 * 16 * 4, -10000]);
+
+    let mut mask: Mask = [[0; 128]; 128];
+    difference_weight_mask(&mut mask, &preds, 4, 1, 0, 8, 4);
+    assert_eq!(mask[0][..4], [38, 39, 42, 64]);
+
+    difference_weight_mask(&mut mask, &preds, 4, 1, 1, 8, 4);
+    assert_eq!(mask[0][..4], [26, 25, 22, 0]);
+
+    // The difference is in units of 8 bits samples.
+    difference_weight_mask(&mut mask, &preds, 4, 1, 0, 10, 2);
+    assert_eq!(mask[0][..4], [38, 39, 42, 64]);
+}
+
+#[test]
+fn the_closest_reference_gets_the_largest_weight() {
+    for dist in [[1, 1], [1, 4], [4, 1], [2, 3], [-3, 2], [0, 5], [40, 1]] {
+        let (fwd, bck) = distance_weights(dist);
+        assert_eq!(fwd + bck, 16);
+        if dist[0].abs() < dist[1].abs() {
+            assert!(fwd > bck);
+        } else if dist[0].abs() > dist[1].abs() {
+            assert!(fwd < bck);
+        }
+    }
+
+    assert_eq!(distance_weights([1, 4]), (13, 3));
+    assert_eq!(distance_weights([2, 3]), (11, 5));
+    assert_eq!(distance_weights([1, 1]), (7, 9));
+}
@@ -0,0 +1,370 @@
+use davone::{
+    constants::INTRA_FILTER_MODES,
+    intra::{
+        filter_corner, filter_edge, intra_edge_filter_strength, intra_edge_upsample, predict_dc,
+        predict_directional, predict_filter_intra, predict_paeth, predict_smooth, predict_smooth_h,
+        predict_smooth_v, upsample_edge, Edge, Prediction,
+    },
+};
+
+/// An edge holding `samples` from its top left corner.
+fn edge(samples: &[u16]) -> Edge {
+    let mut edge = Edge::default();
+    for (i, v) in samples.iter().enumerate() {
+        edge[i as i32 - 1] = *v;
+    }
+
+    edge
+}
+
+/// The `n` samples of `edge` from the entry `start`.
+fn samples(edge: &Edge, start: i32, n: i32) -> Vec<u16> {
+    (start..start + n).map(|i| edge[i]).collect()
+}
+
+/// The `w` by `h` predicted samples of a block, row by row.
+fn block(pred: &Prediction, w: usize, h: usize) -> Vec<Vec<u16>> {
+    pred[..h].iter().map(|row| row[..w].to_vec()).collect()
+}
+
+/// The edges of the 4x4 blocks of the basic and smooth predictions, from the
+/// top left corner.
+const ABOVE: [u16; 9] = [50, 60, 40, 100, 50, 90, 120, 130, 140];
+const LEFT: [u16; 9] = [50, 55, 10, 200, 50, 20, 30, 40, 50];
+
+#[test]
+fn dc_averages_the_available_edges() {
+    let above = edge(&[0, 10, 20, 30, 40, 50, 60, 70, 80]);
+    let left = edge(&[0, 50, 60, 70, 80, 90, 100, 110, 120]);
+    let mut pred: Prediction = [[0; 64]; 64];
+
+    predict_dc(&mut pred, &above, &left, true, true, 2, 2, 8);
+    assert_eq!(block(&pred, 4, 4), vec![vec![45; 4]; 4]);
+
+    // The sum of both edges of a rectangular block is divided with rounding.
+    predict_dc(&mut pred, &above, &left, true, true, 3, 2, 8);
+    assert_eq!(block(&pred, 8, 4), vec![vec![(620 + 6) / 12; 8]; 4]);
+
+    predict_dc(&mut pred, &above, &left, true, false, 2, 2, 8);
+    assert_eq!(block(&pred, 4, 4), vec![vec![25; 4]; 4]);
+
+    predict_dc(&mut pred, &above, &left, false, true, 2, 3, 8);
+    assert_eq!(block(&pred, 4, 8), vec![vec![85; 4]; 8]);
+
+    // Without any edge the block is predicted at half the sample range.
+    predict_dc(&mut pred, &above, &left, false, false, 2, 2, 10);
+    assert_eq!(block(&pred, 4, 4), vec![vec![512; 4]; 4]);
+}
+
+#[test]
+fn paeth_picks_the_closest_edge_sample() {
+    let mut pred: Prediction = [[0; 64]; 64];
+    predict_paeth(&mut pred, &edge(&ABOVE), &edge(&LEFT), 2, 2);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [
+            [60, 40, 100, 55],
+            [10, 10, 50, 10],
+            [200, 200, 200, 200],
+            [60, 40, 100, 50],
+        ]
+    );
+}
+
+#[test]
+fn smooth_blends_towards_the_opposite_corners() {
+    let (above, left) = (edge(&ABOVE), edge(&LEFT));
+    let mut pred: Prediction = [[0; 64]; 64];
+
+    predict_smooth(&mut pred, &above, &left, 2, 2);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [
+            [57, 46, 76, 51],
+            [33, 35, 58, 45],
+            [126, 92, 83, 69],
+            [51, 49, 56, 50],
+        ]
+    );
+
+    // The vertical blend goes from the above row to the bottom left sample.
+    predict_smooth_v(&mut pred, &above, &left, 2, 2);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [
+            [60, 40, 100, 50],
+            [56, 44, 79, 50],
+            [53, 47, 67, 50],
+            [53, 48, 63, 50],
+        ]
+    );
+
+    // The horizontal blend goes from the left column to the top right sample.
+    predict_smooth_h(&mut pred, &above, &left, 2, 2);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [
+            [55, 53, 52, 51],
+            [10, 27, 37, 40],
+            [199, 137, 100, 88],
+            [50, 50, 50, 50],
+        ]
+    );
+}
+
+#[test]
+fn directional_copies_the_edges_at_whole_sample_angles() {
+    let above = edge(&[0, 1, 2, 3, 4, 5, 6, 7, 8]);
+    let left = edge(&[0, 11, 12, 13, 14, 15, 16, 17, 18]);
+    let mut pred: Prediction = [[0; 64]; 64];
+
+    predict_directional(&mut pred, &above, &left, 2, 2, 90, false, false);
+    assert_eq!(block(&pred, 4, 4), vec![vec![1, 2, 3, 4]; 4]);
+
+    predict_directional(&mut pred, &above, &left, 2, 2, 180, false, false);
+    assert_eq!(block(&pred, 4, 4), [[11; 4], [12; 4], [13; 4], [14; 4]]);
+
+    // Up and to the right, along the above row.
+    predict_directional(&mut pred, &above, &left, 2, 2, 45, false, false);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [[2, 3, 4, 5], [3, 4, 5, 6], [4, 5, 6, 7], [5, 6, 7, 8]]
+    );
+
+    // The projections past the sample w + h - 1 repeat it.
+    predict_directional(&mut pred, &above, &left, 2, 2, 3, false, false);
+    assert_eq!(block(&pred, 4, 4), vec![vec![8; 4]; 4]);
+
+    // Up and to the left, through the top left corner.
+    predict_directional(&mut pred, &above, &left, 2, 2, 135, false, false);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [[0, 1, 2, 3], [11, 0, 1, 2], [12, 11, 0, 1], [13, 12, 11, 0]]
+    );
+
+    // Down and to the left.
+    predict_directional(&mut pred, &above, &left, 2, 2, 225, false, false);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [
+            [12, 13, 14, 15],
+            [13, 14, 15, 16],
+            [14, 15, 16, 17],
+            [15, 16, 17, 18],
+        ]
+    );
+}
+
+#[test]
+fn directional_interpolates_between_edge_samples() {
+    let mut pred: Prediction = [[0; 64]; 64];
+    let above = edge(&ABOVE);
+    predict_directional(&mut pred, &above, &Edge::default(), 2, 2, 67, false, false);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [
+            [52, 64, 80, 66],
+            [43, 91, 58, 84],
+            [55, 88, 60, 98],
+            [81, 66, 78, 111],
+        ]
+    );
+
+    // Along a ramp the prediction is the ramp at the projected position,
+    // which moves twice as fast along an upsampled edge.
+    let ramp: Vec<u16> = std::iter::once(0).chain((0..40).map(|k| 8 * k)).collect();
+    let left = edge(&ramp);
+    predict_directional(&mut pred, &Edge::default(), &left, 2, 2, 203, false, false);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [
+            [3, 7, 10, 14],
+            [11, 15, 18, 22],
+            [19, 23, 26, 30],
+            [27, 31, 34, 38],
+        ]
+    );
+
+    predict_directional(&mut pred, &Edge::default(), &left, 2, 2, 203, false, true);
+    assert_eq!(
+        block(&pred, 4, 4),
+        [
+            [7, 14, 20, 27],
+            [23, 30, 36, 43],
+            [39, 46, 52, 59],
+            [55, 62, 68, 75],
+        ]
+    );
+}
+
+#[test]
+fn edge_filter_strength_grows_with_the_block_size_and_the_angle() {
+    assert_eq!(intra_edge_filter_strength(4, 4, false, 45), 0);
+    assert_eq!(intra_edge_filter_strength(4, 4, false, -67), 1);
+    assert_eq!(intra_edge_filter_strength(8, 8, false, 3), 0);
+    assert_eq!(intra_edge_filter_strength(8, 8, false, 42), 1);
+    assert_eq!(intra_edge_filter_strength(16, 8, false, 8), 1);
+    assert_eq!(intra_edge_filter_strength(16, 8, false, 23), 2);
+    assert_eq!(intra_edge_filter_strength(16, 8, false, -45), 3);
+    assert_eq!(intra_edge_filter_strength(16, 16, false, 3), 1);
+    assert_eq!(intra_edge_filter_strength(16, 16, false, 6), 2);
+    assert_eq!(intra_edge_filter_strength(32, 32, false, 3), 3);
+
+    // The blocks next to smooth predictions are filtered harder.
+    assert_eq!(intra_edge_filter_strength(4, 4, true, 45), 1);
+    assert_eq!(intra_edge_filter_strength(4, 4, true, 67), 2);
+    assert_eq!(intra_edge_filter_strength(8, 8, true, 23), 1);
+    assert_eq!(intra_edge_filter_strength(8, 8, true, 48), 2);
+    assert_eq!(intra_edge_filter_strength(16, 8, true, 3), 0);
+    assert_eq!(intra_edge_filter_strength(16, 8, true, 6), 3);
+    assert_eq!(intra_edge_filter_strength(16, 16, true, 3), 3);
+}
+
+#[test]
+fn only_small_blocks_at_steep_angles_are_upsampled() {
+    assert!(intra_edge_upsample(4, 4, false, 3));
+    assert!(intra_edge_upsample(8, 8, false, -36));
+    assert!(!intra_edge_upsample(8, 8, false, 0));
+    assert!(!intra_edge_upsample(8, 8, false, 45));
+    assert!(!intra_edge_upsample(16, 8, false, 3));
+
+    assert!(intra_edge_upsample(4, 4, true, 3));
+    assert!(!intra_edge_upsample(8, 4, true, 3));
+}
+
+#[test]
+fn corner_is_smoothed_with_its_neighbors() {
+    let above = edge(&[100, 20]);
+    let left = edge(&[100, 52]);
+    assert_eq!(
+        filter_corner(&above, &left),
+        (52 * 5 + 100 * 6 + 20 * 5 + 8) >> 4
+    );
+}
+
+#[test]
+fn edge_filter_smooths_steps() {
+    let step = [0, 0, 0, 0, 160, 160, 160, 160];
+
+    let mut e = edge(&step);
+    filter_edge(&mut e, 8, 0);
+    assert_eq!(e, edge(&step));
+
+    let mut e = edge(&step);
+    filter_edge(&mut e, 8, 1);
+    assert_eq!(samples(&e, -1, 8), [0, 0, 0, 40, 120, 160, 160, 160]);
+
+    let mut e = edge(&step);
+    filter_edge(&mut e, 8, 2);
+    assert_eq!(samples(&e, -1, 8), [0, 0, 0, 50, 110, 160, 160, 160]);
+
+    let mut e = edge(&step);
+    filter_edge(&mut e, 8, 3);
+    assert_eq!(samples(&e, -1, 8), [0, 0, 20, 60, 100, 140, 160, 160]);
+
+    // The samples past the end of the edge are neither read nor written.
+    let mut e = edge(&[0, 0, 0, 0, 160, 160, 1000, 1000]);
+    filter_edge(&mut e, 6, 3);
+    assert_eq!(samples(&e, -1, 8), [0, 0, 20, 60, 100, 140, 1000, 1000]);
+}
+
+#[test]
+fn edge_filter_keeps_flat_edges() {
+    for strength in 1..=3 {
+        let mut e = edge(&[700; 65]);
+        filter_edge(&mut e, 65, strength);
+        assert!(samples(&e, -1, 65).iter().all(|v| *v == 700));
+    }
+}
+
+#[test]
+fn upsampling_interleaves_the_edge() {
+    let mut e = edge(&[10, 20, 30, 40, 50]);
+    upsample_edge(&mut e, 4, 8);
+    assert_eq!(samples(&e, -2, 9), [10, 14, 20, 25, 30, 35, 40, 46, 50]);
+}
+
+#[test]
+fn upsampling_is_clipped_to_the_bit_depth() {
+    // The interpolation undershoots below 0 before the step and overshoots
+    // past 1023 after it.
+    let mut e = edge(&[0, 0, 1023, 1023, 1023]);
+    upsample_edge(&mut e, 4, 10);
+    assert_eq!(
+        samples(&e, -2, 9),
+        [0, 0, 0, 512, 1023, 1023, 1023, 1023, 1023]
+    );
+
+    let mut e = edge(&[0, 0, 255, 255, 0]);
+    upsample_edge(&mut e, 4, 8);
+    assert!(samples(&e, -2, 9).iter().all(|v| *v <= 255));
+}
+
+#[test]
+fn filter_intra_keeps_flat_blocks() {
+    // The taps of every sample of a 4x2 unit sum to 16.
+    for bit_depth in [8, 10, 12] {
+        let v = (1 << (bit_depth - 1)) + 3;
+        let e = edge(&[v; 65]);
+        for mode in 0..INTRA_FILTER_MODES {
+            for log2_w in 2..=5 {
+                for log2_h in 2..=5 {
+                    let mut pred: Prediction = [[0; 64]; 64];
+                    predict_filter_intra(&mut pred, &e, &e, log2_w, log2_h, mode, bit_depth);
+                    assert!(pred[..1 << log2_h]
+                        .iter()
+                        .all(|row| row[..1 << log2_w].iter().all(|p| *p == v)));
+                    assert!(pred[..1 << log2_h]
+                        .iter()
+                        .all(|row| row[1 << log2_w..].iter().all(|p| *p == 0)));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn filter_intra_predicts_from_the_previous_units() {
+    let above = edge(&[0, 16, 32, 48, 64]);
+    let left = edge(&[0, 80, 96, 112, 128]);
+    let mut pred: Prediction = [[0; 64]; 64];
+
+    // FILTER_H_PRED, the second unit uses the last row of the first one.
+    predict_filter_intra(&mut pred, &above, &left, 2, 2, 2, 8);
+    assert_eq!(pred[0][..4], [88, 96, 104, 112]);
+    assert_eq!(pred[1][..4], [100, 104, 108, 112]);
+    assert_eq!(pred[2][..4], [114, 116, 118, 120]);
+    assert_eq!(pred[3][..4], [129, 130, 131, 132]);
+}
+
+#[test]
+fn filter_intra_is_clipped_to_the_bit_depth() {
+    for bit_depth in [10, 12] {
+        let max = (1 << bit_depth) - 1;
+        for mode in 0..INTRA_FILTER_MODES {
+            for (log2_w, log2_h) in [(2, 2), (3, 2), (5, 4), (5, 5)] {
+                let (w, h) = (1 << log2_w, 1 << log2_h);
+
+                // The negative tap of the corner overshoots past the
+                // largest sample.
+                let mut e = edge(&[max; 65]);
+                e[-1] = 0;
+                let mut pred: Prediction = [[0; 64]; 64];
+                predict_filter_intra(&mut pred, &e, &e, log2_w, log2_h, mode, bit_depth);
+                assert!(pred[..h]
+                    .iter()
+                    .all(|row| row[..w].iter().all(|p| *p == max)));
+
+                // And undershoots below 0.
+                let mut e = edge(&[0; 65]);
+                e[-1] = max;
+                let mut pred: Prediction = [[0; 64]; 64];
+                predict_filter_intra(&mut pred, &e, &e, log2_w, log2_h, mode, bit_depth);
+                assert!(pred[..h]
+                    .iter()
+                    .all(|row| row[..w].iter().all(|p| *p <= max)));
+                assert_eq!(pred[0][0], 0);
+            }
+        }
+    }
+}
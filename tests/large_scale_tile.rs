@@ -0,0 +1,125 @@
+use davone::{
+    buffer::Buffer,
+    large_scale_tile::LargeScaleTileDecoder,
+    obu::{
+        frame_header::FrameHeader,
+        tile_list::{TileList, TileListEntry},
+        Obu, ObuError, ObuParser,
+    },
+};
+
+/// A 128x16 8 bits 4:2:0 key frame followed by an inter frame predicted from
+/// it, both with two 64x16 tiles, coded by libaom.
+#[rustfmt::skip]
+const TWO_TILE_FRAMES: [u8; 308] = [
+    18, 0, 10, 10, 0, 0, 0, 3, 31, 255, 53, 254, 96, 16, 50, 247, 1, 16, 1, 225, 0, 4, 0, 1, 130,
+    16, 136, 16, 64, 32, 0, 114, 227, 198, 201, 158, 68, 126, 8, 119, 251, 120, 236, 219, 152, 146,
+    40, 10, 115, 103, 229, 251, 88, 252, 52, 4, 183, 58, 242, 100, 155, 38, 236, 165, 16, 212, 250,
+    151, 4, 52, 72, 62, 82, 49, 131, 230, 163, 132, 70, 26, 139, 74, 95, 146, 156, 158, 38, 185, 8,
+    225, 176, 226, 12, 197, 159, 59, 10, 30, 61, 102, 166, 181, 189, 48, 141, 33, 119, 232, 91, 231,
+    220, 114, 127, 76, 191, 81, 170, 69, 108, 176, 159, 238, 4, 237, 194, 34, 82, 117, 130, 174,
+    254, 65, 17, 235, 196, 85, 208, 28, 240, 50, 59, 130, 72, 250, 60, 19, 132, 192, 133, 42, 39,
+    255, 87, 52, 253, 208, 128, 105, 28, 208, 172, 174, 255, 7, 31, 231, 106, 10, 190, 26, 23, 27,
+    92, 177, 114, 17, 29, 85, 206, 177, 29, 61, 210, 69, 127, 190, 164, 62, 235, 25, 228, 154, 41,
+    84, 44, 194, 253, 235, 10, 200, 125, 239, 72, 141, 13, 21, 231, 77, 84, 48, 235, 235, 180, 197,
+    191, 60, 11, 17, 89, 208, 113, 42, 227, 102, 4, 171, 165, 49, 212, 59, 71, 224, 61, 181, 246,
+    178, 139, 50, 66, 76, 223, 255, 133, 191, 78, 144, 132, 8, 185, 118, 200, 13, 35, 141, 248, 100,
+    80, 16, 0, 27, 233, 248, 210, 218, 18, 0, 50, 40, 48, 3, 192, 128, 0, 0, 111, 140, 0, 12, 0, 8,
+    9, 28, 0, 0, 0, 0, 6, 231, 203, 71, 7, 233, 174, 84, 233, 250, 28, 245, 216, 185, 63, 43, 221,
+    77, 11, 138, 160, 248,
+];
+
+#[test]
+fn tile_list_entries_are_decoded_into_the_output_frame() {
+    // Decoding the stream as usual gives the anchor frame, the coded tiles of
+    // the inter frame and the samples they decode to.
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&TWO_TILE_FRAMES);
+    let mut frames = vec![];
+    while buf.remaining() > 0 {
+        if let Obu::Frame(frame) = parser.parse(&mut buf).unwrap() {
+            frames.push((
+                frame.tile_group.tiles,
+                parser.ctx.frame_state.cur_frame.clone(),
+            ));
+        }
+    }
+
+    let [(_, anchor_frame), (tiles, expected)] = <[_; 2]>::try_from(frames).unwrap();
+    assert_eq!(tiles.len(), 2);
+
+    // The frame header of the inter frame is the camera frame header: skip
+    // the temporal delimiter, the sequence header, the key frame and the
+    // second temporal delimiter, then the obu_header and obu_size of the
+    // inter frame.
+    let mut camera = ObuParser::default();
+    let mut buf = Buffer::new(&TWO_TILE_FRAMES);
+    for _ in 0..4 {
+        camera.parse(&mut buf).unwrap();
+    }
+
+    buf.get_bytes(2);
+    FrameHeader::decode(&mut camera.ctx, &mut buf).unwrap();
+
+    // The output frame holds the two tiles swapped.
+    let mut decoder = LargeScaleTileDecoder::default();
+    decoder.set_anchor_frame(5, anchor_frame).unwrap();
+    let entry = |tile_col: usize| TileListEntry {
+        anchor_frame_idx: 5,
+        anchor_tile_row: 0,
+        anchor_tile_col: tile_col as u8,
+        tile_data_size: tiles[tile_col].data.len() as u32,
+        coded_tile_data: tiles[tile_col].data,
+    };
+    let tile_list = TileList {
+        output_frame_width_in_tiles: 2,
+        output_frame_height_in_tiles: 1,
+        tile_list_entrys: vec![entry(1), entry(0)],
+    };
+
+    let output = decoder.decode(&mut camera.ctx, &tile_list).unwrap();
+    assert_eq!((output.width(), output.height()), (128, 16));
+    for (plane, (output, expected)) in output.planes.iter().zip(&expected.planes).enumerate() {
+        let half = if plane == 0 { 64 } else { 32 };
+        for y in 0..output.height {
+            let (left, right) = output.row(y).split_at(half);
+            assert_eq!(
+                left,
+                &expected.row(y)[half..2 * half],
+                "plane {plane} row {y}"
+            );
+            assert_eq!(right, &expected.row(y)[..half], "plane {plane} row {y}");
+        }
+    }
+}
+
+#[test]
+fn entries_outside_the_tiles_of_the_camera_frame_are_rejected() {
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&TWO_TILE_FRAMES);
+    for _ in 0..4 {
+        parser.parse(&mut buf).unwrap();
+    }
+
+    buf.get_bytes(2);
+    FrameHeader::decode(&mut parser.ctx, &mut buf).unwrap();
+
+    let mut decoder = LargeScaleTileDecoder::default();
+    decoder
+        .set_anchor_frame(0, parser.ctx.frame_store[0].clone())
+        .unwrap();
+    let tile_list = TileList {
+        output_frame_width_in_tiles: 1,
+        output_frame_height_in_tiles: 1,
+        tile_list_entrys: vec![TileListEntry {
+            anchor_frame_idx: 0,
+            anchor_tile_row: 0,
+            anchor_tile_col: 2,
+            tile_data_size: 1,
+            coded_tile_data: &[0],
+        }],
+    };
+
+    let err = decoder.decode(&mut parser.ctx, &tile_list).unwrap_err();
+    assert_eq!(err, ObuError::InvalidTileList);
+}
@@ -0,0 +1,253 @@
+use davone::{
+    constants::*,
+    obu::{frame_header::FrameType, ObuContext},
+    tile::{
+        mode_info::{FrameState, Mv, INVALID_MV},
+        motion_field::{
+            get_mv_projection, motion_field_estimation, motion_field_motion_vector_storage,
+        },
+    },
+};
+
+const MI_ROWS: u32 = 16;
+const MI_COLS: u32 = 16;
+
+/// Each reference frame uses its own slot.
+const REF_FRAME_IDX: [u8; REFS_PER_FRAME as usize] = [0, 1, 2, 3, 4, 5, 6];
+
+/// A context for a frame with the order hint 4, the forward references have
+/// the order hint 3 and the backward references the order hint 6. None of
+/// the reference slots holds a usable motion field.
+fn context() -> ObuContext {
+    let mut ctx = ObuContext {
+        order_hint_bits: 7,
+        order_hint: 4,
+        mi_rows: MI_ROWS,
+        mi_cols: MI_COLS,
+        frame_state: FrameState::new(MI_ROWS, MI_COLS),
+        ..Default::default()
+    };
+
+    for ref_frame in LAST_FRAME..=ALTREF_FRAME {
+        ctx.order_hints[ref_frame as usize] = if ref_frame < BWDREF_FRAME { 3 } else { 6 };
+    }
+
+    ctx
+}
+
+/// Saves a uniform motion field in the slot of the reference frame `src`,
+/// every block uses `mv` to point to the LAST_FRAME of that reference frame
+/// which has the order hint `last_hint`.
+fn save_motion_field(ctx: &mut ObuContext, src: i8, mv: Mv, last_hint: u32) {
+    let idx = REF_FRAME_IDX[(src - LAST_FRAME) as usize] as usize;
+    let size = (MI_ROWS * MI_COLS) as usize;
+    ctx.ref_mi_rows[idx] = MI_ROWS;
+    ctx.ref_mi_cols[idx] = MI_COLS;
+    ctx.ref_frame_type[idx] = FrameType::InterFrame;
+    ctx.saved_order_hints[idx][LAST_FRAME as usize] = last_hint;
+    ctx.saved_mvs[idx] = vec![mv; size];
+    ctx.saved_ref_frames[idx] = vec![LAST_FRAME; size];
+}
+
+#[test]
+fn mv_projection_scales_and_clamps() {
+    assert_eq!(get_mv_projection([64, -32], 2, 1), [128, -64]);
+    assert_eq!(get_mv_projection([64, -32], -1, 2), [-32, 16]);
+    assert_eq!(get_mv_projection([-256, 256], 1, 4), [-64, 64]);
+
+    // The distances are clipped to MAX_FRAME_DISTANCE.
+    assert_eq!(
+        get_mv_projection([8, 0], 40, 1),
+        get_mv_projection([8, 0], 31, 1)
+    );
+
+    assert_eq!(
+        get_mv_projection([16, 0], 1, 40),
+        get_mv_projection([16, 0], 1, 31)
+    );
+
+    // The projected motion vector fits in 15 bits.
+    assert_eq!(
+        get_mv_projection([4000, -4000], 31, 1),
+        [(1 << 14) - 1, -(1 << 14) + 1]
+    );
+}
+
+#[test]
+fn motion_field_is_invalid_without_references() {
+    let mut ctx = context();
+    motion_field_estimation(&mut ctx, &REF_FRAME_IDX);
+
+    for ref_frame in LAST_FRAME..=ALTREF_FRAME {
+        for row in 0..MI_ROWS >> 1 {
+            for col in 0..MI_COLS >> 1 {
+                assert_eq!(
+                    ctx.frame_state.motion_field_mv(ref_frame, row, col),
+                    [INVALID_MV; 2]
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn backward_reference_is_projected_horizontally() {
+    let mut ctx = context();
+
+    // The blocks of BWDREF_FRAME move 32 luma samples to the left in the 4
+    // frames to its LAST_FRAME, they cross the current frame 16 samples to
+    // the left of their position, two 8x8 blocks.
+    save_motion_field(&mut ctx, BWDREF_FRAME, [0, -256], 2);
+    motion_field_estimation(&mut ctx, &REF_FRAME_IDX);
+
+    for row in 0..MI_ROWS >> 1 {
+        for col in 0..MI_COLS >> 1 {
+            let expected = |mv| if col < 6 { mv } else { [INVALID_MV; 2] };
+            let frame = &ctx.frame_state;
+            assert_eq!(
+                frame.motion_field_mv(LAST_FRAME, row, col),
+                expected([0, -64])
+            );
+            assert_eq!(
+                frame.motion_field_mv(GOLDEN_FRAME, row, col),
+                expected([0, -64])
+            );
+            assert_eq!(
+                frame.motion_field_mv(BWDREF_FRAME, row, col),
+                expected([0, 128])
+            );
+            assert_eq!(
+                frame.motion_field_mv(ALTREF_FRAME, row, col),
+                expected([0, 128])
+            );
+        }
+    }
+}
+
+#[test]
+fn vertical_projection_stays_in_the_superblock_row() {
+    let mut ctx = context();
+
+    // The blocks are projected two 8x8 blocks up, the first two rows of
+    // each 64x64 row would come from the row above and are dropped.
+    save_motion_field(&mut ctx, BWDREF_FRAME, [-256, 0], 2);
+    motion_field_estimation(&mut ctx, &REF_FRAME_IDX);
+
+    for row in 0..MI_ROWS >> 1 {
+        for col in 0..MI_COLS >> 1 {
+            let mv = ctx.frame_state.motion_field_mv(LAST_FRAME, row, col);
+            if row < 6 {
+                assert_eq!(mv, [-64, 0]);
+            } else {
+                assert_eq!(mv, [INVALID_MV; 2]);
+            }
+        }
+    }
+}
+
+#[test]
+fn last_frame_is_projected_backwards() {
+    let mut ctx = context();
+
+    // The blocks of LAST_FRAME came from 8 luma samples to the right in the
+    // frame before it, they keep moving to the left onto the current frame.
+    save_motion_field(&mut ctx, LAST_FRAME, [0, 64], 2);
+    motion_field_estimation(&mut ctx, &REF_FRAME_IDX);
+
+    for row in 0..MI_ROWS >> 1 {
+        for col in 0..MI_COLS >> 1 {
+            let expected = |mv| if col < 7 { mv } else { [INVALID_MV; 2] };
+            let frame = &ctx.frame_state;
+            assert_eq!(
+                frame.motion_field_mv(LAST_FRAME, row, col),
+                expected([0, 64])
+            );
+            assert_eq!(
+                frame.motion_field_mv(ALTREF_FRAME, row, col),
+                expected([0, -128])
+            );
+        }
+    }
+}
+
+#[test]
+fn unusable_references_are_not_projected() {
+    // An intra only reference frame.
+    let mut ctx = context();
+    save_motion_field(&mut ctx, BWDREF_FRAME, [0, -256], 2);
+    ctx.ref_frame_type[REF_FRAME_IDX[(BWDREF_FRAME - LAST_FRAME) as usize] as usize] =
+        FrameType::InterOnlyFrame;
+    motion_field_estimation(&mut ctx, &REF_FRAME_IDX);
+    assert_eq!(
+        ctx.frame_state.motion_field_mv(LAST_FRAME, 0, 0),
+        [INVALID_MV; 2]
+    );
+
+    // A reference frame of a different size.
+    let mut ctx = context();
+    save_motion_field(&mut ctx, BWDREF_FRAME, [0, -256], 2);
+    ctx.ref_mi_cols[REF_FRAME_IDX[(BWDREF_FRAME - LAST_FRAME) as usize] as usize] = MI_COLS * 2;
+    motion_field_estimation(&mut ctx, &REF_FRAME_IDX);
+    assert_eq!(
+        ctx.frame_state.motion_field_mv(LAST_FRAME, 0, 0),
+        [INVALID_MV; 2]
+    );
+
+    // A reference frame pointing to a frame after it.
+    let mut ctx = context();
+    save_motion_field(&mut ctx, BWDREF_FRAME, [0, -256], 8);
+    motion_field_estimation(&mut ctx, &REF_FRAME_IDX);
+    assert_eq!(
+        ctx.frame_state.motion_field_mv(LAST_FRAME, 0, 0),
+        [INVALID_MV; 2]
+    );
+}
+
+#[test]
+fn later_references_overwrite_earlier_projections() {
+    let mut ctx = context();
+    save_motion_field(&mut ctx, BWDREF_FRAME, [0, 0], 2);
+    save_motion_field(&mut ctx, ALTREF2_FRAME, [0, 8], 2);
+    save_motion_field(&mut ctx, ALTREF_FRAME, [0, 16], 2);
+    save_motion_field(&mut ctx, LAST2_FRAME, [0, 32], 2);
+    motion_field_estimation(&mut ctx, &REF_FRAME_IDX);
+
+    // BWDREF_FRAME and ALTREF2_FRAME fill the stack, ALTREF_FRAME and
+    // LAST2_FRAME are not projected.
+    assert_eq!(
+        ctx.frame_state.motion_field_mv(LAST_FRAME, 0, 0),
+        get_mv_projection([0, 8], 1, 4)
+    );
+}
+
+#[test]
+fn motion_vectors_to_past_frames_are_saved() {
+    let mut ctx = context();
+    let mi = ctx.frame_state.mi_mut(0, 0);
+    mi.ref_frame = [LAST_FRAME, BWDREF_FRAME];
+    mi.mv = [[8, -8], [16, -16]];
+
+    let mi = ctx.frame_state.mi_mut(0, 1);
+    mi.ref_frame = [BWDREF_FRAME, NONE];
+    mi.mv = [[8, -8], [0; 2]];
+
+    let mi = ctx.frame_state.mi_mut(0, 2);
+    mi.ref_frame = [GOLDEN_FRAME, NONE];
+    mi.mv = [[REFMVS_LIMIT as i32 + 1, 0], [0; 2]];
+
+    let mi = ctx.frame_state.mi_mut(0, 3);
+    mi.ref_frame = [INTRA_FRAME, NONE];
+
+    let mi = ctx.frame_state.mi_mut(0, 4);
+    mi.ref_frame = [LAST_FRAME, GOLDEN_FRAME];
+    mi.mv = [[8, -8], [REFMVS_LIMIT as i32, 4]];
+
+    let (mvs, ref_frames) = motion_field_motion_vector_storage(&ctx);
+    assert_eq!(
+        ref_frames[..5],
+        [LAST_FRAME, NONE, NONE, NONE, GOLDEN_FRAME]
+    );
+    assert_eq!(mvs[0], [8, -8]);
+    assert_eq!(mvs[4], [REFMVS_LIMIT as i32, 4]);
+    assert!(ref_frames[5..].iter().all(|r| *r == NONE));
+}
@@ -0,0 +1,69 @@
+use davone::{
+    buffer::Buffer,
+    constants::{BLOCK_32X16, BLOCK_8X16, BLOCK_8X8},
+    obu::{ObuContext, ObuParser},
+    picture::Plane,
+};
+
+/// A 40x24 8 bits 4:2:0 key frame with 64x64 superblocks, coded by libaom.
+#[rustfmt::skip]
+const KEY_FRAME_40X24: [u8; 96] = [
+    18, 0, 10, 6, 24, 21, 39, 191, 32, 16, 50, 84, 21, 0, 0, 0, 64, 64, 222, 79, 30, 23, 226, 63,
+    55, 142, 60, 110, 125, 99, 17, 206, 121, 140, 13, 235, 145, 118, 65, 178, 91, 33, 183, 142,
+    41, 64, 71, 191, 222, 103, 95, 203, 218, 43, 188, 180, 155, 224, 142, 37, 34, 163, 1, 73,
+    196, 116, 153, 144, 109, 5, 233, 135, 23, 90, 179, 166, 34, 165, 125, 119, 68, 10, 14, 66,
+    68, 110, 159, 44, 181, 123, 139, 15, 140, 44, 229, 48,
+];
+
+fn decode(data: &[u8]) -> ObuContext {
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(data);
+    while buf.remaining() > 0 {
+        parser.parse(&mut buf).unwrap();
+    }
+
+    parser.ctx
+}
+
+/// The FNV-1a hash of the visible samples of a plane.
+fn hash(plane: &Plane, width: usize, height: usize) -> u32 {
+    let mut hash = 0x811c9dc5u32;
+    for y in 0..height {
+        for v in &plane.row(y)[..width] {
+            for byte in v.to_le_bytes() {
+                hash = (hash ^ byte as u32).wrapping_mul(0x01000193);
+            }
+        }
+    }
+
+    hash
+}
+
+#[test]
+fn partitions_crossing_the_frame_edges_are_split() {
+    let ctx = decode(&KEY_FRAME_40X24);
+    let frame = &ctx.frame_state;
+    assert_eq!((frame.mi_cols, frame.mi_rows), (10, 6));
+
+    // The 64x64 superblock only has its top half inside the frame
+    // (split_or_horz) and the 32x32 blocks on the right only have their
+    // left half inside the frame (split_or_vert). The bottom right 16x16
+    // block is outside on both sides and is split without reading anything.
+    for r in 0..6 {
+        for c in 0..10 {
+            let expected = match (r, c) {
+                (_, 0..=7) => BLOCK_32X16,
+                (0..=3, _) => BLOCK_8X16,
+                _ => BLOCK_8X8,
+            };
+
+            assert_eq!(frame.mi(r, c).mi_size, expected, "block at {r}, {c}");
+        }
+    }
+
+    // The samples match those decoded by dav1d.
+    let planes = &frame.cur_frame.planes;
+    assert_eq!(hash(&planes[0], 40, 24), 0x7b18f8ef);
+    assert_eq!(hash(&planes[1], 20, 12), 0xe4c6b3a5);
+    assert_eq!(hash(&planes[2], 20, 12), 0x8a2ff21b);
+}
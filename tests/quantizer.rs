@@ -0,0 +1,26 @@
+use davone::quantizer::{ac_q, dc_q};
+
+#[test]
+fn the_lookup_table_is_selected_by_the_bit_depth() {
+    assert_eq!(
+        [dc_q(8, 100), dc_q(10, 100), dc_q(12, 100)],
+        [93, 369, 1469]
+    );
+    assert_eq!(
+        [ac_q(8, 100), ac_q(10, 100), ac_q(12, 100)],
+        [112, 441, 1758]
+    );
+}
+
+#[test]
+fn the_quantizer_index_is_clamped() {
+    for bit_depth in [8, 10, 12] {
+        assert_eq!(dc_q(bit_depth, -20), dc_q(bit_depth, 0));
+        assert_eq!(ac_q(bit_depth, -20), ac_q(bit_depth, 0));
+        assert_eq!(dc_q(bit_depth, 300), dc_q(bit_depth, 255));
+        assert_eq!(ac_q(bit_depth, 300), ac_q(bit_depth, 255));
+    }
+
+    assert_eq!([dc_q(8, 0), dc_q(8, 255)], [4, 1336]);
+    assert_eq!([ac_q(12, 0), ac_q(12, 255)], [4, 29247]);
+}
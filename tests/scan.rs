@@ -0,0 +1,79 @@
+use davone::{
+    constants::*,
+    scan::{get_default_scan, get_mcol_scan, get_mrow_scan},
+    tables::{TX_HEIGHT, TX_WIDTH},
+};
+
+/// The coded size of a transform size, the 64 sample wide or high sizes
+/// only code their top left 32x32 coefficients.
+fn coded_size(tx_sz: u8) -> (usize, usize) {
+    (
+        TX_WIDTH[tx_sz as usize].min(32) as usize,
+        TX_HEIGHT[tx_sz as usize].min(32) as usize,
+    )
+}
+
+/// The diagonal scan of the spec: the diagonals of the square sizes
+/// alternate their direction, the ones of the tall sizes go from the top
+/// right to the bottom left and the ones of the wide sizes the other way.
+fn diagonal_scan(w: usize, h: usize) -> Vec<u16> {
+    let mut scan = vec![];
+    for d in 0..w + h - 1 {
+        let mut diagonal: Vec<u16> = (0..h)
+            .filter(|row| d >= *row && d - row < w)
+            .map(|row| (row * w + d - row) as u16)
+            .collect();
+
+        if w > h || (w == h && d % 2 == 0) {
+            diagonal.reverse();
+        }
+
+        scan.extend(diagonal);
+    }
+
+    scan
+}
+
+#[test]
+fn default_scans_are_diagonal() {
+    for tx_sz in 0..TX_SIZES_ALL {
+        let (w, h) = coded_size(tx_sz);
+        assert_eq!(
+            get_default_scan(tx_sz),
+            diagonal_scan(w, h),
+            "tx size {tx_sz}"
+        );
+    }
+}
+
+#[test]
+fn row_and_column_scans_follow_the_samples() {
+    for tx_sz in 0..TX_SIZES_ALL {
+        let (w, h) = coded_size(tx_sz);
+        if w.max(h) > 16 {
+            // Only the sizes up to 16x16 use the one dimensional transforms.
+            assert_eq!(get_mrow_scan(tx_sz), get_default_scan(tx_sz));
+            assert_eq!(get_mcol_scan(tx_sz), get_default_scan(tx_sz));
+            continue;
+        }
+
+        let mrow: Vec<u16> = (0..w * h).map(|pos| pos as u16).collect();
+        let mcol: Vec<u16> = (0..w)
+            .flat_map(|col| (0..h).map(move |row| (row * w + col) as u16))
+            .collect();
+        assert_eq!(get_mrow_scan(tx_sz), mrow, "tx size {tx_sz}");
+        assert_eq!(get_mcol_scan(tx_sz), mcol, "tx size {tx_sz}");
+    }
+}
+
+#[test]
+fn scans_start_like_the_spec_tables() {
+    assert_eq!(
+        get_default_scan(TX_4X4),
+        [0, 1, 4, 8, 5, 2, 3, 6, 9, 12, 13, 10, 7, 11, 14, 15]
+    );
+    assert_eq!(get_default_scan(TX_4X8)[..8], [0, 1, 4, 2, 5, 8, 3, 6]);
+    assert_eq!(get_default_scan(TX_8X4)[..8], [0, 8, 1, 16, 9, 2, 24, 17]);
+    assert_eq!(get_default_scan(TX_16X16)[..6], [0, 1, 16, 32, 17, 2]);
+    assert_eq!(get_mcol_scan(TX_16X4)[..6], [0, 16, 32, 48, 1, 17]);
+}
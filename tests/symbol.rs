@@ -0,0 +1,194 @@
+mod common;
+
+use common::{Rng, SymbolEncoder};
+use davone::symbol::{update_cdf, SymbolDecoder};
+
+#[derive(Clone, Copy)]
+enum Op {
+    Symbol(usize, usize),
+    Bool(bool),
+    Literal(usize, u32),
+}
+
+/// Produces a random sequence of operations over `cdfs`, symbols are skewed
+/// towards low values so that the adaptation has something to learn.
+fn random_ops(rng: &mut Rng, cdfs: &[Vec<u16>], count: usize) -> Vec<Op> {
+    (0..count)
+        .map(|_| match rng.below(8) {
+            0 => Op::Bool(rng.below(2) == 1),
+            1 => {
+                let n = 1 + rng.below(16) as usize;
+                Op::Literal(n, rng.next() & ((1 << n) - 1))
+            }
+            _ => {
+                let index = rng.below(cdfs.len() as u32) as usize;
+                let n = cdfs[index].len() as u32 - 1;
+                let symbol = rng.below(n).min(rng.below(n));
+                Op::Symbol(index, symbol as usize)
+            }
+        })
+        .collect()
+}
+
+fn encode(cdfs: &[Vec<u16>], ops: &[Op], disable_cdf_update: bool) -> Vec<u8> {
+    let mut cdfs = cdfs.to_vec();
+    let mut encoder = SymbolEncoder::new(disable_cdf_update);
+    for op in ops {
+        match *op {
+            Op::Symbol(index, symbol) => encoder.write_symbol(&mut cdfs[index], symbol),
+            Op::Bool(value) => encoder.write_bool(value),
+            Op::Literal(n, value) => encoder.write_literal(n, value),
+        }
+    }
+
+    encoder.finish()
+}
+
+fn decode(data: &[u8], cdfs: &[Vec<u16>], ops: &[Op], disable_cdf_update: bool) -> Vec<Vec<u16>> {
+    let mut cdfs = cdfs.to_vec();
+    let mut decoder = SymbolDecoder::new(data, disable_cdf_update).unwrap();
+    for (i, op) in ops.iter().enumerate() {
+        match *op {
+            Op::Symbol(index, symbol) => {
+                assert_eq!(decoder.read_symbol(&mut cdfs[index]), symbol, "op {}", i)
+            }
+            Op::Bool(value) => assert_eq!(decoder.read_bool(), value, "op {}", i),
+            Op::Literal(n, value) => assert_eq!(decoder.read_literal(n), value, "op {}", i),
+        }
+    }
+
+    decoder.exit().unwrap();
+    cdfs
+}
+
+fn random_cdfs(rng: &mut Rng) -> Vec<Vec<u16>> {
+    (2..=16).map(|n| rng.cdf(n)).collect()
+}
+
+#[test]
+fn round_trip_with_cdf_update() {
+    for seed in 1..50u64 {
+        let mut rng = Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let cdfs = random_cdfs(&mut rng);
+        let count = 1 + rng.below(2000) as usize;
+        let ops = random_ops(&mut rng, &cdfs, count);
+        let data = encode(&cdfs, &ops, false);
+        let adapted = decode(&data, &cdfs, &ops, false);
+        assert!(adapted.iter().any(|cdf| cdf[cdf.len() - 1] > 0));
+    }
+}
+
+#[test]
+fn round_trip_without_cdf_update() {
+    for seed in 1..20u64 {
+        let mut rng = Rng(seed.wrapping_mul(0x2545_f491_4f6c_dd1d));
+        let cdfs = random_cdfs(&mut rng);
+        let count = 1 + rng.below(2000) as usize;
+        let ops = random_ops(&mut rng, &cdfs, count);
+        let data = encode(&cdfs, &ops, true);
+        assert_eq!(decode(&data, &cdfs, &ops, true), cdfs);
+    }
+}
+
+#[test]
+fn round_trip_single_symbol() {
+    for symbol in 0..4 {
+        let cdfs = vec![vec![8192, 16384, 24576, 32768, 0]];
+        let ops = [Op::Symbol(0, symbol)];
+        let data = encode(&cdfs, &ops, false);
+        decode(&data, &cdfs, &ops, false);
+    }
+}
+
+#[test]
+fn cdf_adaptation_moves_towards_decoded_symbol() {
+    let mut cdf = [8192, 16384, 24576, 32768, 0];
+    update_cdf(&mut cdf, 0);
+    assert!(cdf[0] > 8192 && cdf[1] > 16384 && cdf[2] > 24576);
+    assert_eq!(cdf[3], 32768);
+    assert_eq!(cdf[4], 1);
+
+    let mut cdf = [8192, 16384, 24576, 32768, 0];
+    update_cdf(&mut cdf, 3);
+    assert!(cdf[0] < 8192 && cdf[1] < 16384 && cdf[2] < 24576);
+
+    let mut cdf = [16384, 32768, 40];
+    update_cdf(&mut cdf, 1);
+    assert_eq!(cdf, [16384 - (16384 >> 6), 32768, 40]);
+}
+
+#[test]
+fn zero_padding_is_accepted() {
+    let mut rng = Rng(7);
+    let cdfs = random_cdfs(&mut rng);
+    let ops = random_ops(&mut rng, &cdfs, 300);
+    let mut data = encode(&cdfs, &ops, false);
+    data.extend_from_slice(&[0, 0, 0]);
+    decode(&data, &cdfs, &ops, false);
+}
+
+#[test]
+fn invalid_padding_is_rejected() {
+    let mut rng = Rng(11);
+    let cdfs = random_cdfs(&mut rng);
+    let ops = random_ops(&mut rng, &cdfs, 300);
+    let mut data = encode(&cdfs, &ops, false);
+    data.extend_from_slice(&[0, 0, 1]);
+
+    let mut cdfs = cdfs.clone();
+    let mut decoder = SymbolDecoder::new(&data, false).unwrap();
+    for op in &ops {
+        match *op {
+            Op::Symbol(index, _) => {
+                decoder.read_symbol(&mut cdfs[index]);
+            }
+            Op::Bool(_) => {
+                decoder.read_bool();
+            }
+            Op::Literal(n, _) => {
+                decoder.read_literal(n);
+            }
+        }
+    }
+
+    assert!(decoder.exit().is_err());
+}
+
+#[test]
+fn empty_data_is_rejected() {
+    assert!(SymbolDecoder::new(&[], false).is_err());
+}
+
+/// Writes `x` with the Exp-Golomb code read by read_golomb.
+fn write_golomb(encoder: &mut SymbolEncoder, x: u32) {
+    let x = x + 1;
+    let length = (32 - x.leading_zeros()) as usize;
+    encoder.write_literal(length - 1, 0);
+    encoder.write_literal(length, x);
+}
+
+#[test]
+fn golomb_round_trip() {
+    let values = [0, 1, 2, 3, 6, 7, 100, 12345, (1 << 20) - 2];
+    let mut encoder = SymbolEncoder::new(false);
+    for value in values {
+        write_golomb(&mut encoder, value);
+    }
+
+    let data = encoder.finish();
+    let mut decoder = SymbolDecoder::new(&data, false).unwrap();
+    for value in values {
+        assert_eq!(decoder.read_golomb().unwrap(), value);
+    }
+
+    decoder.exit().unwrap();
+}
+
+#[test]
+fn golomb_codes_longer_than_20_bits_are_rejected() {
+    let mut encoder = SymbolEncoder::new(false);
+    write_golomb(&mut encoder, (1 << 20) - 1);
+    let data = encoder.finish();
+    let mut decoder = SymbolDecoder::new(&data, false).unwrap();
+    assert!(decoder.read_golomb().is_err());
+}
@@ -0,0 +1,245 @@
+mod common;
+
+use common::KEY_FRAME_TWO_TILES;
+use davone::{
+    buffer::Buffer,
+    obu::{
+        frame_header::{TileInfo, UncompressedHeader},
+        tile_group::TileGroup,
+        Obu, ObuContext, ObuError, ObuParser, ObuType,
+    },
+};
+
+/// An 80x80 8 bits 4:2:0 key frame followed by an inter frame with LOCALWARP
+/// blocks, coded by libaom.
+#[rustfmt::skip]
+const LOCAL_WARP_FRAMES: [u8; 506] = [
+    18, 0, 10, 10, 0, 0, 0, 3, 52, 249, 238, 255, 204, 2, 50, 188, 3, 16, 0, 144, 0, 97, 8, 32, 32,
+    0, 24, 0, 255, 121, 223, 45, 219, 249, 151, 190, 189, 149, 132, 232, 188, 128, 4, 91, 58, 69,
+    227, 131, 73, 42, 183, 238, 24, 31, 188, 214, 230, 149, 151, 128, 56, 8, 192, 36, 117, 57, 178,
+    75, 155, 39, 149, 24, 41, 23, 42, 180, 36, 177, 50, 247, 227, 96, 168, 249, 114, 169, 11, 171,
+    118, 123, 135, 3, 216, 44, 9, 118, 26, 234, 63, 53, 21, 238, 131, 72, 135, 38, 6, 67, 25, 187,
+    219, 75, 226, 44, 211, 237, 174, 200, 163, 229, 36, 246, 116, 162, 161, 212, 8, 111, 203, 214,
+    3, 150, 77, 95, 48, 174, 219, 172, 223, 176, 131, 197, 141, 76, 11, 200, 204, 82, 231, 130, 226,
+    105, 253, 187, 245, 12, 109, 123, 242, 1, 91, 218, 172, 250, 15, 79, 198, 122, 235, 227, 130,
+    164, 152, 159, 255, 253, 7, 73, 38, 149, 176, 230, 203, 233, 19, 137, 143, 122, 62, 196, 122,
+    198, 23, 191, 140, 244, 136, 106, 47, 165, 96, 246, 16, 103, 131, 39, 138, 107, 24, 108, 25,
+    146, 63, 54, 115, 170, 64, 248, 178, 220, 214, 181, 219, 40, 177, 71, 123, 240, 35, 70, 187, 0,
+    128, 154, 203, 170, 233, 119, 200, 191, 139, 199, 252, 97, 33, 61, 206, 146, 41, 124, 45, 234,
+    243, 41, 5, 25, 27, 253, 54, 107, 129, 36, 159, 161, 205, 234, 204, 202, 73, 52, 141, 115, 67,
+    21, 249, 166, 12, 165, 153, 5, 211, 42, 229, 74, 254, 179, 67, 16, 28, 54, 247, 0, 4, 77, 92,
+    151, 6, 1, 58, 126, 199, 44, 197, 249, 241, 85, 50, 74, 73, 8, 240, 105, 91, 13, 209, 200, 16,
+    200, 240, 7, 86, 143, 56, 77, 182, 211, 254, 58, 242, 125, 2, 91, 117, 225, 45, 25, 141, 109,
+    102, 202, 83, 136, 144, 116, 229, 244, 148, 1, 251, 205, 94, 129, 200, 136, 45, 43, 90, 56, 215,
+    64, 61, 104, 170, 243, 246, 252, 58, 121, 240, 229, 143, 155, 194, 26, 118, 255, 116, 86, 38,
+    23, 229, 247, 12, 212, 246, 222, 23, 236, 97, 79, 187, 160, 251, 64, 147, 101, 165, 74, 90, 138,
+    67, 92, 222, 82, 177, 57, 201, 192, 222, 202, 125, 45, 64, 212, 219, 108, 14, 117, 20, 92, 226,
+    175, 52, 67, 148, 209, 201, 145, 209, 152, 14, 18, 181, 162, 160, 253, 184, 26, 183, 159, 118,
+    23, 9, 132, 140, 232, 233, 25, 59, 173, 213, 242, 130, 174, 123, 133, 182, 4, 87, 138, 111, 18,
+    0, 50, 41, 48, 3, 192, 128, 0, 0, 122, 192, 1, 134, 30, 128, 144, 64, 0, 32, 0, 241, 105, 75,
+    206, 176, 148, 108, 33, 76, 13, 45, 199, 51, 59, 17, 76, 145, 124, 175, 132, 81, 191, 48, 128
+];
+
+/// A 64x32 8 bits 4:2:0 key frame followed by an inter frame with OBMC blocks,
+/// coded by libaom.
+#[rustfmt::skip]
+const OBMC_FRAMES: [u8; 112] = [
+    18, 0, 10, 10, 0, 0, 0, 2, 167, 255, 117, 254, 96, 16, 50, 67, 16, 0, 208, 0, 0, 2, 135, 4, 0,
+    46, 9, 227, 221, 147, 189, 247, 128, 105, 46, 82, 255, 255, 255, 249, 9, 30, 213, 116, 51, 19,
+    2, 251, 38, 31, 83, 210, 53, 247, 254, 133, 219, 143, 255, 246, 202, 103, 171, 13, 198, 251,
+    200, 95, 174, 251, 3, 134, 128, 220, 152, 65, 166, 90, 87, 167, 30, 55, 72, 18, 0, 50, 25, 48,
+    3, 192, 128, 0, 0, 6, 219, 128, 56, 0, 0, 40, 64, 64, 0, 0, 185, 238, 15, 64, 34, 68, 206, 22
+];
+
+/// A 32x32 8 bits 4:2:0 key frame and two inter frames coded by libaom with
+/// the inter intra prediction enabled, only the last one has an inter intra
+/// block.
+#[rustfmt::skip]
+const INTER_INTRA_FRAMES: [u8; 973] = [
+    18, 0, 10, 10, 0, 0, 0, 2, 39, 254, 251, 252, 192, 32, 50, 195, 3, 16, 0, 208, 0, 106, 70, 52,
+    40, 255, 240, 32, 212, 240, 129, 94, 155, 70, 236, 151, 40, 53, 6, 150, 152, 37, 31, 4, 59, 122,
+    4, 156, 153, 150, 183, 37, 52, 68, 139, 131, 195, 169, 112, 228, 169, 20, 220, 64, 157, 105,
+    156, 49, 215, 144, 212, 253, 206, 156, 36, 168, 135, 200, 117, 143, 126, 220, 110, 193, 154, 94,
+    121, 213, 68, 130, 148, 48, 245, 66, 145, 91, 169, 55, 97, 81, 213, 203, 233, 123, 156, 243, 29,
+    130, 35, 142, 125, 212, 176, 242, 172, 25, 188, 21, 20, 211, 170, 35, 63, 187, 66, 9, 168, 121,
+    25, 89, 8, 164, 209, 139, 2, 14, 207, 170, 3, 152, 246, 186, 245, 137, 194, 57, 36, 231, 46,
+    154, 5, 150, 170, 57, 49, 116, 70, 51, 246, 47, 108, 14, 188, 239, 15, 56, 90, 161, 234, 119,
+    129, 251, 18, 38, 36, 27, 219, 154, 85, 92, 191, 214, 22, 170, 3, 236, 18, 85, 2, 123, 197, 113,
+    35, 139, 119, 196, 231, 95, 7, 137, 130, 250, 199, 144, 213, 248, 200, 241, 237, 242, 172, 44,
+    96, 92, 160, 119, 157, 148, 205, 46, 20, 126, 71, 23, 78, 188, 155, 122, 187, 231, 161, 166,
+    178, 182, 115, 55, 188, 89, 50, 165, 166, 105, 199, 234, 47, 237, 69, 127, 90, 191, 20, 144,
+    133, 251, 231, 24, 37, 1, 38, 253, 72, 100, 169, 76, 171, 48, 68, 245, 224, 216, 27, 31, 54,
+    110, 75, 214, 58, 57, 37, 118, 18, 16, 193, 73, 58, 245, 193, 153, 77, 168, 79, 64, 239, 57,
+    183, 216, 249, 138, 54, 186, 196, 44, 239, 125, 209, 222, 247, 210, 89, 167, 23, 173, 226, 122,
+    141, 82, 72, 192, 148, 152, 244, 232, 43, 203, 133, 243, 131, 48, 215, 5, 79, 132, 132, 233, 20,
+    30, 232, 252, 218, 246, 28, 119, 22, 169, 15, 177, 35, 53, 255, 24, 225, 240, 197, 61, 159, 7,
+    10, 185, 132, 104, 37, 14, 70, 97, 210, 65, 199, 110, 152, 200, 121, 41, 211, 213, 29, 94, 16,
+    161, 174, 117, 232, 55, 209, 253, 192, 92, 82, 253, 66, 240, 69, 77, 100, 25, 198, 204, 77, 168,
+    182, 88, 29, 9, 242, 160, 186, 240, 88, 251, 247, 14, 150, 71, 16, 249, 182, 0, 26, 222, 101,
+    178, 11, 160, 202, 204, 67, 52, 152, 49, 250, 200, 121, 38, 138, 244, 120, 160, 86, 211, 236,
+    199, 202, 241, 189, 200, 122, 124, 78, 17, 177, 165, 207, 247, 64, 19, 97, 171, 199, 158, 0,
+    178, 127, 157, 188, 139, 27, 239, 79, 208, 18, 0, 50, 238, 1, 48, 3, 192, 128, 0, 0, 43, 110, 2,
+    37, 160, 128, 163, 255, 128, 128, 0, 207, 76, 96, 59, 39, 17, 86, 102, 63, 7, 217, 192, 233,
+    232, 82, 78, 64, 118, 184, 223, 81, 172, 46, 168, 73, 175, 61, 116, 184, 216, 220, 15, 164, 144,
+    127, 35, 237, 191, 119, 41, 114, 46, 125, 133, 248, 75, 89, 132, 40, 131, 125, 34, 235, 252,
+    228, 38, 53, 244, 153, 196, 195, 151, 255, 86, 91, 243, 1, 40, 142, 77, 177, 136, 135, 28, 211,
+    101, 68, 116, 252, 18, 11, 123, 255, 92, 25, 253, 202, 168, 73, 111, 42, 108, 111, 159, 118, 62,
+    82, 49, 132, 144, 210, 98, 65, 158, 207, 9, 58, 117, 108, 138, 24, 221, 192, 75, 91, 54, 4, 188,
+    155, 86, 203, 243, 183, 13, 71, 151, 206, 125, 0, 78, 144, 154, 109, 253, 246, 31, 167, 18, 193,
+    5, 232, 143, 70, 76, 66, 19, 174, 159, 209, 152, 192, 178, 175, 198, 152, 44, 48, 227, 164, 177,
+    183, 108, 125, 38, 221, 36, 241, 130, 255, 44, 128, 85, 198, 88, 26, 54, 235, 105, 126, 136, 34,
+    160, 8, 60, 109, 95, 220, 224, 212, 111, 2, 125, 21, 133, 124, 81, 162, 90, 155, 222, 121, 88,
+    153, 119, 114, 251, 131, 74, 223, 101, 49, 26, 248, 50, 52, 43, 255, 220, 96, 3, 160, 18, 0, 50,
+    129, 2, 48, 5, 193, 4, 0, 0, 2, 222, 128, 106, 7, 24, 40, 255, 252, 24, 0, 255, 255, 70, 81,
+    101, 155, 204, 70, 18, 220, 51, 146, 116, 134, 40, 99, 65, 5, 229, 235, 163, 55, 212, 215, 178,
+    203, 71, 22, 22, 245, 66, 74, 205, 228, 85, 44, 212, 87, 251, 204, 110, 213, 199, 26, 217, 239,
+    82, 17, 249, 121, 168, 163, 197, 48, 243, 153, 242, 108, 6, 47, 43, 133, 36, 121, 70, 130, 105,
+    24, 97, 24, 33, 94, 12, 120, 184, 242, 154, 76, 47, 221, 146, 149, 191, 210, 141, 193, 85, 116,
+    6, 200, 198, 29, 158, 183, 112, 85, 250, 146, 117, 212, 103, 89, 253, 216, 189, 239, 162, 134,
+    151, 176, 239, 53, 44, 242, 203, 104, 175, 88, 52, 188, 46, 64, 236, 191, 70, 4, 217, 160, 83,
+    53, 248, 54, 98, 93, 118, 38, 221, 42, 85, 79, 203, 145, 152, 93, 237, 163, 80, 10, 244, 178,
+    163, 141, 226, 147, 211, 34, 117, 220, 201, 220, 159, 167, 36, 225, 80, 193, 214, 216, 11, 174,
+    63, 36, 22, 201, 150, 77, 40, 50, 42, 183, 250, 149, 210, 2, 103, 248, 243, 56, 1, 171, 226,
+    233, 52, 214, 144, 163, 110, 91, 189, 212, 254, 114, 11, 179, 113, 117, 225, 33, 73, 253, 101,
+    147, 232, 9, 107, 99, 123, 20, 61, 65, 95, 79, 139, 91, 100, 8, 175, 152, 5, 254, 240, 158, 97,
+    233, 32, 95, 248, 28, 94, 247
+];
+
+/// A context whose frame has two tile columns, with 2 bytes tile sizes.
+fn context_with_two_tiles() -> ObuContext {
+    ObuContext {
+        frame_header: Some(UncompressedHeader {
+            tile_info: TileInfo {
+                tile_cols_log2: 1,
+                tile_cols: 2,
+                tile_rows: 1,
+                mi_col_starts: vec![0, 16, 32],
+                mi_row_starts: vec![0, 16],
+                tile_size_bytes: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn tile_groups_must_be_in_order_and_inside_the_frame() {
+    let mut ctx = context_with_two_tiles();
+
+    // tile_start_and_end_present_flag, tg_start = 1 and tg_end = 0.
+    let data = [0b1100_0000, 0, 0, 0];
+    let err = TileGroup::decode(&mut ctx, &mut Buffer::new(&data), data.len()).unwrap_err();
+    assert_eq!(err, ObuError::InvalidTileGroup);
+}
+
+#[test]
+fn tile_sizes_must_fit_in_the_tile_group() {
+    let mut ctx = context_with_two_tiles();
+
+    // No tg_start/tg_end, then tile_size_minus_1 = 9 for the first tile
+    // while only 4 bytes follow.
+    let data = [0b0000_0000, 9, 0, 1, 2, 3, 4];
+    let err = TileGroup::decode(&mut ctx, &mut Buffer::new(&data), data.len()).unwrap_err();
+    assert_eq!(err, ObuError::InvalidTileGroup);
+
+    // The tile group is shorter than the tile size field.
+    let data = [0b0000_0000, 9];
+    let err = TileGroup::decode(&mut ctx, &mut Buffer::new(&data), data.len()).unwrap_err();
+    assert_eq!(err, ObuError::InvalidTileGroup);
+}
+
+#[test]
+fn tile_groups_need_a_frame_header() {
+    let mut ctx = ObuContext::default();
+    let data = [0];
+    let err = TileGroup::decode(&mut ctx, &mut Buffer::new(&data), data.len()).unwrap_err();
+    assert_eq!(err, ObuError::NotFoundFrameHeader);
+}
+
+/// Decodes the obus of `data` up to the first error, returns the number of
+/// frames decoded before it and the error.
+fn decode_until_error(data: &[u8]) -> (usize, ObuError) {
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(data);
+    let mut frames = 0;
+    loop {
+        match parser.parse(&mut buf) {
+            Ok(Obu::Frame(_)) => frames += 1,
+            Ok(_) => {}
+            Err(err) => return (frames, err),
+        }
+    }
+}
+
+#[test]
+fn warped_motion_is_unsupported() {
+    // The key frame decodes, the inter frame doesn't.
+    assert_eq!(
+        decode_until_error(&LOCAL_WARP_FRAMES),
+        (1, ObuError::Unsupported)
+    );
+}
+
+#[test]
+fn overlapped_motion_compensation_is_unsupported() {
+    assert_eq!(decode_until_error(&OBMC_FRAMES), (1, ObuError::Unsupported));
+}
+
+#[test]
+fn inter_intra_prediction_is_unsupported() {
+    // The first inter frame reads interintra equal to 0 for every block.
+    assert_eq!(
+        decode_until_error(&INTER_INTRA_FRAMES),
+        (2, ObuError::Unsupported)
+    );
+}
+
+#[test]
+fn metadata_padding_and_reserved_obus_are_skipped() {
+    let mut data = KEY_FRAME_TWO_TILES[..10].to_vec();
+    // A padding obu and an itut t35 metadata obu between the sequence header
+    // and the frame.
+    data.extend_from_slice(&[0x7a, 3, 1, 2, 0x80]);
+    data.extend_from_slice(&[0x2a, 4, 4, 0xb5, 0, 0x80]);
+    data.extend_from_slice(&KEY_FRAME_TWO_TILES[10..]);
+    // A reserved obu without obu_size extends to the end of the data.
+    data.extend_from_slice(&[0x48, 0xff, 0xff]);
+
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&data);
+    let mut skipped = vec![];
+    let mut frames = 0;
+    while buf.remaining() > 0 {
+        match parser.parse(&mut buf).unwrap() {
+            Obu::Skipped(r#type) => skipped.push(r#type),
+            Obu::Frame(_) => frames += 1,
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        skipped,
+        [ObuType::Padding, ObuType::Metadata, ObuType::Reserved(9)]
+    );
+    assert_eq!(frames, 1);
+}
+
+#[test]
+fn the_last_tile_group_updates_the_reference_frames() {
+    let mut parser = ObuParser::default();
+    let mut buf = Buffer::new(&KEY_FRAME_TWO_TILES);
+    while buf.remaining() > 0 {
+        parser.parse(&mut buf).unwrap();
+    }
+
+    // The key frame refreshes every slot with the decoded frame.
+    let ctx = &parser.ctx;
+    assert!(ctx.ref_frame_marking.iter().all(|marked| *marked));
+    assert!(ctx.ref_frame_width.iter().all(|width| *width == 128));
+    assert!(ctx
+        .frame_store
+        .iter()
+        .all(|frame| frame.planes[0].get(5, 3) == ctx.frame_state.cur_frame.planes[0].get(5, 3)));
+}
@@ -0,0 +1,40 @@
+use davone::{
+    buffer::Buffer,
+    obu::{
+        tile_list::{TileList, TileListEntry},
+        ObuError,
+    },
+};
+
+#[test]
+fn tile_list_entries_borrow_their_coded_tile_data() {
+    // anchor_frame_idx, anchor_tile_row, anchor_tile_col,
+    // tile_data_size_minus_1 and coded_tile_data.
+    let data = [127, 2, 3, 0, 2, 0xa, 0xb, 0xc, 0xd];
+    let mut buf = Buffer::new(&data);
+    let entry = TileListEntry::decode(&mut buf).unwrap();
+    assert_eq!(entry.anchor_frame_idx, 127);
+    assert_eq!((entry.anchor_tile_row, entry.anchor_tile_col), (2, 3));
+    assert_eq!(entry.tile_data_size, 3);
+    assert_eq!(entry.coded_tile_data, [0xa, 0xb, 0xc]);
+    assert_eq!(buf.remaining(), 1);
+}
+
+#[test]
+fn anchor_frame_indices_above_127_are_rejected() {
+    let data = [128, 0, 0, 0, 0, 0];
+    let err = TileListEntry::decode(&mut Buffer::new(&data)).unwrap_err();
+    assert_eq!(err, ObuError::InvalidAnchorFrameIdx);
+}
+
+#[test]
+fn tile_data_overrunning_the_obu_is_rejected() {
+    let data = [0, 0, 0, 0, 3, 0xa, 0xb, 0xc];
+    let err = TileListEntry::decode(&mut Buffer::new(&data)).unwrap_err();
+    assert_eq!(err, ObuError::InvalidTileList);
+
+    // The second of two entries overruns the tile list.
+    let data = [0, 1, 0, 1, 0, 0, 0, 0, 0, 0xa, 5, 0, 0, 0, 1, 0xb];
+    let err = TileList::decode(&mut Buffer::new(&data)).unwrap_err();
+    assert_eq!(err, ObuError::InvalidTileList);
+}
@@ -0,0 +1,327 @@
+mod common;
+
+use std::f64::consts::{PI, SQRT_2};
+
+use common::Rng;
+use davone::{
+    constants::*,
+    tables::{TX_HEIGHT, TX_WIDTH},
+    transform::{inverse_adst, inverse_dct, inverse_identity, inverse_transform_2d, inverse_wht},
+};
+
+/// The 1D transforms of the reference implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Dct,
+    Adst,
+    FlipAdst,
+    Identity,
+}
+
+/// The transforms applied to the columns and the rows by each transform
+/// type.
+const TX_TYPE_KINDS: [(Kind, Kind); TX_TYPES as usize] = {
+    use Kind::*;
+
+    [
+        (Dct, Dct),
+        (Adst, Dct),
+        (Dct, Adst),
+        (Adst, Adst),
+        (FlipAdst, Dct),
+        (Dct, FlipAdst),
+        (FlipAdst, FlipAdst),
+        (Adst, FlipAdst),
+        (FlipAdst, Adst),
+        (Identity, Identity),
+        (Dct, Identity),
+        (Identity, Dct),
+        (Adst, Identity),
+        (Identity, Adst),
+        (FlipAdst, Identity),
+        (Identity, FlipAdst),
+    ]
+};
+
+/// The weight of the input `k` in the output `i` of the inverse transform
+/// `kind` of `n` points, computed directly from its definition.
+fn basis(kind: Kind, n: usize, i: usize, k: usize) -> f64 {
+    match kind {
+        Kind::Dct if k == 0 => 1.0 / SQRT_2,
+        Kind::Dct => (PI * ((2 * i + 1) * k) as f64 / (2 * n) as f64).cos(),
+        Kind::Adst if n == 4 => {
+            2.0 * SQRT_2 / 3.0 * (PI * ((i + 1) * (2 * k + 1)) as f64 / 9.0).sin()
+        }
+        Kind::Adst => (PI * ((2 * i + 1) * (2 * k + 1)) as f64 / (4 * n) as f64).sin(),
+        Kind::FlipAdst => basis(Kind::Adst, n, n - 1 - i, k),
+        Kind::Identity if i == k => SQRT_2 * (n as f64 / 4.0).sqrt(),
+        Kind::Identity => 0.0,
+    }
+}
+
+/// The reference 1D inverse transform, a plain matrix multiplication.
+fn reference_1d(kind: Kind, input: &[f64]) -> Vec<f64> {
+    let n = input.len();
+    (0..n)
+        .map(|i| (0..n).map(|k| input[k] * basis(kind, n, i, k)).sum())
+        .collect()
+}
+
+/// Checks the fast 1D transform `kind` of `1 << n` points against the
+/// reference, the error of the integer butterflies grows with the magnitude
+/// of the inputs.
+fn check_1d(rng: &mut Rng, kind: Kind, n: u32) {
+    let n0 = 1 << n;
+    for _ in 0..2000 {
+        let mut t = [0; 64];
+        for v in &mut t[..n0] {
+            *v = rng.signed(4096);
+        }
+
+        let input: Vec<f64> = t[..n0].iter().map(|v| *v as f64).collect();
+        let expected = reference_1d(kind, &input);
+        match kind {
+            Kind::Dct => inverse_dct(&mut t, n),
+            Kind::Adst => inverse_adst(&mut t, n),
+            _ => inverse_identity(&mut t, n),
+        }
+
+        let tolerance = 1.0 + input.iter().map(|v| v.abs()).sum::<f64>() / 2048.0;
+        for (i, expected) in expected.iter().enumerate() {
+            assert!(
+                (t[i] as f64 - expected).abs() <= tolerance,
+                "{kind:?} of {n0} points, output {i} is {} instead of {expected}",
+                t[i]
+            );
+        }
+    }
+}
+
+#[test]
+fn dct_matches_reference() {
+    let mut rng = Rng(0x2545f491);
+    for n in 2..=6 {
+        check_1d(&mut rng, Kind::Dct, n);
+    }
+}
+
+#[test]
+fn adst_matches_reference() {
+    let mut rng = Rng(0x9e3779b9);
+    for n in 2..=4 {
+        check_1d(&mut rng, Kind::Adst, n);
+    }
+}
+
+#[test]
+fn identity_matches_reference() {
+    let mut rng = Rng(0x6a09e667);
+    for n in 2..=5 {
+        check_1d(&mut rng, Kind::Identity, n);
+    }
+}
+
+#[test]
+fn dct_of_dc_is_flat() {
+    for n in 2..=6 {
+        for dc in [-4096, -1000, -1, 1, 7, 1000, 32767] {
+            let mut t = [0; 64];
+            t[0] = dc;
+            inverse_dct(&mut t, n);
+
+            let expected = (dc * 2896 + 2048) >> 12;
+            assert!(t[..1 << n].iter().all(|v| *v == expected));
+        }
+    }
+}
+
+/// The forward Walsh-Hadamard transform of the lossless encoders, including
+/// the scaling by the lossless quantizer.
+fn forward_wht(input: &[[i32; 4]; 4]) -> [[i32; 4]; 4] {
+    fn wht([mut a, mut b, mut c, mut d]: [i32; 4]) -> [i32; 4] {
+        a += b;
+        d -= c;
+        let e = (a - d) >> 1;
+        b = e - b;
+        c = e - c;
+        a -= c;
+        d += b;
+        [a, c, d, b]
+    }
+
+    let mut columns = [[0; 4]; 4];
+    for j in 0..4 {
+        let out = wht([input[0][j], input[1][j], input[2][j], input[3][j]]);
+        for i in 0..4 {
+            columns[i][j] = out[i];
+        }
+    }
+
+    columns.map(|row| wht(row).map(|v| v * 4))
+}
+
+#[test]
+fn wht_is_lossless() {
+    let mut rng = Rng(0xbb67ae85);
+    for bit_depth in [8, 10, 12] {
+        let max = (1 << bit_depth) - 1;
+        for _ in 0..2000 {
+            let mut input = [[0; 4]; 4];
+            for v in input.iter_mut().flatten() {
+                *v = rng.signed(max);
+            }
+
+            let mut dequant = Box::new([[0; 64]; 64]);
+            for (row, coeffs) in dequant.iter_mut().zip(forward_wht(&input)) {
+                row[..4].copy_from_slice(&coeffs);
+            }
+
+            let mut residual = Box::new([[0; 64]; 64]);
+            inverse_transform_2d(&dequant, &mut residual, TX_4X4, DCT_DCT, true, bit_depth);
+            for i in 0..4 {
+                assert_eq!(residual[i][..4], input[i]);
+            }
+        }
+    }
+}
+
+#[test]
+fn wht_shifts_its_inputs() {
+    let mut t = [4, 0, 0, 0];
+    inverse_wht(&mut t, 2);
+    assert_eq!(t, [1, 0, 0, 0]);
+
+    let mut t = [3, 0, 0, 0];
+    inverse_wht(&mut t, 2);
+    assert_eq!(t, [0; 4]);
+}
+
+/// Whether `kind` can be applied to `n` points.
+fn supports(kind: Kind, n: usize) -> bool {
+    match kind {
+        Kind::Dct => true,
+        Kind::Adst | Kind::FlipAdst => n <= 16,
+        Kind::Identity => n <= 32,
+    }
+}
+
+/// The rounding shift after the row transforms of each transform size.
+const ROW_SHIFT: [u32; TX_SIZES_ALL as usize] =
+    [0, 1, 2, 2, 2, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2];
+
+#[test]
+fn inverse_transform_2d_matches_reference() {
+    let mut rng = Rng(0x3c6ef372);
+    for tx_sz in 0..TX_SIZES_ALL {
+        let w = TX_WIDTH[tx_sz as usize] as usize;
+        let h = TX_HEIGHT[tx_sz as usize] as usize;
+        let rect_scale = if w == 2 * h || h == 2 * w {
+            1.0 / SQRT_2
+        } else {
+            1.0
+        };
+
+        let row_shift = ROW_SHIFT[tx_sz as usize];
+
+        for (tx_type, (col_kind, row_kind)) in TX_TYPE_KINDS.into_iter().enumerate() {
+            if !supports(col_kind, h) || !supports(row_kind, w) {
+                continue;
+            }
+
+            for bit_depth in [8, 10, 12] {
+                for _ in 0..8 {
+                    let mut dequant = Box::new([[0; 64]; 64]);
+                    for row in dequant.iter_mut().take(h.min(32)) {
+                        for v in row.iter_mut().take(w.min(32)) {
+                            if rng.next().is_multiple_of(4) {
+                                *v = rng.signed(512);
+                            }
+                        }
+                    }
+
+                    let mut residual = Box::new([[0; 64]; 64]);
+                    inverse_transform_2d(
+                        &dequant,
+                        &mut residual,
+                        tx_sz,
+                        tx_type as u8,
+                        false,
+                        bit_depth,
+                    );
+
+                    let rows: Vec<Vec<f64>> = dequant[..h]
+                        .iter()
+                        .map(|row| {
+                            let input: Vec<f64> =
+                                row[..w].iter().map(|v| *v as f64 * rect_scale).collect();
+                            reference_1d(row_kind, &input)
+                        })
+                        .collect();
+
+                    let scale = (1 << (row_shift + 4)) as f64;
+                    for j in 0..w {
+                        let column: Vec<f64> = rows.iter().map(|row| row[j]).collect();
+                        for (i, expected) in reference_1d(col_kind, &column).iter().enumerate() {
+                            let expected = expected / scale;
+                            assert!(
+                                (residual[i][j] as f64 - expected).abs() <= 2.0,
+                                "tx size {tx_sz} tx type {tx_type}, residual ({i}, {j}) is {} \
+                                 instead of {expected}",
+                                residual[i][j]
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn flipadst_mirrors_adst() {
+    let mut rng = Rng(0xa54ff53a);
+    for tx_sz in [TX_4X4, TX_8X8, TX_16X16, TX_4X8, TX_16X8, TX_4X16] {
+        let w = TX_WIDTH[tx_sz as usize] as usize;
+        let h = TX_HEIGHT[tx_sz as usize] as usize;
+        let mut dequant = Box::new([[0; 64]; 64]);
+        for row in dequant.iter_mut().take(h) {
+            for v in row.iter_mut().take(w) {
+                *v = rng.signed(1024);
+            }
+        }
+
+        let transform = |tx_type| {
+            let mut residual = Box::new([[0; 64]; 64]);
+            inverse_transform_2d(&dequant, &mut residual, tx_sz, tx_type, false, 10);
+            residual
+        };
+
+        let adst = transform(ADST_ADST);
+        let flip_ud = transform(FLIPADST_ADST);
+        let flip_lr = transform(ADST_FLIPADST);
+        let flip_both = transform(FLIPADST_FLIPADST);
+        for i in 0..h {
+            for j in 0..w {
+                assert_eq!(flip_ud[i][j], adst[h - 1 - i][j]);
+                assert_eq!(flip_lr[i][j], adst[i][w - 1 - j]);
+                assert_eq!(flip_both[i][j], adst[h - 1 - i][w - 1 - j]);
+            }
+        }
+    }
+}
+
+#[test]
+fn row_inputs_are_clamped_to_the_bit_depth() {
+    let transform = |dc, bit_depth| {
+        let mut dequant = Box::new([[0; 64]; 64]);
+        dequant[0][0] = dc;
+
+        let mut residual = Box::new([[0; 64]; 64]);
+        inverse_transform_2d(&dequant, &mut residual, TX_8X8, DCT_DCT, false, bit_depth);
+        residual
+    };
+
+    assert_eq!(transform(1 << 20, 8), transform((1 << 15) - 1, 8));
+    assert_eq!(transform(-(1 << 20), 8), transform(-(1 << 15), 8));
+    assert_ne!(transform(1 << 17, 12), transform((1 << 15) - 1, 12));
+}